@@ -16,6 +16,22 @@ pub async fn main() -> Result<(), CliError> {
             let config = config::load_user_config()?;
             handlers::submit_job(config, args).await?;
         }
+        Commands::Resubmit(args) => {
+            let config = config::load_user_config()?;
+            handlers::resubmit_job(config, args).await?;
+        }
+        Commands::Exec(args) => {
+            let config = config::load_user_config()?;
+            handlers::exec_in_job(config, args).await?;
+        }
+        Commands::PortForward(args) => {
+            let config = config::load_user_config()?;
+            handlers::port_forward(config, args).await?;
+        }
+        Commands::Sweep(args) => {
+            let config = config::load_user_config()?;
+            handlers::create_sweep(config, args).await?;
+        }
         Commands::Configure => {
             let config = config::load_user_config()?;
             handlers::configure_user(config).await?;
@@ -29,6 +45,9 @@ pub async fn main() -> Result<(), CliError> {
                 AgentCommands::Configure => {
                     handlers::configure_agent(config).await?;
                 }
+                AgentCommands::PruneImages(args) => {
+                    handlers::prune_images(config, args).await?;
+                }
             }
         }
     }
@@ -1,5 +1,7 @@
+use crate::domain::agent::models::GpuModel;
 use crate::errors::ConfigError;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
@@ -19,6 +21,257 @@ pub struct AgentConfig {
     pub cluster_api_key: String,
     pub node_id: Uuid,
     pub private_registry: Option<PrivateRegistryConfig>,
+    /// Where job checkpoints are kept, in one directory per job. Defaults to
+    /// `~/.lilac/checkpoints`. Jobs can only resume on another node if this
+    /// is storage shared by all nodes, such as an NFS mount.
+    #[serde(default)]
+    pub checkpoint_root: Option<PathBuf>,
+    /// How much disk space, in GB, the images of jobs may take up before the
+    /// least recently used ones are removed. Defaults to 50 GB.
+    #[serde(default)]
+    pub image_cache_budget_gb: Option<u64>,
+    /// How jobs are run on the node.
+    #[serde(default)]
+    pub executor: ExecutorKind,
+    /// The Docker-compatible API socket of the container runtime, e.g. a
+    /// rootless Podman's `/run/user/1000/podman/podman.sock`. Found
+    /// automatically if not set.
+    #[serde(default)]
+    pub container_socket: Option<PathBuf>,
+    /// Where and how jobs run with the Kubernetes executor.
+    #[serde(default)]
+    pub kubernetes: Option<KubernetesConfig>,
+    /// Where and how jobs run with the Slurm executor.
+    #[serde(default)]
+    pub slurm: Option<SlurmConfig>,
+    /// What happens to the running job when the agent is shut down.
+    #[serde(default)]
+    pub shutdown_mode: ShutdownMode,
+}
+
+/// How the agent runs jobs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ExecutorKind {
+    /// In containers, from the job's image, with Docker or Podman.
+    #[default]
+    Docker,
+    /// As plain processes on the node, running the job's definition as a
+    /// shell command. For nodes that can't run containers.
+    Process,
+    /// As pods of a Kubernetes cluster, from the job's image.
+    Kubernetes,
+    /// As batch jobs of a Slurm cluster, from the job's image.
+    Slurm,
+}
+
+/// What the agent does with the job it is running when it is shut down.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ShutdownMode {
+    /// Stops the job, giving it its termination grace period to save a
+    /// checkpoint, and has the control plane re-queue it.
+    #[default]
+    Stop,
+    /// Waits for the job to finish. Shutting down the agent again stops it.
+    Wait,
+}
+
+/// How the agent runs jobs on a Kubernetes cluster. The API server, token
+/// and namespace default to those of the pod the agent runs in.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct KubernetesConfig {
+    pub api_server: Option<String>,
+    pub namespace: Option<String>,
+    /// A file holding the bearer token to authenticate with.
+    pub token_file: Option<PathBuf>,
+    /// The CA certificate, in PEM, that signed the API server's certificate.
+    pub ca_cert: Option<PathBuf>,
+    /// Secrets with the credentials to pull the images of jobs.
+    #[serde(default)]
+    pub image_pull_secrets: Vec<String>,
+    /// Labels of the Kubernetes nodes jobs may run on.
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    /// The persistent volume claim checkpoints are kept on, in one directory
+    /// per job. Without one, checkpoints are lost when a job's pod is deleted.
+    pub checkpoint_claim: Option<String>,
+    /// The resources the agent offers the control plane. Jobs run on
+    /// whichever Kubernetes node fits them, so these are the most one job
+    /// can ask for.
+    #[serde(default)]
+    pub cpu_millicores: i32,
+    #[serde(default)]
+    pub memory_mb: i32,
+    #[serde(default)]
+    pub gpu_count: i32,
+    #[serde(default)]
+    pub gpu_model: Option<GpuModel>,
+}
+
+/// How the agent submits jobs to a Slurm cluster. The agent must run on a
+/// machine that can submit jobs, such as a login node, and its
+/// `~/.lilac/jobs` directory and checkpoint root must be on storage shared
+/// with the compute nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SlurmConfig {
+    pub partition: Option<String>,
+    pub account: Option<String>,
+    /// The most time a job may run for. Defaults to the partition's limit.
+    pub time_limit_minutes: Option<u32>,
+    /// What runs the images of jobs on the compute nodes.
+    #[serde(default)]
+    pub container_runtime: SlurmContainerRuntime,
+    /// More arguments for `sbatch`, e.g. `--qos=high`.
+    #[serde(default)]
+    pub sbatch_args: Vec<String>,
+    /// The resources the agent offers the control plane. Jobs run on
+    /// whichever compute node fits them, so these are the most one job can
+    /// ask for.
+    #[serde(default)]
+    pub cpu_millicores: i32,
+    #[serde(default)]
+    pub memory_mb: i32,
+    #[serde(default)]
+    pub gpu_count: i32,
+    #[serde(default)]
+    pub gpu_model: Option<GpuModel>,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SlurmContainerRuntime {
+    #[default]
+    Enroot,
+    Apptainer,
+}
+
+impl SlurmConfig {
+    fn from_env() -> Self {
+        let number = |name| {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default()
+        };
+
+        Self {
+            partition: env::var("LILAC_SLURM_PARTITION").ok(),
+            account: env::var("LILAC_SLURM_ACCOUNT").ok(),
+            time_limit_minutes: env::var("LILAC_SLURM_TIME_LIMIT_MINUTES")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            container_runtime: env::var("LILAC_SLURM_CONTAINER_RUNTIME")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            sbatch_args: env::var("LILAC_SLURM_SBATCH_ARGS")
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            cpu_millicores: number("LILAC_SLURM_CPU_MILLICORES"),
+            memory_mb: number("LILAC_SLURM_MEMORY_MB"),
+            gpu_count: number("LILAC_SLURM_GPU_COUNT"),
+            gpu_model: env::var("LILAC_SLURM_GPU_MODEL")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+        }
+    }
+}
+
+impl KubernetesConfig {
+    fn from_env() -> Self {
+        let list = |name| {
+            env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let number = |name| {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default()
+        };
+
+        Self {
+            api_server: env::var("LILAC_KUBERNETES_API_SERVER").ok(),
+            namespace: env::var("LILAC_KUBERNETES_NAMESPACE").ok(),
+            token_file: env::var("LILAC_KUBERNETES_TOKEN_FILE")
+                .ok()
+                .map(PathBuf::from),
+            ca_cert: env::var("LILAC_KUBERNETES_CA_CERT").ok().map(PathBuf::from),
+            image_pull_secrets: list("LILAC_KUBERNETES_IMAGE_PULL_SECRETS"),
+            node_selector: list("LILAC_KUBERNETES_NODE_SELECTOR")
+                .iter()
+                .filter_map(|label| label.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            checkpoint_claim: env::var("LILAC_KUBERNETES_CHECKPOINT_CLAIM").ok(),
+            cpu_millicores: number("LILAC_KUBERNETES_CPU_MILLICORES"),
+            memory_mb: number("LILAC_KUBERNETES_MEMORY_MB"),
+            gpu_count: number("LILAC_KUBERNETES_GPU_COUNT"),
+            gpu_model: env::var("LILAC_KUBERNETES_GPU_MODEL")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+        }
+    }
+}
+
+impl AgentConfig {
+    const DEFAULT_IMAGE_CACHE_BUDGET_GB: u64 = 50;
+
+    pub fn image_cache_budget_bytes(&self) -> u64 {
+        self.image_cache_budget_gb
+            .unwrap_or(Self::DEFAULT_IMAGE_CACHE_BUDGET_GB)
+            * 1024
+            * 1024
+            * 1024
+    }
+
+    pub fn checkpoint_root(&self) -> Result<PathBuf, ConfigError> {
+        match &self.checkpoint_root {
+            Some(root) => Ok(root.clone()),
+            None => get_config_path("checkpoints"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -60,7 +313,7 @@ pub fn load_user_config() -> Result<UserConfig, ConfigError> {
     }
 
     let content = fs::read_to_string(&config_path).map_err(|_| ConfigError::ReadFile)?;
-    Ok(toml::from_str(&content).map_err(|_| ConfigError::Parse)?)
+    toml::from_str(&content).map_err(|_| ConfigError::Parse)
 }
 
 pub fn load_agent_config() -> Result<AgentConfig, ConfigError> {
@@ -68,11 +321,14 @@ pub fn load_agent_config() -> Result<AgentConfig, ConfigError> {
 
     // Prioritize environment variables
     if let Ok(api_endpoint) = env::var("LILAC_API_ENDPOINT") {
+        let executor: ExecutorKind = env::var("LILAC_EXECUTOR")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default();
         let config = AgentConfig {
             api_endpoint,
             cluster_api_key: {
-                let key = env::var("LILAC_CLUSTER_API_KEY")
-                    .map_err(|_| ConfigError::ReadFile)?;
+                let key = env::var("LILAC_CLUSTER_API_KEY").map_err(|_| ConfigError::ReadFile)?;
                 if key.is_empty() {
                     return Err(ConfigError::EmptyApiKey);
                 }
@@ -93,6 +349,18 @@ pub fn load_agent_config() -> Result<AgentConfig, ConfigError> {
             } else {
                 None
             },
+            checkpoint_root: env::var("LILAC_CHECKPOINT_ROOT").ok().map(PathBuf::from),
+            image_cache_budget_gb: env::var("LILAC_IMAGE_CACHE_BUDGET_GB")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            executor,
+            container_socket: env::var("LILAC_CONTAINER_SOCKET").ok().map(PathBuf::from),
+            kubernetes: (executor == ExecutorKind::Kubernetes).then(KubernetesConfig::from_env),
+            slurm: (executor == ExecutorKind::Slurm).then(SlurmConfig::from_env),
+            shutdown_mode: env::var("LILAC_SHUTDOWN_MODE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
         };
         // Write to file if env vars are used, to persist the config
         let toml_string = toml::to_string(&config).map_err(|_| ConfigError::WriteFile)?;
@@ -109,6 +377,13 @@ pub fn load_agent_config() -> Result<AgentConfig, ConfigError> {
             cluster_api_key: "".to_string(),
             node_id: Uuid::new_v4(),
             private_registry: None,
+            checkpoint_root: None,
+            image_cache_budget_gb: None,
+            executor: ExecutorKind::default(),
+            container_socket: None,
+            kubernetes: None,
+            slurm: None,
+            shutdown_mode: ShutdownMode::default(),
         };
         let toml_string = toml::to_string(&config).map_err(|_| ConfigError::WriteFile)?;
         fs::create_dir_all(config_path.parent().unwrap())
@@ -119,11 +394,11 @@ pub fn load_agent_config() -> Result<AgentConfig, ConfigError> {
     }
 
     let content = fs::read_to_string(&config_path).map_err(|_| ConfigError::ReadFile)?;
-    Ok(toml::from_str(&content).map_err(|_| ConfigError::Parse)?)
+    toml::from_str(&content).map_err(|_| ConfigError::Parse)
 }
 
 pub fn get_config_path(file_name: &str) -> Result<PathBuf, ConfigError> {
     let home_dir = dirs::home_dir().ok_or(ConfigError::HomeDirNotFound)?;
     let config_dir = home_dir.join(".lilac");
     Ok(config_dir.join(file_name))
-}
\ No newline at end of file
+}
@@ -1,54 +1,238 @@
-use crate::{
-    domain::agent::{
-        models::{HeartbeatRequest, JobInfo, JobStatus},
-        ports::{ControlPlaneApi, JobExecutor, SystemMonitor},
+use crate::domain::agent::{
+    gpus::GpuAllocator,
+    image_cache::ImageCache,
+    models::{
+        AgentState, AssignedGpu, ExecChannel, ExecControl, ExecMessage, ExecSession, FailureReason,
+        HeartbeatRequest, JobExit, JobInfo, JobStatus, JobUsage,
     },
+    ports::{ControlPlaneApi, JobExecutor, StateStore, SystemMonitor},
+    tunnel,
 };
+use crate::errors::JobExecutorError;
+use futures_util::StreamExt;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::{
-    sync::Notify,
+    io::AsyncWriteExt,
+    net::TcpStream,
+    sync::{mpsc, Notify},
     task::JoinHandle,
     time::{self, MissedTickBehavior},
 };
 use uuid::Uuid;
 
-pub struct Daemon<C, S, J>
+type JobHandle = Arc<Mutex<Option<(Uuid, JoinHandle<()>)>>>;
+
+pub struct Daemon<C, S, J, T>
 where
     C: ControlPlaneApi + Clone + 'static,
     S: SystemMonitor,
     J: JobExecutor + Clone + 'static,
+    T: StateStore + 'static,
 {
     control_plane: Arc<C>,
     system_monitor: Arc<S>,
     job_executor: Arc<J>,
+    state_store: Arc<T>,
+    image_cache: Arc<ImageCache<J>>,
     heartbeat_interval: Duration,
     current_job: Arc<Mutex<Option<JobInfo>>>,
-    job_handle: Arc<Mutex<Option<(Uuid, JoinHandle<()>)>>>,
+    job_handle: JobHandle,
     node_id: Uuid,
     heartbeat_now: Arc<Notify>,
+    /// Whether shutting down waits for the running job to finish, rather
+    /// than stopping it.
+    wait_for_job_on_shutdown: bool,
 }
 
-impl<C, S, J> Daemon<C, S, J>
+impl<C, S, J, T> Daemon<C, S, J, T>
 where
     C: ControlPlaneApi + Clone + 'static,
     S: SystemMonitor,
     J: JobExecutor + Clone + 'static,
+    T: StateStore + 'static,
 {
-    pub fn new(control_plane: C, system_monitor: S, job_executor: J, node_id: Uuid) -> Self {
+    pub fn new(
+        control_plane: C,
+        system_monitor: S,
+        job_executor: J,
+        state_store: T,
+        node_id: Uuid,
+        image_cache_budget_bytes: u64,
+        wait_for_job_on_shutdown: bool,
+    ) -> Self {
+        let job_executor = Arc::new(job_executor);
         Self {
             control_plane: Arc::new(control_plane),
             system_monitor: Arc::new(system_monitor),
-            job_executor: Arc::new(job_executor),
+            image_cache: Arc::new(ImageCache::new(
+                job_executor.clone(),
+                image_cache_budget_bytes,
+            )),
+            job_executor,
+            state_store: Arc::new(state_store),
             heartbeat_interval: Duration::from_secs(30),
             current_job: Arc::new(Mutex::new(None)),
             job_handle: Arc::new(Mutex::new(None)),
             node_id,
             heartbeat_now: Arc::new(Notify::new()),
+            wait_for_job_on_shutdown,
+        }
+    }
+
+    /// Stops the job the node is running without reporting how it ended, so
+    /// that the control plane re-queues it once the node deregisters. A job
+    /// that already finished is left to be reported.
+    async fn stop_current_job(&self, gpu_allocator: &Mutex<GpuAllocator>) {
+        let running = self
+            .current_job
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|job_info| !job_info.status.is_finished());
+        if !running {
+            return;
+        }
+        let Some((job_id, handle)) = self.job_handle.lock().unwrap().take() else {
+            return;
+        };
+
+        println!("[DAEMON] Stopping job {}.", job_id);
+        handle.abort();
+        // The job may have finished before it was aborted, in which case it
+        // is reported like any other.
+        let _ = handle.await;
+        if let Err(e) = self.job_executor.stop_job(&job_id.to_string()).await {
+            eprintln!(
+                "[DAEMON] Error stopping job container for job {}: {}",
+                job_id, e
+            );
+        }
+        gpu_allocator.lock().unwrap().release(job_id);
+        update_current_job(&self.current_job, &*self.state_store, |current| {
+            if current
+                .as_ref()
+                .is_some_and(|job_info| !job_info.status.is_finished())
+            {
+                *current = None;
+            }
+        });
+    }
+
+    /// Removes the node from the control plane once it has nothing left to
+    /// report, so that it isn't left to be found dead.
+    async fn deregister(&self) {
+        match self.control_plane.deregister_node(self.node_id).await {
+            Ok(()) => println!("[DAEMON] Deregistered node {}.", self.node_id),
+            Err(e) => eprintln!("[DAEMON] Error deregistering node {}: {}", self.node_id, e),
+        }
+    }
+
+    /// Samples the resource usage of a running job. Failures are logged and
+    /// reported as no usage, since telemetry must never block a heartbeat.
+    async fn sample_job_usage(&self, job_id: Uuid) -> Option<JobUsage> {
+        let job_id = job_id.to_string();
+        let mut usage = match self.job_executor.get_job_usage(&job_id).await {
+            Ok(usage) => usage,
+            Err(e) => {
+                eprintln!("[DAEMON] Failed to sample usage for job {}: {}", job_id, e);
+                return None;
+            }
+        };
+
+        match self.job_executor.get_job_pids(&job_id).await {
+            Ok(pids) => match self.system_monitor.get_gpu_usage(&pids).await {
+                Ok(gpus) => usage.gpus = gpus,
+                Err(e) => eprintln!(
+                    "[DAEMON] Failed to sample GPU usage for job {}: {}",
+                    job_id, e
+                ),
+            },
+            Err(e) => eprintln!(
+                "[DAEMON] Failed to list processes for job {}: {}",
+                job_id, e
+            ),
         }
+
+        Some(usage)
     }
 
-    pub async fn run(self) -> Result<(), anyhow::Error> {
+    /// Picks up where an earlier run of the agent left off. The agent
+    /// reattaches to the container of the job it was running, so that the
+    /// job isn't restarted, and stops the containers of any other jobs.
+    async fn recover(&self, gpu_allocator: &Arc<Mutex<GpuAllocator>>) {
+        let state = self.state_store.load().unwrap_or_else(|e| {
+            eprintln!("[DAEMON] Failed to load agent state: {}", e);
+            AgentState::default()
+        });
+        let existing_jobs = self.job_executor.list_jobs().await.unwrap_or_else(|e| {
+            eprintln!("[DAEMON] Failed to list job containers: {}", e);
+            Vec::new()
+        });
+
+        let known_job_id = state.current_job.as_ref().map(|job| job.current_job_id);
+        for job_id in existing_jobs.iter().filter(|id| Some(**id) != known_job_id) {
+            println!("[DAEMON] Stopping container of unknown job {}.", job_id);
+            if let Err(e) = self.job_executor.stop_job(&job_id.to_string()).await {
+                eprintln!(
+                    "[DAEMON] Error stopping job container for job {}: {}",
+                    job_id, e
+                );
+            }
+        }
+
+        let Some(mut job_info) = state.current_job else {
+            return;
+        };
+        let job_id = job_info.current_job_id;
+        if job_info.status.is_finished() {
+            // The job finished before the agent stopped. It is reported in
+            // the first heartbeat.
+            println!("[DAEMON] Job {} finished while the agent was down.", job_id);
+            *self.current_job.lock().unwrap() = Some(job_info);
+            return;
+        }
+        if !existing_jobs.contains(&job_id) {
+            // The agent stopped before the job's container was created. The
+            // job is started over if the control plane still assigns it.
+            println!("[DAEMON] Job {} has no container, forgetting it.", job_id);
+            update_current_job(&self.current_job, &*self.state_store, |current| {
+                *current = None
+            });
+            return;
+        }
+
+        println!("[DAEMON] Reattaching to job {}.", job_id);
+        let indices: Vec<u32> = job_info.assigned_gpus.iter().map(|gpu| gpu.index).collect();
+        gpu_allocator.lock().unwrap().claim(job_id, &indices);
+        job_info.status = JobStatus::Running;
+        update_current_job(&self.current_job, &*self.state_store, |current| {
+            *current = Some(job_info)
+        });
+
+        let executor = self.job_executor.clone();
+        let current_job = self.current_job.clone();
+        let state_store = self.state_store.clone();
+        let gpu_allocator = gpu_allocator.clone();
+        let heartbeat_now = self.heartbeat_now.clone();
+        let handle = tokio::spawn(async move {
+            let result = executor.wait_job(&job_id.to_string()).await;
+            finish_job(job_id, result, &current_job, &*state_store, &gpu_allocator);
+            heartbeat_now.notify_one();
+        });
+        *self.job_handle.lock().unwrap() = Some((job_id, handle));
+    }
+
+    /// Runs the agent until it is shut down by a message on
+    /// `shutdown_requests`. The node is then drained: the control plane
+    /// assigns it no new jobs, and its job is stopped or, if the daemon
+    /// waits for jobs on shutdown, left to finish. Another message stops the
+    /// job regardless. Once the node has reported its last job, it
+    /// deregisters.
+    pub async fn run(
+        self,
+        mut shutdown_requests: mpsc::UnboundedReceiver<()>,
+    ) -> Result<(), anyhow::Error> {
         println!("[DAEMON] Starting Lilac agent daemon...");
 
         let resources = self
@@ -57,9 +241,20 @@ where
             .await
             .map_err(|e| anyhow::Error::new(e).context("Failed to get node resources"))?;
         println!("[DAEMON] Discovered resources: {:?}", resources);
+        let gpu_allocator = Arc::new(Mutex::new(GpuAllocator::new(resources.gpus.clone())));
+        self.recover(&gpu_allocator).await;
+
+        tokio::spawn(serve_exec_sessions(
+            self.control_plane.clone(),
+            self.job_executor.clone(),
+            self.current_job.clone(),
+            self.node_id,
+        ));
 
         let mut interval = time::interval(self.heartbeat_interval);
         interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut draining = false;
+        let mut stop_job = false;
 
         loop {
             tokio::select! {
@@ -69,23 +264,51 @@ where
                 _ = self.heartbeat_now.notified() => {
                     println!("[DAEMON] Job status changed, sending immediate heartbeat...");
                 }
+                Some(()) = shutdown_requests.recv() => {
+                    if draining {
+                        println!("[DAEMON] Shutting down again, stopping the current job...");
+                        stop_job = true;
+                    } else {
+                        println!("[DAEMON] Shutting down, draining the node...");
+                        draining = true;
+                        stop_job = !self.wait_for_job_on_shutdown;
+                        self.image_cache.stop_prefetch();
+                    }
+                }
+            }
+
+            if stop_job {
+                self.stop_current_job(&gpu_allocator).await;
             }
 
-            let current_job_info = self.current_job.lock().unwrap().clone();
+            let mut current_job_info = self.current_job.lock().unwrap().clone();
+            if let Some(job_info) = &mut current_job_info {
+                if job_info.status == JobStatus::Running {
+                    job_info.usage = self.sample_job_usage(job_info.current_job_id).await;
+                }
+            }
             let request = HeartbeatRequest {
                 memory_info: resources.memory_mb,
                 cpu_info: resources.cpu.clone(),
-                gpu_info: resources.gpus.first().cloned(),
+                gpu_info: resources.gpu_info(),
                 job_info: current_job_info,
+                cached_images: self.image_cache.cached_images().await,
+                draining,
             };
 
             let response = self
                 .control_plane
                 .send_heartbeat(self.node_id, request)
                 .await;
+            let heartbeat_ok = response.is_ok();
+            let prefetch_images = response
+                .as_ref()
+                .map(|response| response.prefetch_images.clone())
+                .unwrap_or_default();
 
             match response {
                 Ok(response) => {
+                    self.image_cache.set_protected(&response.protected_images);
                     let mut current_job_guard = self.current_job.lock().unwrap();
                     let assigned_job_id = response.assigned_job.as_ref().map(|j| j.id);
                     let current_job_id = current_job_guard.as_ref().map(|j| j.current_job_id);
@@ -99,6 +322,8 @@ where
                         if let Some((job_id, handle)) = self.job_handle.lock().unwrap().take() {
                             println!("[DAEMON] Aborting previous job {}.", job_id);
                             handle.abort();
+                            // The aborted task can't release its GPUs itself.
+                            gpu_allocator.lock().unwrap().release(job_id);
                             let job_executor = self.job_executor.clone();
                             tokio::spawn(async move {
                                 if let Err(e) = job_executor.stop_job(&job_id.to_string()).await {
@@ -110,56 +335,100 @@ where
                             });
                         }
 
-                        if let Some(assigned_job) = response.assigned_job {
+                        if let Some(assigned_job) = response.assigned_job.filter(|_| !draining) {
                             let job_id = assigned_job.id;
+                            self.image_cache.stop_prefetch();
                             println!("[DAEMON] Starting new job with ID: {}", job_id);
                             let new_job_info = JobInfo {
                                 current_job_id: job_id,
                                 status: JobStatus::Acknowledged,
+                                usage: None,
+                                failure_reason: None,
+                                assigned_gpus: Vec::new(),
                             };
                             *current_job_guard = Some(new_job_info);
+                            save_state(&*self.state_store, &current_job_guard);
 
                             let executor = self.job_executor.clone();
                             let current_job_clone = self.current_job.clone();
+                            let state_store_clone = self.state_store.clone();
                             let job_handle_clone = self.job_handle.clone();
                             let resources_clone = resources.clone();
                             let heartbeat_now_clone = self.heartbeat_now.clone();
+                            let gpu_allocator_clone = gpu_allocator.clone();
 
                             let handle = tokio::spawn(async move {
-                                if let Some(job_info) = &mut *current_job_clone.lock().unwrap() {
-                                    job_info.status = JobStatus::Starting;
-                                }
+                                update_current_job(
+                                    &current_job_clone,
+                                    &*state_store_clone,
+                                    |current| {
+                                        if let Some(job_info) = current {
+                                            job_info.status = JobStatus::Starting;
+                                        }
+                                    },
+                                );
                                 heartbeat_now_clone.notify_one();
 
-                                if let Some(job_info) = &mut *current_job_clone.lock().unwrap() {
-                                    job_info.status = JobStatus::Running;
-                                }
+                                let gpu_count = assigned_job.resource_requirements.gpu_count();
+                                let gpus = gpu_allocator_clone
+                                    .lock()
+                                    .unwrap()
+                                    .allocate(job_id, gpu_count);
+                                let Some(gpus) = gpus else {
+                                    eprintln!(
+                                        "[JOB {}] Fewer than {} GPUs are free.",
+                                        job_id, gpu_count
+                                    );
+                                    update_current_job(
+                                        &current_job_clone,
+                                        &*state_store_clone,
+                                        |current| {
+                                            if let Some(job_info) = current {
+                                                job_info.status = JobStatus::Failed;
+                                                job_info.failure_reason =
+                                                    Some(FailureReason::Error);
+                                            }
+                                        },
+                                    );
+                                    heartbeat_now_clone.notify_one();
+                                    return;
+                                };
 
-                                let final_status =
-                                    match executor.run_job(assigned_job, &resources_clone).await {
-                                        Ok(0) => {
-                                            println!("[JOB {}] Execution finished successfully.", job_id);
-                                            JobStatus::Succeeded
+                                update_current_job(
+                                    &current_job_clone,
+                                    &*state_store_clone,
+                                    |current| {
+                                        if let Some(job_info) = current {
+                                            job_info.status = JobStatus::Running;
+                                            job_info.assigned_gpus =
+                                                gpus.iter().map(AssignedGpu::from).collect();
                                         }
-                                        Ok(exit_code) => {
-                                            eprintln!("[JOB {}] Execution finished with a non-zero exit code: {}", job_id, exit_code);
-                                            JobStatus::Failed
-                                        }
-                                        Err(e) => {
-                                            eprintln!("[JOB {}] Execution failed: {}", job_id, e);
-                                            JobStatus::Failed
-                                        }
-                                    };
+                                    },
+                                );
 
-                                if let Some(job_info) = &mut *current_job_clone.lock().unwrap() {
-                                    job_info.status = final_status;
-                                }
+                                let result = executor
+                                    .run_job(assigned_job, &resources_clone, &gpus)
+                                    .await;
+                                finish_job(
+                                    job_id,
+                                    result,
+                                    &current_job_clone,
+                                    &*state_store_clone,
+                                    &gpu_allocator_clone,
+                                );
                                 heartbeat_now_clone.notify_one();
                             });
                             *job_handle_clone.lock().unwrap() = Some((job_id, handle));
                         } else {
                             *current_job_guard = None;
-                            println!("[DAEMON] Node is now available.");
+                            save_state(&*self.state_store, &current_job_guard);
+                            if draining {
+                                // A job assigned to the node is re-queued
+                                // when it deregisters.
+                                println!("[DAEMON] Node is drained.");
+                            } else {
+                                println!("[DAEMON] Node is now available.");
+                            }
                         }
                     }
                 }
@@ -167,6 +436,572 @@ where
                     eprintln!("[DAEMON] Error sending heartbeat: {}. Will retry.", e);
                 }
             }
+
+            let idle = self.current_job.lock().unwrap().is_none();
+            if draining {
+                if idle {
+                    self.deregister().await;
+                    return Ok(());
+                }
+                if stop_job && !heartbeat_ok {
+                    // The job finished but couldn't be reported. It is
+                    // reported by the next run of the agent, which the node
+                    // stays registered for.
+                    println!("[DAEMON] Shutting down without reporting the last job.");
+                    return Ok(());
+                }
+                continue;
+            }
+
+            // Images are only evicted while the node is idle, so that a job
+            // never waits on it, and right after a heartbeat, so that the
+            // images of queued jobs are known.
+            if idle && heartbeat_ok {
+                self.image_cache.enforce_budget().await;
+                self.image_cache.start_prefetch(prefetch_images);
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Saves the job this node is running, so that a restarted agent can pick up
+/// where this one left off.
+fn save_state<T: StateStore>(state_store: &T, current_job: &Option<JobInfo>) {
+    let state = AgentState {
+        current_job: current_job.clone(),
+    };
+    if let Err(e) = state_store.save(&state) {
+        eprintln!("[DAEMON] Failed to save agent state: {}", e);
+    }
+}
+
+/// Applies `update` to the job this node is running and saves the result.
+fn update_current_job<T: StateStore>(
+    current_job: &Mutex<Option<JobInfo>>,
+    state_store: &T,
+    update: impl FnOnce(&mut Option<JobInfo>),
+) {
+    let mut current_job = current_job.lock().unwrap();
+    update(&mut current_job);
+    save_state(state_store, &current_job);
+}
+
+/// Records how a job's run ended and frees its GPUs.
+fn finish_job<T: StateStore>(
+    job_id: Uuid,
+    result: Result<JobExit, JobExecutorError>,
+    current_job: &Mutex<Option<JobInfo>>,
+    state_store: &T,
+    gpu_allocator: &Mutex<GpuAllocator>,
+) {
+    let (final_status, failure_reason) = match result {
+        Ok(exit) if exit.oom_killed => {
+            eprintln!(
+                "[JOB {}] Execution was killed for running out of memory.",
+                job_id
+            );
+            (JobStatus::Failed, Some(FailureReason::OutOfMemory))
+        }
+        Ok(exit) if exit.exit_code == 0 => {
+            println!("[JOB {}] Execution finished successfully.", job_id);
+            (JobStatus::Succeeded, None)
+        }
+        Ok(exit) => {
+            eprintln!(
+                "[JOB {}] Execution finished with a non-zero exit code: {}",
+                job_id, exit.exit_code
+            );
+            (JobStatus::Failed, Some(FailureReason::Error))
+        }
+        Err(e) => {
+            eprintln!("[JOB {}] Execution failed: {}", job_id, e);
+            (JobStatus::Failed, Some(FailureReason::Error))
+        }
+    };
+
+    gpu_allocator.lock().unwrap().release(job_id);
+    update_current_job(current_job, state_store, |current| {
+        if let Some(job_info) = current {
+            job_info.status = final_status;
+            job_info.failure_reason = failure_reason;
+        }
+    });
+}
+
+/// Serves the exec sessions users open on this node until the daemon stops.
+async fn serve_exec_sessions<C, J>(
+    control_plane: Arc<C>,
+    job_executor: Arc<J>,
+    current_job: Arc<Mutex<Option<JobInfo>>>,
+    node_id: Uuid,
+) where
+    C: ControlPlaneApi + 'static,
+    J: JobExecutor + 'static,
+{
+    loop {
+        let sessions = match control_plane.wait_for_exec_sessions(node_id).await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                eprintln!(
+                    "[DAEMON] Error waiting for exec sessions: {}. Will retry.",
+                    e
+                );
+                time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+
+        for session in sessions {
+            let control_plane = control_plane.clone();
+            let job_executor = job_executor.clone();
+            let current_job = current_job.clone();
+            tokio::spawn(async move {
+                let session_id = session.id;
+                if let Err(e) =
+                    serve_exec_session(control_plane, job_executor, current_job, node_id, session)
+                        .await
+                {
+                    eprintln!("[DAEMON] Exec session {} failed: {}", session_id, e);
+                }
+            });
+        }
+    }
+}
+
+/// Runs a session's command in the current job's container and relays it
+/// to the user until the command exits or the user disconnects.
+async fn serve_exec_session<C, J>(
+    control_plane: Arc<C>,
+    job_executor: Arc<J>,
+    current_job: Arc<Mutex<Option<JobInfo>>>,
+    node_id: Uuid,
+    session: ExecSession,
+) -> Result<(), anyhow::Error>
+where
+    C: ControlPlaneApi,
+    J: JobExecutor,
+{
+    println!(
+        "[DAEMON] Attaching to exec session {} for job {}",
+        session.id, session.job_id
+    );
+    let mut channel = control_plane
+        .attach_exec_session(node_id, session.id)
+        .await?;
+
+    let current_job_id = current_job
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|j| j.current_job_id);
+    if current_job_id != Some(session.job_id) {
+        let message = format!("job {} is not running on this node", session.job_id);
+        let _ = channel
+            .tx
+            .send(ExecMessage::Control(ExecControl::Error { message }))
+            .await;
+        return Ok(());
+    }
+
+    let job_id = session.job_id.to_string();
+    if let Some(port) = session.port {
+        return serve_tunnel(job_executor, channel, &job_id, port).await;
+    }
+
+    let mut process = match job_executor
+        .exec(&job_id, session.command, session.tty)
+        .await
+    {
+        Ok(process) => process,
+        Err(e) => {
+            let message = format!("failed to start command: {}", e);
+            let _ = channel
+                .tx
+                .send(ExecMessage::Control(ExecControl::Error { message }))
+                .await;
+            return Ok(());
+        }
+    };
+
+    let mut input_open = true;
+    loop {
+        tokio::select! {
+            output = process.output.next() => match output {
+                Some(Ok(data)) => {
+                    if channel.tx.send(ExecMessage::Data(data)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                Some(Err(e)) => {
+                    eprintln!("[DAEMON] Error reading output of exec {}: {}", process.id, e);
+                    break;
+                }
+                None => break,
+            },
+            message = channel.rx.recv(), if input_open => match message {
+                Some(ExecMessage::Data(data)) => {
+                    process.input.write_all(&data).await?;
+                    process.input.flush().await?;
+                }
+                Some(ExecMessage::Control(ExecControl::Resize { cols, rows })) => {
+                    if let Err(e) = job_executor.resize_exec(&process.id, cols, rows).await {
+                        eprintln!("[DAEMON] Error resizing exec {}: {}", process.id, e);
+                    }
+                }
+                Some(ExecMessage::Control(ExecControl::Eof)) => {
+                    process.input.shutdown().await?;
+                }
+                Some(ExecMessage::Control(_)) => {}
+                // The user disconnected. The command keeps its input open
+                // until it exits on its own, like a dropped `docker exec`.
+                None => input_open = false,
+            },
+        }
+    }
+
+    let control = match job_executor.get_exec_exit_code(&process.id).await {
+        Ok(code) => ExecControl::Exit { code },
+        Err(e) => ExecControl::Error {
+            message: format!("failed to get exit code: {}", e),
+        },
+    };
+    let _ = channel.tx.send(ExecMessage::Control(control)).await;
+    println!("[DAEMON] Exec session {} finished.", session.id);
+
+    Ok(())
+}
+
+/// Connects a tunnel session to one of the current job's exposed ports.
+async fn serve_tunnel<J>(
+    job_executor: Arc<J>,
+    channel: ExecChannel,
+    job_id: &str,
+    port: u16,
+) -> Result<(), anyhow::Error>
+where
+    J: JobExecutor,
+{
+    let stream = match job_executor.get_port_address(job_id, port).await {
+        Ok(address) => TcpStream::connect(address)
+            .await
+            .map_err(anyhow::Error::from),
+        Err(e) => Err(e.into()),
+    };
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            let message = format!("failed to connect to port {}: {}", port, e);
+            let _ = channel
+                .tx
+                .send(ExecMessage::Control(ExecControl::Error { message }))
+                .await;
+            return Ok(());
+        }
+    };
+
+    tunnel::relay(stream, channel).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::agent::models::{
+        CachedImage, ExecProcess, ExecSession, Gpu, GpuManufacturer, GpuModel, GpuUsage,
+        HeartbeatResponse, JobDetails, NodeResources,
+    };
+    use crate::errors::{ControlPlaneApiError, StateStoreError, SystemMonitorError};
+    use async_trait::async_trait;
+    use std::net::SocketAddr;
+
+    /// The control plane isn't reached while recovering.
+    #[derive(Clone)]
+    struct NoControlPlane;
+
+    #[async_trait]
+    impl ControlPlaneApi for NoControlPlane {
+        async fn send_heartbeat(
+            &self,
+            _node_id: Uuid,
+            _req: HeartbeatRequest,
+        ) -> Result<HeartbeatResponse, ControlPlaneApiError> {
+            unimplemented!()
+        }
+
+        async fn deregister_node(&self, _node_id: Uuid) -> Result<(), ControlPlaneApiError> {
+            unimplemented!()
+        }
+
+        async fn get_job_details(&self, _job_id: Uuid) -> Result<JobDetails, ControlPlaneApiError> {
+            unimplemented!()
+        }
+
+        async fn wait_for_exec_sessions(
+            &self,
+            _node_id: Uuid,
+        ) -> Result<Vec<ExecSession>, ControlPlaneApiError> {
+            unimplemented!()
+        }
+
+        async fn attach_exec_session(
+            &self,
+            _node_id: Uuid,
+            _session_id: Uuid,
+        ) -> Result<ExecChannel, ControlPlaneApiError> {
+            unimplemented!()
+        }
+    }
+
+    struct NoSystemMonitor;
+
+    #[async_trait]
+    impl SystemMonitor for NoSystemMonitor {
+        async fn get_node_resources(&self) -> Result<NodeResources, SystemMonitorError> {
+            unimplemented!()
+        }
+
+        async fn get_gpu_usage(&self, _pids: &[u32]) -> Result<Vec<GpuUsage>, SystemMonitorError> {
+            unimplemented!()
+        }
+    }
+
+    /// Lists the containers of `jobs`, like an executor lists those labelled
+    /// with its node. Jobs waited on exit with `exit`, or never without one.
+    #[derive(Clone, Default)]
+    struct FakeExecutor {
+        jobs: Vec<Uuid>,
+        exit: Option<JobExit>,
+        stopped: Arc<Mutex<Vec<String>>>,
+        waited: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl JobExecutor for FakeExecutor {
+        async fn run_job(
+            &self,
+            _job_details: JobDetails,
+            _resources: &NodeResources,
+            _gpus: &[Gpu],
+        ) -> Result<JobExit, JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn stop_job(&self, job_id: &str) -> Result<(), JobExecutorError> {
+            self.stopped.lock().unwrap().push(job_id.to_string());
+            Ok(())
+        }
+
+        async fn list_jobs(&self) -> Result<Vec<Uuid>, JobExecutorError> {
+            Ok(self.jobs.clone())
+        }
+
+        async fn wait_job(&self, job_id: &str) -> Result<JobExit, JobExecutorError> {
+            self.waited.lock().unwrap().push(job_id.to_string());
+            match self.exit {
+                Some(exit) => Ok(exit),
+                None => std::future::pending().await,
+            }
+        }
+
+        async fn pull_image(&self, _image: &str) -> Result<(), JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn list_images(&self) -> Result<Vec<CachedImage>, JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn remove_image(&self, _image: &CachedImage) -> Result<(), JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn get_job_usage(&self, _job_id: &str) -> Result<JobUsage, JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn get_job_pids(&self, _job_id: &str) -> Result<Vec<u32>, JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn exec(
+            &self,
+            _job_id: &str,
+            _command: Vec<String>,
+            _tty: bool,
+        ) -> Result<ExecProcess, JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn resize_exec(
+            &self,
+            _exec_id: &str,
+            _cols: u16,
+            _rows: u16,
+        ) -> Result<(), JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn get_exec_exit_code(&self, _exec_id: &str) -> Result<i64, JobExecutorError> {
+            unimplemented!()
+        }
+
+        async fn get_port_address(
+            &self,
+            _job_id: &str,
+            _port: u16,
+        ) -> Result<SocketAddr, JobExecutorError> {
+            unimplemented!()
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryStateStore {
+        state: Arc<Mutex<AgentState>>,
+    }
+
+    impl StateStore for MemoryStateStore {
+        fn load(&self) -> Result<AgentState, StateStoreError> {
+            Ok(self.state.lock().unwrap().clone())
+        }
+
+        fn save(&self, state: &AgentState) -> Result<(), StateStoreError> {
+            *self.state.lock().unwrap() = state.clone();
+            Ok(())
+        }
+    }
+
+    type TestDaemon = Daemon<NoControlPlane, NoSystemMonitor, FakeExecutor, MemoryStateStore>;
+
+    /// A daemon whose earlier run was running `job_id` on GPU 1.
+    fn daemon(job_id: Uuid, executor: FakeExecutor) -> (TestDaemon, MemoryStateStore) {
+        let state_store = MemoryStateStore::default();
+        state_store
+            .save(&AgentState {
+                current_job: Some(JobInfo {
+                    current_job_id: job_id,
+                    status: JobStatus::Running,
+                    usage: None,
+                    failure_reason: None,
+                    assigned_gpus: vec![AssignedGpu {
+                        index: 1,
+                        uuid: None,
+                    }],
+                }),
+            })
+            .unwrap();
+        let daemon = Daemon::new(
+            NoControlPlane,
+            NoSystemMonitor,
+            executor,
+            state_store.clone(),
+            Uuid::new_v4(),
+            0,
+            false,
+        );
+        (daemon, state_store)
+    }
+
+    fn gpu_allocator() -> Arc<Mutex<GpuAllocator>> {
+        let gpus = (0..2)
+            .map(|index| Gpu {
+                manufacturer: GpuManufacturer::Nvidia,
+                model: GpuModel::T4,
+                count: 1,
+                memory_mb: 16384,
+                index,
+                uuid: None,
+            })
+            .collect();
+        Arc::new(Mutex::new(GpuAllocator::new(gpus)))
+    }
+
+    fn current_status(daemon: &TestDaemon) -> Option<JobStatus> {
+        daemon
+            .current_job
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|job_info| job_info.status.clone())
+    }
+
+    #[tokio::test]
+    async fn test_recover_reattaches_to_running_container() {
+        let job_id = Uuid::new_v4();
+        let executor = FakeExecutor {
+            jobs: vec![job_id],
+            ..Default::default()
+        };
+        let (daemon, _) = daemon(job_id, executor.clone());
+        let gpu_allocator = gpu_allocator();
+
+        daemon.recover(&gpu_allocator).await;
+        tokio::task::yield_now().await;
+
+        assert_eq!(current_status(&daemon), Some(JobStatus::Running));
+        assert!(executor.stopped.lock().unwrap().is_empty());
+        assert_eq!(*executor.waited.lock().unwrap(), vec![job_id.to_string()]);
+        // The job keeps its GPU, so only the other one is free.
+        let mut gpu_allocator = gpu_allocator.lock().unwrap();
+        assert!(gpu_allocator.allocate(Uuid::new_v4(), 2).is_none());
+        let gpus = gpu_allocator.allocate(Uuid::new_v4(), 1).unwrap();
+        assert_eq!(gpus[0].index, 0);
+        drop(gpu_allocator);
+
+        let (_, handle) = daemon.job_handle.lock().unwrap().take().unwrap();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_recover_reports_container_that_exited_while_down() {
+        let job_id = Uuid::new_v4();
+        let executor = FakeExecutor {
+            jobs: vec![job_id],
+            exit: Some(JobExit {
+                exit_code: 1,
+                oom_killed: false,
+            }),
+            ..Default::default()
+        };
+        let (daemon, state_store) = daemon(job_id, executor.clone());
+        let gpu_allocator = gpu_allocator();
+
+        daemon.recover(&gpu_allocator).await;
+        let (_, handle) = daemon.job_handle.lock().unwrap().take().unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(current_status(&daemon), Some(JobStatus::Failed));
+        let saved = state_store.load().unwrap().current_job.unwrap();
+        assert_eq!(saved.status, JobStatus::Failed);
+        assert_eq!(saved.failure_reason, Some(FailureReason::Error));
+        // Its GPU is free again.
+        assert!(gpu_allocator
+            .lock()
+            .unwrap()
+            .allocate(Uuid::new_v4(), 2)
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_recover_ignores_container_of_another_node() {
+        // The job's container is labelled with another node's ID, so the
+        // executor doesn't list it. Another container of this node is of a
+        // job the agent doesn't know.
+        let job_id = Uuid::new_v4();
+        let unknown_job_id = Uuid::new_v4();
+        let executor = FakeExecutor {
+            jobs: vec![unknown_job_id],
+            ..Default::default()
+        };
+        let (daemon, state_store) = daemon(job_id, executor.clone());
+
+        daemon.recover(&gpu_allocator()).await;
+
+        assert_eq!(
+            *executor.stopped.lock().unwrap(),
+            vec![unknown_job_id.to_string()]
+        );
+        assert!(executor.waited.lock().unwrap().is_empty());
+        assert!(daemon.job_handle.lock().unwrap().is_none());
+        assert_eq!(current_status(&daemon), None);
+        assert!(state_store.load().unwrap().current_job.is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::domain::agent::models::Gpu;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Hands the node's GPUs out to jobs, so that no two jobs share a device.
+#[derive(Debug)]
+pub struct GpuAllocator {
+    free: Vec<Gpu>,
+    allocated: HashMap<Uuid, Vec<Gpu>>,
+}
+
+impl GpuAllocator {
+    pub fn new(gpus: Vec<Gpu>) -> Self {
+        Self {
+            free: gpus,
+            allocated: HashMap::new(),
+        }
+    }
+
+    /// Hands `count` free GPUs, lowest indices first, to the job. Returns
+    /// `None` if fewer are free. A job that already holds GPUs keeps them.
+    pub fn allocate(&mut self, job_id: Uuid, count: usize) -> Option<Vec<Gpu>> {
+        if let Some(gpus) = self.allocated.get(&job_id) {
+            return Some(gpus.clone());
+        }
+        if self.free.len() < count {
+            return None;
+        }
+
+        self.free.sort_by_key(|gpu| gpu.index);
+        let gpus: Vec<Gpu> = self.free.drain(..count).collect();
+        self.allocated.insert(job_id, gpus.clone());
+        Some(gpus)
+    }
+
+    /// Hands the GPUs with the given indices to the job, e.g. when the agent
+    /// reattaches to a job that was given them before it restarted.
+    pub fn claim(&mut self, job_id: Uuid, indices: &[u32]) {
+        let (claimed, free) = self
+            .free
+            .drain(..)
+            .partition(|gpu| indices.contains(&gpu.index));
+        self.free = free;
+        self.allocated.entry(job_id).or_default().extend(claimed);
+    }
+
+    /// Returns the GPUs of the job, if it holds any, to the free ones.
+    pub fn release(&mut self, job_id: Uuid) {
+        if let Some(gpus) = self.allocated.remove(&job_id) {
+            self.free.extend(gpus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::agent::models::{GpuManufacturer, GpuModel};
+
+    fn allocator(count: u32) -> GpuAllocator {
+        GpuAllocator::new(
+            (0..count)
+                .rev()
+                .map(|index| Gpu {
+                    manufacturer: GpuManufacturer::Nvidia,
+                    model: GpuModel::RadeonProV520,
+                    count: 1,
+                    memory_mb: 16384,
+                    index,
+                    uuid: None,
+                })
+                .collect(),
+        )
+    }
+
+    fn indices(gpus: &[Gpu]) -> Vec<u32> {
+        let mut indices: Vec<u32> = gpus.iter().map(|gpu| gpu.index).collect();
+        indices.sort();
+        indices
+    }
+
+    #[test]
+    fn test_allocate_lowest_indices_first() {
+        let mut allocator = allocator(4);
+        let job_id = Uuid::new_v4();
+
+        let gpus = allocator.allocate(job_id, 2).unwrap();
+
+        assert_eq!(indices(&gpus), vec![0, 1]);
+        // Asking again returns the GPUs the job already holds.
+        assert_eq!(indices(&allocator.allocate(job_id, 2).unwrap()), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_allocate_without_enough_free_gpus() {
+        let mut allocator = allocator(4);
+        allocator.allocate(Uuid::new_v4(), 3).unwrap();
+
+        assert!(allocator.allocate(Uuid::new_v4(), 2).is_none());
+        // A failed allocation doesn't take any GPUs.
+        assert_eq!(
+            indices(&allocator.allocate(Uuid::new_v4(), 1).unwrap()),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn test_released_gpus_can_be_allocated_again() {
+        let mut allocator = allocator(2);
+        let first = Uuid::new_v4();
+        allocator.allocate(first, 2).unwrap();
+        assert!(allocator.allocate(Uuid::new_v4(), 1).is_none());
+
+        allocator.release(first);
+
+        let gpus = allocator.allocate(Uuid::new_v4(), 2).unwrap();
+        assert_eq!(indices(&gpus), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_claim_of_gpus_in_use_keeps_them_with_their_holder() {
+        let mut allocator = allocator(4);
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        allocator.claim(first, &[0, 1]);
+
+        // Two reattached jobs can't both have been given GPU 1.
+        allocator.claim(second, &[1, 2]);
+
+        assert_eq!(indices(&allocator.allocated[&first]), vec![0, 1]);
+        assert_eq!(indices(&allocator.allocated[&second]), vec![2]);
+        assert_eq!(
+            indices(&allocator.allocate(Uuid::new_v4(), 1).unwrap()),
+            vec![3]
+        );
+        assert!(allocator.allocate(Uuid::new_v4(), 1).is_none());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::domain::agent::{models::CachedImage, ports::JobExecutor};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// Keeps the images of jobs on the node, so that jobs sharing an image don't
+/// pull it again, while holding the disk space they take up to a budget.
+pub struct ImageCache<J: JobExecutor> {
+    job_executor: Arc<J>,
+    budget_bytes: u64,
+    /// Normalized references of the images that must not be evicted.
+    protected: Mutex<HashSet<String>>,
+    /// The task pulling images ahead of time, if one was started.
+    prefetch: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<J: JobExecutor> ImageCache<J> {
+    pub fn new(job_executor: Arc<J>, budget_bytes: u64) -> Self {
+        Self {
+            job_executor,
+            budget_bytes,
+            protected: Mutex::new(HashSet::new()),
+            prefetch: Mutex::new(None),
+        }
+    }
+
+    /// Replaces the images that must not be evicted, e.g. those of the jobs
+    /// waiting in the control plane's queues.
+    pub fn set_protected(&self, images: &[String]) {
+        *self.protected.lock().unwrap() = images
+            .iter()
+            .map(|image| normalize_image_reference(image))
+            .collect();
+    }
+
+    /// The normalized references, by tag and digest, of the cached images.
+    /// Failures are logged and reported as an empty cache.
+    pub async fn cached_images(&self) -> Vec<String> {
+        match self.job_executor.list_images().await {
+            Ok(images) => images
+                .iter()
+                .flat_map(|image| image.tags.iter().chain(image.digests.iter()))
+                .map(|reference| normalize_image_reference(reference))
+                .collect(),
+            Err(e) => {
+                eprintln!("[IMAGES] Failed to list cached images: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Evicts the least recently used images until the cache fits in its
+    /// budget. Protected images are kept even if that leaves it over budget.
+    pub async fn enforce_budget(&self) -> Vec<CachedImage> {
+        let images = match self.job_executor.list_images().await {
+            Ok(images) => images,
+            Err(e) => {
+                eprintln!("[IMAGES] Failed to list cached images: {}", e);
+                return Vec::new();
+            }
+        };
+        let protected = self.protected.lock().unwrap().clone();
+        let evictions = select_evictions(images, &protected, self.budget_bytes);
+        self.remove_images(evictions).await
+    }
+
+    /// Removes every cached image that isn't protected.
+    pub async fn prune_all(&self) -> Vec<CachedImage> {
+        let images = match self.job_executor.list_images().await {
+            Ok(images) => images,
+            Err(e) => {
+                eprintln!("[IMAGES] Failed to list cached images: {}", e);
+                return Vec::new();
+            }
+        };
+        let protected = self.protected.lock().unwrap().clone();
+        let evictions = images
+            .into_iter()
+            .filter(|image| !is_protected(image, &protected))
+            .collect();
+        self.remove_images(evictions).await
+    }
+
+    /// Pulls the given images one after another in the background, unless
+    /// an earlier pull is still running. The images of queued jobs are
+    /// protected, so they stay cached until those jobs run.
+    pub fn start_prefetch(&self, images: Vec<String>)
+    where
+        J: 'static,
+    {
+        let mut prefetch = self.prefetch.lock().unwrap();
+        let running = prefetch
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished());
+        if images.is_empty() || running {
+            return;
+        }
+
+        let job_executor = self.job_executor.clone();
+        *prefetch = Some(tokio::spawn(async move {
+            for image in images {
+                match job_executor.pull_image(&image).await {
+                    Ok(()) => println!("[IMAGES] Pre-pulled image {}.", image),
+                    Err(e) => eprintln!("[IMAGES] Failed to pre-pull image {}: {}", image, e),
+                }
+            }
+        }));
+    }
+
+    /// Stops pulling images ahead of time, e.g. because a job was assigned
+    /// and its own image comes first.
+    pub fn stop_prefetch(&self) {
+        if let Some(handle) = self.prefetch.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+
+    /// Removes the given images and returns those that were removed. Images
+    /// still used by a container are skipped.
+    async fn remove_images(&self, images: Vec<CachedImage>) -> Vec<CachedImage> {
+        let mut removed = Vec::new();
+        for image in images {
+            match self.job_executor.remove_image(&image).await {
+                Ok(()) => {
+                    println!(
+                        "[IMAGES] Evicted image {} ({} MB).",
+                        image.tags.first().unwrap_or(&image.id),
+                        image.size_bytes / 1024 / 1024
+                    );
+                    removed.push(image);
+                }
+                Err(e) => eprintln!("[IMAGES] Failed to evict image {}: {}", image.id, e),
+            }
+        }
+        removed
+    }
+}
+
+/// Picks the images to evict for the cache to fit in `budget_bytes`, least
+/// recently used first.
+fn select_evictions(
+    mut images: Vec<CachedImage>,
+    protected: &HashSet<String>,
+    budget_bytes: u64,
+) -> Vec<CachedImage> {
+    let mut total_bytes: u64 = images.iter().map(|image| image.size_bytes).sum();
+    images.sort_by_key(|image| image.last_used_at);
+
+    let mut evictions = Vec::new();
+    for image in images {
+        if total_bytes <= budget_bytes {
+            break;
+        }
+        if is_protected(&image, protected) {
+            continue;
+        }
+        total_bytes = total_bytes.saturating_sub(image.size_bytes);
+        evictions.push(image);
+    }
+    evictions
+}
+
+fn is_protected(image: &CachedImage, protected: &HashSet<String>) -> bool {
+    image
+        .tags
+        .iter()
+        .chain(image.digests.iter())
+        .any(|reference| protected.contains(&normalize_image_reference(reference)))
+}
+
+/// Spells an image reference the way Docker lists it, so that references
+/// naming the same image compare equal: Docker Hub's registry and `library/`
+/// namespace are dropped, a missing tag means `latest`, and the tag of a
+/// reference pinned by digest is dropped. The control plane normalizes
+/// references the same way.
+pub fn normalize_image_reference(reference: &str) -> String {
+    let reference = reference.trim();
+    let reference = reference
+        .strip_prefix("docker.io/")
+        .or_else(|| reference.strip_prefix("index.docker.io/"))
+        .unwrap_or(reference);
+    let reference = reference.strip_prefix("library/").unwrap_or(reference);
+
+    let (path, name) = reference.rsplit_once('/').unwrap_or(("", reference));
+    let name = match name.split_once('@') {
+        Some((name, digest)) => {
+            let name = name.split_once(':').map_or(name, |(name, _)| name);
+            format!("{}@{}", name, digest)
+        }
+        None if name.contains(':') => name.to_string(),
+        None => format!("{}:latest", name),
+    };
+    if path.is_empty() {
+        name
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn image(tag: &str, size_gb: u64, last_used_at: u64) -> CachedImage {
+        CachedImage {
+            id: format!("sha256:{}", tag),
+            tags: vec![tag.to_string()],
+            digests: Vec::new(),
+            size_bytes: size_gb * GB,
+            last_used_at,
+        }
+    }
+
+    fn tags(images: &[CachedImage]) -> Vec<&str> {
+        images.iter().map(|image| image.tags[0].as_str()).collect()
+    }
+
+    #[test]
+    fn test_select_evictions_least_recently_used_first() {
+        let images = vec![
+            image("train:v3", 4, 300),
+            image("train:v1", 4, 100),
+            image("train:v2", 4, 200),
+        ];
+
+        let evictions = select_evictions(images, &HashSet::new(), 5 * GB);
+
+        assert_eq!(tags(&evictions), vec!["train:v1", "train:v2"]);
+    }
+
+    #[test]
+    fn test_select_evictions_within_budget() {
+        let images = vec![image("train:v1", 4, 100), image("train:v2", 4, 200)];
+
+        assert!(select_evictions(images.clone(), &HashSet::new(), 8 * GB).is_empty());
+        assert_eq!(
+            tags(&select_evictions(images, &HashSet::new(), 8 * GB - 1)),
+            vec!["train:v1"]
+        );
+    }
+
+    #[test]
+    fn test_select_evictions_keeps_protected_images() {
+        let images = vec![
+            image("train:v1", 4, 100),
+            image("train:v2", 4, 200),
+            image("train:v3", 4, 300),
+        ];
+        // Protected references are normalized, so one spelled with Docker
+        // Hub's registry still matches the image's tag.
+        let protected = HashSet::from([normalize_image_reference("docker.io/library/train:v1")]);
+
+        let evictions = select_evictions(images, &protected, 5 * GB);
+
+        assert_eq!(tags(&evictions), vec!["train:v2", "train:v3"]);
+    }
+
+    #[test]
+    fn test_select_evictions_over_budget_with_only_protected_images() {
+        let images = vec![image("train:v1", 4, 100), image("train:v2", 4, 200)];
+        let protected = HashSet::from(["train:v1".to_string(), "train:v2".to_string()]);
+
+        assert!(select_evictions(images, &protected, GB).is_empty());
+    }
+}
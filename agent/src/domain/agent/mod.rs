@@ -1,3 +1,6 @@
 pub mod daemon;
+pub mod gpus;
+pub mod image_cache;
 pub mod models;
-pub mod ports;
\ No newline at end of file
+pub mod ports;
+pub mod tunnel;
\ No newline at end of file
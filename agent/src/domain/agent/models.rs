@@ -1,14 +1,29 @@
+use crate::errors::JobExecutorError;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::pin::Pin;
+use tokio::{io::AsyncWrite, sync::mpsc};
 use uuid::Uuid;
 
 /// Represents the static hardware resources of a compute node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeResources {
     pub cpu: Cpu,
+    /// One entry per GPU of the node, ordered by index.
     pub gpus: Vec<Gpu>,
     pub memory_mb: i32,
 }
 
+impl NodeResources {
+    /// The node's GPUs as reported to the control plane: the first GPU's
+    /// model, counted once for every GPU of the node.
+    pub fn gpu_info(&self) -> Option<Gpu> {
+        let count = self.gpus.iter().map(|gpu| gpu.count).sum();
+        self.gpus.first().cloned().map(|gpu| Gpu { count, ..gpu })
+    }
+}
+
 #[derive(
     Clone, Debug, Serialize, Deserialize, PartialEq, Eq, strum::EnumString, strum::Display,
 )]
@@ -113,6 +128,27 @@ pub struct Gpu {
     pub model: GpuModel,
     pub count: i32,
     pub memory_mb: i32,
+    /// The GPU's index on the node.
+    #[serde(default)]
+    pub index: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+}
+
+/// A GPU of the node that was handed to a job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssignedGpu {
+    pub index: u32,
+    pub uuid: Option<String>,
+}
+
+impl From<&Gpu> for AssignedGpu {
+    fn from(gpu: &Gpu) -> Self {
+        Self {
+            index: gpu.index,
+            uuid: gpu.uuid.clone(),
+        }
+    }
 }
 
 
@@ -123,18 +159,101 @@ pub struct HeartbeatRequest {
     pub cpu_info: Cpu,
     pub gpu_info: Option<Gpu>,
     pub job_info: Option<JobInfo>,
+    /// The images of jobs cached on the node, by tag and digest, so that the
+    /// control plane can prefer nodes that don't have to pull a job's image.
+    pub cached_images: Vec<String>,
+    /// Whether the agent is shutting down, so that the control plane assigns
+    /// the node no new jobs.
+    pub draining: bool,
 }
 
 /// The response from a heartbeat call, which may include a job to run.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeartbeatResponse {
     pub assigned_job: Option<JobDetails>,
+    /// The images of queued jobs, which must not be evicted from the cache.
+    #[serde(default)]
+    pub protected_images: Vec<String>,
+    /// Images of jobs near the head of the queues, to pull while the node is
+    /// idle.
+    #[serde(default)]
+    pub prefetch_images: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct JobInfo {
     pub current_job_id: Uuid,
     pub status: JobStatus,
+    /// Resource usage of the job, sampled right before the heartbeat is sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<JobUsage>,
+    /// Why the job failed, sent along with the `failed` status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<FailureReason>,
+    /// The GPUs of the node the job runs on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assigned_gpus: Vec<AssignedGpu>,
+}
+
+/// Why a job failed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The job exited with a non-zero code, or could not be run at all.
+    Error,
+    /// The job was killed for using more memory than it asked for.
+    OutOfMemory,
+}
+
+/// How a job's process ended.
+#[derive(Debug, Clone, Copy)]
+pub struct JobExit {
+    pub exit_code: i64,
+    /// Whether the kernel killed the job for exceeding its memory limit.
+    pub oom_killed: bool,
+}
+
+/// An image pulled for a job that is still on this node.
+#[derive(Debug, Clone)]
+pub struct CachedImage {
+    pub id: String,
+    /// The references the image is tagged with, e.g. `pytorch/pytorch:2.1.0`.
+    pub tags: Vec<String>,
+    /// The references pinning the image by digest, e.g.
+    /// `pytorch/pytorch@sha256:...`.
+    pub digests: Vec<String>,
+    pub size_bytes: u64,
+    /// When a job last used the image, in seconds since the Unix epoch.
+    pub last_used_at: u64,
+}
+
+/// What the agent remembers across restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentState {
+    /// The job the agent is running, or ran and has yet to see the control
+    /// plane acknowledge as finished.
+    pub current_job: Option<JobInfo>,
+}
+
+/// A point-in-time sample of the resources a running job is consuming.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobUsage {
+    pub cpu_millicores: i32,
+    pub memory_rss_mb: i32,
+    pub memory_peak_mb: i32,
+    pub network_rx_bytes: i64,
+    pub network_tx_bytes: i64,
+    pub block_read_bytes: i64,
+    pub block_write_bytes: i64,
+    pub gpus: Vec<GpuUsage>,
+}
+
+/// Usage of a single GPU by the processes of a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuUsage {
+    pub index: i32,
+    pub utilization_percent: i32,
+    pub memory_used_mb: i32,
 }
 
 /// The full details of a job, fetched by the agent when assigned.
@@ -142,6 +261,122 @@ pub struct JobInfo {
 pub struct JobDetails {
     pub id: Uuid,
     pub docker_uri: String,
+    /// Environment variables to set in the job's container.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Ports of services in the job's container that users can tunnel to.
+    #[serde(default)]
+    pub exposed_ports: Vec<u16>,
+    /// How long the job has to exit after it is sent SIGTERM, before it is
+    /// killed.
+    #[serde(default = "JobDetails::default_termination_grace_period_secs")]
+    pub termination_grace_period_secs: i64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub queue_id: Option<Uuid>,
+    #[serde(default)]
+    pub cluster_id: Option<Uuid>,
+    #[serde(default)]
+    pub resource_requirements: JobResourceRequirements,
+    /// 1 for the job's first run, counting up each time it is re-queued.
+    #[serde(default = "JobDetails::default_attempt")]
+    pub attempt: i32,
+    /// Lets the job's code call back into the control plane as the job.
+    #[serde(default)]
+    pub job_token: Option<String>,
+}
+
+impl JobDetails {
+    fn default_termination_grace_period_secs() -> i64 {
+        30
+    }
+
+    fn default_attempt() -> i32 {
+        1
+    }
+}
+
+/// The resources a job asked for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobResourceRequirements {
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    #[serde(default)]
+    pub gpus: Option<RequestedGpus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestedGpus {
+    pub count: i32,
+}
+
+impl JobResourceRequirements {
+    pub fn gpu_count(&self) -> usize {
+        self.gpus.as_ref().map_or(0, |gpus| gpus.count.max(0) as usize)
+    }
+}
+
+/// What a job's container is told about the job and where it runs, both as
+/// `LILAC_*` environment variables and as JSON in the file named by
+/// `LILAC_METADATA_FILE`. Jobs rely on these names, so they must not change.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobMetadata {
+    pub job_id: Uuid,
+    pub job_name: String,
+    pub attempt: i32,
+    pub queue_id: Option<Uuid>,
+    pub cluster_id: Option<Uuid>,
+    pub node_id: Uuid,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    /// Indices of the node's GPUs the job's container can use.
+    pub gpu_indices: Vec<u32>,
+    pub api_endpoint: String,
+    pub job_token: Option<String>,
+}
+
+impl JobMetadata {
+    pub fn new(job: &JobDetails, gpus: &[Gpu], node_id: Uuid, api_endpoint: &str) -> Self {
+        Self {
+            job_id: job.id,
+            job_name: job.name.clone(),
+            attempt: job.attempt,
+            queue_id: job.queue_id,
+            cluster_id: job.cluster_id,
+            node_id,
+            cpu_millicores: job.resource_requirements.cpu_millicores,
+            memory_mb: job.resource_requirements.memory_mb,
+            gpu_indices: gpus.iter().map(|gpu| gpu.index).collect(),
+            api_endpoint: api_endpoint.to_string(),
+            job_token: job.job_token.clone(),
+        }
+    }
+
+    /// The metadata as environment variables. Unknown values are left out.
+    pub fn env(&self) -> Vec<(String, String)> {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let env = [
+            ("LILAC_JOB_ID", self.job_id.to_string()),
+            ("LILAC_JOB_NAME", self.job_name.clone()),
+            ("LILAC_ATTEMPT", self.attempt.to_string()),
+            ("LILAC_QUEUE_ID", optional(self.queue_id.map(|id| id.to_string()))),
+            ("LILAC_CLUSTER_ID", optional(self.cluster_id.map(|id| id.to_string()))),
+            ("LILAC_NODE_ID", self.node_id.to_string()),
+            ("LILAC_CPU_MILLICORES", self.cpu_millicores.to_string()),
+            ("LILAC_MEMORY_MB", self.memory_mb.to_string()),
+            (
+                "LILAC_GPU_INDICES",
+                self.gpu_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(","),
+            ),
+            ("LILAC_API_ENDPOINT", self.api_endpoint.clone()),
+            ("LILAC_JOB_TOKEN", optional(self.job_token.clone())),
+        ];
+        env.into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
 }
 
 /// The status of a job, reported by the agent.
@@ -153,4 +388,64 @@ pub enum JobStatus {
     Running,
     Succeeded,
     Failed,
-}
\ No newline at end of file
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed)
+    }
+}
+
+/// An interactive session a user opened in the container of this node's job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecSession {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub command: Vec<String>,
+    pub tty: bool,
+    /// If set, the session tunnels a TCP connection to this port of the job
+    /// instead of running `command`.
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+/// A control message of an exec session, sent as a JSON text frame. Terminal
+/// input and output are sent as binary frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ExecControl {
+    Resize { cols: u16, rows: u16 },
+    Eof,
+    Exit { code: i64 },
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecMessage {
+    Data(Vec<u8>),
+    Control(ExecControl),
+}
+
+/// One end of an exec session. The session ends once either end is dropped.
+#[derive(Debug)]
+pub struct ExecChannel {
+    pub tx: mpsc::Sender<ExecMessage>,
+    pub rx: mpsc::Receiver<ExecMessage>,
+}
+
+impl ExecChannel {
+    /// Creates the two connected ends of a session.
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, a_rx) = mpsc::channel(64);
+        let (b_tx, b_rx) = mpsc::channel(64);
+        (Self { tx: a_tx, rx: b_rx }, Self { tx: b_tx, rx: a_rx })
+    }
+}
+
+/// A command running in a job's container.
+pub struct ExecProcess {
+    pub id: String,
+    /// The command's output. With a TTY, stdout and stderr are interleaved.
+    pub output: Pin<Box<dyn Stream<Item = Result<Vec<u8>, JobExecutorError>> + Send>>,
+    pub input: Pin<Box<dyn AsyncWrite + Send>>,
+}
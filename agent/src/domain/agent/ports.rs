@@ -1,8 +1,12 @@
 use crate::{
-    domain::agent::models::{HeartbeatRequest, HeartbeatResponse, JobDetails, NodeResources},
-    errors::{ControlPlaneApiError, JobExecutorError, SystemMonitorError},
+    domain::agent::models::{
+        AgentState, CachedImage, ExecChannel, ExecProcess, ExecSession, Gpu, GpuUsage, HeartbeatRequest,
+        HeartbeatResponse, JobDetails, JobExit, JobUsage, NodeResources,
+    },
+    errors::{ControlPlaneApiError, JobExecutorError, StateStoreError, SystemMonitorError},
 };
 use async_trait::async_trait;
+use std::net::SocketAddr;
 use uuid::Uuid;
 
 /// Port for interacting with the Lilac control plane API. update to do proper error handling
@@ -16,8 +20,26 @@ pub trait ControlPlaneApi: Send + Sync {
         req: HeartbeatRequest,
     ) -> Result<HeartbeatResponse, ControlPlaneApiError>;
 
+    /// Removes this node from the control plane, which re-queues any job it
+    /// was assigned that hasn't finished.
+    async fn deregister_node(&self, node_id: Uuid) -> Result<(), ControlPlaneApiError>;
+
     /// Fetches the full details for an assigned job.
     async fn get_job_details(&self, job_id: Uuid) -> Result<JobDetails, ControlPlaneApiError>;
+
+    /// Waits for users to open exec sessions on this node. Returns an empty
+    /// list if none were opened before the control plane's poll timeout.
+    async fn wait_for_exec_sessions(
+        &self,
+        node_id: Uuid,
+    ) -> Result<Vec<ExecSession>, ControlPlaneApiError>;
+
+    /// Connects to an exec session, relaying it to the user who opened it.
+    async fn attach_exec_session(
+        &self,
+        node_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<ExecChannel, ControlPlaneApiError>;
 }
 
 /// Port for monitoring the local system's hardware resources.
@@ -25,16 +47,78 @@ pub trait ControlPlaneApi: Send + Sync {
 pub trait SystemMonitor: Send + Sync {
     /// Gathers information about the system's CPU, memory, and GPUs.
     async fn get_node_resources(&self) -> Result<NodeResources, SystemMonitorError>;
+
+    /// Reports per-GPU utilization and memory of the given host processes.
+    async fn get_gpu_usage(&self, pids: &[u32]) -> Result<Vec<GpuUsage>, SystemMonitorError>;
 }
 
 /// Port for executing jobs, typically in a containerized environment.
 #[async_trait]
 pub trait JobExecutor: Send + Sync {
-    /// Runs the specified job and returns the exit code.
+    /// Runs the specified job on the given GPUs of the node and returns how
+    /// it exited. The job must not be able to use any other GPU.
     async fn run_job(
         &self,
         job_details: JobDetails,
         resources: &NodeResources,
-    ) -> Result<i64, JobExecutorError>;
+        gpus: &[Gpu],
+    ) -> Result<JobExit, JobExecutorError>;
     async fn stop_job(&self, job_id: &str) -> Result<(), JobExecutorError>;
-}
\ No newline at end of file
+
+    /// Lists the jobs that have a container on this node, whether it is
+    /// still running or not.
+    async fn list_jobs(&self) -> Result<Vec<Uuid>, JobExecutorError>;
+
+    /// Waits for a job started by an earlier run of the agent to exit, and
+    /// cleans up after it like [JobExecutor::run_job] does.
+    async fn wait_job(&self, job_id: &str) -> Result<JobExit, JobExecutorError>;
+
+    /// Pulls an image for a job that is yet to run on this node, so that it
+    /// can be listed by [JobExecutor::list_images].
+    async fn pull_image(&self, image: &str) -> Result<(), JobExecutorError>;
+
+    /// Lists the images pulled for jobs that are still on this node. Other
+    /// images of the node are left alone.
+    async fn list_images(&self) -> Result<Vec<CachedImage>, JobExecutorError>;
+
+    /// Removes an image listed by [JobExecutor::list_images]. Fails if a
+    /// container still uses it.
+    async fn remove_image(&self, image: &CachedImage) -> Result<(), JobExecutorError>;
+
+    /// Samples the CPU, memory, network and block IO usage of a running job.
+    /// GPU usage is left empty; see [SystemMonitor::get_gpu_usage].
+    async fn get_job_usage(&self, job_id: &str) -> Result<JobUsage, JobExecutorError>;
+
+    /// Lists the host process IDs belonging to a running job.
+    async fn get_job_pids(&self, job_id: &str) -> Result<Vec<u32>, JobExecutorError>;
+
+    /// Starts a command in a running job's container, attached to its input
+    /// and output.
+    async fn exec(
+        &self,
+        job_id: &str,
+        command: Vec<String>,
+        tty: bool,
+    ) -> Result<ExecProcess, JobExecutorError>;
+
+    /// Resizes the TTY of a command started with [JobExecutor::exec].
+    async fn resize_exec(&self, exec_id: &str, cols: u16, rows: u16)
+        -> Result<(), JobExecutorError>;
+
+    /// Returns the exit code of a command started with [JobExecutor::exec]
+    /// once its output has ended.
+    async fn get_exec_exit_code(&self, exec_id: &str) -> Result<i64, JobExecutorError>;
+
+    /// Returns the address on this node at which one of a running job's
+    /// exposed ports can be reached.
+    async fn get_port_address(&self, job_id: &str, port: u16)
+        -> Result<SocketAddr, JobExecutorError>;
+}
+
+/// Port for keeping the agent's state across restarts. It is saved on every
+/// change, so unlike the other ports it is synchronous.
+pub trait StateStore: Send + Sync {
+    /// Loads the saved state, or the default state if none was saved yet.
+    fn load(&self) -> Result<AgentState, StateStoreError>;
+    fn save(&self, state: &AgentState) -> Result<(), StateStoreError>;
+}
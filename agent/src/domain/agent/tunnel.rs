@@ -0,0 +1,48 @@
+use crate::domain::agent::models::{ExecChannel, ExecControl, ExecMessage};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Relays a connection over a tunnel session until both directions are
+/// closed or either end fails. Each direction is closed with an `Eof`
+/// control message, so that half-closed connections keep working.
+pub async fn relay<S>(stream: S, mut channel: ExecChannel) -> Result<(), anyhow::Error>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut buf = vec![0; BUFFER_SIZE];
+    let mut sending = true;
+    let mut receiving = true;
+
+    while sending || receiving {
+        tokio::select! {
+            read = reader.read(&mut buf), if sending => {
+                let message = match read? {
+                    0 => {
+                        sending = false;
+                        ExecMessage::Control(ExecControl::Eof)
+                    }
+                    n => ExecMessage::Data(buf[..n].to_vec()),
+                };
+                if channel.tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+            message = channel.rx.recv(), if receiving => match message {
+                Some(ExecMessage::Data(data)) => writer.write_all(&data).await?,
+                Some(ExecMessage::Control(ExecControl::Eof)) => {
+                    receiving = false;
+                    writer.shutdown().await?;
+                }
+                Some(ExecMessage::Control(ExecControl::Error { message })) => {
+                    return Err(anyhow::anyhow!(message));
+                }
+                Some(ExecMessage::Control(_)) => {}
+                None => break,
+            },
+        }
+    }
+
+    Ok(())
+}
@@ -70,6 +70,18 @@ pub enum JobExecutorError {
     Unknown(#[from] anyhow::Error),
 }
 
+#[derive(Debug, Error)]
+pub enum StateStoreError {
+    #[error("could not read agent state")]
+    ReadFile,
+    #[error("could not parse agent state")]
+    Parse,
+    #[error("could not write agent state")]
+    WriteFile,
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
 #[derive(Debug, Error)]
 pub enum UserApiError {
     #[error("unauthorized")]
@@ -11,6 +11,14 @@ pub struct Cli {
 pub enum Commands {
     /// Submit a new training job
     Submit(SubmitArgs),
+    /// Queue a copy of a finished training job
+    Resubmit(ResubmitArgs),
+    /// Run a command in a running training job's container
+    Exec(ExecArgs),
+    /// Forward local ports to services in a running training job
+    PortForward(PortForwardArgs),
+    /// Start a hyperparameter sweep described in a JSON or TOML file
+    Sweep(SweepArgs),
     /// Configure the Lilac CLI for submitting jobs
     Configure,
     /// Commands for the Lilac agent daemon
@@ -37,11 +45,76 @@ pub struct SubmitArgs {
     /// Number of GPUs required
     #[arg(long)]
     pub gpu_count: Option<i32>,
+    /// Label to attach to the job as key=value, e.g. project=vision. Can be repeated
+    #[arg(long = "label", value_parser = parse_key_value)]
+    pub labels: Vec<(String, String)>,
+    /// Annotation to attach to the job as key=value. Can be repeated
+    #[arg(long = "annotation", value_parser = parse_key_value)]
+    pub annotations: Vec<(String, String)>,
+    /// Environment variable to set in the job's container as NAME=value. Can be repeated
+    #[arg(long = "env", value_parser = parse_key_value)]
+    pub env: Vec<(String, String)>,
+    /// Port of a service in the job, such as TensorBoard, to make reachable
+    /// with `lilac port-forward`. Can be repeated
+    #[arg(long = "expose")]
+    pub exposed_ports: Vec<u16>,
+    /// Run an interactive session, such as a Jupyter notebook, instead of a
+    /// batch job. Its URL is printed once the job is submitted
+    #[arg(long, action, conflicts_with = "template")]
+    pub session: bool,
+    /// Port the session's server listens on. Defaults to 8888
+    #[arg(long, requires = "session")]
+    pub session_port: Option<u16>,
+    /// Minutes the session may go unused before it is stopped. Defaults to 60
+    #[arg(long, requires = "session")]
+    pub idle_timeout: Option<i32>,
+    /// Seconds the job has to save a checkpoint and exit after it is asked to
+    /// stop, e.g. when it is re-queued. Defaults to 30
+    #[arg(long, conflicts_with = "template")]
+    pub grace_period: Option<i32>,
+    /// ID of a job template to submit. Any other arguments given override the template
+    #[arg(long)]
+    pub template: Option<String>,
     /// Skip interactive prompts and submit directly
     #[arg(long, action)]
     pub non_interactive: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct ResubmitArgs {
+    /// ID of the finished job to resubmit
+    pub job_id: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ExecArgs {
+    /// ID of the running job
+    pub job_id: String,
+    /// Don't allocate a TTY, e.g. when piping input into the command
+    #[arg(long, short = 'T', action)]
+    pub no_tty: bool,
+    /// Command to run, given after `--`, e.g. `lilac exec <job-id> -- bash`
+    #[arg(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct PortForwardArgs {
+    /// ID of the running job
+    pub job_id: String,
+    /// Ports to forward as LOCAL:REMOTE, e.g. 8080:6006, or just PORT to use
+    /// the same port locally
+    #[arg(required = true, value_parser = parse_port_mapping)]
+    pub ports: Vec<(u16, u16)>,
+}
+
+#[derive(Args, Debug)]
+pub struct SweepArgs {
+    /// Path to the sweep's spec. Files ending in `.toml` are read as TOML,
+    /// anything else as JSON
+    pub file: std::path::PathBuf,
+}
+
 #[derive(Args)]
 pub struct AgentArgs {
     #[command(subcommand)]
@@ -54,4 +127,34 @@ pub enum AgentCommands {
     Start,
     /// Configure the Lilac agent
     Configure,
-}
\ No newline at end of file
+    /// Remove cached job images until the cache fits in its disk budget
+    PruneImages(PruneImagesArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PruneImagesArgs {
+    /// Remove every cached image that no container uses, regardless of the
+    /// budget
+    #[arg(long, action)]
+    pub all: bool,
+}
+
+fn parse_port_mapping(s: &str) -> Result<(u16, u16), String> {
+    let parse = |port: &str| {
+        port.parse::<u16>()
+            .ok()
+            .filter(|port| *port != 0)
+            .ok_or_else(|| format!("invalid port '{}'", port))
+    };
+    match s.split_once(':') {
+        Some((local, remote)) => Ok((parse(local)?, parse(remote)?)),
+        None => parse(s).map(|port| (port, port)),
+    }
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{}'", s))?;
+    Ok((key.to_string(), value.to_string()))
+}
@@ -1,35 +1,168 @@
 use crate::{
-    config,
-    domain::agent::daemon::Daemon,
+    config::{self, ExecutorKind, ShutdownMode},
+    domain::agent::{
+        daemon::Daemon,
+        image_cache::ImageCache,
+        models::{ExecChannel, ExecControl, ExecMessage},
+        ports::{JobExecutor, SystemMonitor},
+        tunnel,
+    },
     errors::CliError,
-    inbound::cli::SubmitArgs,
+    errors::UserApiError,
+    inbound::cli::{
+        ExecArgs, PortForwardArgs, PruneImagesArgs, ResubmitArgs, SubmitArgs, SweepArgs,
+    },
     outbound,
-    outbound::user_api::{ApiClient, GpuRequirement, ResourceRequirements, SubmitJobRequest},
+    outbound::user_api::{
+        ApiClient, GpuRequirement, JobKind, ResourceRequirements, SessionRequest,
+        SubmitFromTemplateRequest, SubmitJobRequest, SubmitJobResponse,
+    },
 };
+use crossterm::terminal;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 use std::fs;
+use std::io::{IsTerminal, Read, Write};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
 
 pub async fn start_agent(config: config::AgentConfig) -> Result<(), CliError> {
     println!("Initializing Lilac agent...");
 
-    // 1. Initialize all the adapters.
+    match config.executor {
+        ExecutorKind::Docker => {
+            let docker_executor = outbound::docker::DockerExecutor::new(config.clone())
+                .await
+                .map_err(|e| CliError::Unknown(e.into()))?;
+            run_daemon(
+                config,
+                outbound::system::HybridMonitor::new(),
+                docker_executor,
+            )
+            .await
+        }
+        ExecutorKind::Process => {
+            let process_executor = outbound::process::ProcessExecutor::new(config.clone())
+                .map_err(|e| CliError::Unknown(e.into()))?;
+            run_daemon(
+                config,
+                outbound::system::HybridMonitor::new(),
+                process_executor,
+            )
+            .await
+        }
+        ExecutorKind::Kubernetes => {
+            let kubernetes_executor = outbound::kubernetes::KubernetesExecutor::new(config.clone())
+                .map_err(|e| CliError::Unknown(e.into()))?;
+            let kubernetes = config.kubernetes.clone().unwrap_or_default();
+            let system_monitor = outbound::system::StaticMonitor::new(
+                kubernetes.cpu_millicores,
+                kubernetes.memory_mb,
+                kubernetes.gpu_count,
+                kubernetes.gpu_model,
+            );
+            run_daemon(config, system_monitor, kubernetes_executor).await
+        }
+        ExecutorKind::Slurm => {
+            let slurm_executor = outbound::slurm::SlurmExecutor::new(config.clone())
+                .map_err(|e| CliError::Unknown(e.into()))?;
+            let slurm = config.slurm.clone().unwrap_or_default();
+            let system_monitor = outbound::system::StaticMonitor::new(
+                slurm.cpu_millicores,
+                slurm.memory_mb,
+                slurm.gpu_count,
+                slurm.gpu_model,
+            );
+            run_daemon(config, system_monitor, slurm_executor).await
+        }
+    }
+}
+
+async fn run_daemon<S, J>(
+    config: config::AgentConfig,
+    system_monitor: S,
+    job_executor: J,
+) -> Result<(), CliError>
+where
+    S: SystemMonitor,
+    J: JobExecutor + Clone + 'static,
+{
+    // 1. Initialize the remaining adapters.
     let control_plane_client = outbound::control_plane::ControlPlaneClient::new(config.clone());
-    let system_monitor = outbound::system::HybridMonitor::new();
-    let docker_executor = outbound::docker::DockerExecutor::new(config.clone())
-        .map_err(|e| CliError::Unknown(e.into()))?;
+    let state_store =
+        outbound::state::FileStateStore::new().map_err(|e| CliError::Unknown(e.into()))?;
 
     // 2. Initialize and run the daemon.
     let daemon = Daemon::new(
         control_plane_client,
         system_monitor,
-        docker_executor,
+        job_executor,
+        state_store,
         config.node_id,
+        config.image_cache_budget_bytes(),
+        config.shutdown_mode == ShutdownMode::Wait,
     );
 
-    daemon
-        .run()
+    let (shutdown_tx, shutdown_rx) = mpsc::unbounded_channel();
+    tokio::spawn(forward_shutdown_signals(shutdown_tx));
+
+    daemon.run(shutdown_rx).await.map_err(CliError::Unknown)?;
+    Ok(())
+}
+
+/// Asks the daemon to shut down each time the agent receives SIGTERM or
+/// SIGINT.
+async fn forward_shutdown_signals(shutdown_tx: mpsc::UnboundedSender<()>) {
+    let (mut sigterm, mut sigint) = match (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::interrupt()),
+    ) {
+        (Ok(sigterm), Ok(sigint)) => (sigterm, sigint),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("[DAEMON] Failed to listen for shutdown signals: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = sigterm.recv() => {},
+            _ = sigint.recv() => {},
+        }
+        if shutdown_tx.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+pub async fn prune_images(
+    config: config::AgentConfig,
+    args: &PruneImagesArgs,
+) -> Result<(), CliError> {
+    if config.executor != ExecutorKind::Docker {
+        println!("The {} executor doesn't cache images.", config.executor);
+        return Ok(());
+    }
+    let docker_executor = outbound::docker::DockerExecutor::new(config.clone())
         .await
         .map_err(|e| CliError::Unknown(e.into()))?;
+    let image_cache = ImageCache::new(Arc::new(docker_executor), config.image_cache_budget_bytes());
+
+    let removed = if args.all {
+        image_cache.prune_all().await
+    } else {
+        image_cache.enforce_budget().await
+    };
+    let freed_mb: u64 = removed
+        .iter()
+        .map(|image| image.size_bytes / 1024 / 1024)
+        .sum();
+    println!(
+        "✅ Removed {} cached images, freeing {} MB.",
+        removed.len(),
+        freed_mb
+    );
     Ok(())
 }
 
@@ -55,10 +188,8 @@ pub async fn configure_user(config: config::UserConfig) -> Result<(), CliError>
         },
     };
 
-    let toml_string =
-        toml::to_string(&new_config).map_err(|e| CliError::Unknown(e.into()))?;
-    let config_path =
-        config::get_config_path("config.toml")?;
+    let toml_string = toml::to_string(&new_config).map_err(|e| CliError::Unknown(e.into()))?;
+    let config_path = config::get_config_path("config.toml")?;
     fs::create_dir_all(config_path.parent().unwrap())?;
     fs::write(config_path, toml_string)?;
 
@@ -84,6 +215,13 @@ pub async fn configure_agent(config: config::AgentConfig) -> Result<(), CliError
         cluster_api_key,
         node_id: config.node_id,
         private_registry: None,
+        checkpoint_root: config.checkpoint_root,
+        image_cache_budget_gb: config.image_cache_budget_gb,
+        executor: config.executor,
+        container_socket: config.container_socket,
+        kubernetes: config.kubernetes,
+        slurm: config.slurm,
+        shutdown_mode: config.shutdown_mode,
     };
 
     if Confirm::with_theme(&theme)
@@ -108,10 +246,8 @@ pub async fn configure_agent(config: config::AgentConfig) -> Result<(), CliError
         });
     }
 
-    let toml_string =
-        toml::to_string(&new_config).map_err(|e| CliError::Unknown(e.into()))?;
-    let config_path =
-        config::get_config_path("agent.toml")?;
+    let toml_string = toml::to_string(&new_config).map_err(|e| CliError::Unknown(e.into()))?;
+    let config_path = config::get_config_path("agent.toml")?;
     fs::create_dir_all(config_path.parent().unwrap())?;
     fs::write(config_path, toml_string)?;
 
@@ -120,6 +256,10 @@ pub async fn configure_agent(config: config::AgentConfig) -> Result<(), CliError
 }
 
 pub async fn submit_job(config: config::UserConfig, args: &SubmitArgs) -> Result<(), CliError> {
+    if let Some(template_id) = &args.template {
+        return submit_from_template(config, args, template_id).await;
+    }
+
     if args.non_interactive
         && (args.name.is_none()
             || args.docker_uri.is_none()
@@ -153,7 +293,7 @@ pub async fn submit_job(config: config::UserConfig, args: &SubmitArgs) -> Result
     let selected_queue = if let Some(queue_id) = &args.queue_id {
         queues
             .iter()
-            .find(|q| q.id.to_string() == *queue_id)
+            .find(|q| q.id == *queue_id)
             .ok_or_else(|| CliError::InvalidArguments)?
             .clone()
     } else {
@@ -184,53 +324,53 @@ pub async fn submit_job(config: config::UserConfig, args: &SubmitArgs) -> Result
 
     let mut gpu_count: Option<i32> = args.gpu_count;
 
-    if !args.non_interactive && gpu_count.is_none() {
-        if Confirm::with_theme(&theme)
+    if !args.non_interactive
+        && gpu_count.is_none()
+        && Confirm::with_theme(&theme)
             .with_prompt("Do you require GPUs?")
             .default(false)
             .interact()?
-        {
-            let count: i32 = Input::with_theme(&theme)
-                .with_prompt("How many GPUs?")
-                .interact_text()?;
-            gpu_count = Some(count);
-        }
+    {
+        let count: i32 = Input::with_theme(&theme)
+            .with_prompt("How many GPUs?")
+            .interact_text()?;
+        gpu_count = Some(count);
     }
 
     println!("\nJob Summary:");
     println!("- Name: {}", name);
     println!("- Docker Image: {}", docker_uri);
-    println!(
-        "- Queue: {} ({})",
-        selected_queue.name, selected_queue.id
-    );
+    println!("- Queue: {} ({})", selected_queue.name, selected_queue.id);
     println!("- CPU: {}m", requested_cpu);
     println!("- Memory: {}MB", requested_memory);
     if let Some(count) = gpu_count {
         println!("- GPUs: {} x any", count);
     }
+    if !args.labels.is_empty() {
+        let labels: Vec<String> = args
+            .labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        println!("- Labels: {}", labels.join(", "));
+    }
 
-    if !args.non_interactive {
-        if !Confirm::with_theme(&theme)
+    if !args.non_interactive
+        && !Confirm::with_theme(&theme)
             .with_prompt("Proceed with job submission?")
             .default(true)
             .interact()?
-        {
-            println!("Submission cancelled.");
-            return Ok(());
-        }
+    {
+        println!("Submission cancelled.");
+        return Ok(());
     }
 
     println!("\n📨 Submitting job to the Lilac scheduler...");
-    let gpus = if let Some(count) = gpu_count {
-        Some(GpuRequirement {
-            count,
-            model: None,
-            memory_gb: None,
-        })
-    } else {
-        None
-    };
+    let gpus = gpu_count.map(|count| GpuRequirement {
+        count,
+        model: None,
+        memory_gb: None,
+    });
 
     let request = SubmitJobRequest {
         name,
@@ -241,14 +381,91 @@ pub async fn submit_job(config: config::UserConfig, args: &SubmitArgs) -> Result
             memory_mb: requested_memory,
             gpus,
         },
+        labels: args.labels.iter().cloned().collect(),
+        annotations: args.annotations.iter().cloned().collect(),
+        env: args.env.iter().cloned().collect(),
+        exposed_ports: args.exposed_ports.clone(),
+        kind: if args.session {
+            JobKind::Interactive
+        } else {
+            JobKind::Batch
+        },
+        session: args.session.then_some(SessionRequest {
+            port: args.session_port,
+            idle_timeout_minutes: args.idle_timeout,
+        }),
+        termination_grace_period_secs: args.grace_period,
+    };
+
+    report_submission(client.submit_job(request).await);
+    Ok(())
+}
+
+async fn submit_from_template(
+    config: config::UserConfig,
+    args: &SubmitArgs,
+    template_id: &str,
+) -> Result<(), CliError> {
+    // Resource requirements replace the template's as a whole, so a partial
+    // override would silently drop the rest.
+    let resource_requirements = match (args.cpu, args.memory) {
+        (Some(cpu_millicores), Some(memory_mb)) => Some(ResourceRequirements {
+            cpu_millicores,
+            memory_mb,
+            gpus: args.gpu_count.map(|count| GpuRequirement {
+                count,
+                model: None,
+                memory_gb: None,
+            }),
+        }),
+        (None, None) if args.gpu_count.is_none() => None,
+        _ => {
+            eprintln!(
+                "❌ --cpu and --memory must be given together to override a template's resources."
+            );
+            return Err(CliError::InvalidArguments);
+        }
+    };
+
+    let request = SubmitFromTemplateRequest {
+        name: args.name.clone(),
+        definition: args.docker_uri.clone(),
+        queue_id: args.queue_id.clone(),
+        resource_requirements,
+        env: args.env.iter().cloned().collect(),
+        labels: args.labels.iter().cloned().collect(),
+        annotations: args.annotations.iter().cloned().collect(),
+        exposed_ports: args.exposed_ports.clone(),
     };
 
-    match client.submit_job(request).await {
+    println!("📨 Submitting job from template {}...", template_id);
+    let client = ApiClient::new(config);
+    report_submission(client.submit_from_template(template_id, request).await);
+    Ok(())
+}
+
+pub async fn resubmit_job(config: config::UserConfig, args: &ResubmitArgs) -> Result<(), CliError> {
+    println!("📨 Resubmitting job {}...", args.job_id);
+    let client = ApiClient::new(config);
+    report_submission(client.resubmit_job(&args.job_id).await);
+    Ok(())
+}
+
+fn report_submission(result: Result<SubmitJobResponse, UserApiError>) {
+    match result {
         Ok(response) => {
             println!(
                 "      ✅ Job submitted successfully! Job ID: {}",
                 response.id
             );
+            for warning in &response.warnings {
+                println!("      ⚠️  {}", warning);
+            }
+            if let Some(session_url) = &response.session_url {
+                println!("      🔗 Once the session is running, open it at:");
+                println!("         {}", session_url);
+                println!("         Keep this URL private: it lets anyone with it use the session.");
+            }
         }
         Err(e) => {
             eprintln!("\n❌ Error submitting job: {}", e);
@@ -259,5 +476,182 @@ pub async fn submit_job(config: config::UserConfig, args: &SubmitArgs) -> Result
             eprintln!("  - Have you configured the correct API key with `lilac configure`?");
         }
     }
+}
+
+pub async fn create_sweep(config: config::UserConfig, args: &SweepArgs) -> Result<(), CliError> {
+    let contents = fs::read_to_string(&args.file)?;
+    let is_toml = args.file.extension().is_some_and(|ext| ext == "toml");
+    let spec: serde_json::Value = if is_toml {
+        toml::from_str(&contents).map_err(|e| anyhow::anyhow!("invalid sweep spec: {}", e))?
+    } else {
+        serde_json::from_str(&contents).map_err(|e| anyhow::anyhow!("invalid sweep spec: {}", e))?
+    };
+
+    println!("📨 Creating sweep from {}...", args.file.display());
+    let client = ApiClient::new(config);
+    match client.create_sweep(&spec).await {
+        Ok(response) => {
+            println!(
+                "      ✅ Sweep created successfully! Sweep ID: {}",
+                response.id
+            );
+            for trial in &response.trials {
+                println!(
+                    "      🧪 Trial {} submitted as job {}",
+                    trial.number, trial.job_id
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("\n❌ Error creating sweep: {}", e);
+            eprintln!("\nPlease check the following:");
+            eprintln!(
+                "  - Is the Lilac server running and reachable at the configured API endpoint?"
+            );
+            eprintln!("  - Have you configured the correct API key with `lilac configure`?");
+        }
+    }
+    Ok(())
+}
+
+pub async fn exec_in_job(config: config::UserConfig, args: &ExecArgs) -> Result<(), CliError> {
+    let tty = !args.no_tty && std::io::stdin().is_terminal();
+    let client = ApiClient::new(config);
+    let channel = match client.exec(&args.job_id, &args.command, tty).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            eprintln!("❌ Error connecting to job {}: {}", args.job_id, e);
+            std::process::exit(1);
+        }
+    };
+
+    if tty {
+        let (cols, rows) = terminal::size()?;
+        let _ = channel
+            .tx
+            .send(ExecMessage::Control(ExecControl::Resize { cols, rows }))
+            .await;
+        terminal::enable_raw_mode()?;
+    }
+    let outcome = relay_exec(channel, tty).await;
+    if tty {
+        let _ = terminal::disable_raw_mode();
+    }
+
+    match outcome? {
+        Ok(0) => Ok(()),
+        Ok(code) => std::process::exit(code as i32),
+        Err(message) => {
+            eprintln!("❌ {}", message);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Relays the terminal to an exec session. Returns the command's exit code,
+/// or the reason the session ended without one.
+async fn relay_exec(mut channel: ExecChannel, tty: bool) -> Result<Result<i64, String>, CliError> {
+    // Stdin is read on a plain thread, since a blocked tokio stdin read would
+    // keep the runtime from shutting down once the command exits.
+    let input = channel.tx.clone();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
+        let mut buf = [0u8; 4096];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if input
+                        .blocking_send(ExecMessage::Data(buf[..n].to_vec()))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = input.blocking_send(ExecMessage::Control(ExecControl::Eof));
+    });
+
+    #[cfg(unix)]
+    if tty {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut window_changes = signal(SignalKind::window_change())?;
+        let resizes = channel.tx.clone();
+        tokio::spawn(async move {
+            while window_changes.recv().await.is_some() {
+                let Ok((cols, rows)) = terminal::size() else {
+                    continue;
+                };
+                let resize = ExecMessage::Control(ExecControl::Resize { cols, rows });
+                if resizes.send(resize).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    #[cfg(not(unix))]
+    let _ = tty;
+
+    let mut stdout = std::io::stdout();
+    while let Some(message) = channel.rx.recv().await {
+        match message {
+            ExecMessage::Data(data) => {
+                stdout.write_all(&data)?;
+                stdout.flush()?;
+            }
+            ExecMessage::Control(ExecControl::Exit { code }) => return Ok(Ok(code)),
+            ExecMessage::Control(ExecControl::Error { message }) => return Ok(Err(message)),
+            ExecMessage::Control(_) => {}
+        }
+    }
+
+    Ok(Err("connection to the job was lost".to_string()))
+}
+
+pub async fn port_forward(
+    config: config::UserConfig,
+    args: &PortForwardArgs,
+) -> Result<(), CliError> {
+    let client = ApiClient::new(config);
+    for &(local_port, remote_port) in &args.ports {
+        let listener = TcpListener::bind(("127.0.0.1", local_port)).await?;
+        println!(
+            "🔌 Forwarding 127.0.0.1:{} -> port {} of job {}",
+            local_port, remote_port, args.job_id
+        );
+        tokio::spawn(forward_connections(
+            client.clone(),
+            listener,
+            args.job_id.clone(),
+            remote_port,
+        ));
+    }
+
+    tokio::signal::ctrl_c().await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Tunnels each connection accepted on `listener` to a port of the job.
+async fn forward_connections(client: ApiClient, listener: TcpListener, job_id: String, port: u16) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("❌ Error accepting connection: {}", e);
+                continue;
+            }
+        };
+        let client = client.clone();
+        let job_id = job_id.clone();
+        tokio::spawn(async move {
+            let result = match client.port_forward(&job_id, port).await {
+                Ok(channel) => tunnel::relay(stream, channel).await,
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                eprintln!("❌ Error forwarding to port {}: {}", port, e);
+            }
+        });
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
     config::AgentConfig,
     domain::agent::{
-        models::{HeartbeatRequest, HeartbeatResponse, JobDetails},
+        models::{ExecChannel, ExecSession, HeartbeatRequest, HeartbeatResponse, JobDetails},
         ports::ControlPlaneApi,
     },
     errors::ControlPlaneApiError,
+    outbound::websocket,
 };
 use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
@@ -58,6 +59,29 @@ impl ControlPlaneApi for ControlPlaneClient {
         }
     }
 
+    async fn deregister_node(&self, node_id: Uuid) -> Result<(), ControlPlaneApiError> {
+        let api_key = &self.config.cluster_api_key;
+
+        let url = format!("{}/node/{}", self.config.api_endpoint, node_id);
+        let response = self
+            .client
+            .delete(&url)
+            .bearer_auth(api_key)
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(()),
+            StatusCode::UNAUTHORIZED => Err(ControlPlaneApiError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(ControlPlaneApiError::NotFound),
+            StatusCode::INTERNAL_SERVER_ERROR => Err(ControlPlaneApiError::InternalServerError),
+            _ => Err(ControlPlaneApiError::Unknown(anyhow::anyhow!(
+                "Failed to deregister node: {}",
+                response.status()
+            ))),
+        }
+    }
+
     async fn get_job_details(
         &self,
         job_id: Uuid,
@@ -86,4 +110,61 @@ impl ControlPlaneApi for ControlPlaneClient {
             ))),
         }
     }
-}
\ No newline at end of file
+
+    async fn wait_for_exec_sessions(
+        &self,
+        node_id: Uuid,
+    ) -> Result<Vec<ExecSession>, ControlPlaneApiError> {
+        let api_key = &self.config.cluster_api_key;
+
+        let url = format!("{}/node/{}/exec_sessions", self.config.api_endpoint, node_id);
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(api_key)
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let sessions = response.json::<Vec<ExecSession>>().await?;
+                Ok(sessions)
+            }
+            StatusCode::UNAUTHORIZED => Err(ControlPlaneApiError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(ControlPlaneApiError::NotFound),
+            StatusCode::INTERNAL_SERVER_ERROR => Err(ControlPlaneApiError::InternalServerError),
+            _ => Err(ControlPlaneApiError::Unknown(anyhow::anyhow!(
+                "Failed to get exec sessions: {}",
+                response.status()
+            ))),
+        }
+    }
+
+    async fn attach_exec_session(
+        &self,
+        node_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<ExecChannel, ControlPlaneApiError> {
+        let api_key = &self.config.cluster_api_key;
+
+        let url = websocket::websocket_url(
+            &self.config.api_endpoint,
+            &format!("/node/{}/exec_sessions/{}/attach", node_id, session_id),
+        )?;
+        let socket = websocket::connect(&url, api_key).await.map_err(|e| {
+            match websocket::rejection(&e).map(|(status, _)| status) {
+                Some(StatusCode::UNAUTHORIZED) => ControlPlaneApiError::Unauthorized,
+                Some(StatusCode::NOT_FOUND) => ControlPlaneApiError::NotFound,
+                Some(StatusCode::INTERNAL_SERVER_ERROR) => {
+                    ControlPlaneApiError::InternalServerError
+                }
+                _ => ControlPlaneApiError::Unknown(anyhow::anyhow!(
+                    "Failed to attach to exec session: {}",
+                    e
+                )),
+            }
+        })?;
+
+        Ok(websocket::exec_channel(socket))
+    }
+}
@@ -1,30 +1,383 @@
 use crate::{
-    config::AgentConfig,
-    domain::agent::{models::JobDetails, ports::JobExecutor, models::NodeResources},
+    config::{self, AgentConfig},
+    domain::agent::{
+        models::{
+            CachedImage, ExecProcess, Gpu, JobDetails, JobExit, JobMetadata,
+            JobResourceRequirements, JobUsage, NodeResources,
+        },
+        ports::JobExecutor,
+    },
     errors::JobExecutorError,
 };
 use async_trait::async_trait;
 use bollard::container::{
-    Config, CreateContainerOptions, RemoveContainerOptions, StartContainerOptions,
-    StopContainerOptions, WaitContainerOptions,
+    Config, CreateContainerOptions, InspectContainerOptions, ListContainersOptions,
+    MemoryStatsStats, RemoveContainerOptions, StartContainerOptions, Stats, StatsOptions,
+    StopContainerOptions, TopOptions, WaitContainerOptions,
 };
-use bollard::image::CreateImageOptions;
-use bollard::{auth::DockerCredentials, Docker};
+use bollard::exec::{CreateExecOptions, ResizeExecOptions, StartExecOptions, StartExecResults};
+use bollard::image::{CreateImageOptions, ListImagesOptions, RemoveImageOptions};
+use bollard::service::{DeviceMapping, HostConfig, SystemInfoCgroupVersionEnum};
+use bollard::{auth::DockerCredentials, Docker, API_DEFAULT_VERSION};
 use futures_util::stream::StreamExt;
+use std::collections::HashMap;
+use std::env;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Where a job's checkpoint directory is mounted in its container.
+const CHECKPOINT_MOUNT_PATH: &str = "/lilac/checkpoint";
+/// Where a job's metadata file is mounted in its container.
+const METADATA_MOUNT_PATH: &str = "/lilac/metadata.json";
+/// The most processes a job's container can run at once, so that a runaway
+/// job can't exhaust the node's PIDs.
+const PIDS_LIMIT: i64 = 4096;
+/// The label holding the ID of the job a container runs.
+const JOB_ID_LABEL: &str = "lilac.job_id";
+/// The label holding the ID of the node whose agent started a container.
+const NODE_ID_LABEL: &str = "lilac.node_id";
+/// How long a request to the container runtime may take, in seconds.
+const REQUEST_TIMEOUT_SECS: u64 = 120;
+
+/// The container engine serving the Docker API the agent talks to.
+#[derive(Debug, Clone, Copy, PartialEq, strum::Display)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
 
 #[derive(Clone)]
 pub struct DockerExecutor {
     docker: Docker,
     config: AgentConfig,
+    runtime: ContainerRuntime,
+    /// Whether containers can be limited to the resources their jobs asked
+    /// for. Rootless containers can only be limited with cgroup v2.
+    resource_limits: bool,
+    /// Held while the image usage file is updated, since images are pulled
+    /// ahead of time while jobs run.
+    image_usage_lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl DockerExecutor {
-    pub fn new(config: AgentConfig) -> Result<Self, JobExecutorError> {
-        // Connect to the local Docker daemon.
-        // This will fail if Docker is not running.
-        let docker =
-            Docker::connect_with_local_defaults().map_err(|e| JobExecutorError::Unknown(e.into()))?;
-        Ok(Self { docker, config })
+    /// Connects to the container runtime and works out which one it is. This
+    /// fails if no runtime is running.
+    pub async fn new(config: AgentConfig) -> Result<Self, JobExecutorError> {
+        let docker = Self::connect(&config).await?;
+
+        // Podman's Docker-compatible API lists itself among the components.
+        let version = docker
+            .version()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let runtime = if version
+            .components
+            .unwrap_or_default()
+            .iter()
+            .any(|component| component.name.starts_with("Podman"))
+        {
+            ContainerRuntime::Podman
+        } else {
+            ContainerRuntime::Docker
+        };
+
+        let info = docker
+            .info()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let rootless = info
+            .security_options
+            .unwrap_or_default()
+            .iter()
+            .any(|option| option.contains("name=rootless"));
+        let resource_limits =
+            !rootless || info.cgroup_version == Some(SystemInfoCgroupVersionEnum::_2);
+
+        println!(
+            "[DOCKER] Using {} {}{}",
+            runtime,
+            version.version.unwrap_or_default(),
+            if rootless { " (rootless)" } else { "" }
+        );
+        if !resource_limits {
+            println!("[DOCKER] Rootless containers need cgroup v2 to be limited; jobs run without resource limits.");
+        }
+
+        Ok(Self {
+            docker,
+            config,
+            runtime,
+            resource_limits,
+            image_usage_lock: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    /// Connects to the configured API socket or, without one, to `DOCKER_HOST`
+    /// or the first of the usual sockets of Docker and Podman that answers.
+    async fn connect(config: &AgentConfig) -> Result<Docker, JobExecutorError> {
+        if let Some(socket) = &config.container_socket {
+            return Self::connect_socket(socket).await.map_err(|e| {
+                anyhow::anyhow!("no container runtime at {}: {}", socket.display(), e).into()
+            });
+        }
+
+        if env::var_os("DOCKER_HOST").is_some() {
+            let docker = Docker::connect_with_local_defaults()
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+            docker
+                .ping()
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+            return docker
+                .negotiate_version()
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()));
+        }
+
+        let mut sockets = vec![PathBuf::from("/var/run/docker.sock")];
+        // Rootless Podman serves its API from the user's runtime directory.
+        if let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR") {
+            sockets.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+        }
+        sockets.push(PathBuf::from("/run/podman/podman.sock"));
+
+        for socket in sockets.iter().filter(|socket| socket.exists()) {
+            match Self::connect_socket(socket).await {
+                Ok(docker) => return Ok(docker),
+                Err(e) => eprintln!(
+                    "[DOCKER] No container runtime at {}: {}",
+                    socket.display(),
+                    e
+                ),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "no container runtime found at {}; start Docker or Podman, or set LILAC_CONTAINER_SOCKET",
+            sockets.iter().map(|socket| socket.display().to_string()).collect::<Vec<_>>().join(", ")
+        )
+        .into())
+    }
+
+    async fn connect_socket(socket: &Path) -> Result<Docker, bollard::errors::Error> {
+        let docker = Docker::connect_with_socket(
+            &socket.to_string_lossy(),
+            REQUEST_TIMEOUT_SECS,
+            API_DEFAULT_VERSION,
+        )?;
+        docker.ping().await?;
+        // Podman supports older API versions than the client defaults to.
+        docker.negotiate_version().await
+    }
+
+    /// Where the metadata file of a job is kept on this node while it runs.
+    fn metadata_path(job_id: &str) -> Result<PathBuf, JobExecutorError> {
+        let run_dir =
+            config::get_config_path("run").map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        Ok(run_dir.join(format!("{}.json", job_id)))
+    }
+
+    /// Writes the metadata file of a job, to be mounted into its container.
+    async fn write_metadata_file(
+        &self,
+        metadata: &JobMetadata,
+    ) -> Result<PathBuf, JobExecutorError> {
+        let path = Self::metadata_path(&metadata.job_id.to_string())?;
+        if let Some(run_dir) = path.parent() {
+            tokio::fs::create_dir_all(run_dir)
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        }
+        let contents =
+            serde_json::to_vec_pretty(metadata).map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        tokio::fs::write(&path, contents)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        Ok(path)
+    }
+
+    async fn remove_metadata_file(job_id: &str) {
+        if let Ok(path) = Self::metadata_path(job_id) {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+    }
+
+    /// Where the images pulled for jobs are recorded, along with when a job
+    /// last used them. Docker itself doesn't track when an image was used.
+    fn image_usage_path() -> Result<PathBuf, JobExecutorError> {
+        config::get_config_path("run/image-usage.json")
+            .map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    /// Reads the last use, in seconds since the Unix epoch, of each image by
+    /// its ID.
+    async fn read_image_usage() -> Result<HashMap<String, u64>, JobExecutorError> {
+        let path = Self::image_usage_path()?;
+        match tokio::fs::read(&path).await {
+            Ok(contents) => {
+                serde_json::from_slice(&contents).map_err(|e| JobExecutorError::Unknown(e.into()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(JobExecutorError::Unknown(e.into())),
+        }
+    }
+
+    async fn write_image_usage(usage: &HashMap<String, u64>) -> Result<(), JobExecutorError> {
+        let path = Self::image_usage_path()?;
+        if let Some(run_dir) = path.parent() {
+            tokio::fs::create_dir_all(run_dir)
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        }
+        let contents =
+            serde_json::to_vec_pretty(usage).map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        tokio::fs::write(&path, contents)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    /// Records that a job used the image now. Failures are only logged, since
+    /// they at worst make the image look older than it is.
+    async fn touch_image(&self, image_id: &str) {
+        let _guard = self.image_usage_lock.lock().await;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let result = match Self::read_image_usage().await {
+            Ok(mut usage) => {
+                usage.insert(image_id.to_string(), now);
+                Self::write_image_usage(&usage).await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("[DOCKER] Failed to record use of image {}: {}", image_id, e);
+        }
+    }
+
+    /// Limits a job's container to the CPU and memory the job asked for. Swap
+    /// is disabled, so a job that outgrows its memory is OOM-killed instead of
+    /// slowing down the whole node.
+    fn apply_resource_limits(
+        host_config: &mut HostConfig,
+        requirements: &JobResourceRequirements,
+        resources: &NodeResources,
+    ) {
+        host_config.pids_limit = Some(PIDS_LIMIT);
+
+        if requirements.cpu_millicores > 0 {
+            // Docker rejects limits above the number of CPUs of the node.
+            let millicores = requirements.cpu_millicores.min(resources.cpu.millicores);
+            host_config.nano_cpus = Some(millicores as i64 * 1_000_000);
+        }
+
+        if requirements.memory_mb > 0 {
+            let memory_bytes = requirements.memory_mb as i64 * 1024 * 1024;
+            host_config.memory = Some(memory_bytes);
+            host_config.memory_swap = Some(memory_bytes);
+            // Docker's default of 64 MB is too small for the data loader
+            // workers of most frameworks. Pages in /dev/shm count towards
+            // the memory limit, so this doesn't let the job use more.
+            host_config.shm_size = Some(memory_bytes / 2);
+        }
+    }
+
+    /// How the NVIDIA runtime, or CDI, should refer to the given GPUs. UUIDs
+    /// are preferred, since indices can change when the node reboots.
+    fn gpu_device_ids(gpus: &[Gpu]) -> Vec<String> {
+        gpus.iter()
+            .map(|gpu| gpu.uuid.clone().unwrap_or_else(|| gpu.index.to_string()))
+            .collect()
+    }
+
+    /// Environment variables that limit the job to its GPUs. Images such as
+    /// `nvidia/cuda` ask for all GPUs of the node by default, which would
+    /// bypass the device request on nodes where the NVIDIA runtime is the
+    /// default one.
+    fn gpu_env(device_ids: &[String]) -> Vec<(String, String)> {
+        if device_ids.is_empty() {
+            return vec![("NVIDIA_VISIBLE_DEVICES".to_string(), "void".to_string())];
+        }
+        // Inside the container, the job's GPUs are numbered from 0.
+        let cuda_devices = (0..device_ids.len())
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        vec![
+            ("NVIDIA_VISIBLE_DEVICES".to_string(), device_ids.join(",")),
+            ("CUDA_VISIBLE_DEVICES".to_string(), cuda_devices),
+        ]
+    }
+
+    /// Converts a raw Docker stats sample into the usage reported to the control plane.
+    fn job_usage_from_stats(stats: &Stats) -> JobUsage {
+        // CPU usage is derived from the delta between this sample and the previous one,
+        // scaled to the number of CPUs the container can see.
+        let cpu_delta = stats
+            .cpu_stats
+            .cpu_usage
+            .total_usage
+            .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+        let system_delta = stats
+            .cpu_stats
+            .system_cpu_usage
+            .unwrap_or_default()
+            .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or_default());
+        let online_cpus = stats.cpu_stats.online_cpus.unwrap_or(1);
+        let cpu_millicores = if system_delta > 0 {
+            (cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 1000.0) as i32
+        } else {
+            0
+        };
+
+        // Page cache is reclaimable, so it is excluded from the resident set.
+        let usage_bytes = stats.memory_stats.usage.unwrap_or_default();
+        let rss_bytes = match stats.memory_stats.stats {
+            Some(MemoryStatsStats::V1(v1)) => v1.rss,
+            Some(MemoryStatsStats::V2(v2)) => v2.anon,
+            None => usage_bytes,
+        };
+        // cgroup v2 does not report a peak, so fall back to the current usage.
+        let peak_bytes = stats.memory_stats.max_usage.unwrap_or(usage_bytes);
+
+        let (network_rx_bytes, network_tx_bytes) = stats
+            .networks
+            .as_ref()
+            .map(|networks| {
+                networks.values().fold((0, 0), |(rx, tx), network| {
+                    (rx + network.rx_bytes, tx + network.tx_bytes)
+                })
+            })
+            .unwrap_or_default();
+
+        let (block_read_bytes, block_write_bytes) = stats
+            .blkio_stats
+            .io_service_bytes_recursive
+            .as_ref()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .fold((0, 0), |(read, write), entry| match entry.op.as_str() {
+                        "read" | "Read" => (read + entry.value, write),
+                        "write" | "Write" => (read, write + entry.value),
+                        _ => (read, write),
+                    })
+            })
+            .unwrap_or_default();
+
+        JobUsage {
+            cpu_millicores,
+            memory_rss_mb: (rss_bytes / 1024 / 1024) as i32,
+            memory_peak_mb: (peak_bytes / 1024 / 1024) as i32,
+            network_rx_bytes: network_rx_bytes as i64,
+            network_tx_bytes: network_tx_bytes as i64,
+            block_read_bytes: block_read_bytes as i64,
+            block_write_bytes: block_write_bytes as i64,
+            gpus: Vec::new(),
+        }
     }
 }
 
@@ -34,34 +387,12 @@ impl JobExecutor for DockerExecutor {
         &self,
         job_details: JobDetails,
         resources: &NodeResources,
-    ) -> Result<i64, JobExecutorError> {
+        gpus: &[Gpu],
+    ) -> Result<JobExit, JobExecutorError> {
         println!("[DOCKER] Starting job: {}", job_details.id);
-        println!("[DOCKER] Pulling image: {}", job_details.docker_uri);
 
         // 1. Pull the Docker image.
-        let credentials = if let Some(private_registry) = &self.config.private_registry {
-            Some(DockerCredentials {
-                serveraddress: Some(private_registry.registry_url.clone()),
-                username: Some(private_registry.username.clone()),
-                password: Some(private_registry.secret.clone()),
-                ..Default::default()
-            })
-        } else {
-            None
-        };
-
-        let mut stream = self.docker.create_image(
-            Some(CreateImageOptions {
-                from_image: job_details.docker_uri.clone(),
-                ..Default::default()
-            }),
-            None,
-            credentials,
-        );
-
-        while let Some(result) = stream.next().await {
-            result.map_err(|e| JobExecutorError::Unknown(e.into()))?;
-        }
+        self.pull_image(&job_details.docker_uri).await?;
 
         // 2. Clean up any old container with the same name, just in case.
         let container_name = format!("lilac-job-{}", job_details.id);
@@ -81,23 +412,117 @@ impl JobExecutor for DockerExecutor {
             ..Default::default()
         });
 
-        let mut host_config = bollard::service::HostConfig {
+        // Publish exposed ports on the loopback interface only. Users reach
+        // them through tunnels relayed by the agent, never directly.
+        let exposed_ports: HashMap<String, HashMap<(), ()>> = job_details
+            .exposed_ports
+            .iter()
+            .map(|port| (format!("{}/tcp", port), HashMap::new()))
+            .collect();
+        let port_bindings = exposed_ports
+            .keys()
+            .map(|port| {
+                let binding = bollard::service::PortBinding {
+                    host_ip: Some("127.0.0.1".to_string()),
+                    host_port: None,
+                };
+                (port.clone(), Some(vec![binding]))
+            })
+            .collect();
+
+        // Checkpoint directories are named after the job, so a job that is
+        // re-queued finds the checkpoints its earlier runs saved.
+        let checkpoint_dir = self
+            .config
+            .checkpoint_root()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?
+            .join(job_details.id.to_string());
+        tokio::fs::create_dir_all(&checkpoint_dir)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let metadata = JobMetadata::new(
+            &job_details,
+            gpus,
+            self.config.node_id,
+            &self.config.api_endpoint,
+        );
+        let metadata_path = self.write_metadata_file(&metadata).await?;
+
+        let mut host_config = HostConfig {
+            port_bindings: Some(port_bindings),
+            binds: Some(vec![
+                format!("{}:{}", checkpoint_dir.display(), CHECKPOINT_MOUNT_PATH),
+                format!("{}:{}:ro", metadata_path.display(), METADATA_MOUNT_PATH),
+            ]),
             ..Default::default()
         };
+        if self.resource_limits {
+            Self::apply_resource_limits(
+                &mut host_config,
+                &job_details.resource_requirements,
+                resources,
+            );
+        }
 
-        if !resources.gpus.is_empty() {
+        let device_ids = Self::gpu_device_ids(gpus);
+        if !device_ids.is_empty() && self.runtime == ContainerRuntime::Podman {
+            // Podman passes GPUs through by their CDI names, as generated by
+            // `nvidia-ctk cdi generate`.
+            host_config.devices = Some(
+                device_ids
+                    .iter()
+                    .map(|id| DeviceMapping {
+                        path_on_host: Some(format!("nvidia.com/gpu={}", id)),
+                        path_in_container: Some(String::new()),
+                        cgroup_permissions: Some("rwm".to_string()),
+                    })
+                    .collect(),
+            );
+        } else if !device_ids.is_empty() {
             host_config.device_requests = Some(vec![bollard::service::DeviceRequest {
                 driver: Some("".to_string()),
-                count: Some(-1),
-                device_ids: None,
+                count: None,
+                device_ids: Some(device_ids.clone()),
                 capabilities: Some(vec![vec!["gpu".to_string()]]),
                 options: None,
             }]);
         }
 
+        let mut env: Vec<String> = job_details
+            .env
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        // Lilac's own variables come last, so that they win over the job's.
+        env.extend(
+            metadata
+                .env()
+                .into_iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        );
+        env.extend(
+            Self::gpu_env(&device_ids)
+                .into_iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        );
+        env.push(format!("LILAC_METADATA_FILE={}", METADATA_MOUNT_PATH));
+        env.push(format!("LILAC_CHECKPOINT_DIR={}", CHECKPOINT_MOUNT_PATH));
+
+        // Labels let a restarted agent find the containers of its jobs.
+        let labels = HashMap::from([
+            (JOB_ID_LABEL.to_string(), job_details.id.to_string()),
+            (NODE_ID_LABEL.to_string(), self.config.node_id.to_string()),
+        ]);
+
         let config = Config {
             image: Some(job_details.docker_uri.clone()),
+            labels: Some(labels),
+            env: Some(env),
+            exposed_ports: Some(exposed_ports),
             host_config: Some(host_config),
+            // Used by `stop_job`, so the job gets its own grace period.
+            stop_timeout: Some(job_details.termination_grace_period_secs),
             ..Default::default()
         };
 
@@ -115,19 +540,84 @@ impl JobExecutor for DockerExecutor {
             .map_err(|e| JobExecutorError::Unknown(e.into()))?;
         println!("[DOCKER] Started container for job {}", job_details.id);
 
-        // 5. Wait for the container to finish.
+        // 5. Wait for the container to finish and clean up after it.
+        self.wait_job(&job_details.id.to_string()).await
+    }
+
+    async fn pull_image(&self, image: &str) -> Result<(), JobExecutorError> {
+        println!("[DOCKER] Pulling image: {}", image);
+        let credentials = self
+            .config
+            .private_registry
+            .as_ref()
+            .map(|private_registry| DockerCredentials {
+                serveraddress: Some(private_registry.registry_url.clone()),
+                username: Some(private_registry.username.clone()),
+                password: Some(private_registry.secret.clone()),
+                ..Default::default()
+            });
+
+        let mut stream = self.docker.create_image(
+            Some(CreateImageOptions {
+                from_image: image.to_string(),
+                ..Default::default()
+            }),
+            None,
+            credentials,
+        );
+
+        while let Some(result) = stream.next().await {
+            result.map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        }
+        let pulled = self
+            .docker
+            .inspect_image(image)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        if let Some(image_id) = &pulled.id {
+            self.touch_image(image_id).await;
+        }
+
+        Ok(())
+    }
+
+    async fn wait_job(&self, job_id: &str) -> Result<JobExit, JobExecutorError> {
+        let container_name = format!("lilac-job-{}", job_id);
         let wait_options = Some(WaitContainerOptions {
             condition: "not-running",
         });
-        let mut stream = self.docker.wait_container(&container.id, wait_options);
-        let wait_result = stream.next().await.unwrap().unwrap();
-        let exit_code = wait_result.status_code;
+        let mut stream = self.docker.wait_container(&container_name, wait_options);
+        let exit_code = match stream.next().await {
+            Some(Ok(wait_result)) => wait_result.status_code,
+            // Bollard reports non-zero exit codes as errors.
+            Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. })) => code,
+            Some(Err(e)) => return Err(JobExecutorError::Unknown(e.into())),
+            None => {
+                return Err(anyhow::anyhow!("no exit code returned for {}", container_name).into())
+            }
+        };
         println!(
             "[JOB {}] Execution finished with exit code: {}",
-            job_details.id, exit_code
+            job_id, exit_code
         );
 
-        // 6. Remove the container.
+        let container = self
+            .docker
+            .inspect_container(&container_name, None::<InspectContainerOptions>)
+            .await
+            .ok();
+        // The exit code alone can't tell an OOM kill from any other SIGKILL.
+        let oom_killed = container
+            .as_ref()
+            .and_then(|container| container.state.as_ref())
+            .and_then(|state| state.oom_killed)
+            .unwrap_or(false);
+        // The container refers to its image by ID.
+        if let Some(image_id) = container.and_then(|container| container.image) {
+            self.touch_image(&image_id).await;
+        }
+
+        // Remove the container.
         self.docker
             .remove_container(
                 &container_name,
@@ -139,29 +629,37 @@ impl JobExecutor for DockerExecutor {
             .await
             .map_err(|e| JobExecutorError::Unknown(e.into()))?;
         println!("[DOCKER] Removed container: {}", container_name);
+        Self::remove_metadata_file(job_id).await;
 
-        // 7. Remove the image to save space.
-        let _ = self
-            .docker
-            .remove_image(&job_details.docker_uri, None, None)
-            .await;
-        println!("[DOCKER] Attempted to remove image: {}", job_details.docker_uri);
-
-
-        Ok(exit_code)
+        // The image is kept for later jobs. The daemon evicts it once the
+        // cache outgrows its budget.
+        Ok(JobExit {
+            exit_code,
+            oom_killed,
+        })
     }
 
     async fn stop_job(&self, job_id: &str) -> Result<(), JobExecutorError> {
         let container_name = format!("lilac-job-{}", job_id);
         println!("[DOCKER] Stopping container: {}", container_name);
 
-        // Stop the container. Give it 30 seconds to shut down gracefully.
-        let stop_options = Some(StopContainerOptions { t: 30 });
-        self.docker
-            .stop_container(&container_name, stop_options)
+        // Stop the container. Without a timeout, Docker sends SIGTERM and
+        // waits for the job's grace period, set when the container was
+        // created, before killing it.
+        match self
+            .docker
+            .stop_container(&container_name, None::<StopContainerOptions>)
             .await
-            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        {
+            // 304 means the container had already stopped.
+            Ok(())
+            | Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 304, ..
+            }) => {}
+            Err(e) => return Err(JobExecutorError::Unknown(e.into())),
+        }
         println!("[DOCKER] Stopped container: {}", container_name);
+        Self::remove_metadata_file(job_id).await;
 
         // Remove the container.
         let remove_options = Some(RemoveContainerOptions {
@@ -173,7 +671,10 @@ impl JobExecutor for DockerExecutor {
             .await
             .map_err(|e| {
                 if !e.to_string().contains("404") {
-                    eprintln!("[DOCKER] Error removing container {}: {}", container_name, e);
+                    eprintln!(
+                        "[DOCKER] Error removing container {}: {}",
+                        container_name, e
+                    );
                 }
                 JobExecutorError::Unknown(e.into())
             })?;
@@ -181,4 +682,242 @@ impl JobExecutor for DockerExecutor {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    async fn list_jobs(&self) -> Result<Vec<Uuid>, JobExecutorError> {
+        let filters = HashMap::from([(
+            "label".to_string(),
+            vec![format!("{}={}", NODE_ID_LABEL, self.config.node_id)],
+        )]);
+        let containers = self
+            .docker
+            .list_containers(Some(ListContainersOptions {
+                all: true,
+                filters,
+                ..Default::default()
+            }))
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let job_ids = containers
+            .into_iter()
+            .filter_map(|container| container.labels?.get(JOB_ID_LABEL)?.parse().ok())
+            .collect();
+
+        Ok(job_ids)
+    }
+
+    async fn list_images(&self) -> Result<Vec<CachedImage>, JobExecutorError> {
+        let usage = Self::read_image_usage().await?;
+        let images = self
+            .docker
+            .list_images(Some(ListImagesOptions::<String> {
+                all: false,
+                ..Default::default()
+            }))
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let cached_images = images
+            .into_iter()
+            .filter_map(|image| {
+                let last_used_at = *usage.get(&image.id)?;
+                Some(CachedImage {
+                    tags: image
+                        .repo_tags
+                        .into_iter()
+                        .filter(|tag| tag != "<none>:<none>")
+                        .collect(),
+                    digests: image
+                        .repo_digests
+                        .into_iter()
+                        .filter(|digest| digest != "<none>@<none>")
+                        .collect(),
+                    id: image.id,
+                    size_bytes: image.size.max(0) as u64,
+                    last_used_at,
+                })
+            })
+            .collect();
+
+        Ok(cached_images)
+    }
+
+    async fn remove_image(&self, image: &CachedImage) -> Result<(), JobExecutorError> {
+        // Removing an image by ID fails if it has several tags, so each tag is
+        // removed instead. Removing the last one removes the image.
+        let references = if image.tags.is_empty() {
+            vec![image.id.clone()]
+        } else {
+            image.tags.clone()
+        };
+        for reference in &references {
+            self.docker
+                .remove_image(reference, None::<RemoveImageOptions>, None)
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        }
+        println!("[DOCKER] Removed image: {}", references.join(", "));
+
+        let _guard = self.image_usage_lock.lock().await;
+        let mut usage = Self::read_image_usage().await?;
+        usage.remove(&image.id);
+        Self::write_image_usage(&usage).await
+    }
+
+    async fn get_job_usage(&self, job_id: &str) -> Result<JobUsage, JobExecutorError> {
+        let container_name = format!("lilac-job-{}", job_id);
+
+        // A non-streaming, non-one-shot request waits for a second sample so that
+        // `precpu_stats` is populated and CPU usage can be computed.
+        let mut stream = self.docker.stats(
+            &container_name,
+            Some(StatsOptions {
+                stream: false,
+                one_shot: false,
+            }),
+        );
+        let stats = stream
+            .next()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("no stats returned for {}", container_name))?
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        Ok(Self::job_usage_from_stats(&stats))
+    }
+
+    async fn get_job_pids(&self, job_id: &str) -> Result<Vec<u32>, JobExecutorError> {
+        let container_name = format!("lilac-job-{}", job_id);
+        let top = self
+            .docker
+            .top_processes(&container_name, Some(TopOptions { ps_args: "-o pid" }))
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let pids = top
+            .processes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|process| process.first().and_then(|pid| pid.trim().parse().ok()))
+            .collect();
+
+        Ok(pids)
+    }
+
+    async fn exec(
+        &self,
+        job_id: &str,
+        command: Vec<String>,
+        tty: bool,
+    ) -> Result<ExecProcess, JobExecutorError> {
+        let container_name = format!("lilac-job-{}", job_id);
+        let exec = self
+            .docker
+            .create_exec(
+                &container_name,
+                CreateExecOptions {
+                    cmd: Some(command),
+                    attach_stdin: Some(true),
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    tty: Some(tty),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let started = self
+            .docker
+            .start_exec(
+                &exec.id,
+                Some(StartExecOptions {
+                    detach: false,
+                    tty,
+                    output_capacity: None,
+                }),
+            )
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        match started {
+            StartExecResults::Attached { output, input } => {
+                println!(
+                    "[DOCKER] Started exec {} in container: {}",
+                    exec.id, container_name
+                );
+                let output = output.map(|chunk| {
+                    chunk
+                        .map(|log| log.into_bytes().to_vec())
+                        .map_err(|e| JobExecutorError::Unknown(e.into()))
+                });
+                Ok(ExecProcess {
+                    id: exec.id,
+                    output: Box::pin(output),
+                    input,
+                })
+            }
+            StartExecResults::Detached => Err(JobExecutorError::Unknown(anyhow::anyhow!(
+                "exec {} started detached",
+                exec.id
+            ))),
+        }
+    }
+
+    async fn resize_exec(
+        &self,
+        exec_id: &str,
+        cols: u16,
+        rows: u16,
+    ) -> Result<(), JobExecutorError> {
+        self.docker
+            .resize_exec(
+                exec_id,
+                ResizeExecOptions {
+                    height: rows,
+                    width: cols,
+                },
+            )
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    async fn get_exec_exit_code(&self, exec_id: &str) -> Result<i64, JobExecutorError> {
+        let inspect = self
+            .docker
+            .inspect_exec(exec_id)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        inspect
+            .exit_code
+            .ok_or_else(|| anyhow::anyhow!("exec {} has not exited", exec_id).into())
+    }
+
+    async fn get_port_address(
+        &self,
+        job_id: &str,
+        port: u16,
+    ) -> Result<SocketAddr, JobExecutorError> {
+        let container_name = format!("lilac-job-{}", job_id);
+        let container = self
+            .docker
+            .inspect_container(&container_name, None::<InspectContainerOptions>)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        // Docker picks a free host port for each exposed port when the
+        // container starts.
+        let host_port = container
+            .network_settings
+            .and_then(|settings| settings.ports)
+            .and_then(|mut ports| ports.remove(&format!("{}/tcp", port)))
+            .flatten()
+            .and_then(|bindings| bindings.into_iter().find_map(|b| b.host_port))
+            .and_then(|host_port| host_port.parse::<u16>().ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!("port {} of {} is not published", port, container_name)
+            })?;
+
+        Ok(SocketAddr::from(([127, 0, 0, 1], host_port)))
+    }
+}
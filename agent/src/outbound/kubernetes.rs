@@ -0,0 +1,1327 @@
+use crate::{
+    config::{self, AgentConfig, KubernetesConfig},
+    domain::agent::{
+        models::{
+            CachedImage, ExecProcess, Gpu, JobDetails, JobExit, JobMetadata, JobUsage,
+            NodeResources,
+        },
+        ports::JobExecutor,
+    },
+    errors::JobExecutorError,
+};
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Where Kubernetes mounts the credentials of a pod's service account.
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+/// The name of the container running the job in its pod.
+const CONTAINER_NAME: &str = "job";
+/// Where a job's checkpoint directory is mounted in its container.
+const CHECKPOINT_MOUNT_PATH: &str = "/lilac/checkpoint";
+/// Where a job's metadata file is mounted in its container.
+const METADATA_MOUNT_PATH: &str = "/lilac/metadata.json";
+/// The label holding the ID of the job a pod runs.
+const JOB_ID_LABEL: &str = "lilac.job_id";
+/// The label holding the ID of the node whose agent created a pod.
+const NODE_ID_LABEL: &str = "lilac.node_id";
+/// The annotation holding a job's metadata, which is mounted into its
+/// container as a file.
+const METADATA_ANNOTATION: &str = "lilac.metadata";
+/// How long a watch of a pod may last before it is started again, in seconds.
+const WATCH_TIMEOUT_SECS: u64 = 300;
+/// Reasons a container waits for that it won't recover from by itself.
+const FATAL_WAITING_REASONS: &[&str] = &[
+    "ImagePullBackOff",
+    "InvalidImageName",
+    "ErrImageNeverPull",
+    "CreateContainerConfigError",
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Pod {
+    #[serde(default)]
+    metadata: ObjectMeta,
+    #[serde(default)]
+    status: PodStatus,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectMeta {
+    resource_version: Option<String>,
+    deletion_grace_period_seconds: Option<u64>,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodStatus {
+    phase: Option<String>,
+    reason: Option<String>,
+    message: Option<String>,
+    #[serde(rename = "podIP")]
+    pod_ip: Option<String>,
+    #[serde(default)]
+    container_statuses: Vec<ContainerStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerStatus {
+    name: String,
+    #[serde(default)]
+    state: ContainerState,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ContainerState {
+    waiting: Option<ContainerWaiting>,
+    running: Option<Value>,
+    terminated: Option<ContainerTerminated>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerWaiting {
+    reason: Option<String>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContainerTerminated {
+    exit_code: i64,
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodList {
+    items: Vec<Pod>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    object: Value,
+}
+
+/// How the API server explains a failed request.
+#[derive(Debug, Deserialize)]
+struct Status {
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodMetrics {
+    containers: Vec<ContainerMetrics>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerMetrics {
+    usage: HashMap<String, String>,
+}
+
+impl Pod {
+    fn job_container(&self) -> Option<&ContainerState> {
+        self.status
+            .container_statuses
+            .iter()
+            .find(|container| container.name == CONTAINER_NAME)
+            .map(|container| &container.state)
+    }
+
+    /// Whether the job's container has started, so that it has logs.
+    fn has_started(&self) -> bool {
+        self.job_container()
+            .is_some_and(|state| state.running.is_some() || state.terminated.is_some())
+    }
+
+    /// How the job ended, or `None` while it hasn't.
+    fn outcome(&self) -> Option<Result<JobExit, JobExecutorError>> {
+        let state = self.job_container();
+        // Pods of jobs are never restarted, so a terminated container is final.
+        if let Some(terminated) = state.and_then(|state| state.terminated.as_ref()) {
+            return Some(Ok(JobExit {
+                exit_code: terminated.exit_code,
+                oom_killed: terminated.reason.as_deref() == Some("OOMKilled"),
+            }));
+        }
+        if let Some(waiting) = state.and_then(|state| state.waiting.as_ref()) {
+            if let Some(reason) = waiting
+                .reason
+                .as_deref()
+                .filter(|reason| FATAL_WAITING_REASONS.contains(reason))
+            {
+                return Some(Err(anyhow::anyhow!(
+                    "container can't start: {}: {}",
+                    reason,
+                    waiting.message.as_deref().unwrap_or_default()
+                )
+                .into()));
+            }
+        }
+        match self.status.phase.as_deref() {
+            Some("Succeeded") => Some(Ok(JobExit {
+                exit_code: 0,
+                oom_killed: false,
+            })),
+            // E.g. the pod was evicted before its container started.
+            Some("Failed") => Some(Err(anyhow::anyhow!(
+                "pod failed: {}: {}",
+                self.status.reason.as_deref().unwrap_or_default(),
+                self.status.message.as_deref().unwrap_or_default()
+            )
+            .into())),
+            _ => None,
+        }
+    }
+}
+
+/// Runs jobs as pods of a Kubernetes cluster, one pod per job, through the
+/// cluster's API server. Pods are created in one namespace and labelled with
+/// the agent's node ID, so that a restarted agent finds them again.
+///
+/// The API server can be any URL, so the executor can be pointed at a fake
+/// one.
+#[derive(Clone)]
+pub struct KubernetesExecutor {
+    client: reqwest::Client,
+    api_server: String,
+    namespace: String,
+    token_file: Option<PathBuf>,
+    config: AgentConfig,
+    kubernetes: KubernetesConfig,
+}
+
+impl KubernetesExecutor {
+    pub fn new(config: AgentConfig) -> Result<Self, JobExecutorError> {
+        let kubernetes = config.kubernetes.clone().unwrap_or_default();
+        let service_account = Path::new(SERVICE_ACCOUNT_DIR);
+
+        let api_server = match &kubernetes.api_server {
+            Some(api_server) => api_server.trim_end_matches('/').to_string(),
+            None => {
+                let host = env::var("KUBERNETES_SERVICE_HOST").map_err(|_| {
+                    anyhow::anyhow!("the agent isn't running in a Kubernetes cluster; set LILAC_KUBERNETES_API_SERVER")
+                })?;
+                let port =
+                    env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".to_string());
+                if host.contains(':') {
+                    format!("https://[{}]:{}", host, port)
+                } else {
+                    format!("https://{}:{}", host, port)
+                }
+            }
+        };
+        let namespace = kubernetes
+            .namespace
+            .clone()
+            .or_else(|| fs::read_to_string(service_account.join("namespace")).ok())
+            .map(|namespace| namespace.trim().to_string())
+            .unwrap_or_else(|| "default".to_string());
+        let token_file = kubernetes
+            .token_file
+            .clone()
+            .or_else(|| Some(service_account.join("token")).filter(|path| path.exists()));
+        let ca_cert = kubernetes
+            .ca_cert
+            .clone()
+            .or_else(|| Some(service_account.join("ca.crt")).filter(|path| path.exists()));
+
+        let mut client = reqwest::Client::builder();
+        if let Some(ca_cert) = ca_cert {
+            let pem = fs::read(&ca_cert).map_err(|e| JobExecutorError::Unknown(e.into()))?;
+            let certificate = reqwest::Certificate::from_pem(&pem)
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+            client = client.add_root_certificate(certificate);
+        }
+        let client = client
+            .build()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        println!(
+            "[KUBERNETES] Running jobs in namespace {} of {}",
+            namespace, api_server
+        );
+        Ok(Self {
+            client,
+            api_server,
+            namespace,
+            token_file,
+            config,
+            kubernetes,
+        })
+    }
+
+    fn pod_name(job_id: &str) -> String {
+        format!("lilac-job-{}", job_id)
+    }
+
+    fn pods_path(&self) -> String {
+        format!("/api/v1/namespaces/{}/pods", self.namespace)
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+    ) -> Result<RequestBuilder, JobExecutorError> {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.api_server, path));
+        if let Some(token_file) = &self.token_file {
+            // Service account tokens are rotated, so the file is read anew
+            // for every request.
+            let token = tokio::fs::read_to_string(token_file)
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+            request = request.bearer_auth(token.trim());
+        }
+        Ok(request)
+    }
+
+    /// Sends a request, turning error responses into errors.
+    async fn send(request: RequestBuilder) -> Result<Response, JobExecutorError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        Self::check(response).await
+    }
+
+    /// Turns an error response into an error.
+    async fn check(response: Response) -> Result<Response, JobExecutorError> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let message = response
+            .json::<Status>()
+            .await
+            .ok()
+            .and_then(|status| status.message)
+            .unwrap_or_default();
+        Err(anyhow::anyhow!("Kubernetes API returned {}: {}", status, message).into())
+    }
+
+    async fn get_pod(&self, pod_name: &str) -> Result<Option<Pod>, JobExecutorError> {
+        let request = self
+            .request(Method::GET, &format!("{}/{}", self.pods_path(), pod_name))
+            .await?;
+        let response = request
+            .send()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = Self::check(response).await?;
+        response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    /// Deletes a pod, giving its container its termination grace period to
+    /// exit. Pods that are already gone are ignored.
+    async fn delete_pod(&self, pod_name: &str) -> Result<(), JobExecutorError> {
+        let request = self
+            .request(
+                Method::DELETE,
+                &format!("{}/{}", self.pods_path(), pod_name),
+            )
+            .await?;
+        let response = request
+            .send()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        if response.status() != StatusCode::NOT_FOUND {
+            Self::check(response).await?;
+        }
+        Ok(())
+    }
+
+    /// Describes the pod running a job. The job's metadata travels in an
+    /// annotation, which the downward API mounts into the container as a file.
+    fn pod_manifest(
+        &self,
+        job_details: &JobDetails,
+        gpus: &[Gpu],
+        metadata: &JobMetadata,
+    ) -> Result<Value, JobExecutorError> {
+        let job_id = job_details.id.to_string();
+        let requirements = &job_details.resource_requirements;
+
+        let mut env: Vec<(String, String)> = job_details.env.clone().into_iter().collect();
+        // Lilac's own variables come last, so that they win over the job's.
+        env.extend(metadata.env());
+        if gpus.is_empty() {
+            // Images such as `nvidia/cuda` ask for all GPUs of the node by
+            // default, which nodes using the NVIDIA runtime by default grant.
+            env.push(("NVIDIA_VISIBLE_DEVICES".to_string(), "void".to_string()));
+        }
+        env.push((
+            "LILAC_METADATA_FILE".to_string(),
+            METADATA_MOUNT_PATH.to_string(),
+        ));
+        env.push((
+            "LILAC_CHECKPOINT_DIR".to_string(),
+            CHECKPOINT_MOUNT_PATH.to_string(),
+        ));
+        let env: Vec<Value> = env
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+
+        // Requests equal to limits give the pod the Guaranteed QoS class, so
+        // it is the last to be evicted.
+        let mut limits = serde_json::Map::new();
+        if requirements.cpu_millicores > 0 {
+            limits.insert(
+                "cpu".to_string(),
+                json!(format!("{}m", requirements.cpu_millicores)),
+            );
+        }
+        if requirements.memory_mb > 0 {
+            limits.insert(
+                "memory".to_string(),
+                json!(format!("{}Mi", requirements.memory_mb)),
+            );
+        }
+        if !gpus.is_empty() {
+            limits.insert("nvidia.com/gpu".to_string(), json!(gpus.len().to_string()));
+        }
+
+        // The default /dev/shm of 64 MB is too small for the data loader
+        // workers of most frameworks. It counts towards the memory limit.
+        let shm = if requirements.memory_mb > 0 {
+            json!({ "medium": "Memory", "sizeLimit": format!("{}Mi", requirements.memory_mb / 2) })
+        } else {
+            json!({ "medium": "Memory" })
+        };
+        let checkpoint = match &self.kubernetes.checkpoint_claim {
+            Some(claim) => {
+                json!({ "name": "checkpoint", "persistentVolumeClaim": { "claimName": claim } })
+            }
+            None => json!({ "name": "checkpoint", "emptyDir": {} }),
+        };
+
+        let metadata_json = serde_json::to_string_pretty(metadata)
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let image_pull_secrets: Vec<Value> = self
+            .kubernetes
+            .image_pull_secrets
+            .iter()
+            .map(|name| json!({ "name": name }))
+            .collect();
+        let ports: Vec<Value> = job_details
+            .exposed_ports
+            .iter()
+            .map(|port| json!({ "containerPort": port }))
+            .collect();
+
+        Ok(json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "name": Self::pod_name(&job_id),
+                "labels": {
+                    JOB_ID_LABEL: job_id,
+                    NODE_ID_LABEL: self.config.node_id.to_string(),
+                },
+                "annotations": { METADATA_ANNOTATION: metadata_json },
+            },
+            "spec": {
+                "restartPolicy": "Never",
+                "terminationGracePeriodSeconds": job_details.termination_grace_period_secs,
+                "imagePullSecrets": image_pull_secrets,
+                "nodeSelector": self.kubernetes.node_selector,
+                "containers": [{
+                    "name": CONTAINER_NAME,
+                    "image": job_details.docker_uri,
+                    "env": env,
+                    "ports": ports,
+                    "resources": { "limits": limits, "requests": limits },
+                    "volumeMounts": [
+                        { "name": "shm", "mountPath": "/dev/shm" },
+                        // Checkpoint directories are named after the job, so
+                        // a job that is re-queued finds its earlier ones.
+                        { "name": "checkpoint", "mountPath": CHECKPOINT_MOUNT_PATH, "subPath": job_id },
+                        {
+                            "name": "metadata",
+                            "mountPath": METADATA_MOUNT_PATH,
+                            "subPath": "metadata.json",
+                            "readOnly": true,
+                        },
+                    ],
+                }],
+                "volumes": [
+                    { "name": "shm", "emptyDir": shm },
+                    checkpoint,
+                    {
+                        "name": "metadata",
+                        "downwardAPI": {
+                            "items": [{
+                                "path": "metadata.json",
+                                "fieldRef": {
+                                    "fieldPath": format!("metadata.annotations['{}']", METADATA_ANNOTATION),
+                                },
+                            }],
+                        },
+                    },
+                ],
+            },
+        }))
+    }
+
+    /// Starts copying the log of a job's container to
+    /// `~/.lilac/jobs/<job_id>/job.log` until the container exits. The whole
+    /// log is copied, so a restarted agent rewrites the file.
+    fn follow_logs(&self, job_id: &str) -> JoinHandle<()> {
+        let executor = self.clone();
+        let job_id = job_id.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = executor.copy_logs(&job_id).await {
+                eprintln!(
+                    "[KUBERNETES] Failed to follow the logs of job {}: {}",
+                    job_id, e
+                );
+            }
+        })
+    }
+
+    async fn copy_logs(&self, job_id: &str) -> Result<(), JobExecutorError> {
+        let path = config::get_config_path("jobs")
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?
+            .join(job_id)
+            .join("job.log");
+        if let Some(job_dir) = path.parent() {
+            tokio::fs::create_dir_all(job_dir)
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        }
+        let mut file = tokio::fs::File::create(&path)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let path = format!(
+            "{}/{}/log?container={}&follow=true",
+            self.pods_path(),
+            Self::pod_name(job_id),
+            CONTAINER_NAME
+        );
+        let mut response = Self::send(self.request(Method::GET, &path).await?).await?;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        }
+        // Writes to the file finish in the background until it is flushed.
+        file.flush()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        Ok(())
+    }
+}
+
+/// Follows the changes to a pod. Each watch ends after a while, after which
+/// the pod is read again and watched from its latest version.
+struct PodWatch<'a> {
+    executor: &'a KubernetesExecutor,
+    pod_name: String,
+    response: Option<Response>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> PodWatch<'a> {
+    fn new(executor: &'a KubernetesExecutor, pod_name: String) -> Self {
+        Self {
+            executor,
+            pod_name,
+            response: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Starts watching the pod and returns its current state. Errors other
+    /// than the pod being gone are retried, so that a job outlives a blip of
+    /// the API server.
+    async fn start(&mut self) -> Result<Pod, JobExecutorError> {
+        loop {
+            let result = match self.executor.get_pod(&self.pod_name).await {
+                Ok(Some(pod)) => self.watch(&pod).await.map(|response| (pod, response)),
+                Ok(None) => {
+                    return Err(anyhow::anyhow!("pod {} no longer exists", self.pod_name).into())
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok((pod, response)) => {
+                    self.response = Some(response);
+                    self.buffer.clear();
+                    return Ok(pod);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "[KUBERNETES] Failed to watch pod {}: {}. Will retry.",
+                        self.pod_name, e
+                    );
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    }
+
+    async fn watch(&self, pod: &Pod) -> Result<Response, JobExecutorError> {
+        let path = format!(
+            "{}?fieldSelector=metadata.name%3D{}&watch=true&timeoutSeconds={}&resourceVersion={}",
+            self.executor.pods_path(),
+            self.pod_name,
+            WATCH_TIMEOUT_SECS,
+            pod.metadata.resource_version.as_deref().unwrap_or_default()
+        );
+        KubernetesExecutor::send(self.executor.request(Method::GET, &path).await?).await
+    }
+
+    /// The pod's next state.
+    async fn next(&mut self) -> Result<Pod, JobExecutorError> {
+        loop {
+            let Some(response) = &mut self.response else {
+                return self.start().await;
+            };
+
+            // Events arrive as JSON, one per line.
+            if let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let event: WatchEvent = serde_json::from_slice(&line)
+                    .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+                match event.kind.as_str() {
+                    "ADDED" | "MODIFIED" => {
+                        return serde_json::from_value(event.object)
+                            .map_err(|e| JobExecutorError::Unknown(e.into()))
+                    }
+                    "DELETED" => {
+                        return Err(anyhow::anyhow!("pod {} was deleted", self.pod_name).into())
+                    }
+                    // E.g. the version watched from is too old to watch from.
+                    _ => self.response = None,
+                }
+                continue;
+            }
+
+            match response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                // The watch timed out or was cut off.
+                Ok(None) | Err(_) => self.response = None,
+            }
+        }
+    }
+}
+
+/// Parses a Kubernetes quantity, e.g. `250m`, `1500000n` or `512Mi`.
+fn parse_quantity(quantity: &str) -> Option<f64> {
+    const SUFFIXES: &[(&str, f64)] = &[
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+    let quantity = quantity.trim();
+    for (suffix, multiplier) in SUFFIXES {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<f64>().ok().map(|number| number * multiplier);
+        }
+    }
+    quantity.parse().ok()
+}
+
+#[async_trait]
+impl JobExecutor for KubernetesExecutor {
+    async fn run_job(
+        &self,
+        job_details: JobDetails,
+        _resources: &NodeResources,
+        gpus: &[Gpu],
+    ) -> Result<JobExit, JobExecutorError> {
+        let job_id = job_details.id.to_string();
+        println!("[KUBERNETES] Starting job: {}", job_id);
+
+        let metadata = JobMetadata::new(
+            &job_details,
+            gpus,
+            self.config.node_id,
+            &self.config.api_endpoint,
+        );
+        let pod = self.pod_manifest(&job_details, gpus, &metadata)?;
+        let request = self
+            .request(Method::POST, &self.pods_path())
+            .await?
+            .json(&pod);
+        Self::send(request).await?;
+        println!("[KUBERNETES] Created pod: {}", Self::pod_name(&job_id));
+
+        self.wait_job(&job_id).await
+    }
+
+    async fn wait_job(&self, job_id: &str) -> Result<JobExit, JobExecutorError> {
+        let pod_name = Self::pod_name(job_id);
+        let mut watch = PodWatch::new(self, pod_name.clone());
+        let mut logs = None;
+        let result = loop {
+            let pod = match watch.next().await {
+                Ok(pod) => pod,
+                Err(e) => break Err(e),
+            };
+            if logs.is_none() && pod.has_started() {
+                logs = Some(self.follow_logs(job_id));
+            }
+            if let Some(result) = pod.outcome() {
+                break result;
+            }
+        };
+        drop(watch);
+
+        // Give the log a moment to catch up before the pod is deleted.
+        if let Some(logs) = logs {
+            let _ = tokio::time::timeout(Duration::from_secs(10), logs).await;
+        }
+        if let Err(e) = self.delete_pod(&pod_name).await {
+            eprintln!("[KUBERNETES] Failed to delete pod {}: {}", pod_name, e);
+        } else {
+            println!("[KUBERNETES] Deleted pod: {}", pod_name);
+        }
+
+        let exit = result?;
+        println!(
+            "[JOB {}] Execution finished with exit code: {}",
+            job_id, exit.exit_code
+        );
+        Ok(exit)
+    }
+
+    async fn stop_job(&self, job_id: &str) -> Result<(), JobExecutorError> {
+        let pod_name = Self::pod_name(job_id);
+        println!("[KUBERNETES] Stopping pod: {}", pod_name);
+        self.delete_pod(&pod_name).await?;
+
+        // Like `docker stop`, return once the container is gone. Kubernetes
+        // sends SIGTERM, then SIGKILL once the grace period is over.
+        let mut deadline = None;
+        while let Some(pod) = self.get_pod(&pod_name).await? {
+            let grace_period = pod
+                .metadata
+                .deletion_grace_period_seconds
+                .unwrap_or_default();
+            let deadline = *deadline
+                .get_or_insert_with(|| Instant::now() + Duration::from_secs(grace_period + 30));
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!("pod {} is still terminating", pod_name).into());
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        println!("[KUBERNETES] Stopped pod: {}", pod_name);
+
+        Ok(())
+    }
+
+    async fn list_jobs(&self) -> Result<Vec<Uuid>, JobExecutorError> {
+        let path = format!(
+            "{}?labelSelector={}%3D{}",
+            self.pods_path(),
+            NODE_ID_LABEL,
+            self.config.node_id
+        );
+        let pods: PodList = Self::send(self.request(Method::GET, &path).await?)
+            .await?
+            .json()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let job_ids = pods
+            .items
+            .iter()
+            .filter_map(|pod| pod.metadata.labels.get(JOB_ID_LABEL))
+            .filter_map(|job_id| job_id.parse().ok())
+            .collect();
+
+        Ok(job_ids)
+    }
+
+    /// Kubernetes nodes pull the images of pods themselves.
+    async fn pull_image(&self, _image: &str) -> Result<(), JobExecutorError> {
+        Ok(())
+    }
+
+    async fn list_images(&self) -> Result<Vec<CachedImage>, JobExecutorError> {
+        Ok(Vec::new())
+    }
+
+    async fn remove_image(&self, image: &CachedImage) -> Result<(), JobExecutorError> {
+        Err(anyhow::anyhow!("image {} was not pulled by this agent", image.id).into())
+    }
+
+    /// Reads the usage of the job's pod from the metrics API, which needs
+    /// the metrics server. Only CPU and memory are known.
+    async fn get_job_usage(&self, job_id: &str) -> Result<JobUsage, JobExecutorError> {
+        let path = format!(
+            "/apis/metrics.k8s.io/v1beta1/namespaces/{}/pods/{}",
+            self.namespace,
+            Self::pod_name(job_id)
+        );
+        let metrics: PodMetrics = Self::send(self.request(Method::GET, &path).await?)
+            .await?
+            .json()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let usage = |resource: &str| -> f64 {
+            metrics
+                .containers
+                .iter()
+                .filter_map(|container| container.usage.get(resource))
+                .filter_map(|quantity| parse_quantity(quantity))
+                .sum()
+        };
+        let memory_mb = (usage("memory") / 1024.0 / 1024.0) as i32;
+
+        Ok(JobUsage {
+            cpu_millicores: (usage("cpu") * 1000.0) as i32,
+            memory_rss_mb: memory_mb,
+            memory_peak_mb: memory_mb,
+            ..Default::default()
+        })
+    }
+
+    /// Pods run on other machines, so none of their processes are local.
+    async fn get_job_pids(&self, _job_id: &str) -> Result<Vec<u32>, JobExecutorError> {
+        Ok(Vec::new())
+    }
+
+    async fn exec(
+        &self,
+        job_id: &str,
+        _command: Vec<String>,
+        _tty: bool,
+    ) -> Result<ExecProcess, JobExecutorError> {
+        Err(anyhow::anyhow!(
+            "exec isn't supported for job {}; use kubectl exec on pod {}",
+            job_id,
+            Self::pod_name(job_id)
+        )
+        .into())
+    }
+
+    async fn resize_exec(
+        &self,
+        exec_id: &str,
+        _cols: u16,
+        _rows: u16,
+    ) -> Result<(), JobExecutorError> {
+        Err(anyhow::anyhow!("exec {} not found", exec_id).into())
+    }
+
+    async fn get_exec_exit_code(&self, exec_id: &str) -> Result<i64, JobExecutorError> {
+        Err(anyhow::anyhow!("exec {} not found", exec_id).into())
+    }
+
+    /// Pods are reached at their IP, so this only works for agents running
+    /// in the same cluster.
+    async fn get_port_address(
+        &self,
+        job_id: &str,
+        port: u16,
+    ) -> Result<SocketAddr, JobExecutorError> {
+        let pod_name = Self::pod_name(job_id);
+        let pod = self
+            .get_pod(&pod_name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("pod {} not found", pod_name))?;
+        let ip = pod
+            .status
+            .pod_ip
+            .as_deref()
+            .and_then(|ip| IpAddr::from_str(ip).ok())
+            .ok_or_else(|| anyhow::anyhow!("pod {} has no IP yet", pod_name))?;
+
+        Ok(SocketAddr::new(ip, port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ExecutorKind, ShutdownMode},
+        domain::agent::models::{
+            Architecture, Cpu, CpuManufacturer, GpuManufacturer, GpuModel, JobResourceRequirements,
+            RequestedGpus,
+        },
+        outbound::TEST_ENV_LOCK,
+    };
+    use axum::{
+        extract::State,
+        response::{IntoResponse, Response as AxumResponse},
+        routing::get,
+        Json, Router,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// What the fake API server knows about the one pod it serves.
+    #[derive(Default)]
+    struct FakeCluster {
+        /// The pod as it is read, or `None` while there is none.
+        pod: Option<Value>,
+        /// The pod a job created.
+        created: Option<Value>,
+        /// The events each watch of the pod streams before it ends.
+        events: Vec<Value>,
+        deleted: bool,
+        /// How many more times a deleted pod is read before it is gone.
+        reads_after_delete: usize,
+        /// The requests the server received, e.g. `DELETE pod`.
+        requests: Vec<String>,
+    }
+
+    type FakeApi = Arc<Mutex<FakeCluster>>;
+
+    fn not_found() -> AxumResponse {
+        let status = json!({ "kind": "Status", "message": "pods not found" });
+        (axum::http::StatusCode::NOT_FOUND, Json(status)).into_response()
+    }
+
+    async fn create_pod(State(api): State<FakeApi>, Json(manifest): Json<Value>) -> AxumResponse {
+        let mut cluster = api.lock().unwrap();
+        cluster.requests.push("POST pods".to_string());
+        cluster.pod = Some(pod_with(json!({ "phase": "Pending" })));
+        cluster.created = Some(manifest.clone());
+        (axum::http::StatusCode::CREATED, Json(manifest)).into_response()
+    }
+
+    async fn watch_pods(State(api): State<FakeApi>) -> String {
+        let mut cluster = api.lock().unwrap();
+        cluster.requests.push("WATCH pods".to_string());
+        cluster
+            .events
+            .iter()
+            .map(|event| format!("{}\n", event))
+            .collect()
+    }
+
+    async fn read_pod(State(api): State<FakeApi>) -> AxumResponse {
+        let mut cluster = api.lock().unwrap();
+        cluster.requests.push("GET pod".to_string());
+        if cluster.deleted {
+            if cluster.reads_after_delete == 0 {
+                cluster.pod = None;
+            } else {
+                cluster.reads_after_delete -= 1;
+            }
+        }
+        match &cluster.pod {
+            Some(pod) => Json(pod.clone()).into_response(),
+            None => not_found(),
+        }
+    }
+
+    async fn delete_pod(State(api): State<FakeApi>) -> AxumResponse {
+        let mut cluster = api.lock().unwrap();
+        cluster.requests.push("DELETE pod".to_string());
+        match &cluster.pod {
+            Some(pod) => {
+                let pod = pod.clone();
+                cluster.deleted = true;
+                Json(pod).into_response()
+            }
+            None => not_found(),
+        }
+    }
+
+    /// Serves the parts of the Kubernetes API the executor uses, for the
+    /// pods of the `lilac` namespace, and returns its URL.
+    async fn serve(api: FakeApi) -> String {
+        let app = Router::new()
+            .route(
+                "/api/v1/namespaces/lilac/pods",
+                get(watch_pods).post(create_pod),
+            )
+            .route(
+                "/api/v1/namespaces/lilac/pods/{name}",
+                get(read_pod).delete(delete_pod),
+            )
+            .route(
+                "/api/v1/namespaces/lilac/pods/{name}/log",
+                get(|| async { "epoch 1: loss 0.42\n" }),
+            )
+            .with_state(api);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    fn pod_with(status: Value) -> Value {
+        json!({
+            "metadata": { "resourceVersion": "1", "deletionGracePeriodSeconds": 30 },
+            "status": status,
+        })
+    }
+
+    fn modified(container_state: Value) -> Value {
+        json!({
+            "type": "MODIFIED",
+            "object": pod_with(json!({
+                "phase": "Running",
+                "containerStatuses": [{ "name": CONTAINER_NAME, "state": container_state }],
+            })),
+        })
+    }
+
+    fn executor(api_server: &str, kubernetes: KubernetesConfig) -> KubernetesExecutor {
+        let config = AgentConfig {
+            api_endpoint: "http://localhost:8080".to_string(),
+            cluster_api_key: "key".to_string(),
+            node_id: Uuid::new_v4(),
+            private_registry: None,
+            checkpoint_root: None,
+            image_cache_budget_gb: None,
+            executor: ExecutorKind::Kubernetes,
+            container_socket: None,
+            kubernetes: Some(KubernetesConfig {
+                api_server: Some(api_server.to_string()),
+                namespace: Some("lilac".to_string()),
+                ..kubernetes
+            }),
+            slurm: None,
+            shutdown_mode: ShutdownMode::Stop,
+        };
+        KubernetesExecutor::new(config).unwrap()
+    }
+
+    fn job_details() -> JobDetails {
+        JobDetails {
+            id: Uuid::new_v4(),
+            docker_uri: "pytorch/pytorch:2.1.0".to_string(),
+            env: HashMap::new(),
+            exposed_ports: Vec::new(),
+            termination_grace_period_secs: 30,
+            name: "train".to_string(),
+            queue_id: None,
+            cluster_id: None,
+            resource_requirements: JobResourceRequirements::default(),
+            attempt: 1,
+            job_token: None,
+        }
+    }
+
+    fn gpu(index: u32) -> Gpu {
+        Gpu {
+            manufacturer: GpuManufacturer::Nvidia,
+            model: GpuModel::A100,
+            count: 1,
+            memory_mb: 40960,
+            index,
+            uuid: None,
+        }
+    }
+
+    fn resources() -> NodeResources {
+        NodeResources {
+            cpu: Cpu {
+                manufacturer: CpuManufacturer::Intel,
+                architecture: Architecture::X86_64,
+                millicores: 8000,
+            },
+            gpus: Vec::new(),
+            memory_mb: 16384,
+        }
+    }
+
+    fn manifest(executor: &KubernetesExecutor, job_details: &JobDetails, gpus: &[Gpu]) -> Value {
+        let metadata = JobMetadata::new(
+            job_details,
+            gpus,
+            executor.config.node_id,
+            "http://localhost:8080",
+        );
+        executor.pod_manifest(job_details, gpus, &metadata).unwrap()
+    }
+
+    /// The value a container sees for a variable: that of its last entry.
+    fn env_value<'a>(pod: &'a Value, name: &str) -> Option<&'a str> {
+        pod["spec"]["containers"][0]["env"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|var| var["name"] == name)
+            .and_then(|var| var["value"].as_str())
+    }
+
+    fn error_message(result: Result<JobExit, JobExecutorError>) -> String {
+        match result {
+            Err(JobExecutorError::Unknown(e)) => e.to_string(),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pod_manifest_limits() {
+        let executor = executor("http://127.0.0.1:1", KubernetesConfig::default());
+        let mut job_details = job_details();
+        job_details.resource_requirements = JobResourceRequirements {
+            cpu_millicores: 1500,
+            memory_mb: 4096,
+            gpus: Some(RequestedGpus { count: 2 }),
+        };
+
+        let pod = manifest(&executor, &job_details, &[gpu(0), gpu(1)]);
+
+        let resources = &pod["spec"]["containers"][0]["resources"];
+        assert_eq!(
+            resources["limits"],
+            json!({ "cpu": "1500m", "memory": "4096Mi", "nvidia.com/gpu": "2" })
+        );
+        assert_eq!(resources["requests"], resources["limits"]);
+        assert_eq!(pod["spec"]["volumes"][0]["emptyDir"]["sizeLimit"], "2048Mi");
+        assert_eq!(env_value(&pod, "NVIDIA_VISIBLE_DEVICES"), None);
+        assert_eq!(env_value(&pod, "LILAC_GPU_INDICES"), Some("0,1"));
+    }
+
+    #[test]
+    fn test_pod_manifest_without_limits_hides_gpus() {
+        let executor = executor("http://127.0.0.1:1", KubernetesConfig::default());
+
+        let pod = manifest(&executor, &job_details(), &[]);
+
+        let resources = &pod["spec"]["containers"][0]["resources"];
+        assert_eq!(resources["limits"], json!({}));
+        assert_eq!(
+            pod["spec"]["volumes"][0]["emptyDir"],
+            json!({ "medium": "Memory" })
+        );
+        assert_eq!(env_value(&pod, "NVIDIA_VISIBLE_DEVICES"), Some("void"));
+    }
+
+    #[test]
+    fn test_pod_manifest_env_precedence() {
+        let executor = executor("http://127.0.0.1:1", KubernetesConfig::default());
+        let mut job_details = job_details();
+        job_details.env = HashMap::from([
+            ("EPOCHS".to_string(), "3".to_string()),
+            ("LILAC_JOB_ID".to_string(), "spoofed".to_string()),
+            ("LILAC_CHECKPOINT_DIR".to_string(), "/tmp".to_string()),
+        ]);
+
+        let pod = manifest(&executor, &job_details, &[]);
+
+        assert_eq!(env_value(&pod, "EPOCHS"), Some("3"));
+        assert_eq!(
+            env_value(&pod, "LILAC_JOB_ID"),
+            Some(job_details.id.to_string().as_str())
+        );
+        assert_eq!(
+            env_value(&pod, "LILAC_CHECKPOINT_DIR"),
+            Some(CHECKPOINT_MOUNT_PATH)
+        );
+        assert_eq!(
+            env_value(&pod, "LILAC_METADATA_FILE"),
+            Some(METADATA_MOUNT_PATH)
+        );
+    }
+
+    #[test]
+    fn test_pod_manifest_pull_secrets_and_labels() {
+        let kubernetes = KubernetesConfig {
+            image_pull_secrets: vec!["registry".to_string(), "mirror".to_string()],
+            checkpoint_claim: Some("checkpoints".to_string()),
+            ..Default::default()
+        };
+        let executor = executor("http://127.0.0.1:1", kubernetes);
+        let job_details = job_details();
+
+        let pod = manifest(&executor, &job_details, &[]);
+
+        assert_eq!(
+            pod["spec"]["imagePullSecrets"],
+            json!([{ "name": "registry" }, { "name": "mirror" }])
+        );
+        assert_eq!(
+            pod["spec"]["volumes"][1]["persistentVolumeClaim"]["claimName"],
+            "checkpoints"
+        );
+        let job_id = job_details.id.to_string();
+        assert_eq!(
+            pod["metadata"]["name"],
+            KubernetesExecutor::pod_name(&job_id)
+        );
+        assert_eq!(pod["metadata"]["labels"][JOB_ID_LABEL], job_id);
+        assert_eq!(
+            pod["metadata"]["labels"][NODE_ID_LABEL],
+            executor.config.node_id.to_string()
+        );
+        assert_eq!(pod["spec"]["restartPolicy"], "Never");
+        assert_eq!(pod["spec"]["terminationGracePeriodSeconds"], 30);
+    }
+
+    /// Runs a job whose pod goes through `events`, and returns how it ended
+    /// along with what the fake API server saw.
+    async fn run_job_through(events: Vec<Value>) -> (Result<JobExit, JobExecutorError>, FakeApi) {
+        let api = FakeApi::new(Mutex::new(FakeCluster {
+            events,
+            ..Default::default()
+        }));
+        let executor = executor(&serve(api.clone()).await, KubernetesConfig::default());
+
+        let result = executor.run_job(job_details(), &resources(), &[]).await;
+        (result, api)
+    }
+
+    #[tokio::test]
+    async fn test_run_job_exit_code() {
+        let _env = TEST_ENV_LOCK.lock().await;
+        let home = tempfile::tempdir().unwrap();
+        env::set_var("HOME", home.path());
+
+        let (result, api) = run_job_through(vec![
+            modified(json!({ "running": { "startedAt": "2025-08-22T12:00:00Z" } })),
+            modified(json!({ "terminated": { "exitCode": 3, "reason": "Error" } })),
+        ])
+        .await;
+
+        let exit = result.unwrap();
+        assert_eq!(exit.exit_code, 3);
+        assert!(!exit.oom_killed);
+        let cluster = api.lock().unwrap();
+        assert_eq!(
+            cluster.requests.first().map(String::as_str),
+            Some("POST pods")
+        );
+        assert_eq!(
+            cluster.requests.last().map(String::as_str),
+            Some("DELETE pod")
+        );
+        let job_id = cluster.created.as_ref().unwrap()["metadata"]["labels"][JOB_ID_LABEL]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let log = fs::read_to_string(home.path().join(".lilac/jobs").join(job_id).join("job.log"));
+        assert_eq!(log.unwrap(), "epoch 1: loss 0.42\n");
+    }
+
+    #[tokio::test]
+    async fn test_run_job_oom_killed() {
+        let _env = TEST_ENV_LOCK.lock().await;
+        let home = tempfile::tempdir().unwrap();
+        env::set_var("HOME", home.path());
+
+        let (result, _) = run_job_through(vec![modified(
+            json!({ "terminated": { "exitCode": 137, "reason": "OOMKilled" } }),
+        )])
+        .await;
+
+        let exit = result.unwrap();
+        assert_eq!(exit.exit_code, 137);
+        assert!(exit.oom_killed);
+    }
+
+    #[tokio::test]
+    async fn test_run_job_image_pull_back_off() {
+        let (result, api) = run_job_through(vec![
+            modified(json!({ "waiting": { "reason": "ContainerCreating" } })),
+            modified(json!({
+                "waiting": { "reason": "ImagePullBackOff", "message": "Back-off pulling image" }
+            })),
+        ])
+        .await;
+
+        let message = error_message(result);
+        assert!(message.contains("ImagePullBackOff"), "{}", message);
+        assert!(message.contains("Back-off pulling image"), "{}", message);
+        let cluster = api.lock().unwrap();
+        assert_eq!(
+            cluster.requests.last().map(String::as_str),
+            Some("DELETE pod")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_job_pod_failed() {
+        let (result, _) = run_job_through(vec![json!({
+            "type": "MODIFIED",
+            "object": pod_with(json!({
+                "phase": "Failed",
+                "reason": "Evicted",
+                "message": "The node was low on resource: memory.",
+            })),
+        })])
+        .await;
+
+        let message = error_message(result);
+        assert!(message.contains("Evicted"), "{}", message);
+        assert!(message.contains("low on resource"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_waits_for_pod_to_go() {
+        let api = FakeApi::new(Mutex::new(FakeCluster {
+            pod: Some(modified(json!({ "running": {} }))["object"].clone()),
+            reads_after_delete: 2,
+            ..Default::default()
+        }));
+        let executor = executor(&serve(api.clone()).await, KubernetesConfig::default());
+
+        executor
+            .stop_job(&Uuid::new_v4().to_string())
+            .await
+            .unwrap();
+
+        let cluster = api.lock().unwrap();
+        assert_eq!(
+            cluster.requests,
+            ["DELETE pod", "GET pod", "GET pod", "GET pod"]
+        );
+        assert!(cluster.pod.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_of_pod_already_gone() {
+        let api = FakeApi::default();
+        let executor = executor(&serve(api.clone()).await, KubernetesConfig::default());
+
+        executor
+            .stop_job(&Uuid::new_v4().to_string())
+            .await
+            .unwrap();
+
+        let cluster = api.lock().unwrap();
+        assert_eq!(cluster.requests, ["DELETE pod", "GET pod"]);
+    }
+}
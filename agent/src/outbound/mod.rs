@@ -1,4 +1,14 @@
 pub mod control_plane;
 pub mod docker;
+pub mod kubernetes;
+pub mod process;
+pub mod slurm;
+pub mod state;
 pub mod system;
-pub mod user_api;
\ No newline at end of file
+pub mod user_api;
+pub mod websocket;
+
+/// Serializes the tests that change the process's environment, e.g. `HOME`
+/// or `PATH`, which every test running at the same time sees.
+#[cfg(test)]
+pub(crate) static TEST_ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
@@ -0,0 +1,894 @@
+use crate::{
+    config::{self, AgentConfig},
+    domain::agent::{
+        models::{
+            CachedImage, ExecProcess, Gpu, JobDetails, JobExit, JobMetadata,
+            JobResourceRequirements, JobUsage, NodeResources,
+        },
+        ports::JobExecutor,
+    },
+    errors::JobExecutorError,
+};
+use async_trait::async_trait;
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+
+/// The most processes a job can run at once, so that a runaway job can't
+/// exhaust the node's PIDs.
+const PIDS_LIMIT: i64 = 4096;
+/// Runs a job's command. It joins the job's cgroup, if it has one, and
+/// records the command's exit code, so that a restarted agent can still
+/// learn how the job ended. `$1` is the command, `$2` the exit code file and
+/// `$3` the cgroup's `cgroup.procs` file, or empty. SIGTERM is sent to the
+/// whole process group, so the wrapper traps it to outlive the command for
+/// its grace period; a trap, unlike ignoring the signal, isn't inherited.
+const WRAPPER_SCRIPT: &str = r#"trap : TERM; [ -n "$3" ] && echo $$ > "$3"; /bin/sh -c "$1"; code=$?; echo $code > "$2.tmp" && mv "$2.tmp" "$2"; exit $code"#;
+
+/// What the agent needs to know about a job's process after it restarts.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProcessRecord {
+    /// The PID of the job's wrapper, which also leads its process group.
+    pid: i32,
+    termination_grace_period_secs: i64,
+    /// The job's environment, which commands run with `exec` get too.
+    env: Vec<(String, String)>,
+}
+
+/// Runs jobs as plain processes on the node, for nodes that can't run
+/// containers. A job's definition is the shell command to run.
+///
+/// Each job runs in `~/.lilac/jobs/<job_id>/work`, with its output in
+/// `~/.lilac/jobs/<job_id>/job.log`. If the agent was delegated a cgroup v2
+/// subtree, e.g. by systemd's `Delegate=yes`, jobs are limited to the
+/// resources they asked for like containers are; otherwise they run
+/// unlimited.
+#[derive(Clone)]
+pub struct ProcessExecutor {
+    config: AgentConfig,
+    /// The cgroup that job cgroups are created in, if the agent has one.
+    cgroup_root: Option<PathBuf>,
+    /// The processes of jobs started by this agent, which must be waited on
+    /// for them to be reaped.
+    children: Arc<Mutex<HashMap<String, Child>>>,
+    /// Commands started with `exec`, until their exit codes are collected.
+    execs: Arc<Mutex<HashMap<String, Child>>>,
+}
+
+impl ProcessExecutor {
+    pub fn new(config: AgentConfig) -> Result<Self, JobExecutorError> {
+        let cgroup_root = Self::prepare_cgroup_root();
+        match &cgroup_root {
+            Some(root) => println!("[PROCESS] Limiting jobs with cgroups under {}", root.display()),
+            None => println!("[PROCESS] No cgroup v2 subtree is delegated to the agent; jobs run without resource limits."),
+        }
+        Ok(Self {
+            config,
+            cgroup_root,
+            children: Arc::new(Mutex::new(HashMap::new())),
+            execs: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Sets up the agent's cgroup to hold one child cgroup per job. A cgroup
+    /// with processes can't hand controllers to its children, so the agent
+    /// first moves itself into a child cgroup of its own.
+    fn prepare_cgroup_root() -> Option<PathBuf> {
+        let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+        let path = cgroups.lines().find_map(|line| line.strip_prefix("0::"))?;
+        let root = Path::new("/sys/fs/cgroup").join(path.trim_start_matches('/'));
+
+        let agent = root.join("agent");
+        fs::create_dir_all(&agent).ok()?;
+        fs::write(agent.join("cgroup.procs"), std::process::id().to_string()).ok()?;
+        for controller in ["+cpu", "+memory", "+pids", "+io"] {
+            let _ = fs::write(root.join("cgroup.subtree_control"), controller);
+        }
+        let controllers = fs::read_to_string(root.join("cgroup.subtree_control")).ok()?;
+        controllers.contains("memory").then_some(root)
+    }
+
+    fn jobs_dir() -> Result<PathBuf, JobExecutorError> {
+        config::get_config_path("jobs").map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    fn job_dir(job_id: &str) -> Result<PathBuf, JobExecutorError> {
+        Ok(Self::jobs_dir()?.join(job_id))
+    }
+
+    fn cgroup_dir(&self, job_id: &str) -> Option<PathBuf> {
+        self.cgroup_root
+            .as_ref()
+            .map(|root| root.join(format!("lilac-job-{}", job_id)))
+    }
+
+    async fn read_record(job_id: &str) -> Result<ProcessRecord, JobExecutorError> {
+        let path = Self::job_dir(job_id)?.join("process.json");
+        let contents = tokio::fs::read(&path)
+            .await
+            .map_err(|e| anyhow::anyhow!("job {} has no process: {}", job_id, e))?;
+        serde_json::from_slice(&contents).map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    /// Creates the cgroup of a job, limited to the CPU and memory the job
+    /// asked for. Swap is disabled, so a job that outgrows its memory is
+    /// OOM-killed instead of slowing down the whole node. Limits that can't
+    /// be set are logged and skipped.
+    fn create_cgroup(
+        cgroup: &Path,
+        requirements: &JobResourceRequirements,
+        resources: &NodeResources,
+    ) -> Result<(), JobExecutorError> {
+        fs::create_dir_all(cgroup).map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let mut limits = vec![("pids.max", PIDS_LIMIT.to_string())];
+        if requirements.cpu_millicores > 0 {
+            let millicores = requirements.cpu_millicores.min(resources.cpu.millicores);
+            // The quota is in microseconds per 100ms period.
+            limits.push(("cpu.max", format!("{} 100000", millicores as i64 * 100)));
+        }
+        if requirements.memory_mb > 0 {
+            let memory_bytes = requirements.memory_mb as i64 * 1024 * 1024;
+            limits.push(("memory.max", memory_bytes.to_string()));
+            limits.push(("memory.swap.max", "0".to_string()));
+        }
+        for (file, value) in limits {
+            if let Err(e) = fs::write(cgroup.join(file), &value) {
+                eprintln!(
+                    "[PROCESS] Failed to set {} of {}: {}",
+                    file,
+                    cgroup.display(),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The job's GPUs, for CUDA. UUIDs are preferred, since CUDA numbers
+    /// GPUs differently from NVML unless told to use PCI bus order.
+    fn gpu_env(gpus: &[Gpu]) -> Vec<(String, String)> {
+        let devices = gpus
+            .iter()
+            .map(|gpu| gpu.uuid.clone().unwrap_or_else(|| gpu.index.to_string()))
+            .collect::<Vec<_>>()
+            .join(",");
+        vec![
+            ("CUDA_DEVICE_ORDER".to_string(), "PCI_BUS_ID".to_string()),
+            // An empty list hides every GPU from jobs that asked for none.
+            ("CUDA_VISIBLE_DEVICES".to_string(), devices),
+        ]
+    }
+
+    /// Lists the processes of a job: those in its cgroup, or without one,
+    /// those in its process group.
+    fn job_pids(&self, job_id: &str, pid: i32) -> Vec<u32> {
+        if let Some(procs) = self
+            .cgroup_dir(job_id)
+            .and_then(|cgroup| fs::read_to_string(cgroup.join("cgroup.procs")).ok())
+        {
+            return procs
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect();
+        }
+
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|candidate| {
+                let Ok(stat) = fs::read_to_string(format!("/proc/{}/stat", candidate)) else {
+                    return false;
+                };
+                // The fields after the command name are the state, the parent
+                // PID and the process group.
+                let Some((_, fields)) = stat.rsplit_once(')') else {
+                    return false;
+                };
+                let fields: Vec<&str> = fields.split_whitespace().collect();
+                fields.len() > 2 && fields[0] != "Z" && fields[2] == pid.to_string()
+            })
+            .collect()
+    }
+
+    /// Sends a signal to every process of a job.
+    fn signal_job(&self, job_id: &str, pid: i32, signal: i32) {
+        // SAFETY: kill(2) has no memory safety requirements. A negative PID
+        // signals the job's process group.
+        unsafe {
+            libc::kill(-pid, signal);
+        }
+        // Processes that left the process group are still in the cgroup.
+        if signal == libc::SIGKILL {
+            if let Some(cgroup) = self.cgroup_dir(job_id) {
+                let _ = fs::write(cgroup.join("cgroup.kill"), "1");
+            }
+        }
+    }
+
+    /// Whether the kernel killed any process of the job for exceeding its
+    /// memory limit.
+    fn oom_killed(&self, job_id: &str) -> bool {
+        self.cgroup_dir(job_id)
+            .and_then(|cgroup| fs::read_to_string(cgroup.join("memory.events")).ok())
+            .and_then(|events| {
+                events
+                    .lines()
+                    .find_map(|line| line.strip_prefix("oom_kill "))
+                    .and_then(|count| count.trim().parse::<u64>().ok())
+            })
+            .is_some_and(|count| count > 0)
+    }
+
+    /// Kills whatever is left of a job and removes everything but its log.
+    async fn clean_up(&self, job_id: &str, pid: i32) {
+        self.signal_job(job_id, pid, libc::SIGKILL);
+        if let Some(cgroup) = self.cgroup_dir(job_id) {
+            // A cgroup can only be removed once its processes are gone.
+            for _ in 0..50 {
+                if tokio::fs::remove_dir(&cgroup).await.is_ok() || !cgroup.exists() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+        if let Ok(job_dir) = Self::job_dir(job_id) {
+            let _ = tokio::fs::remove_dir_all(job_dir.join("work")).await;
+            for file in ["process.json", "exit_code", "metadata.json"] {
+                let _ = tokio::fs::remove_file(job_dir.join(file)).await;
+            }
+        }
+        println!("[PROCESS] Cleaned up job {}", job_id);
+    }
+
+    /// Reads everything written to `reader` in chunks.
+    fn read_chunks<R>(reader: R) -> impl Stream<Item = Result<Vec<u8>, JobExecutorError>>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        stream::unfold(reader, |mut reader| async move {
+            let mut buf = vec![0u8; 4096];
+            match reader.read(&mut buf).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buf.truncate(n);
+                    Some((Ok(buf), reader))
+                }
+                Err(e) => Some((Err(JobExecutorError::Unknown(e.into())), reader)),
+            }
+        })
+    }
+
+    /// Reads the cgroup's CPU time in microseconds.
+    async fn cpu_usage_usec(cgroup: &Path) -> Option<u64> {
+        let stat = tokio::fs::read_to_string(cgroup.join("cpu.stat"))
+            .await
+            .ok()?;
+        stat.lines()
+            .find_map(|line| line.strip_prefix("usage_usec "))
+            .and_then(|usage| usage.trim().parse().ok())
+    }
+
+    async fn read_u64(path: PathBuf) -> Option<u64> {
+        tokio::fs::read_to_string(path)
+            .await
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+}
+
+#[async_trait]
+impl JobExecutor for ProcessExecutor {
+    async fn run_job(
+        &self,
+        job_details: JobDetails,
+        resources: &NodeResources,
+        gpus: &[Gpu],
+    ) -> Result<JobExit, JobExecutorError> {
+        let job_id = job_details.id.to_string();
+        println!("[PROCESS] Starting job: {}", job_id);
+
+        let job_dir = Self::job_dir(&job_id)?;
+        let work_dir = job_dir.join("work");
+        tokio::fs::create_dir_all(&work_dir)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let checkpoint_dir = self
+            .config
+            .checkpoint_root()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?
+            .join(&job_id);
+        tokio::fs::create_dir_all(&checkpoint_dir)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let metadata = JobMetadata::new(
+            &job_details,
+            gpus,
+            self.config.node_id,
+            &self.config.api_endpoint,
+        );
+        let metadata_path = job_dir.join("metadata.json");
+        let contents = serde_json::to_vec_pretty(&metadata)
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        tokio::fs::write(&metadata_path, contents)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let mut env: Vec<(String, String)> = job_details.env.clone().into_iter().collect();
+        // Lilac's own variables come last, so that they win over the job's.
+        env.extend(metadata.env());
+        env.extend(Self::gpu_env(gpus));
+        env.push((
+            "LILAC_METADATA_FILE".to_string(),
+            metadata_path.display().to_string(),
+        ));
+        env.push((
+            "LILAC_CHECKPOINT_DIR".to_string(),
+            checkpoint_dir.display().to_string(),
+        ));
+
+        let cgroup_procs = match self.cgroup_dir(&job_id) {
+            Some(cgroup) => {
+                Self::create_cgroup(&cgroup, &job_details.resource_requirements, resources)?;
+                cgroup.join("cgroup.procs").display().to_string()
+            }
+            None => String::new(),
+        };
+
+        let log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(job_dir.join("job.log"))
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let log_err = log
+            .try_clone()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let child = Command::new("/bin/sh")
+            .arg("-c")
+            .arg(WRAPPER_SCRIPT)
+            .arg("sh")
+            .arg(&job_details.docker_uri)
+            .arg(job_dir.join("exit_code"))
+            .arg(cgroup_procs)
+            .current_dir(&work_dir)
+            .envs(env.iter().cloned())
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(log))
+            .stderr(Stdio::from(log_err))
+            // Leading its own process group lets the job be signalled as a
+            // whole, and keeps it running if the agent is interrupted.
+            .process_group(0)
+            .spawn()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let pid = child
+            .id()
+            .ok_or_else(|| anyhow::anyhow!("job {} exited before it started", job_id))?;
+        println!("[PROCESS] Started job {} as process {}", job_id, pid);
+
+        let record = ProcessRecord {
+            pid: pid as i32,
+            termination_grace_period_secs: job_details.termination_grace_period_secs,
+            env,
+        };
+        let contents =
+            serde_json::to_vec_pretty(&record).map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        tokio::fs::write(job_dir.join("process.json"), contents)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        self.children.lock().unwrap().insert(job_id.clone(), child);
+
+        self.wait_job(&job_id).await
+    }
+
+    async fn wait_job(&self, job_id: &str) -> Result<JobExit, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let child = self.children.lock().unwrap().remove(job_id);
+        let exit_code = match child {
+            Some(mut child) => {
+                let status = child
+                    .wait()
+                    .await
+                    .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+                // Like a shell, report death by a signal as 128 + the signal.
+                status
+                    .code()
+                    .or_else(|| status.signal().map(|signal| 128 + signal))
+                    .unwrap_or(-1) as i64
+            }
+            None => {
+                // The job was started by an earlier run of the agent, so its
+                // exit code can only be read from the file its wrapper wrote.
+                while !self.job_pids(job_id, record.pid).is_empty() {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                let exit_code = Self::job_dir(job_id)?.join("exit_code");
+                tokio::fs::read_to_string(&exit_code)
+                    .await
+                    .ok()
+                    .and_then(|code| code.trim().parse().ok())
+                    .ok_or_else(|| anyhow::anyhow!("job {} exited without an exit code", job_id))?
+            }
+        };
+        println!(
+            "[JOB {}] Execution finished with exit code: {}",
+            job_id, exit_code
+        );
+
+        let oom_killed = self.oom_killed(job_id);
+        self.clean_up(job_id, record.pid).await;
+
+        Ok(JobExit {
+            exit_code,
+            oom_killed,
+        })
+    }
+
+    async fn stop_job(&self, job_id: &str) -> Result<(), JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        println!("[PROCESS] Stopping job: {}", job_id);
+
+        // Like `docker stop`: SIGTERM, then SIGKILL once the job's grace
+        // period is over.
+        self.signal_job(job_id, record.pid, libc::SIGTERM);
+        let deadline = Instant::now()
+            + Duration::from_secs(record.termination_grace_period_secs.max(0) as u64);
+        while Instant::now() < deadline && !self.job_pids(job_id, record.pid).is_empty() {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        self.signal_job(job_id, record.pid, libc::SIGKILL);
+
+        let child = self.children.lock().unwrap().remove(job_id);
+        if let Some(mut child) = child {
+            let _ = child.wait().await;
+        }
+        self.clean_up(job_id, record.pid).await;
+        println!("[PROCESS] Stopped job: {}", job_id);
+
+        Ok(())
+    }
+
+    async fn list_jobs(&self) -> Result<Vec<Uuid>, JobExecutorError> {
+        let jobs_dir = Self::jobs_dir()?;
+        let Ok(entries) = fs::read_dir(&jobs_dir) else {
+            return Ok(Vec::new());
+        };
+
+        // Jobs keep their process record until they are cleaned up, like
+        // containers that have exited but weren't removed yet.
+        let job_ids = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("process.json").exists())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+
+        Ok(job_ids)
+    }
+
+    /// Jobs run on the node itself, so there are no images to pull.
+    async fn pull_image(&self, _image: &str) -> Result<(), JobExecutorError> {
+        Ok(())
+    }
+
+    async fn list_images(&self) -> Result<Vec<CachedImage>, JobExecutorError> {
+        Ok(Vec::new())
+    }
+
+    async fn remove_image(&self, image: &CachedImage) -> Result<(), JobExecutorError> {
+        Err(anyhow::anyhow!("image {} was not pulled by this agent", image.id).into())
+    }
+
+    async fn get_job_usage(&self, job_id: &str) -> Result<JobUsage, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let Some(cgroup) = self.cgroup_dir(job_id) else {
+            // Without a cgroup, only the resident memory of the job's
+            // processes can be measured.
+            let mut rss_kb = 0;
+            for pid in self.job_pids(job_id, record.pid) {
+                let status = tokio::fs::read_to_string(format!("/proc/{}/status", pid))
+                    .await
+                    .unwrap_or_default();
+                rss_kb += status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("VmRSS:"))
+                    .and_then(|value| {
+                        value
+                            .trim()
+                            .trim_end_matches("kB")
+                            .trim()
+                            .parse::<i64>()
+                            .ok()
+                    })
+                    .unwrap_or_default();
+            }
+            return Ok(JobUsage {
+                memory_rss_mb: (rss_kb / 1024) as i32,
+                memory_peak_mb: (rss_kb / 1024) as i32,
+                ..Default::default()
+            });
+        };
+
+        // CPU usage is the CPU time used over a one second sample.
+        let started = Instant::now();
+        let cpu_before = Self::cpu_usage_usec(&cgroup).await.unwrap_or_default();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let cpu_after = Self::cpu_usage_usec(&cgroup).await.unwrap_or_default();
+        let elapsed_usec = started.elapsed().as_micros().max(1) as u64;
+        let cpu_millicores = (cpu_after.saturating_sub(cpu_before) * 1000 / elapsed_usec) as i32;
+
+        let usage_bytes = Self::read_u64(cgroup.join("memory.current"))
+            .await
+            .unwrap_or_default();
+        // Page cache is reclaimable, so it is excluded from the resident set.
+        let rss_bytes = tokio::fs::read_to_string(cgroup.join("memory.stat"))
+            .await
+            .ok()
+            .and_then(|stat| {
+                stat.lines()
+                    .find_map(|line| line.strip_prefix("anon "))
+                    .and_then(|anon| anon.trim().parse().ok())
+            })
+            .unwrap_or(usage_bytes);
+        // `memory.peak` needs Linux 5.19, so fall back to the current usage.
+        let peak_bytes = Self::read_u64(cgroup.join("memory.peak"))
+            .await
+            .unwrap_or(usage_bytes);
+
+        let (block_read_bytes, block_write_bytes) =
+            tokio::fs::read_to_string(cgroup.join("io.stat"))
+                .await
+                .unwrap_or_default()
+                .split_whitespace()
+                .fold((0, 0), |(read, write), field| {
+                    let value = |prefix| {
+                        field
+                            .strip_prefix(prefix)
+                            .and_then(|v: &str| v.parse::<i64>().ok())
+                    };
+                    match (value("rbytes="), value("wbytes=")) {
+                        (Some(bytes), _) => (read + bytes, write),
+                        (_, Some(bytes)) => (read, write + bytes),
+                        _ => (read, write),
+                    }
+                });
+
+        // Jobs share the node's network, so their traffic can't be told
+        // apart from the rest.
+        Ok(JobUsage {
+            cpu_millicores,
+            memory_rss_mb: (rss_bytes / 1024 / 1024) as i32,
+            memory_peak_mb: (peak_bytes / 1024 / 1024) as i32,
+            block_read_bytes,
+            block_write_bytes,
+            ..Default::default()
+        })
+    }
+
+    async fn get_job_pids(&self, job_id: &str) -> Result<Vec<u32>, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        Ok(self.job_pids(job_id, record.pid))
+    }
+
+    /// Runs the command in the job's working directory, with its
+    /// environment and in its cgroup. Without containers there is no TTY to
+    /// allocate, so commands always run without one.
+    async fn exec(
+        &self,
+        job_id: &str,
+        command: Vec<String>,
+        _tty: bool,
+    ) -> Result<ExecProcess, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("no command given"))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(Self::job_dir(job_id)?.join("work"))
+            .envs(record.env.iter().cloned())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        if let (Some(cgroup), Some(pid)) = (self.cgroup_dir(job_id), child.id()) {
+            if let Err(e) = fs::write(cgroup.join("cgroup.procs"), pid.to_string()) {
+                eprintln!(
+                    "[PROCESS] Failed to move exec into the cgroup of job {}: {}",
+                    job_id, e
+                );
+            }
+        }
+
+        let (Some(input), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(anyhow::anyhow!("exec in job {} has no pipes", job_id).into());
+        };
+        let exec_id = Uuid::new_v4().to_string();
+        println!("[PROCESS] Started exec {} in job: {}", exec_id, job_id);
+        self.execs.lock().unwrap().insert(exec_id.clone(), child);
+
+        let output = stream::select(Self::read_chunks(stdout), Self::read_chunks(stderr));
+        Ok(ExecProcess {
+            id: exec_id,
+            output: Box::pin(output),
+            input: Box::pin(input),
+        })
+    }
+
+    async fn resize_exec(
+        &self,
+        _exec_id: &str,
+        _cols: u16,
+        _rows: u16,
+    ) -> Result<(), JobExecutorError> {
+        Ok(())
+    }
+
+    async fn get_exec_exit_code(&self, exec_id: &str) -> Result<i64, JobExecutorError> {
+        let child = self.execs.lock().unwrap().remove(exec_id);
+        let mut child = child.ok_or_else(|| anyhow::anyhow!("exec {} not found", exec_id))?;
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        Ok(status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal))
+            .unwrap_or(-1) as i64)
+    }
+
+    /// Jobs listen on the node's own network.
+    async fn get_port_address(
+        &self,
+        _job_id: &str,
+        port: u16,
+    ) -> Result<SocketAddr, JobExecutorError> {
+        Ok(SocketAddr::from(([127, 0, 0, 1], port)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ExecutorKind, ShutdownMode},
+        domain::agent::models::{Architecture, Cpu, CpuManufacturer},
+        outbound::TEST_ENV_LOCK,
+    };
+    use std::env;
+    use tempfile::TempDir;
+    use tokio::sync::MutexGuard;
+
+    /// A home directory of its own for the agent, which jobs are run in.
+    struct TestHome {
+        dir: TempDir,
+        _env: MutexGuard<'static, ()>,
+        home: Option<std::ffi::OsString>,
+    }
+
+    impl TestHome {
+        async fn new() -> Self {
+            let env = TEST_ENV_LOCK.lock().await;
+            let dir = tempfile::tempdir().unwrap();
+            let home = env::var_os("HOME");
+            env::set_var("HOME", dir.path());
+            Self {
+                dir,
+                _env: env,
+                home,
+            }
+        }
+
+        fn job_dir(&self, job_id: &Uuid) -> PathBuf {
+            self.dir.path().join(".lilac/jobs").join(job_id.to_string())
+        }
+
+        /// Waits for a job to create `file` in its directory.
+        async fn wait_for(&self, job_id: &Uuid, file: &str) {
+            let path = self.job_dir(job_id).join(file);
+            for _ in 0..100 {
+                if path.exists() {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            panic!("job {} didn't create {}", job_id, file);
+        }
+    }
+
+    impl Drop for TestHome {
+        fn drop(&mut self) {
+            match &self.home {
+                Some(home) => env::set_var("HOME", home),
+                None => env::remove_var("HOME"),
+            }
+        }
+    }
+
+    /// An executor without cgroups, as creating one with `new` would move
+    /// the test process into a cgroup of its own.
+    fn executor() -> ProcessExecutor {
+        ProcessExecutor {
+            config: AgentConfig {
+                api_endpoint: "http://localhost:8080".to_string(),
+                cluster_api_key: "key".to_string(),
+                node_id: Uuid::new_v4(),
+                private_registry: None,
+                checkpoint_root: None,
+                image_cache_budget_gb: None,
+                executor: ExecutorKind::Process,
+                container_socket: None,
+                kubernetes: None,
+                slurm: None,
+                shutdown_mode: ShutdownMode::Stop,
+            },
+            cgroup_root: None,
+            children: Arc::new(Mutex::new(HashMap::new())),
+            execs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn job_details(command: &str) -> JobDetails {
+        JobDetails {
+            id: Uuid::new_v4(),
+            docker_uri: command.to_string(),
+            env: HashMap::new(),
+            exposed_ports: Vec::new(),
+            termination_grace_period_secs: 30,
+            name: "train".to_string(),
+            queue_id: None,
+            cluster_id: None,
+            resource_requirements: JobResourceRequirements::default(),
+            attempt: 1,
+            job_token: None,
+        }
+    }
+
+    fn resources() -> NodeResources {
+        NodeResources {
+            cpu: Cpu {
+                manufacturer: CpuManufacturer::Intel,
+                architecture: Architecture::X86_64,
+                millicores: 8000,
+            },
+            gpus: Vec::new(),
+            memory_mb: 16384,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_job_captures_output_and_exit_code() {
+        let home = TestHome::new().await;
+        let executor = executor();
+        let job = job_details("echo hello; echo oops >&2; exit 3");
+        let job_id = job.id;
+
+        let exit = executor.run_job(job, &resources(), &[]).await.unwrap();
+
+        assert_eq!(exit.exit_code, 3);
+        assert!(!exit.oom_killed);
+        let log = fs::read_to_string(home.job_dir(&job_id).join("job.log")).unwrap();
+        assert_eq!(log, "hello\noops\n");
+        // Everything but the log is cleaned up once the job has exited.
+        assert!(!home.job_dir(&job_id).join("work").exists());
+        assert!(executor.list_jobs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_job_reports_death_by_signal() {
+        let _home = TestHome::new().await;
+        let executor = executor();
+
+        let exit = executor
+            .run_job(job_details("kill -KILL $$"), &resources(), &[])
+            .await
+            .unwrap();
+
+        assert_eq!(exit.exit_code, 128 + libc::SIGKILL as i64);
+    }
+
+    #[tokio::test]
+    async fn test_run_job_in_work_dir_with_env() {
+        let home = TestHome::new().await;
+        let executor = executor();
+        let mut job = job_details(
+            r#"pwd > ../out; echo "$GREETING" >> ../out; echo "$LILAC_CHECKPOINT_DIR" >> ../out"#,
+        );
+        job.env = HashMap::from([
+            ("GREETING".to_string(), "hi".to_string()),
+            // Lilac's own variables can't be overridden by the job.
+            ("LILAC_CHECKPOINT_DIR".to_string(), "/tmp".to_string()),
+        ]);
+        let job_id = job.id;
+
+        let exit = executor.run_job(job, &resources(), &[]).await.unwrap();
+
+        assert_eq!(exit.exit_code, 0);
+        let job_dir = home.job_dir(&job_id);
+        let out = fs::read_to_string(job_dir.join("out")).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                job_dir.join("work").display().to_string(),
+                "hi".to_string(),
+                home.dir
+                    .path()
+                    .join(".lilac/checkpoints")
+                    .join(job_id.to_string())
+                    .display()
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_within_grace_period() {
+        let home = TestHome::new().await;
+        let executor = executor();
+        let job = job_details("touch ../ready; sleep 30");
+        let job_id = job.id;
+        let running = {
+            let executor = executor.clone();
+            tokio::spawn(async move { executor.run_job(job, &resources(), &[]).await })
+        };
+        home.wait_for(&job_id, "ready").await;
+        home.wait_for(&job_id, "process.json").await;
+
+        let started = Instant::now();
+        executor.stop_job(&job_id.to_string()).await.unwrap();
+
+        // The job exits on SIGTERM, so its grace period isn't waited out.
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let exit = running.await.unwrap().unwrap();
+        assert_eq!(exit.exit_code, 128 + libc::SIGTERM as i64);
+        assert!(executor.list_jobs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_kills_job_after_grace_period() {
+        let home = TestHome::new().await;
+        let executor = executor();
+        let mut job = job_details("trap '' TERM; touch ../ready; sleep 30");
+        job.termination_grace_period_secs = 1;
+        let job_id = job.id;
+        let running = {
+            let executor = executor.clone();
+            tokio::spawn(async move { executor.run_job(job, &resources(), &[]).await })
+        };
+        home.wait_for(&job_id, "ready").await;
+        home.wait_for(&job_id, "process.json").await;
+        let pid = ProcessExecutor::read_record(&job_id.to_string())
+            .await
+            .unwrap()
+            .pid;
+
+        let started = Instant::now();
+        executor.stop_job(&job_id.to_string()).await.unwrap();
+
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_secs(1), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(10), "{elapsed:?}");
+        assert!(executor.job_pids(&job_id.to_string(), pid).is_empty());
+        running.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,1116 @@
+use crate::{
+    config::{self, AgentConfig, SlurmConfig, SlurmContainerRuntime},
+    domain::agent::{
+        models::{
+            CachedImage, ExecProcess, Gpu, JobDetails, JobExit, JobMetadata, JobUsage,
+            NodeResources,
+        },
+        ports::JobExecutor,
+    },
+    errors::JobExecutorError,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// Where a job's checkpoint directory is mounted in its container.
+const CHECKPOINT_MOUNT_PATH: &str = "/lilac/checkpoint";
+/// Where a job's metadata file is mounted in its container.
+const METADATA_MOUNT_PATH: &str = "/lilac/metadata.json";
+/// How often the state of a submitted job is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// The exit code of a job whose image couldn't be pulled or unpacked.
+const CONTAINER_ERROR_EXIT_CODE: i64 = 125;
+
+/// What the agent needs to know about a submitted job after it restarts.
+#[derive(Debug, Serialize, Deserialize)]
+struct SlurmRecord {
+    slurm_job_id: String,
+    termination_grace_period_secs: i64,
+}
+
+/// Runs jobs as batch jobs of a Slurm cluster. Each job is submitted with
+/// `sbatch` as a script that runs the job's image with enroot or Apptainer,
+/// and followed with `squeue` and `sacct` until it ends. The Slurm commands
+/// are looked up on `PATH`, so stand-ins can take their place.
+///
+/// Scripts, logs and metadata are kept in `~/.lilac/jobs/<job_id>`, which the
+/// compute nodes must be able to read and write.
+#[derive(Clone)]
+pub struct SlurmExecutor {
+    config: AgentConfig,
+    slurm: SlurmConfig,
+}
+
+impl SlurmExecutor {
+    pub fn new(config: AgentConfig) -> Result<Self, JobExecutorError> {
+        let output = std::process::Command::new("sbatch")
+            .arg("--version")
+            .output()
+            .map_err(|e| anyhow::anyhow!("Slurm's sbatch can't be run: {}", e))?;
+        println!(
+            "[SLURM] Submitting jobs with {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+
+        Ok(Self {
+            slurm: config.slurm.clone().unwrap_or_default(),
+            config,
+        })
+    }
+
+    fn job_dir(job_id: &str) -> Result<PathBuf, JobExecutorError> {
+        config::get_config_path("jobs")
+            .map(|jobs_dir| jobs_dir.join(job_id))
+            .map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    async fn read_record(job_id: &str) -> Result<SlurmRecord, JobExecutorError> {
+        let path = Self::job_dir(job_id)?.join("slurm.json");
+        let contents = tokio::fs::read(&path)
+            .await
+            .map_err(|e| anyhow::anyhow!("job {} was not submitted: {}", job_id, e))?;
+        serde_json::from_slice(&contents).map_err(|e| JobExecutorError::Unknown(e.into()))
+    }
+
+    async fn output(program: &str, args: &[String]) -> Result<Output, JobExecutorError> {
+        Command::new(program)
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to run {}: {}", program, e).into())
+    }
+
+    /// Runs a Slurm command and returns what it printed.
+    async fn run(program: &str, args: &[String]) -> Result<String, JobExecutorError> {
+        let output = Self::output(program, args).await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// The state of a Slurm job, e.g. `PENDING` or `RUNNING`, or `None` once
+    /// it has left the queue.
+    async fn queue_state(slurm_job_id: &str) -> Result<Option<String>, JobExecutorError> {
+        let args = ["-h", "-j", slurm_job_id, "-o", "%T"].map(str::to_string);
+        let output = Self::output("squeue", &args).await?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            // Slurm forgets jobs some time after they end.
+            if stderr.contains("Invalid job id") {
+                return Ok(None);
+            }
+            return Err(anyhow::anyhow!("squeue failed: {}", stderr.trim()).into());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .next()
+            .map(str::trim)
+            .filter(|state| !state.is_empty())
+            .map(str::to_string))
+    }
+
+    /// How a job that left the queue ended, from Slurm's accounting or,
+    /// where accounting is disabled, from the exit code its script recorded.
+    async fn job_exit(job_id: &str, slurm_job_id: &str) -> Result<JobExit, JobExecutorError> {
+        let args =
+            ["-j", slurm_job_id, "-X", "-n", "-P", "-o", "State,ExitCode"].map(str::to_string);
+        // Accounting can take a moment to catch up with the queue.
+        for _ in 0..3 {
+            match Self::run("sacct", &args).await {
+                Ok(output) => {
+                    if let Some((state, exit_code)) = output
+                        .lines()
+                        .next()
+                        .and_then(|line| line.trim().split_once('|'))
+                    {
+                        return Self::parse_exit(slurm_job_id, state, exit_code);
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "[SLURM] Failed to read accounting of job {}: {}",
+                        slurm_job_id, e
+                    );
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+
+        let exit_code = Self::job_dir(job_id)?.join("exit_code");
+        let exit_code = tokio::fs::read_to_string(&exit_code)
+            .await
+            .ok()
+            .and_then(|code| code.trim().parse().ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Slurm job {} ended without an exit code", slurm_job_id)
+            })?;
+        Ok(JobExit {
+            exit_code,
+            oom_killed: false,
+        })
+    }
+
+    /// Turns `sacct`'s state and `<code>:<signal>` exit code into how the job
+    /// ended. Jobs that Slurm ended, e.g. for running out of time, failed.
+    fn parse_exit(
+        slurm_job_id: &str,
+        state: &str,
+        exit_code: &str,
+    ) -> Result<JobExit, JobExecutorError> {
+        let (code, signal) = exit_code.split_once(':').unwrap_or((exit_code, "0"));
+        let code: i64 = code.parse().unwrap_or(-1);
+        let signal: i64 = signal.parse().unwrap_or_default();
+        // Like a shell, report death by a signal as 128 + the signal. Slurm
+        // reports some jobs it ended, e.g. for running out of memory, with
+        // made-up signals beyond the real ones.
+        let exit_code = if (1..=64).contains(&signal) {
+            128 + signal
+        } else {
+            code
+        };
+
+        // Cancelled jobs are reported as e.g. `CANCELLED by 1000`.
+        match state.split_whitespace().next().unwrap_or_default() {
+            "COMPLETED" | "FAILED" => Ok(JobExit {
+                exit_code,
+                oom_killed: false,
+            }),
+            "OUT_OF_MEMORY" => Ok(JobExit {
+                exit_code,
+                oom_killed: true,
+            }),
+            _ => Err(anyhow::anyhow!("Slurm job {} ended as {}", slurm_job_id, state).into()),
+        }
+    }
+
+    /// Removes everything a job left behind but its log.
+    async fn clean_up(job_id: &str) {
+        if let Ok(job_dir) = Self::job_dir(job_id) {
+            for file in ["slurm.json", "job.sh", "exit_code", "metadata.json"] {
+                let _ = tokio::fs::remove_file(job_dir.join(file)).await;
+            }
+        }
+    }
+
+    /// The arguments to submit a job's script with.
+    fn sbatch_args(&self, job_details: &JobDetails, job_dir: &Path) -> Vec<String> {
+        let requirements = &job_details.resource_requirements;
+        let mut args = vec![
+            "--parsable".to_string(),
+            format!("--job-name=lilac-job-{}", job_details.id),
+            format!("--chdir={}", job_dir.display()),
+            format!("--output={}", job_dir.join("job.log").display()),
+            "--open-mode=append".to_string(),
+            "--nodes=1".to_string(),
+            "--ntasks=1".to_string(),
+        ];
+        if requirements.cpu_millicores > 0 {
+            // Slurm allocates whole CPUs.
+            let cpus = (requirements.cpu_millicores + 999) / 1000;
+            args.push(format!("--cpus-per-task={}", cpus));
+        }
+        if requirements.memory_mb > 0 {
+            args.push(format!("--mem={}M", requirements.memory_mb));
+        }
+        if requirements.gpu_count() > 0 {
+            args.push(format!("--gres=gpu:{}", requirements.gpu_count()));
+        }
+        if let Some(minutes) = self.slurm.time_limit_minutes {
+            args.push(format!("--time={}", minutes));
+        }
+        if let Some(partition) = &self.slurm.partition {
+            args.push(format!("--partition={}", partition));
+        }
+        if let Some(account) = &self.slurm.account {
+            args.push(format!("--account={}", account));
+        }
+        args.extend(self.slurm.sbatch_args.iter().cloned());
+        args.push(job_dir.join("job.sh").display().to_string());
+        args
+    }
+
+    /// Renders the script that runs a job's image on the compute node and
+    /// records its exit code.
+    fn render_script(
+        &self,
+        job_details: &JobDetails,
+        env: &[(String, String)],
+        job_dir: &Path,
+        checkpoint_dir: &Path,
+    ) -> String {
+        let image = &job_details.docker_uri;
+        let has_gpus = job_details.resource_requirements.gpu_count() > 0;
+        let checkpoint_mount = format!("{}:{}", checkpoint_dir.display(), CHECKPOINT_MOUNT_PATH);
+        let metadata_mount = format!(
+            "{}:{}",
+            job_dir.join("metadata.json").display(),
+            METADATA_MOUNT_PATH
+        );
+        let env: Vec<&(String, String)> = env
+            .iter()
+            .filter(|(name, _)| {
+                let valid = is_env_name(name);
+                if !valid {
+                    eprintln!(
+                        "[SLURM] Skipping environment variable {:?} of job {}.",
+                        name, job_details.id
+                    );
+                }
+                valid
+            })
+            .collect();
+
+        let mut script = format!("#!/bin/bash\n# Runs Lilac job {}.\n", job_details.id);
+        match self.slurm.container_runtime {
+            SlurmContainerRuntime::Enroot => {
+                for (name, value) in &env {
+                    script.push_str(&format!("export {}={}\n", name, shell_quote(value)));
+                }
+                // enroot's NVIDIA hook exposes the GPUs named here, and Slurm
+                // names the ones it allocated in SLURM_JOB_GPUS.
+                if has_gpus {
+                    script.push_str("export NVIDIA_VISIBLE_DEVICES=\"${SLURM_JOB_GPUS:-void}\"\n");
+                } else {
+                    script.push_str("export NVIDIA_VISIBLE_DEVICES=void\n");
+                }
+                let env_args: String = env
+                    .iter()
+                    .map(|(name, _)| format!(" --env {}", name))
+                    .collect();
+                script.push_str(&format!(
+                    "name=\"lilac-job-{job_id}-$SLURM_JOB_ID\"\n\
+                     image=\"${{TMPDIR:-/tmp}}/$name.sqsh\"\n\
+                     enroot import -o \"$image\" {image} || exit {error}\n\
+                     enroot create -n \"$name\" \"$image\" || exit {error}\n\
+                     rm -f \"$image\"\n\
+                     enroot start --rw --mount {checkpoint} --mount {metadata}{env_args} --env NVIDIA_VISIBLE_DEVICES \"$name\"\n\
+                     code=$?\n\
+                     enroot remove -f \"$name\"\n",
+                    job_id = job_details.id,
+                    image = shell_quote(&enroot_image(image)),
+                    error = CONTAINER_ERROR_EXIT_CODE,
+                    checkpoint = shell_quote(&format!("{}:x-create=dir,bind", checkpoint_mount)),
+                    metadata = shell_quote(&format!("{}:x-create=file,bind,ro", metadata_mount)),
+                    env_args = env_args,
+                ));
+            }
+            SlurmContainerRuntime::Apptainer => {
+                // Apptainer passes variables prefixed with APPTAINERENV_ into
+                // the container, over those of the image.
+                for (name, value) in &env {
+                    script.push_str(&format!(
+                        "export APPTAINERENV_{}={}\n",
+                        name,
+                        shell_quote(value)
+                    ));
+                }
+                script.push_str(&format!(
+                    "apptainer run --cleanenv{nv} --bind {checkpoint} --bind {metadata} {image}\n\
+                     code=$?\n",
+                    nv = if has_gpus { " --nv" } else { "" },
+                    checkpoint = shell_quote(&checkpoint_mount),
+                    metadata = shell_quote(&format!("{}:ro", metadata_mount)),
+                    image = shell_quote(&format!("docker://{}", image)),
+                ));
+            }
+        }
+        let exit_code = job_dir.join("exit_code").display().to_string();
+        script.push_str(&format!(
+            "echo $code > {tmp} && mv {tmp} {path}\nexit $code\n",
+            tmp = shell_quote(&format!("{}.tmp", exit_code)),
+            path = shell_quote(&exit_code),
+        ));
+        script
+    }
+}
+
+/// Quotes a value for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Spells an image reference the way enroot expects, with `#` between the
+/// registry and the repository, e.g. `docker://ghcr.io#acme/train:v1`.
+fn enroot_image(image: &str) -> String {
+    match image.split_once('/') {
+        Some((registry, repository))
+            if registry.contains('.') || registry.contains(':') || registry == "localhost" =>
+        {
+            format!("docker://{}#{}", registry, repository)
+        }
+        _ => format!("docker://{}", image),
+    }
+}
+
+/// Parses a size as `sstat` prints it, e.g. `1536K`, in bytes.
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let (number, multiplier) = match size.chars().last()? {
+        'K' => (&size[..size.len() - 1], 1024.0),
+        'M' => (&size[..size.len() - 1], 1024.0 * 1024.0),
+        'G' => (&size[..size.len() - 1], 1024.0 * 1024.0 * 1024.0),
+        'T' => (&size[..size.len() - 1], 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        _ => (size, 1.0),
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .map(|number| (number * multiplier) as u64)
+}
+
+#[async_trait]
+impl JobExecutor for SlurmExecutor {
+    async fn run_job(
+        &self,
+        job_details: JobDetails,
+        _resources: &NodeResources,
+        gpus: &[Gpu],
+    ) -> Result<JobExit, JobExecutorError> {
+        let job_id = job_details.id.to_string();
+        println!("[SLURM] Submitting job: {}", job_id);
+
+        let job_dir = Self::job_dir(&job_id)?;
+        tokio::fs::create_dir_all(&job_dir)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        // Checkpoint directories are named after the job, so a job that is
+        // re-queued finds the checkpoints its earlier runs saved.
+        let checkpoint_dir = self
+            .config
+            .checkpoint_root()
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?
+            .join(&job_id);
+        tokio::fs::create_dir_all(&checkpoint_dir)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let metadata = JobMetadata::new(
+            &job_details,
+            gpus,
+            self.config.node_id,
+            &self.config.api_endpoint,
+        );
+        let contents = serde_json::to_vec_pretty(&metadata)
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        tokio::fs::write(job_dir.join("metadata.json"), contents)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        let mut env: Vec<(String, String)> = job_details.env.clone().into_iter().collect();
+        // Lilac's own variables come last, so that they win over the job's.
+        env.extend(metadata.env());
+        env.push((
+            "LILAC_METADATA_FILE".to_string(),
+            METADATA_MOUNT_PATH.to_string(),
+        ));
+        env.push((
+            "LILAC_CHECKPOINT_DIR".to_string(),
+            CHECKPOINT_MOUNT_PATH.to_string(),
+        ));
+        let script = self.render_script(&job_details, &env, &job_dir, &checkpoint_dir);
+        tokio::fs::write(job_dir.join("job.sh"), script)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        // `--parsable` prints the job's ID, followed by `;<cluster>` on
+        // federated clusters.
+        let output = Self::run("sbatch", &self.sbatch_args(&job_details, &job_dir)).await?;
+        let slurm_job_id = output
+            .trim()
+            .split(';')
+            .next()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("sbatch printed no job ID"))?
+            .to_string();
+        println!(
+            "[SLURM] Submitted job {} as Slurm job {}",
+            job_id, slurm_job_id
+        );
+
+        let record = SlurmRecord {
+            slurm_job_id,
+            termination_grace_period_secs: job_details.termination_grace_period_secs,
+        };
+        let contents =
+            serde_json::to_vec_pretty(&record).map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        tokio::fs::write(job_dir.join("slurm.json"), contents)
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+
+        self.wait_job(&job_id).await
+    }
+
+    async fn wait_job(&self, job_id: &str) -> Result<JobExit, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let mut last_state = None;
+        loop {
+            match Self::queue_state(&record.slurm_job_id).await {
+                Ok(Some(state)) => {
+                    if last_state.as_ref() != Some(&state) {
+                        println!("[SLURM] Slurm job {} is {}", record.slurm_job_id, state);
+                        last_state = Some(state);
+                    }
+                }
+                Ok(None) => break,
+                // The controller may be briefly unreachable, which mustn't
+                // fail a job that is still running.
+                Err(e) => eprintln!(
+                    "[SLURM] Failed to check Slurm job {}: {}. Will retry.",
+                    record.slurm_job_id, e
+                ),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        let result = Self::job_exit(job_id, &record.slurm_job_id).await;
+        Self::clean_up(job_id).await;
+
+        let exit = result?;
+        println!(
+            "[JOB {}] Execution finished with exit code: {}",
+            job_id, exit.exit_code
+        );
+        Ok(exit)
+    }
+
+    async fn stop_job(&self, job_id: &str) -> Result<(), JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let slurm_job_id = record.slurm_job_id.clone();
+        println!(
+            "[SLURM] Cancelling Slurm job {} of job {}",
+            slurm_job_id, job_id
+        );
+
+        // Like `docker stop`: SIGTERM, then cancel the job outright once its
+        // grace period is over. Jobs still waiting can be cancelled right away.
+        if Self::queue_state(&slurm_job_id).await?.as_deref() == Some("RUNNING") {
+            Self::run(
+                "scancel",
+                &[
+                    "--full".to_string(),
+                    "--signal=TERM".to_string(),
+                    slurm_job_id.clone(),
+                ],
+            )
+            .await?;
+            let deadline = Instant::now()
+                + Duration::from_secs(record.termination_grace_period_secs.max(0) as u64);
+            while Instant::now() < deadline && Self::queue_state(&slurm_job_id).await?.is_some() {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+        if Self::queue_state(&slurm_job_id).await?.is_some() {
+            Self::run("scancel", std::slice::from_ref(&slurm_job_id)).await?;
+        }
+
+        Self::clean_up(job_id).await;
+        println!("[SLURM] Cancelled Slurm job {}", slurm_job_id);
+        Ok(())
+    }
+
+    async fn list_jobs(&self) -> Result<Vec<Uuid>, JobExecutorError> {
+        let jobs_dir =
+            config::get_config_path("jobs").map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        let Ok(entries) = fs::read_dir(&jobs_dir) else {
+            return Ok(Vec::new());
+        };
+
+        // Jobs keep their record until they are cleaned up, even after they
+        // left Slurm's queue.
+        let job_ids = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("slurm.json").exists())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+
+        Ok(job_ids)
+    }
+
+    /// Compute nodes pull the images of jobs themselves.
+    async fn pull_image(&self, _image: &str) -> Result<(), JobExecutorError> {
+        Ok(())
+    }
+
+    async fn list_images(&self) -> Result<Vec<CachedImage>, JobExecutorError> {
+        Ok(Vec::new())
+    }
+
+    async fn remove_image(&self, image: &CachedImage) -> Result<(), JobExecutorError> {
+        Err(anyhow::anyhow!("image {} was not pulled by this agent", image.id).into())
+    }
+
+    /// Reads the memory used by the job's batch step from `sstat`. Slurm
+    /// only reports CPU time, not how many CPUs are busy, so CPU usage isn't
+    /// known.
+    async fn get_job_usage(&self, job_id: &str) -> Result<JobUsage, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let args = [
+            "-n",
+            "-P",
+            "-j",
+            &format!("{}.batch", record.slurm_job_id),
+            "-o",
+            "MaxRSS",
+        ]
+        .map(str::to_string);
+        let output = Self::run("sstat", &args).await?;
+        let max_rss_bytes = output
+            .lines()
+            .filter_map(parse_size)
+            .max()
+            .unwrap_or_default();
+        let max_rss_mb = (max_rss_bytes / 1024 / 1024) as i32;
+
+        Ok(JobUsage {
+            memory_rss_mb: max_rss_mb,
+            memory_peak_mb: max_rss_mb,
+            ..Default::default()
+        })
+    }
+
+    /// Jobs run on compute nodes, so none of their processes are local.
+    async fn get_job_pids(&self, _job_id: &str) -> Result<Vec<u32>, JobExecutorError> {
+        Ok(Vec::new())
+    }
+
+    async fn exec(
+        &self,
+        job_id: &str,
+        _command: Vec<String>,
+        _tty: bool,
+    ) -> Result<ExecProcess, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        Err(anyhow::anyhow!(
+            "exec isn't supported for job {}; use srun --jobid={} --overlap --pty bash",
+            job_id,
+            record.slurm_job_id
+        )
+        .into())
+    }
+
+    async fn resize_exec(
+        &self,
+        exec_id: &str,
+        _cols: u16,
+        _rows: u16,
+    ) -> Result<(), JobExecutorError> {
+        Err(anyhow::anyhow!("exec {} not found", exec_id).into())
+    }
+
+    async fn get_exec_exit_code(&self, exec_id: &str) -> Result<i64, JobExecutorError> {
+        Err(anyhow::anyhow!("exec {} not found", exec_id).into())
+    }
+
+    /// Containers share the network of the compute node they run on.
+    async fn get_port_address(
+        &self,
+        job_id: &str,
+        port: u16,
+    ) -> Result<SocketAddr, JobExecutorError> {
+        let record = Self::read_record(job_id).await?;
+        let args = ["-h", "-j", &record.slurm_job_id, "-o", "%N"].map(str::to_string);
+        let output = Self::run("squeue", &args).await?;
+        let node = output
+            .lines()
+            .next()
+            .map(|node| node.trim().to_string())
+            .filter(|node| !node.is_empty())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Slurm job {} isn't running on a node", record.slurm_job_id)
+            })?;
+
+        let mut addresses = tokio::net::lookup_host((node.as_str(), port))
+            .await
+            .map_err(|e| JobExecutorError::Unknown(e.into()))?;
+        addresses
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("node {} has no address", node).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ExecutorKind, ShutdownMode},
+        domain::agent::models::{
+            Architecture, Cpu, CpuManufacturer, JobResourceRequirements, RequestedGpus,
+        },
+        outbound::TEST_ENV_LOCK,
+    };
+    use std::collections::HashMap;
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+    use tokio::sync::MutexGuard;
+
+    /// Stand-ins for the Slurm commands, put first on `PATH` along with a
+    /// home directory of their own. They log how they were called to
+    /// `calls`, and behave according to the files next to them:
+    ///
+    /// - `sbatch_output` is what `sbatch` prints. If `exit_code` exists,
+    ///   `sbatch` copies it into the job's directory, as the job would.
+    /// - `state` is what `squeue` prints; without it, the job is unknown.
+    /// - `sacct_output` is what `sacct` prints; without it, accounting is
+    ///   disabled.
+    /// - `scancel` ends the job, unless it sends a signal and `ignore_term`
+    ///   exists.
+    struct FakeSlurm {
+        dir: TempDir,
+        _env: MutexGuard<'static, ()>,
+        path: Option<std::ffi::OsString>,
+        home: Option<std::ffi::OsString>,
+    }
+
+    const STUBS: &[(&str, &str)] = &[
+        (
+            "sbatch",
+            r#"if [ "$1" = --version ]; then echo "slurm 23.02.7"; exit 0; fi
+for arg; do case "$arg" in --chdir=*) dir="${arg#--chdir=}";; esac; done
+if [ -f "$STUB/exit_code" ]; then cp "$STUB/exit_code" "$dir/exit_code"; fi
+cat "$STUB/sbatch_output""#,
+        ),
+        (
+            "squeue",
+            r#"if [ -f "$STUB/state" ]; then cat "$STUB/state"; exit 0; fi
+echo "slurm_load_jobs error: Invalid job id specified" >&2
+exit 1"#,
+        ),
+        (
+            "sacct",
+            r#"if [ -f "$STUB/sacct_output" ]; then cat "$STUB/sacct_output"; exit 0; fi
+echo "Slurm accounting storage is disabled" >&2
+exit 1"#,
+        ),
+        (
+            "scancel",
+            r#"case "$*" in --full\ --signal=*) [ -f "$STUB/ignore_term" ] && exit 0;; esac
+rm -f "$STUB/state""#,
+        ),
+    ];
+
+    impl FakeSlurm {
+        async fn new() -> Self {
+            let env = TEST_ENV_LOCK.lock().await;
+            let dir = tempfile::tempdir().unwrap();
+            let bin = dir.path().join("bin");
+            fs::create_dir_all(&bin).unwrap();
+            for (name, body) in STUBS {
+                let script = format!(
+                    "#!/bin/sh\nSTUB={}\necho \"{} $*\" >> \"$STUB/calls\"\n{}\n",
+                    shell_quote(&dir.path().display().to_string()),
+                    name,
+                    body
+                );
+                let path = bin.join(name);
+                fs::write(&path, script).unwrap();
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+
+            let path = env::var_os("PATH");
+            let home = env::var_os("HOME");
+            let mut paths = vec![bin];
+            paths.extend(env::split_paths(&path.clone().unwrap_or_default()));
+            env::set_var("PATH", env::join_paths(paths).unwrap());
+            env::set_var("HOME", dir.path().join("home"));
+            Self {
+                dir,
+                _env: env,
+                path,
+                home,
+            }
+        }
+
+        fn set(&self, file: &str, contents: &str) {
+            fs::write(self.dir.path().join(file), contents).unwrap();
+        }
+
+        fn calls(&self) -> Vec<String> {
+            fs::read_to_string(self.dir.path().join("calls"))
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+
+        fn job_dir(&self, job_id: &str) -> PathBuf {
+            self.dir.path().join("home/.lilac/jobs").join(job_id)
+        }
+
+        /// Records a job as submitted to Slurm as `slurm_job_id`.
+        fn submitted(&self, job_id: &str, slurm_job_id: &str, grace_period_secs: i64) {
+            let record = SlurmRecord {
+                slurm_job_id: slurm_job_id.to_string(),
+                termination_grace_period_secs: grace_period_secs,
+            };
+            fs::create_dir_all(self.job_dir(job_id)).unwrap();
+            fs::write(
+                self.job_dir(job_id).join("slurm.json"),
+                serde_json::to_vec(&record).unwrap(),
+            )
+            .unwrap();
+        }
+    }
+
+    impl Drop for FakeSlurm {
+        fn drop(&mut self) {
+            for (name, value) in [("PATH", &self.path), ("HOME", &self.home)] {
+                match value {
+                    Some(value) => env::set_var(name, value),
+                    None => env::remove_var(name),
+                }
+            }
+        }
+    }
+
+    fn config(slurm: SlurmConfig) -> AgentConfig {
+        AgentConfig {
+            api_endpoint: "http://localhost:8080".to_string(),
+            cluster_api_key: "key".to_string(),
+            node_id: Uuid::new_v4(),
+            private_registry: None,
+            checkpoint_root: None,
+            image_cache_budget_gb: None,
+            executor: ExecutorKind::Slurm,
+            container_socket: None,
+            kubernetes: None,
+            slurm: Some(slurm),
+            shutdown_mode: ShutdownMode::Stop,
+        }
+    }
+
+    fn executor(slurm: SlurmConfig) -> SlurmExecutor {
+        SlurmExecutor {
+            config: config(slurm.clone()),
+            slurm,
+        }
+    }
+
+    fn job_details() -> JobDetails {
+        JobDetails {
+            id: Uuid::new_v4(),
+            docker_uri: "ghcr.io/acme/train:v1".to_string(),
+            env: HashMap::new(),
+            exposed_ports: Vec::new(),
+            termination_grace_period_secs: 30,
+            name: "train".to_string(),
+            queue_id: None,
+            cluster_id: None,
+            resource_requirements: JobResourceRequirements::default(),
+            attempt: 1,
+            job_token: None,
+        }
+    }
+
+    fn resources() -> NodeResources {
+        NodeResources {
+            cpu: Cpu {
+                manufacturer: CpuManufacturer::Intel,
+                architecture: Architecture::X86_64,
+                millicores: 8000,
+            },
+            gpus: Vec::new(),
+            memory_mb: 16384,
+        }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn exit(state: &str, exit_code: &str) -> JobExit {
+        SlurmExecutor::parse_exit("42", state, exit_code).unwrap()
+    }
+
+    #[test]
+    fn test_parse_exit() {
+        let completed = exit("COMPLETED", "0:0");
+        assert_eq!(completed.exit_code, 0);
+        assert!(!completed.oom_killed);
+
+        let failed = exit("FAILED", "2:0");
+        assert_eq!(failed.exit_code, 2);
+        assert!(!failed.oom_killed);
+
+        let out_of_memory = exit("OUT_OF_MEMORY", "0:125");
+        assert_eq!(out_of_memory.exit_code, 0);
+        assert!(out_of_memory.oom_killed);
+    }
+
+    #[test]
+    fn test_parse_exit_signals() {
+        // Killed by SIGKILL and SIGTERM, like a shell reports them.
+        assert_eq!(exit("FAILED", "0:9").exit_code, 137);
+        assert_eq!(exit("FAILED", "0:15").exit_code, 143);
+        // Signals beyond the real ones are made up by Slurm.
+        assert_eq!(exit("FAILED", "1:125").exit_code, 1);
+        assert_eq!(exit("FAILED", "3").exit_code, 3);
+    }
+
+    #[test]
+    fn test_parse_exit_of_jobs_slurm_ended() {
+        for state in ["CANCELLED by 1000", "CANCELLED", "TIMEOUT", "NODE_FAIL"] {
+            let result = SlurmExecutor::parse_exit("42", state, "0:15");
+            match result {
+                Err(JobExecutorError::Unknown(e)) => {
+                    assert_eq!(e.to_string(), format!("Slurm job 42 ended as {}", state))
+                }
+                other => panic!("expected {} to fail, got {:?}", state, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+
+        let value = "it's $HOME `id` \"quoted\" \\ done";
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", shell_quote(value)))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), value);
+    }
+
+    #[test]
+    fn test_enroot_image() {
+        assert_eq!(
+            enroot_image("pytorch/pytorch:2.1.0"),
+            "docker://pytorch/pytorch:2.1.0"
+        );
+        assert_eq!(enroot_image("ubuntu"), "docker://ubuntu");
+        assert_eq!(
+            enroot_image("ghcr.io/acme/train:v1"),
+            "docker://ghcr.io#acme/train:v1"
+        );
+        assert_eq!(
+            enroot_image("registry:5000/train"),
+            "docker://registry:5000#train"
+        );
+        assert_eq!(enroot_image("localhost/train"), "docker://localhost#train");
+    }
+
+    #[test]
+    fn test_render_script_enroot() {
+        let executor = executor(SlurmConfig::default());
+        let job_details = job_details();
+        let env = env(&[("MESSAGE", "it's done"), ("BAD-NAME", "x"), ("1ST", "x")]);
+
+        let script = executor.render_script(
+            &job_details,
+            &env,
+            Path::new("/home/lilac/.lilac/jobs/1"),
+            Path::new("/shared/checkpoints/1"),
+        );
+
+        assert!(script.starts_with("#!/bin/bash\n"));
+        assert!(script.contains("export MESSAGE='it'\\''s done'\n"));
+        assert!(!script.contains("BAD-NAME"));
+        assert!(!script.contains("1ST"));
+        assert!(script.contains("export NVIDIA_VISIBLE_DEVICES=void\n"));
+        assert!(script.contains(
+            "enroot import -o \"$image\" 'docker://ghcr.io#acme/train:v1' || exit 125\n"
+        ));
+        assert!(script.contains(
+            "--mount '/shared/checkpoints/1:/lilac/checkpoint:x-create=dir,bind' \
+             --mount '/home/lilac/.lilac/jobs/1/metadata.json:/lilac/metadata.json:x-create=file,bind,ro' \
+             --env MESSAGE --env NVIDIA_VISIBLE_DEVICES \"$name\"\n"
+        ));
+        assert!(script.ends_with(
+            "echo $code > '/home/lilac/.lilac/jobs/1/exit_code.tmp' \
+             && mv '/home/lilac/.lilac/jobs/1/exit_code.tmp' '/home/lilac/.lilac/jobs/1/exit_code'\n\
+             exit $code\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_script_enroot_with_gpus() {
+        let executor = executor(SlurmConfig::default());
+        let mut job_details = job_details();
+        job_details.resource_requirements.gpus = Some(RequestedGpus { count: 1 });
+
+        let script = executor.render_script(
+            &job_details,
+            &[],
+            Path::new("/jobs/1"),
+            Path::new("/checkpoints/1"),
+        );
+
+        assert!(script.contains("export NVIDIA_VISIBLE_DEVICES=\"${SLURM_JOB_GPUS:-void}\"\n"));
+    }
+
+    #[test]
+    fn test_render_script_apptainer() {
+        let executor = executor(SlurmConfig {
+            container_runtime: SlurmContainerRuntime::Apptainer,
+            ..Default::default()
+        });
+        let mut job_details = job_details();
+        job_details.resource_requirements.gpus = Some(RequestedGpus { count: 2 });
+        let env = env(&[("MESSAGE", "hello")]);
+
+        let script = executor.render_script(
+            &job_details,
+            &env,
+            Path::new("/jobs/1"),
+            Path::new("/checkpoints/1"),
+        );
+
+        assert!(script.contains("export APPTAINERENV_MESSAGE='hello'\n"));
+        assert!(script.contains(
+            "apptainer run --cleanenv --nv --bind '/checkpoints/1:/lilac/checkpoint' \
+             --bind '/jobs/1/metadata.json:/lilac/metadata.json:ro' \
+             'docker://ghcr.io/acme/train:v1'\n"
+        ));
+        assert!(!script.contains("enroot"));
+    }
+
+    #[tokio::test]
+    async fn test_run_job_with_accounting() {
+        let slurm = FakeSlurm::new().await;
+        slurm.set("sbatch_output", "4242;cluster-a\n");
+        slurm.set("sacct_output", "OUT_OF_MEMORY|0:125\n");
+        let executor = SlurmExecutor::new(config(SlurmConfig::default())).unwrap();
+        let job_details = job_details();
+        let job_id = job_details.id.to_string();
+
+        let exit = executor
+            .run_job(job_details, &resources(), &[])
+            .await
+            .unwrap();
+
+        assert!(exit.oom_killed);
+        let calls = slurm.calls();
+        assert!(calls[1].starts_with("sbatch --parsable "), "{:?}", calls);
+        assert!(calls[1].ends_with("/job.sh"), "{:?}", calls);
+        assert_eq!(calls[2], "squeue -h -j 4242 -o %T");
+        assert_eq!(calls[3], "sacct -j 4242 -X -n -P -o State,ExitCode");
+        // Only the log is left behind.
+        assert!(!slurm.job_dir(&job_id).join("slurm.json").exists());
+        assert!(!slurm.job_dir(&job_id).join("job.sh").exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_job_without_accounting() {
+        let slurm = FakeSlurm::new().await;
+        slurm.set("sbatch_output", "4243\n");
+        slurm.set("exit_code", "3\n");
+        let executor = SlurmExecutor::new(config(SlurmConfig::default())).unwrap();
+
+        let exit = executor
+            .run_job(job_details(), &resources(), &[])
+            .await
+            .unwrap();
+
+        assert_eq!(exit.exit_code, 3);
+        assert!(!exit.oom_killed);
+        assert_eq!(slurm.calls().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_run_job_without_job_id() {
+        let slurm = FakeSlurm::new().await;
+        slurm.set("sbatch_output", "\n");
+        let executor = SlurmExecutor::new(config(SlurmConfig::default())).unwrap();
+
+        let result = executor.run_job(job_details(), &resources(), &[]).await;
+
+        match result {
+            Err(JobExecutorError::Unknown(e)) => {
+                assert_eq!(e.to_string(), "sbatch printed no job ID")
+            }
+            other => panic!("expected no job ID, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_terminates_running_job() {
+        let slurm = FakeSlurm::new().await;
+        let job_id = Uuid::new_v4().to_string();
+        slurm.submitted(&job_id, "4242", 30);
+        slurm.set("state", "RUNNING\n");
+        let executor = SlurmExecutor::new(config(SlurmConfig::default())).unwrap();
+
+        executor.stop_job(&job_id).await.unwrap();
+
+        assert_eq!(
+            slurm.calls()[1..],
+            [
+                "squeue -h -j 4242 -o %T",
+                "scancel --full --signal=TERM 4242",
+                "squeue -h -j 4242 -o %T",
+                "squeue -h -j 4242 -o %T",
+            ]
+        );
+        assert!(!slurm.job_dir(&job_id).join("slurm.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_cancels_job_ignoring_term() {
+        let slurm = FakeSlurm::new().await;
+        let job_id = Uuid::new_v4().to_string();
+        slurm.submitted(&job_id, "4242", 1);
+        slurm.set("state", "RUNNING\n");
+        slurm.set("ignore_term", "");
+        let executor = SlurmExecutor::new(config(SlurmConfig::default())).unwrap();
+
+        executor.stop_job(&job_id).await.unwrap();
+
+        let calls = slurm.calls();
+        assert_eq!(calls[2], "scancel --full --signal=TERM 4242");
+        assert_eq!(calls.last().unwrap(), "scancel 4242");
+        assert_eq!(
+            calls
+                .iter()
+                .filter(|call| call.starts_with("scancel"))
+                .count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_job_cancels_pending_job() {
+        let slurm = FakeSlurm::new().await;
+        let job_id = Uuid::new_v4().to_string();
+        slurm.submitted(&job_id, "4242", 30);
+        slurm.set("state", "PENDING\n");
+        let executor = SlurmExecutor::new(config(SlurmConfig::default())).unwrap();
+
+        executor.stop_job(&job_id).await.unwrap();
+
+        assert_eq!(
+            slurm.calls()[1..],
+            [
+                "squeue -h -j 4242 -o %T",
+                "squeue -h -j 4242 -o %T",
+                "scancel 4242",
+            ]
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{
+    config,
+    domain::agent::{models::AgentState, ports::StateStore},
+    errors::StateStoreError,
+};
+use std::fs;
+use std::path::PathBuf;
+
+/// Keeps the agent's state in `~/.lilac/run/agent-state.json`.
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    pub fn new() -> Result<Self, StateStoreError> {
+        Ok(Self {
+            path: config::get_config_path("run/agent-state.json")?,
+        })
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn load(&self) -> Result<AgentState, StateStoreError> {
+        if !self.path.exists() {
+            return Ok(AgentState::default());
+        }
+        let content = fs::read_to_string(&self.path).map_err(|_| StateStoreError::ReadFile)?;
+        serde_json::from_str(&content).map_err(|_| StateStoreError::Parse)
+    }
+
+    fn save(&self, state: &AgentState) -> Result<(), StateStoreError> {
+        let content = serde_json::to_vec_pretty(state).map_err(|_| StateStoreError::WriteFile)?;
+        if let Some(run_dir) = self.path.parent() {
+            fs::create_dir_all(run_dir).map_err(|_| StateStoreError::WriteFile)?;
+        }
+        // Write to a temporary file first, so that a crash mid-write can't
+        // leave a truncated state behind.
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).map_err(|_| StateStoreError::WriteFile)?;
+        fs::rename(&tmp_path, &self.path).map_err(|_| StateStoreError::WriteFile)
+    }
+}
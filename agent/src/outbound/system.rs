@@ -1,26 +1,43 @@
 use crate::{
     domain::agent::{
         models::{
-            Architecture, Cpu, CpuManufacturer, Gpu, GpuManufacturer, GpuModel, NodeResources,
+            Architecture, Cpu, CpuManufacturer, Gpu, GpuManufacturer, GpuModel, GpuUsage,
+            NodeResources,
         },
         ports::SystemMonitor,
     },
     errors::SystemMonitorError,
 };
 use async_trait::async_trait;
-use nvml_wrapper::Nvml;
+use log::warn;
+use nvml_wrapper::{
+    enums::device::UsedGpuMemory, struct_wrappers::device::ProcessUtilizationSample, Nvml,
+};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
-use sysinfo::System;
-use log::{warn};
+use std::sync::Mutex;
 use strum::IntoEnumIterator;
+use sysinfo::System;
 
-pub struct HybridMonitor;
+pub struct HybridMonitor {
+    /// The timestamp of the newest utilization sample read from each GPU, so
+    /// that each reading only covers what happened since the previous one.
+    last_seen_samples: Mutex<HashMap<u32, u64>>,
+}
+
+impl Default for HybridMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl HybridMonitor {
     pub fn new() -> Self {
-        Self
+        Self {
+            last_seen_samples: Mutex::new(HashMap::new()),
+        }
     }
 
     fn get_cpu_millicores() -> Result<i32, SystemMonitorError> {
@@ -31,8 +48,12 @@ impl HybridMonitor {
                 .to_string();
             let parts: Vec<&str> = cpu_max.split_whitespace().collect();
             if parts.len() == 2 && parts[0] != "max" {
-                let max_us: f64 = parts[0].parse().map_err(|_| SystemMonitorError::ReadError)?;
-                let period_us: f64 = parts[1].parse().map_err(|_| SystemMonitorError::ReadError)?;
+                let max_us: f64 = parts[0]
+                    .parse()
+                    .map_err(|_| SystemMonitorError::ReadError)?;
+                let period_us: f64 = parts[1]
+                    .parse()
+                    .map_err(|_| SystemMonitorError::ReadError)?;
                 return Ok(((max_us / period_us) * 1000.0) as i32);
             }
         }
@@ -93,11 +114,14 @@ impl SystemMonitor for HybridMonitor {
 
         let gpus = match Nvml::init() {
             Ok(nvml) => {
-                let device_count =
-                    nvml.device_count().map_err(|_| SystemMonitorError::ReadError)?;
+                let device_count = nvml
+                    .device_count()
+                    .map_err(|_| SystemMonitorError::ReadError)?;
                 let mut gpu_configs = Vec::with_capacity(device_count as usize);
                 for i in 0..device_count {
-                    let device = nvml.device_by_index(i).map_err(|_| SystemMonitorError::ReadError)?;
+                    let device = nvml
+                        .device_by_index(i)
+                        .map_err(|_| SystemMonitorError::ReadError)?;
                     let model_name = device.name().map_err(|_| SystemMonitorError::ReadError)?;
                     gpu_configs.push(Gpu {
                         manufacturer: GpuManufacturer::Nvidia,
@@ -109,12 +133,17 @@ impl SystemMonitor for HybridMonitor {
                             .total
                             / 1024
                             / 1024) as i32,
+                        index: i,
+                        uuid: device.uuid().ok(),
                     });
                 }
                 gpu_configs
             }
             Err(e) => {
-                warn!("Failed to initialize NVML, no GPUs will be reported. Error: {:?}", e);
+                warn!(
+                    "Failed to initialize NVML, no GPUs will be reported. Error: {:?}",
+                    e
+                );
                 Vec::new()
             }
         };
@@ -127,4 +156,135 @@ impl SystemMonitor for HybridMonitor {
 
         Ok(resources)
     }
-}
\ No newline at end of file
+
+    async fn get_gpu_usage(&self, pids: &[u32]) -> Result<Vec<GpuUsage>, SystemMonitorError> {
+        let nvml = match Nvml::init() {
+            Ok(nvml) => nvml,
+            // No NVML means no GPUs, which is not an error for CPU-only jobs.
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let device_count = nvml
+            .device_count()
+            .map_err(|_| SystemMonitorError::ReadError)?;
+        let mut usage = Vec::new();
+        for i in 0..device_count {
+            let device = nvml
+                .device_by_index(i)
+                .map_err(|_| SystemMonitorError::ReadError)?;
+            let processes = device
+                .running_compute_processes()
+                .map_err(|_| SystemMonitorError::ReadError)?;
+
+            let job_processes: Vec<_> = processes
+                .iter()
+                .filter(|process| pids.contains(&process.pid))
+                .collect();
+            if job_processes.is_empty() {
+                continue;
+            }
+
+            let memory_used_bytes: u64 = job_processes
+                .iter()
+                .map(|process| match process.used_gpu_memory {
+                    UsedGpuMemory::Used(bytes) => bytes,
+                    UsedGpuMemory::Unavailable => 0,
+                })
+                .sum();
+
+            // The driver buffers several samples per process, so only those taken since the
+            // last reading are asked for, and only the newest of them counts for each process.
+            // No new samples means the job has been idle on this device.
+            let last_seen = self.last_seen_samples.lock().unwrap().get(&i).copied();
+            let samples = device
+                .process_utilization_stats(last_seen)
+                .unwrap_or_default();
+            if let Some(newest) = samples.iter().map(|sample| sample.timestamp).max() {
+                self.last_seen_samples.lock().unwrap().insert(i, newest);
+            }
+
+            let mut latest_per_pid: HashMap<u32, &ProcessUtilizationSample> = HashMap::new();
+            for sample in samples.iter().filter(|sample| pids.contains(&sample.pid)) {
+                let latest = latest_per_pid.entry(sample.pid).or_insert(sample);
+                if sample.timestamp > latest.timestamp {
+                    *latest = sample;
+                }
+            }
+            let utilization_percent: u32 =
+                latest_per_pid.values().map(|sample| sample.sm_util).sum();
+
+            usage.push(GpuUsage {
+                index: i as i32,
+                utilization_percent: utilization_percent as i32,
+                memory_used_mb: (memory_used_bytes / 1024 / 1024) as i32,
+            });
+        }
+
+        Ok(usage)
+    }
+}
+
+/// Reports resources from the agent's configuration instead of measuring
+/// its machine, for executors whose jobs run on other machines, such as
+/// those of a Kubernetes or Slurm cluster. Jobs can land on any of those,
+/// so the resources offered are the most one job can ask for.
+pub struct StaticMonitor {
+    cpu_millicores: i32,
+    memory_mb: i32,
+    gpu_count: i32,
+    gpu_model: GpuModel,
+}
+
+impl StaticMonitor {
+    pub fn new(
+        cpu_millicores: i32,
+        memory_mb: i32,
+        gpu_count: i32,
+        gpu_model: Option<GpuModel>,
+    ) -> Self {
+        if cpu_millicores <= 0 || memory_mb <= 0 {
+            warn!("No CPU or memory is configured for the agent to offer, so no jobs will fit.");
+        }
+        Self {
+            cpu_millicores,
+            memory_mb,
+            gpu_count,
+            gpu_model: gpu_model.unwrap_or(GpuModel::T4),
+        }
+    }
+}
+
+#[async_trait]
+impl SystemMonitor for StaticMonitor {
+    async fn get_node_resources(&self) -> Result<NodeResources, SystemMonitorError> {
+        // The make of the cluster's CPUs isn't known, and the agent's own
+        // architecture is the best guess at theirs.
+        let cpu = Cpu {
+            manufacturer: CpuManufacturer::Intel,
+            architecture: Architecture::from_str(std::env::consts::ARCH)
+                .unwrap_or(Architecture::X86_64),
+            millicores: self.cpu_millicores,
+        };
+        let gpus = (0..self.gpu_count.max(0) as u32)
+            .map(|index| Gpu {
+                manufacturer: GpuManufacturer::Nvidia,
+                model: self.gpu_model.clone(),
+                count: 1,
+                memory_mb: 0,
+                index,
+                uuid: None,
+            })
+            .collect();
+
+        Ok(NodeResources {
+            cpu,
+            gpus,
+            memory_mb: self.memory_mb,
+        })
+    }
+
+    /// The jobs' processes aren't on this machine, so neither is their usage.
+    async fn get_gpu_usage(&self, _pids: &[u32]) -> Result<Vec<GpuUsage>, SystemMonitorError> {
+        Ok(Vec::new())
+    }
+}
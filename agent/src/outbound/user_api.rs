@@ -1,6 +1,10 @@
-use crate::{config::UserConfig, errors::UserApiError};
-use reqwest::{Client, RequestBuilder, StatusCode};
+use crate::{
+    config::UserConfig, domain::agent::models::ExecChannel, errors::UserApiError,
+    outbound::websocket,
+};
+use reqwest::{Client, RequestBuilder, StatusCode, Url};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -25,11 +29,84 @@ pub struct SubmitJobRequest {
     pub definition: String, // Docker image
     pub queue_id: String,
     pub resource_requirements: ResourceRequirements,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exposed_ports: Vec<u16>,
+    pub kind: JobKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub termination_grace_period_secs: Option<i32>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Batch,
+    Interactive,
+}
+
+/// Settings of an interactive session's server. Unset fields use the
+/// control plane's defaults.
+#[derive(Serialize, Debug, Default)]
+pub struct SessionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_minutes: Option<i32>,
+}
+
+/// Overrides applied to a job template's spec when submitting it. Unset
+/// fields keep the template's values; env, labels and annotations are merged.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SubmitFromTemplateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_requirements: Option<ResourceRequirements>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exposed_ports: Vec<u16>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct SubmitJobResponse {
     pub id: String,
+    /// Reasons the job may stay queued for a while, e.g. no node being online
+    /// that is large enough to run it.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Where the server of an interactive session can be opened in a
+    /// browser, including its access token.
+    #[serde(default)]
+    pub session_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SweepTrial {
+    pub number: i32,
+    pub job_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateSweepResponse {
+    pub id: String,
+    pub trials: Vec<SweepTrial>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -67,13 +144,48 @@ impl ApiClient {
         let url = format!("{}/training_jobs", self.config.api_endpoint);
 
         let req_builder = self.client.post(&url).json(&request);
+        self.send_submission(req_builder).await
+    }
+
+    pub async fn submit_from_template(
+        &self,
+        template_id: &str,
+        request: SubmitFromTemplateRequest,
+    ) -> Result<SubmitJobResponse, UserApiError> {
+        let url = format!(
+            "{}/job_templates/{}/submit",
+            self.config.api_endpoint, template_id
+        );
+
+        let req_builder = self.client.post(&url).json(&request);
+        self.send_submission(req_builder).await
+    }
+
+    pub async fn resubmit_job(&self, job_id: &str) -> Result<SubmitJobResponse, UserApiError> {
+        let url = format!(
+            "{}/training_jobs/{}/resubmit",
+            self.config.api_endpoint, job_id
+        );
+
+        let req_builder = self.client.post(&url);
+        self.send_submission(req_builder).await
+    }
+
+    async fn send_submission(
+        &self,
+        req_builder: RequestBuilder,
+    ) -> Result<SubmitJobResponse, UserApiError> {
         let req_builder = self.add_auth(req_builder);
 
         let response = req_builder.send().await?;
 
         match response.status() {
             StatusCode::OK | StatusCode::CREATED => {
-                let job_response = response.json::<SubmitJobResponse>().await?;
+                let mut job_response = response.json::<SubmitJobResponse>().await?;
+                // The control plane returns the URL relative to its endpoint.
+                job_response.session_url = job_response
+                    .session_url
+                    .map(|path| format!("{}{}", self.config.api_endpoint, path));
                 Ok(job_response)
             }
             StatusCode::UNAUTHORIZED => Err(UserApiError::Unauthorized),
@@ -89,6 +201,34 @@ impl ApiClient {
         }
     }
 
+    /// Creates a sweep from a spec in the control plane's format. The spec is
+    /// passed through as is, so that the control plane validates it.
+    pub async fn create_sweep(
+        &self,
+        spec: &serde_json::Value,
+    ) -> Result<CreateSweepResponse, UserApiError> {
+        let url = format!("{}/sweeps", self.config.api_endpoint);
+
+        let req_builder = self.client.post(&url).json(spec);
+        let req_builder = self.add_auth(req_builder);
+
+        let response = req_builder.send().await?;
+
+        match response.status() {
+            StatusCode::CREATED => Ok(response.json::<CreateSweepResponse>().await?),
+            StatusCode::UNAUTHORIZED => Err(UserApiError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(UserApiError::NotFound),
+            StatusCode::INTERNAL_SERVER_ERROR => Err(UserApiError::InternalServerError),
+            _ => {
+                let error_text = response.text().await?;
+                Err(UserApiError::Unknown(anyhow::anyhow!(
+                    "Failed to create sweep: {}",
+                    error_text
+                )))
+            }
+        }
+    }
+
     pub async fn get_queues(&self) -> Result<Vec<Queue>, UserApiError> {
         let url = format!("{}/queues", self.config.api_endpoint);
 
@@ -114,4 +254,61 @@ impl ApiClient {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Opens an interactive session running `command` in a job's container.
+    pub async fn exec(
+        &self,
+        job_id: &str,
+        command: &[String],
+        tty: bool,
+    ) -> Result<ExecChannel, UserApiError> {
+        let mut url = websocket::websocket_url(
+            &self.config.api_endpoint,
+            &format!("/training_jobs/{}/exec", job_id),
+        )?;
+        {
+            let mut query = url.query_pairs_mut();
+            for arg in command {
+                query.append_pair("command", arg);
+            }
+            query.append_pair("tty", &tty.to_string());
+        }
+
+        self.open_session(&url).await
+    }
+
+    /// Opens a tunnel to one of a job's exposed ports.
+    pub async fn port_forward(&self, job_id: &str, port: u16) -> Result<ExecChannel, UserApiError> {
+        let url = websocket::websocket_url(
+            &self.config.api_endpoint,
+            &format!("/training_jobs/{}/port_forward/{}", job_id, port),
+        )?;
+        self.open_session(&url).await
+    }
+
+    async fn open_session(&self, url: &Url) -> Result<ExecChannel, UserApiError> {
+        let token = self
+            .config
+            .api_key
+            .as_deref()
+            .ok_or(UserApiError::Unauthorized)?;
+
+        let socket = websocket::connect(url, token)
+            .await
+            .map_err(|e| match websocket::rejection(&e) {
+                Some((StatusCode::UNAUTHORIZED, _)) => UserApiError::Unauthorized,
+                Some((StatusCode::NOT_FOUND, _)) => UserApiError::NotFound,
+                Some((StatusCode::INTERNAL_SERVER_ERROR, _)) => UserApiError::InternalServerError,
+                Some((_, message)) => UserApiError::Unknown(anyhow::anyhow!(
+                    "Failed to connect to job: {}",
+                    message
+                )),
+                None => UserApiError::Unknown(anyhow::anyhow!(
+                    "Failed to connect to job: {}",
+                    e
+                )),
+            })?;
+
+        Ok(websocket::exec_channel(socket))
+    }
+}
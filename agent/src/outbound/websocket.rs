@@ -0,0 +1,97 @@
+use crate::domain::agent::models::{ExecChannel, ExecMessage};
+use futures_util::{SinkExt, StreamExt};
+use reqwest::{StatusCode, Url};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{self, client::IntoClientRequest, http::HeaderValue, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Builds the WebSocket URL of an API path, e.g. `wss://lilac.example.com/api/...`
+/// for an endpoint of `https://lilac.example.com/api`.
+pub fn websocket_url(api_endpoint: &str, path: &str) -> Result<Url, anyhow::Error> {
+    let mut url = Url::parse(&format!("{}{}", api_endpoint, path))?;
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow::anyhow!("cannot use {} for WebSockets", api_endpoint))?;
+    Ok(url)
+}
+
+/// Opens a WebSocket authenticated with a bearer token.
+pub async fn connect(url: &Url, token: &str) -> Result<Socket, tungstenite::Error> {
+    let mut request = url.as_str().into_client_request()?;
+    let authorization = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|e| tungstenite::Error::HttpFormat(e.into()))?;
+    request.headers_mut().insert("Authorization", authorization);
+
+    let (socket, _) = tokio_tungstenite::connect_async(request).await?;
+    Ok(socket)
+}
+
+/// Returns the status and error message of a WebSocket handshake the server
+/// refused, or None if it failed for another reason.
+pub fn rejection(err: &tungstenite::Error) -> Option<(StatusCode, String)> {
+    let tungstenite::Error::Http(response) = err else {
+        return None;
+    };
+    let body = response
+        .body()
+        .as_deref()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    // The control plane reports errors as `{"error": "..."}`.
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+        .unwrap_or_else(|| body.to_string());
+    Some((response.status(), message))
+}
+
+/// Relays an exec session over the socket in the background. Terminal data
+/// is sent as binary frames and control messages as JSON text frames.
+pub fn exec_channel(socket: Socket) -> ExecChannel {
+    let (ours, theirs) = ExecChannel::pair();
+    tokio::spawn(relay(socket, theirs));
+    ours
+}
+
+async fn relay(socket: Socket, mut channel: ExecChannel) {
+    let (mut sink, mut stream) = socket.split();
+    loop {
+        tokio::select! {
+            incoming = stream.next() => {
+                let message = match incoming {
+                    Some(Ok(Message::Binary(data))) => ExecMessage::Data(data.to_vec()),
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                        Ok(control) => ExecMessage::Control(control),
+                        Err(_) => continue,
+                    },
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if channel.tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+            outgoing = channel.rx.recv() => {
+                let message = match outgoing {
+                    Some(ExecMessage::Data(data)) => Message::Binary(data.into()),
+                    Some(ExecMessage::Control(control)) => Message::Text(
+                        serde_json::to_string(&control)
+                            .expect("exec control messages to serialize")
+                            .into(),
+                    ),
+                    None => {
+                        let _ = sink.send(Message::Close(None)).await;
+                        break;
+                    }
+                };
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
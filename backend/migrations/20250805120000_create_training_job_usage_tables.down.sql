@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS training_job_usage_peaks;
+DROP TABLE IF EXISTS training_job_usage_samples;
@@ -0,0 +1,29 @@
+CREATE TABLE training_job_usage_samples (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES training_jobs(id) ON DELETE CASCADE,
+    node_id UUID NOT NULL,
+    sampled_at TIMESTAMPTZ NOT NULL,
+    cpu_millicores INTEGER NOT NULL,
+    memory_rss_mb INTEGER NOT NULL,
+    memory_peak_mb INTEGER NOT NULL,
+    network_rx_bytes BIGINT NOT NULL,
+    network_tx_bytes BIGINT NOT NULL,
+    block_read_bytes BIGINT NOT NULL,
+    block_write_bytes BIGINT NOT NULL,
+    gpus JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS idx_training_job_usage_samples_job_id ON training_job_usage_samples (job_id, sampled_at);
+
+CREATE TABLE training_job_usage_peaks (
+    job_id UUID PRIMARY KEY REFERENCES training_jobs(id) ON DELETE CASCADE,
+    cpu_millicores INTEGER NOT NULL,
+    memory_mb INTEGER NOT NULL,
+    gpu_memory_mb INTEGER NOT NULL,
+    gpu_utilization_percent INTEGER NOT NULL,
+    network_rx_bytes BIGINT NOT NULL,
+    network_tx_bytes BIGINT NOT NULL,
+    block_read_bytes BIGINT NOT NULL,
+    block_write_bytes BIGINT NOT NULL,
+    last_sampled_at TIMESTAMPTZ NOT NULL
+);
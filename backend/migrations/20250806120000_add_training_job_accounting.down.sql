@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS training_job_usage_records;
+
+DROP INDEX IF EXISTS idx_training_jobs_user_id;
+
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS started_at;
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS user_id;
@@ -0,0 +1,23 @@
+ALTER TABLE training_jobs ADD COLUMN user_id UUID REFERENCES users(user_id) ON DELETE SET NULL;
+ALTER TABLE training_jobs ADD COLUMN started_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_training_jobs_user_id ON training_jobs (user_id);
+
+-- Usage records outlive the jobs, users, queues and clusters they refer to, so
+-- none of these columns reference the tables they came from.
+CREATE TABLE training_job_usage_records (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL,
+    user_id UUID,
+    queue_id UUID,
+    cluster_id UUID NOT NULL,
+    node_id UUID NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ NOT NULL,
+    cpu_millicores INTEGER NOT NULL,
+    memory_mb INTEGER NOT NULL,
+    gpu_count INTEGER NOT NULL,
+    gpu_model TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_training_job_usage_records_period ON training_job_usage_records (started_at, ended_at);
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS queue_quotas;
+DROP TABLE IF EXISTS user_quotas;
+
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS pending_reason;
@@ -0,0 +1,19 @@
+ALTER TABLE training_jobs ADD COLUMN pending_reason TEXT;
+
+CREATE TABLE user_quotas (
+    user_id UUID PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    max_gpus INTEGER,
+    max_cpu_millicores INTEGER,
+    max_memory_mb INTEGER,
+    max_running_jobs INTEGER,
+    monthly_gpu_hours DOUBLE PRECISION
+);
+
+CREATE TABLE queue_quotas (
+    queue_id UUID PRIMARY KEY REFERENCES queues(queue_id) ON DELETE CASCADE,
+    max_gpus INTEGER,
+    max_cpu_millicores INTEGER,
+    max_memory_mb INTEGER,
+    max_running_jobs INTEGER,
+    monthly_gpu_hours DOUBLE PRECISION
+);
@@ -0,0 +1,17 @@
+-- The capacity of every node that has ever sent a heartbeat. Unlike
+-- cluster_nodes, rows are kept when a node goes away, so that jobs can be
+-- validated against what a cluster is able to run even while it is scaled down.
+CREATE TABLE cluster_node_capacities (
+    node_id UUID PRIMARY KEY,
+    cluster_id UUID NOT NULL REFERENCES clusters(cluster_id) ON DELETE CASCADE,
+    cpu_millicores INTEGER NOT NULL,
+    memory_mb INTEGER NOT NULL,
+    gpu_count INTEGER NOT NULL,
+    last_seen_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_cluster_node_capacities_cluster_id ON cluster_node_capacities (cluster_id);
+
+INSERT INTO cluster_node_capacities (node_id, cluster_id, cpu_millicores, memory_mb, gpu_count, last_seen_at)
+SELECT node_id, cluster_id, (cpu).millicores, memory_mb, COALESCE((gpu).count, 0), heartbeat_timestamp
+FROM cluster_nodes;
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_training_jobs_labels;
+
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS annotations;
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS labels;
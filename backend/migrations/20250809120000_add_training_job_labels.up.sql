@@ -0,0 +1,4 @@
+ALTER TABLE training_jobs ADD COLUMN labels JSONB NOT NULL DEFAULT '{}'::jsonb;
+ALTER TABLE training_jobs ADD COLUMN annotations JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+CREATE INDEX IF NOT EXISTS idx_training_jobs_labels ON training_jobs USING GIN (labels);
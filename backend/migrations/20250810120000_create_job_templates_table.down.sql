@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS job_templates;
+
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS resubmitted_from;
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS env;
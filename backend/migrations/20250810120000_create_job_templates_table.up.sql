@@ -0,0 +1,22 @@
+ALTER TABLE training_jobs ADD COLUMN env JSONB NOT NULL DEFAULT '{}'::jsonb;
+ALTER TABLE training_jobs ADD COLUMN resubmitted_from UUID REFERENCES training_jobs(id) ON DELETE SET NULL;
+
+CREATE TABLE job_templates (
+    template_id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    shared BOOLEAN NOT NULL DEFAULT FALSE,
+    definition TEXT NOT NULL,
+    queue_id UUID REFERENCES queues(queue_id) ON DELETE SET NULL,
+    resource_requirements JSONB NOT NULL,
+    env JSONB NOT NULL DEFAULT '{}'::jsonb,
+    labels JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC'),
+    updated_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC'),
+    UNIQUE (owner_id, name)
+);
+
+CREATE TRIGGER update_job_templates_updated_at
+BEFORE UPDATE ON job_templates
+FOR EACH ROW
+EXECUTE PROCEDURE set_updated_at_now();
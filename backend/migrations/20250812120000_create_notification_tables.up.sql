@@ -0,0 +1,40 @@
+CREATE TABLE notification_subscriptions (
+    subscription_id UUID PRIMARY KEY,
+    owner_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- One of 'user', 'queue' or 'job'. Queue and job subscriptions set the
+    -- matching column.
+    scope TEXT NOT NULL,
+    queue_id UUID REFERENCES queues(queue_id) ON DELETE CASCADE,
+    job_id UUID REFERENCES training_jobs(id) ON DELETE CASCADE,
+    event_kinds TEXT[] NOT NULL,
+    channel JSONB NOT NULL,
+    backlog_threshold INTEGER,
+    -- Whether the queue was over the backlog threshold on the last scheduler
+    -- cycle, so that a backlog is only reported once.
+    backlog_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC')
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_subscriptions_owner_id ON notification_subscriptions (owner_id);
+
+CREATE TABLE notification_deliveries (
+    delivery_id UUID PRIMARY KEY,
+    subscription_id UUID NOT NULL REFERENCES notification_subscriptions(subscription_id) ON DELETE CASCADE,
+    event_kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    -- One of 'pending', 'succeeded' or 'failed'.
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC'),
+    updated_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC')
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_due ON notification_deliveries (next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_subscription_id ON notification_deliveries (subscription_id, created_at);
+
+CREATE TRIGGER update_notification_deliveries_updated_at
+BEFORE UPDATE ON notification_deliveries
+FOR EACH ROW
+EXECUTE PROCEDURE set_updated_at_now();
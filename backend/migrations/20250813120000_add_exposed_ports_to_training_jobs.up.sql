@@ -0,0 +1 @@
+ALTER TABLE training_jobs ADD COLUMN exposed_ports JSONB NOT NULL DEFAULT '[]'::jsonb;
@@ -0,0 +1,8 @@
+ALTER TABLE queue_quotas DROP COLUMN IF EXISTS max_sessions;
+ALTER TABLE user_quotas DROP COLUMN IF EXISTS max_sessions;
+
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS last_activity_at;
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS session;
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS kind;
+
+DROP TYPE IF EXISTS training_job_kind;
@@ -0,0 +1,8 @@
+CREATE TYPE training_job_kind AS ENUM ('batch', 'interactive');
+
+ALTER TABLE training_jobs ADD COLUMN kind training_job_kind NOT NULL DEFAULT 'batch';
+ALTER TABLE training_jobs ADD COLUMN session JSONB;
+ALTER TABLE training_jobs ADD COLUMN last_activity_at TIMESTAMPTZ;
+
+ALTER TABLE user_quotas ADD COLUMN max_sessions INTEGER;
+ALTER TABLE queue_quotas ADD COLUMN max_sessions INTEGER;
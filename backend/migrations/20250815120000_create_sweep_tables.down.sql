@@ -0,0 +1,8 @@
+DROP TABLE IF EXISTS sweep_trials;
+DROP TABLE IF EXISTS sweeps;
+
+DROP TYPE IF EXISTS sweep_trial_status;
+DROP TYPE IF EXISTS sweep_status;
+DROP TYPE IF EXISTS sweep_strategy;
+
+DROP TABLE IF EXISTS training_job_metrics;
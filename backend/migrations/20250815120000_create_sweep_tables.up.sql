@@ -0,0 +1,55 @@
+CREATE TABLE training_job_metrics (
+    job_id UUID NOT NULL REFERENCES training_jobs(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    step BIGINT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    reported_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (job_id, name, step)
+);
+
+CREATE TYPE sweep_strategy AS ENUM ('grid', 'random', 'tpe');
+CREATE TYPE sweep_status AS ENUM ('running', 'completed', 'cancelled');
+CREATE TYPE sweep_trial_status AS ENUM ('running', 'succeeded', 'failed', 'stopped');
+
+CREATE TABLE sweeps (
+    sweep_id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    queue_id UUID NOT NULL REFERENCES queues(queue_id) ON DELETE CASCADE,
+    definition TEXT NOT NULL,
+    resource_requirements JSONB NOT NULL,
+    env JSONB NOT NULL DEFAULT '{}'::jsonb,
+    labels JSONB NOT NULL DEFAULT '{}'::jsonb,
+    parameters JSONB NOT NULL,
+    objective JSONB NOT NULL,
+    strategy sweep_strategy NOT NULL,
+    early_stopping JSONB,
+    max_trials INTEGER NOT NULL,
+    max_parallel INTEGER NOT NULL,
+    status sweep_status NOT NULL DEFAULT 'running',
+    created_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC'),
+    updated_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC')
+);
+
+CREATE TRIGGER update_sweeps_updated_at
+BEFORE UPDATE ON sweeps
+FOR EACH ROW
+EXECUTE PROCEDURE set_updated_at_now();
+
+-- Trials outlive their jobs: a deleted job leaves its trial behind as failed.
+CREATE TABLE sweep_trials (
+    sweep_id UUID NOT NULL REFERENCES sweeps(sweep_id) ON DELETE CASCADE,
+    number INTEGER NOT NULL,
+    job_id UUID NOT NULL,
+    parameters JSONB NOT NULL,
+    status sweep_trial_status NOT NULL DEFAULT 'running',
+    objective_value DOUBLE PRECISION,
+    created_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC'),
+    updated_at timestamptz NOT NULL DEFAULT (now() at time zone 'UTC'),
+    PRIMARY KEY (sweep_id, number)
+);
+
+CREATE TRIGGER update_sweep_trials_updated_at
+BEFORE UPDATE ON sweep_trials
+FOR EACH ROW
+EXECUTE PROCEDURE set_updated_at_now();
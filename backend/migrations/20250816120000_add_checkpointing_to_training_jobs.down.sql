@@ -0,0 +1,2 @@
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS restart_count;
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS termination_grace_period_secs;
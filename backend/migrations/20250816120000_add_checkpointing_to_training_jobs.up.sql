@@ -0,0 +1,2 @@
+ALTER TABLE training_jobs ADD COLUMN termination_grace_period_secs INTEGER NOT NULL DEFAULT 30;
+ALTER TABLE training_jobs ADD COLUMN restart_count INTEGER NOT NULL DEFAULT 0;
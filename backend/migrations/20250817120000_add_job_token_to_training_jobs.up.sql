@@ -0,0 +1 @@
+ALTER TABLE training_jobs ADD COLUMN job_token TEXT;
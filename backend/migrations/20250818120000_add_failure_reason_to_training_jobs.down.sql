@@ -0,0 +1,3 @@
+ALTER TABLE training_jobs DROP COLUMN IF EXISTS failure_reason;
+
+DROP TYPE IF EXISTS training_job_failure_reason;
@@ -0,0 +1,3 @@
+CREATE TYPE training_job_failure_reason AS ENUM ('error', 'out_of_memory');
+
+ALTER TABLE training_jobs ADD COLUMN failure_reason training_job_failure_reason;
@@ -0,0 +1 @@
+ALTER TABLE training_jobs ADD COLUMN assigned_gpus JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1 @@
+ALTER TABLE cluster_nodes DROP COLUMN IF EXISTS cached_images;
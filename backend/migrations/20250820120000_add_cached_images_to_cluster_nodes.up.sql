@@ -0,0 +1 @@
+ALTER TABLE cluster_nodes ADD COLUMN cached_images TEXT[] NOT NULL DEFAULT '{}';
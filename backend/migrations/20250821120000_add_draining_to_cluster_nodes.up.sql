@@ -0,0 +1 @@
+ALTER TABLE cluster_nodes ADD COLUMN draining BOOLEAN NOT NULL DEFAULT FALSE;
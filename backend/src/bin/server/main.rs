@@ -5,16 +5,31 @@ use tower_sessions::{cookie::Key, Expiry, SessionManagerLayer};
 use server::{
     config::{LilacConfig, LogFormat},
     domain::{
-        auth::service::AuthServiceImpl, cluster::service::ClusterServiceImpl,
-        queue::service::QueueServiceImpl, scheduler::service::SchedulerService,
-        training_job::service::TrainingJobServiceImpl, user::service::UserServiceImpl,
+        accounting::service::AccountingServiceImpl,
+        auth::service::AuthServiceImpl,
+        cluster::service::ClusterServiceImpl,
+        exec::service::ExecServiceImpl,
+        idempotency::service::{IdempotencyService, IdempotencyServiceImpl},
+        job_template::service::JobTemplateServiceImpl,
+        notification::service::{NotificationService, NotificationServiceImpl},
+        queue::service::QueueServiceImpl,
+        quota::service::QuotaServiceImpl,
+        scheduler::service::SchedulerService,
+        sweep::service::{SweepService, SweepServiceImpl},
+        training_job::service::{TrainingJobService, TrainingJobServiceImpl},
+        user::service::UserServiceImpl,
     },
     inbound::http::{AppState, HttpServer},
     outbound::{
         jwt::JwtManager,
+        notification::sender::ChannelNotificationSender,
         persistence::postgres::{
             cluster_repository::PostgresClusterRepository,
-            queue_repository::PostgresQueueRepository, session_repository::PostgresSessionStore,
+            idempotency_repository::PostgresIdempotencyRepository,
+            job_template_repository::PostgresJobTemplateRepository,
+            notification_repository::PostgresNotificationRepository,
+            queue_repository::PostgresQueueRepository, quota_repository::PostgresQuotaRepository,
+            session_repository::PostgresSessionStore, sweep_repository::PostgresSweepRepository,
             training_job_repository::PostgresTrainingJobRepository,
             user_repository::PostgresUserRepository,
         },
@@ -61,11 +76,27 @@ async fn main() -> anyhow::Result<()> {
     let jwt_manager = Arc::new(JwtManager::new(config.secret_key.expose_secret()));
     let training_job_repo = Arc::new(PostgresTrainingJobRepository::new(db_pool.clone()));
     let queue_repo = Arc::new(PostgresQueueRepository::new(db_pool.clone()));
+    let quota_repo = Arc::new(PostgresQuotaRepository::new(db_pool.clone()));
+    let job_template_repo = Arc::new(PostgresJobTemplateRepository::new(db_pool.clone()));
+    let idempotency_repo = Arc::new(PostgresIdempotencyRepository::new(db_pool.clone()));
+    let notification_repo = Arc::new(PostgresNotificationRepository::new(db_pool.clone()));
+    let sweep_repo = Arc::new(PostgresSweepRepository::new(db_pool.clone()));
+    let notification_sender = Arc::new(ChannelNotificationSender::new(
+        config.notifications.smtp.as_ref(),
+        config.notifications.allowed_webhook_hosts.clone(),
+    )?);
 
     // 3. Construct domain services
+    let notification_service = Arc::new(NotificationServiceImpl::new(
+        notification_repo,
+        notification_sender,
+        config.notifications.max_delivery_attempts,
+    ));
     let cluster_service = Arc::new(ClusterServiceImpl::new(
         cluster_repo.clone(),
         training_job_repo.clone(),
+        notification_service.clone(),
+        config.image_prefetch_depth,
     ));
     let user_service = Arc::new(UserServiceImpl::new(user_repo.clone()));
     let session_store = PostgresSessionStore::new(db_pool.clone());
@@ -79,19 +110,51 @@ async fn main() -> anyhow::Result<()> {
     let training_job_service = Arc::new(TrainingJobServiceImpl::new(
         training_job_repo.clone(),
         cluster_repo.clone(),
+        queue_repo.clone(),
+        notification_service.clone(),
     ));
     let queue_service = Arc::new(QueueServiceImpl::new(
         queue_repo.clone(),
         training_job_repo.clone(),
     ));
 
+    let accounting_service = Arc::new(AccountingServiceImpl::new(
+        training_job_repo.clone(),
+        config.gpu_hour_rates.clone(),
+    ));
+    let quota_service = Arc::new(QuotaServiceImpl::new(
+        quota_repo.clone(),
+        training_job_repo.clone(),
+    ));
+    let job_template_service = Arc::new(JobTemplateServiceImpl::new(
+        job_template_repo,
+        training_job_service.clone(),
+    ));
+    let sweep_service = Arc::new(SweepServiceImpl::new(
+        sweep_repo,
+        training_job_repo.clone(),
+        training_job_service.clone(),
+    ));
+    let exec_service = Arc::new(ExecServiceImpl::new(
+        training_job_repo.clone(),
+        cluster_repo.clone(),
+        config.admin_usernames.clone(),
+        std::time::Duration::from_secs(30),
+    ));
+    let idempotency_service = Arc::new(IdempotencyServiceImpl::new(
+        idempotency_repo,
+        chrono::Duration::hours(config.idempotency_key_retention_hours.into()),
+    ));
+
     // 4. Construct Scheduler
     let agent_adapter = Arc::new(AgentSchedulerAdapter::new(cluster_repo.clone()));
     let scheduler_service = Arc::new(SchedulerService::new(
         training_job_repo.clone(),
         queue_repo.clone(),
         cluster_repo.clone(),
+        quota_repo.clone(),
         agent_adapter,
+        notification_service.clone(),
     ));
 
     // 5. Spawn background tasks
@@ -105,6 +168,70 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
+    let purge_service = idempotency_service.clone();
+    let idempotency_purge_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            match purge_service.purge_expired().await {
+                Ok(purged) => tracing::debug!("Purged {} expired idempotency keys", purged),
+                Err(e) => tracing::error!("Failed to purge idempotency keys: {}", e),
+            }
+        }
+    });
+
+    let usage_purge_service = training_job_service.clone();
+    let usage_sample_retention =
+        chrono::Duration::hours(config.usage_sample_retention_hours.into());
+    let usage_purge_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            let before = chrono::Utc::now() - usage_sample_retention;
+            match usage_purge_service.purge_usage_samples(before).await {
+                Ok(purged) => tracing::debug!("Purged {} usage samples", purged),
+                Err(e) => tracing::error!("Failed to purge usage samples: {}", e),
+            }
+        }
+    });
+
+    let dispatch_service = notification_service.clone();
+    let notification_dispatch_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            if let Err(e) = dispatch_service.dispatch_due().await {
+                tracing::error!("Failed to dispatch notifications: {}", e);
+            }
+        }
+    });
+
+    let idle_session_service = training_job_service.clone();
+    let idle_session_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            match idle_session_service.stop_idle_sessions().await {
+                Ok(stopped) if !stopped.is_empty() => {
+                    tracing::info!("Stopped {} idle interactive sessions", stopped.len())
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Failed to stop idle sessions: {}", e),
+            }
+        }
+    });
+
+    let sweep_controller_service = sweep_service.clone();
+    let sweep_controller_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_controller_service.advance_sweeps().await {
+                tracing::error!("Failed to advance sweeps: {}", e);
+            }
+        }
+    });
+
     // 6. Construct and run inbound adapter (HTTP server)
     let app_state = AppState {
         config: config.clone(),
@@ -113,6 +240,13 @@ async fn main() -> anyhow::Result<()> {
         auth_service,
         training_job_service,
         queue_service,
+        accounting_service,
+        quota_service,
+        job_template_service,
+        idempotency_service,
+        notification_service,
+        exec_service,
+        sweep_service,
     };
     let http_server = HttpServer::new(app_state, session_layer, config.http_port).await?;
 
@@ -120,6 +254,11 @@ async fn main() -> anyhow::Result<()> {
     tokio::select! {
         _ = http_server.run() => {},
         _ = scheduler_handle => {},
+        _ = idempotency_purge_handle => {},
+        _ = usage_purge_handle => {},
+        _ = notification_dispatch_handle => {},
+        _ = idle_session_handle => {},
+        _ = sweep_controller_handle => {},
     }
 
     Ok(())
@@ -1,6 +1,6 @@
 use secrecy::SecretString;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 use tracing::{level_filters::LevelFilter, Level};
 use tracing_subscriber::filter::Directive;
 
@@ -45,6 +45,64 @@ impl From<&LogLevel> for Directive {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Plain SMTP, e.g. for a local test server.
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<SecretString>,
+    /// The sender of notification emails, e.g. `Lilac <lilac@example.com>`.
+    pub from: String,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct NotificationsConfig {
+    /// How many times a delivery is attempted before it is marked as failed.
+    #[serde(default = "default_max_delivery_attempts")]
+    pub max_delivery_attempts: i32,
+    /// The server email notifications are sent through. Email subscriptions
+    /// can't be created without one.
+    pub smtp: Option<SmtpConfig>,
+    /// Hosts that webhooks may be delivered to even though they are on a
+    /// loopback, link-local or private network, e.g. `hooks.internal`.
+    /// Webhooks to any other such host are refused.
+    #[serde(default)]
+    pub allowed_webhook_hosts: Vec<String>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            max_delivery_attempts: default_max_delivery_attempts(),
+            smtp: None,
+            allowed_webhook_hosts: Vec::new(),
+        }
+    }
+}
+
+fn default_max_delivery_attempts() -> i32 {
+    5
+}
+
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct LilacConfig {
@@ -60,6 +118,37 @@ pub struct LilacConfig {
     pub disable_sign_up: bool,
     #[serde(default)]
     pub allowed_usernames: Option<Vec<String>>,
+    /// Users who may exec into, bulk-update, relabel and resubmit any job,
+    /// not just their own, and set quotas.
+    #[serde(default)]
+    pub admin_usernames: Vec<String>,
+    /// The cost of one GPU hour, keyed by GPU model (e.g. `A100`). Used to
+    /// price usage reports; models without a rate are reported without cost.
+    #[serde(default)]
+    pub gpu_hour_rates: HashMap<String, f64>,
+    /// How long responses to requests made with an `Idempotency-Key` are kept
+    /// for replay.
+    #[serde(default = "default_idempotency_key_retention_hours")]
+    pub idempotency_key_retention_hours: u32,
+    /// How long the usage samples of a finished job are kept. Its peak usage
+    /// and usage records are kept regardless.
+    #[serde(default = "default_usage_sample_retention_hours")]
+    pub usage_sample_retention_hours: u32,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// How many jobs at the head of each queue have their images pulled
+    /// ahead of time by idle nodes of the clusters the queue targets. 0, the
+    /// default, disables pre-pulling.
+    #[serde(default)]
+    pub image_prefetch_depth: u32,
+}
+
+fn default_idempotency_key_retention_hours() -> u32 {
+    24
+}
+
+fn default_usage_sample_retention_hours() -> u32 {
+    24 * 7
 }
 
 impl LilacConfig {
@@ -0,0 +1,152 @@
+pub mod models;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{UsageGroupBy, UsageRecord, UsageReportFilters},
+        service::{AccountingService, AccountingServiceError, AccountingServiceImpl},
+    };
+    use crate::domain::{
+        cluster::models::{ClusterId, ClusterNode, NodeId},
+        training_job::{
+            models::{JobId, TrainingJob},
+            ports::MockTrainingJobRepository,
+        },
+        user::models::UserId,
+    };
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use std::{collections::HashMap, sync::Arc};
+
+    fn usage_record(user_id: UserId, start_hour: u32, end_hour: u32) -> UsageRecord {
+        UsageRecord {
+            job_id: JobId::generate(),
+            user_id: Some(user_id),
+            queue_id: None,
+            cluster_id: ClusterId::generate(),
+            node_id: NodeId::generate(),
+            // Runs from `start_hour` on August 1st to `end_hour` on August 2nd.
+            started_at: Utc.with_ymd_and_hms(2025, 8, 1, start_hour, 0, 0).unwrap(),
+            ended_at: Utc.with_ymd_and_hms(2025, 8, 2, end_hour, 0, 0).unwrap(),
+            cpu_millicores: 2000,
+            memory_mb: 4096,
+            gpu_count: 2,
+            gpu_model: Some("A100".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_usage_report_by_user_per_day() {
+        let user_id = UserId::generate();
+        let record = usage_record(user_id, 18, 6);
+        let from = Utc.with_ymd_and_hms(2025, 8, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap();
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_list_usage_records()
+            .times(1)
+            .returning(move |_, _| Ok(vec![record.clone()]));
+
+        let service = AccountingServiceImpl::new(
+            Arc::new(mock_repo),
+            HashMap::from([("a100".to_string(), 2.5)]),
+        );
+        let report = service
+            .get_usage_report(UsageReportFilters {
+                from,
+                to,
+                group_by: UsageGroupBy::User,
+                daily: true,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.rows.len(), 2);
+        let first_day = &report.rows[0];
+        assert_eq!(first_day.key, user_id.to_string());
+        assert_eq!(first_day.day, NaiveDate::from_ymd_opt(2025, 8, 1));
+        assert_eq!(first_day.job_count, 1);
+        assert_eq!(first_day.gpu_hours, 12.0);
+        assert_eq!(first_day.cpu_core_hours, 12.0);
+        assert_eq!(first_day.memory_gb_hours, 24.0);
+        assert_eq!(first_day.cost, Some(30.0));
+        let second_day = &report.rows[1];
+        assert_eq!(second_day.day, NaiveDate::from_ymd_opt(2025, 8, 2));
+        assert_eq!(second_day.gpu_hours, 12.0);
+    }
+
+    #[tokio::test]
+    async fn test_get_usage_report_clips_to_period() {
+        let user_id = UserId::generate();
+        let record = usage_record(user_id, 0, 0);
+        let from = Utc.with_ymd_and_hms(2025, 8, 1, 12, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 8, 1, 18, 0, 0).unwrap();
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_list_usage_records()
+            .times(1)
+            .returning(move |_, _| Ok(vec![record.clone()]));
+
+        let service = AccountingServiceImpl::new(Arc::new(mock_repo), HashMap::new());
+        let report = service
+            .get_usage_report(UsageReportFilters {
+                from,
+                to,
+                group_by: UsageGroupBy::Cluster,
+                daily: false,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.rows.len(), 1);
+        assert_eq!(report.rows[0].day, None);
+        assert_eq!(report.rows[0].gpu_hours, 12.0);
+        assert_eq!(report.rows[0].cost, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_usage_report_rejects_empty_period() {
+        let mock_repo = MockTrainingJobRepository::new();
+        let service = AccountingServiceImpl::new(Arc::new(mock_repo), HashMap::new());
+        let now = Utc::now();
+
+        let result = service
+            .get_usage_report(UsageReportFilters {
+                from: now,
+                to: now,
+                group_by: UsageGroupBy::Day,
+                daily: false,
+            })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AccountingServiceError::InvalidPeriod(_))
+        ));
+    }
+
+    #[test]
+    fn test_usage_record_for_run_that_never_reported_running() {
+        let node = ClusterNode::new_mock();
+        let assigned_at = Utc.with_ymd_and_hms(2025, 8, 1, 12, 0, 0).unwrap();
+        let ended_at = Utc.with_ymd_and_hms(2025, 8, 1, 12, 0, 30).unwrap();
+        let job = TrainingJob {
+            node_id: Some(node.id),
+            started_at: None,
+            assigned_at: Some(assigned_at),
+            ..TrainingJob::new_mock()
+        };
+
+        let record = UsageRecord::for_run(&job, &node, ended_at).unwrap();
+        assert_eq!(record.started_at, assigned_at);
+        assert_eq!(record.ended_at, ended_at);
+
+        let never_placed = TrainingJob {
+            assigned_at: None,
+            ..job
+        };
+        assert!(UsageRecord::for_run(&never_placed, &node, ended_at).is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    cluster::models::{ClusterId, ClusterNode, NodeId},
+    queue::models::QueueId,
+    training_job::models::{JobId, TrainingJob},
+    user::models::UserId,
+};
+
+/// The resources a job held on a node for one continuous run.
+///
+/// A record is written whenever a run ends: when the job finishes, is
+/// cancelled, or is re-queued off a dead node. A job that was re-queued
+/// will therefore have one record per attempt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageRecord {
+    pub job_id: JobId,
+    pub user_id: Option<UserId>,
+    pub queue_id: Option<QueueId>,
+    pub cluster_id: ClusterId,
+    pub node_id: NodeId,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub gpu_count: i32,
+    /// The model of the GPUs on the node the job ran on, if it used any.
+    pub gpu_model: Option<String>,
+}
+
+impl UsageRecord {
+    /// Builds the record for the current run of `job` on `node`, or None if
+    /// the job was never placed on a node. Runs that ended before the agent
+    /// reported them running are counted from when the job was placed.
+    pub fn for_run(job: &TrainingJob, node: &ClusterNode, ended_at: DateTime<Utc>) -> Option<Self> {
+        let started_at = job.started_at.or(job.assigned_at)?;
+        let requirements = &job.resource_requirements;
+        let gpu_count = requirements.gpu_count();
+
+        Some(Self {
+            job_id: job.id,
+            user_id: job.user_id,
+            queue_id: job.queue_id,
+            cluster_id: node.cluster_id,
+            node_id: node.id,
+            started_at,
+            ended_at: ended_at.max(started_at),
+            cpu_millicores: requirements.cpu_millicores,
+            memory_mb: requirements.memory_mb,
+            gpu_count,
+            gpu_model: node
+                .gpu
+                .as_ref()
+                .filter(|_| gpu_count > 0)
+                .map(|gpu| gpu.model.to_string()),
+        })
+    }
+
+    /// The part of this record's run that falls in `[from, to)`, in hours.
+    pub fn hours_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+        let start = self.started_at.max(from);
+        let end = self.ended_at.min(to);
+        if end <= start {
+            return 0.0;
+        }
+        (end - start).num_milliseconds() as f64 / 3_600_000.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroupBy {
+    #[default]
+    User,
+    Queue,
+    Cluster,
+    Day,
+}
+
+impl fmt::Display for UsageGroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            UsageGroupBy::User => "user",
+            UsageGroupBy::Queue => "queue",
+            UsageGroupBy::Cluster => "cluster",
+            UsageGroupBy::Day => "day",
+        };
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageReportFilters {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub group_by: UsageGroupBy,
+    /// Break each group down further by UTC day.
+    pub daily: bool,
+}
+
+/// Aggregated usage for one group, GPU model and (optionally) day.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageReportRow {
+    /// The user, queue or cluster id, or the day, depending on how the report
+    /// is grouped. `unknown` for usage of jobs without an owner or queue.
+    pub key: String,
+    pub day: Option<NaiveDate>,
+    pub gpu_model: Option<String>,
+    pub job_count: i64,
+    pub gpu_hours: f64,
+    pub cpu_core_hours: f64,
+    pub memory_gb_hours: f64,
+    /// GPU hours priced at the configured rate for the GPU model. None if no
+    /// rate is configured for the model.
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub group_by: UsageGroupBy,
+    pub rows: Vec<UsageReportRow>,
+}
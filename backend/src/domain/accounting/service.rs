@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use thiserror::Error;
+
+use super::models::{UsageGroupBy, UsageRecord, UsageReport, UsageReportFilters, UsageReportRow};
+use crate::domain::training_job::{
+    models::JobId,
+    ports::{TrainingJobRepository, TrainingJobRepositoryError},
+};
+
+#[derive(Debug, Error)]
+pub enum AccountingServiceError {
+    #[error("invalid report period: {0}")]
+    InvalidPeriod(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<TrainingJobRepositoryError> for AccountingServiceError {
+    fn from(err: TrainingJobRepositoryError) -> Self {
+        match err {
+            TrainingJobRepositoryError::Unknown(err) => AccountingServiceError::Unknown(err),
+            _ => AccountingServiceError::Unknown(anyhow::anyhow!(err)),
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait AccountingService: Send + Sync {
+    /// Lists the usage records of all runs that overlap `[from, to)`.
+    async fn get_usage_records(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<UsageRecord>, AccountingServiceError>;
+    async fn get_usage_report(
+        &self,
+        filters: UsageReportFilters,
+    ) -> Result<UsageReport, AccountingServiceError>;
+}
+
+pub struct AccountingServiceImpl {
+    repository: Arc<dyn TrainingJobRepository>,
+    /// Cost of one GPU hour, keyed by lowercased GPU model.
+    gpu_hour_rates: HashMap<String, f64>,
+}
+
+impl AccountingServiceImpl {
+    pub fn new(
+        repository: Arc<dyn TrainingJobRepository>,
+        gpu_hour_rates: HashMap<String, f64>,
+    ) -> Self {
+        Self {
+            repository,
+            gpu_hour_rates: gpu_hour_rates
+                .into_iter()
+                .map(|(model, rate)| (model.to_lowercase(), rate))
+                .collect(),
+        }
+    }
+}
+
+fn validate_period(from: DateTime<Utc>, to: DateTime<Utc>) -> Result<(), AccountingServiceError> {
+    if from >= to {
+        return Err(AccountingServiceError::InvalidPeriod(format!(
+            "'from' ({from}) must be before 'to' ({to})"
+        )));
+    }
+    Ok(())
+}
+
+fn start_of_day(day: NaiveDate) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc()
+}
+
+/// Splits the part of `record` that falls in `[from, to)` at UTC midnight.
+fn daily_segments(
+    record: &UsageRecord,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<(NaiveDate, DateTime<Utc>, DateTime<Utc>)> {
+    let start = record.started_at.max(from);
+    let end = record.ended_at.min(to);
+    let mut segments = Vec::new();
+
+    let mut day = start.date_naive();
+    while start_of_day(day) < end {
+        let Some(next_day) = day.checked_add_days(Days::new(1)) else {
+            break;
+        };
+        segments.push((
+            day,
+            start_of_day(day).max(start),
+            start_of_day(next_day).min(end),
+        ));
+        day = next_day;
+    }
+
+    segments
+}
+
+#[derive(Default)]
+struct RowTotals {
+    jobs: HashSet<JobId>,
+    gpu_hours: f64,
+    cpu_core_hours: f64,
+    memory_gb_hours: f64,
+}
+
+#[async_trait]
+impl AccountingService for AccountingServiceImpl {
+    async fn get_usage_records(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<UsageRecord>, AccountingServiceError> {
+        validate_period(from, to)?;
+        Ok(self.repository.list_usage_records(from, to).await?)
+    }
+
+    async fn get_usage_report(
+        &self,
+        filters: UsageReportFilters,
+    ) -> Result<UsageReport, AccountingServiceError> {
+        validate_period(filters.from, filters.to)?;
+        let records = self
+            .repository
+            .list_usage_records(filters.from, filters.to)
+            .await?;
+
+        let daily = filters.daily || filters.group_by == UsageGroupBy::Day;
+        let mut totals: BTreeMap<(String, Option<NaiveDate>, Option<String>), RowTotals> =
+            BTreeMap::new();
+
+        for record in &records {
+            let segments = if daily {
+                daily_segments(record, filters.from, filters.to)
+                    .into_iter()
+                    .map(|(day, start, end)| (Some(day), start, end))
+                    .collect()
+            } else {
+                vec![(None, filters.from, filters.to)]
+            };
+
+            for (day, start, end) in segments {
+                let hours = record.hours_between(start, end);
+                if hours <= 0.0 {
+                    continue;
+                }
+
+                let key = match filters.group_by {
+                    UsageGroupBy::User => record.user_id.map(|id| id.to_string()),
+                    UsageGroupBy::Queue => record.queue_id.map(|id| id.to_string()),
+                    UsageGroupBy::Cluster => Some(record.cluster_id.to_string()),
+                    UsageGroupBy::Day => day.map(|day| day.to_string()),
+                }
+                .unwrap_or_else(|| "unknown".to_string());
+
+                let row = totals
+                    .entry((key, day, record.gpu_model.clone()))
+                    .or_default();
+                row.jobs.insert(record.job_id);
+                row.gpu_hours += hours * record.gpu_count as f64;
+                row.cpu_core_hours += hours * record.cpu_millicores as f64 / 1000.0;
+                row.memory_gb_hours += hours * record.memory_mb as f64 / 1024.0;
+            }
+        }
+
+        let rows = totals
+            .into_iter()
+            .map(|((key, day, gpu_model), row)| UsageReportRow {
+                cost: gpu_model
+                    .as_ref()
+                    .and_then(|model| self.gpu_hour_rates.get(&model.to_lowercase()))
+                    .map(|rate| rate * row.gpu_hours),
+                key,
+                day: if filters.daily { day } else { None },
+                gpu_model,
+                job_count: row.jobs.len() as i64,
+                gpu_hours: row.gpu_hours,
+                cpu_core_hours: row.cpu_core_hours,
+                memory_gb_hours: row.memory_gb_hours,
+            })
+            .collect();
+
+        Ok(UsageReport {
+            from: filters.from,
+            to: filters.to,
+            group_by: filters.group_by,
+            rows,
+        })
+    }
+}
@@ -35,7 +35,7 @@ impl Token {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Claims { pub sub: UserId,
     pub exp: usize,
     pub iat: usize,
@@ -48,6 +48,11 @@ impl FromRequestParts<AppState> for Claims {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        // 1. Reuse the claims if a middleware has already validated the token.
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(claims.clone());
+        }
+
         // 2. Handle the Option returned by `typed_get` correctly.
         let bearer_token =
             parts
@@ -1,5 +1,7 @@
 use crate::{
-    domain::training_job::models::{JobId, TrainingJobStatus},
+    domain::training_job::models::{
+        AssignedGpu, FailureReason, JobId, ResourceRequirements, ResourceUsage, TrainingJobStatus,
+    },
     identifier,
 };
 use chrono::{DateTime, Utc};
@@ -162,6 +164,11 @@ pub struct ClusterNode {
     pub updated_at: DateTime<Utc>,
     pub assigned_job_id: Option<JobId>,
     pub reported_job_id: Option<JobId>,
+    /// The Docker images the node's agent keeps cached, by tag and digest.
+    pub cached_images: Vec<String>,
+    /// Whether the node's agent is shutting down. Draining nodes are
+    /// assigned no new jobs.
+    pub draining: bool,
 }
 
 impl ClusterNode {
@@ -184,14 +191,95 @@ impl ClusterNode {
             updated_at: Utc::now(),
             assigned_job_id: None,
             reported_job_id: None,
+            cached_images: Vec::new(),
+            draining: false,
         }
     }
+
+    pub fn capacity(&self) -> NodeCapacity {
+        NodeCapacity {
+            node_id: self.id,
+            cluster_id: self.cluster_id,
+            cpu_millicores: self.cpu.millicores,
+            memory_mb: self.memory_mb,
+            gpu_count: self.gpu.as_ref().map_or(0, |gpu| gpu.count),
+            last_seen_at: self.heartbeat_timestamp,
+        }
+    }
+
+    /// Whether the node's agent has `image` cached, so a job using it starts
+    /// without pulling it.
+    pub fn has_image(&self, image: &str) -> bool {
+        let image = normalize_image_reference(image);
+        self.cached_images
+            .iter()
+            .any(|cached| normalize_image_reference(cached) == image)
+    }
+}
+
+/// Spells an image reference the way Docker lists it, so that references to
+/// the same image compare equal: Docker Hub's registry and `library/`
+/// namespace are dropped, a missing tag means `latest`, and the tag of a
+/// reference pinned by digest is dropped.
+pub fn normalize_image_reference(reference: &str) -> String {
+    let reference = reference.trim();
+    let reference = reference
+        .strip_prefix("docker.io/")
+        .or_else(|| reference.strip_prefix("index.docker.io/"))
+        .unwrap_or(reference);
+    let reference = reference.strip_prefix("library/").unwrap_or(reference);
+
+    let (path, name) = reference.rsplit_once('/').unwrap_or(("", reference));
+    let join = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", path, name)
+        }
+    };
+    match name.split_once('@') {
+        Some((name, digest)) => {
+            let name = name.split_once(':').map_or(name, |(name, _)| name);
+            join(&format!("{}@{}", name, digest))
+        }
+        None if name.contains(':') => reference.to_string(),
+        None => join(&format!("{}:latest", name)),
+    }
+}
+
+/// The total resources of a node, as last reported by its agent.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct NodeCapacity {
+    pub node_id: NodeId,
+    pub cluster_id: ClusterId,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub gpu_count: i32,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+impl NodeCapacity {
+    /// Whether a job with `requirements` could run on this node if it were idle.
+    pub fn fits(&self, requirements: &ResourceRequirements) -> bool {
+        self.cpu_millicores >= requirements.cpu_millicores
+            && self.memory_mb >= requirements.memory_mb
+            && self.gpu_count >= requirements.gpu_count()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct JobInfo {
     pub current_job_id: JobId,
     pub status: TrainingJobStatus,
+    /// Resource usage sampled by the agent while the job is running.
+    #[serde(default)]
+    pub usage: Option<ResourceUsage>,
+    /// Why the job failed, sent along with the `failed` status.
+    #[serde(default)]
+    pub failure_reason: Option<FailureReason>,
+    /// The GPUs of the node the agent handed to the job.
+    #[serde(default)]
+    pub assigned_gpus: Vec<AssignedGpu>,
 }
 
 #[derive(Clone, Debug)]
@@ -203,6 +291,8 @@ pub struct UpdateNodeStatusRequest {
     pub cpu_info: Cpu,
     pub gpu_info: Option<Gpu>,
     pub job_info: Option<JobInfo>,
+    pub cached_images: Vec<String>,
+    pub draining: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -269,6 +359,49 @@ impl ClusterNode {
             updated_at: Utc::now(),
             assigned_job_id: None,
             reported_job_id: None,
+            cached_images: Vec::new(),
+            draining: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_image_reference() {
+        assert_eq!(normalize_image_reference("ubuntu"), "ubuntu:latest");
+        assert_eq!(
+            normalize_image_reference("docker.io/library/ubuntu:22.04"),
+            "ubuntu:22.04"
+        );
+        assert_eq!(
+            normalize_image_reference("docker.io/pytorch/pytorch"),
+            "pytorch/pytorch:latest"
+        );
+        assert_eq!(
+            normalize_image_reference("localhost:5000/team/trainer"),
+            "localhost:5000/team/trainer:latest"
+        );
+        assert_eq!(
+            normalize_image_reference("ghcr.io/acme/resnet:1.2@sha256:abc"),
+            "ghcr.io/acme/resnet@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_has_image() {
+        let node = ClusterNode {
+            cached_images: vec![
+                "pytorch/pytorch:2.1.0".to_string(),
+                "pytorch/pytorch@sha256:abc".to_string(),
+            ],
+            ..ClusterNode::new_mock()
+        };
+
+        assert!(node.has_image("docker.io/pytorch/pytorch:2.1.0"));
+        assert!(node.has_image("pytorch/pytorch:2.1.0@sha256:abc"));
+        assert!(!node.has_image("pytorch/pytorch"));
+    }
+}
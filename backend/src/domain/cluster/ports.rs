@@ -3,7 +3,7 @@ use thiserror::Error;
 
 use crate::domain::{
     cluster::models::{
-        ClusterDetails, ClusterNode, ClusterSummary, NodeId, UpdateNodeStatusRequest,
+        ClusterDetails, ClusterNode, ClusterSummary, NodeCapacity, NodeId, UpdateNodeStatusRequest,
     },
     training_job::models::{JobId, TrainingJob},
     user::models::{ApiKey, ApiKeyId},
@@ -48,10 +48,17 @@ pub trait ClusterRepository: Send + Sync {
         &self,
         id: &NodeId,
     ) -> Result<ClusterNode, ClusterRepositoryError>;
+    /// Records the heartbeat of a node, creating the node if it is new.
     async fn update_cluster_node_status(
         &self,
         req: &UpdateNodeStatusRequest,
     ) -> Result<ClusterNode, ClusterRepositoryError>;
+    /// Lists the capacity of every node ever seen in the cluster, including
+    /// nodes that have since gone away.
+    async fn list_node_capacities(
+        &self,
+        id: &ClusterId,
+    ) -> Result<Vec<NodeCapacity>, ClusterRepositoryError>;
     async fn delete_cluster_node(&self, node_id: &NodeId) -> Result<(), ClusterRepositoryError>;
     async fn clear_assigned_job_id(&self, node_id: &NodeId) -> Result<(), ClusterRepositoryError>;
     async fn assign_job_to_node(
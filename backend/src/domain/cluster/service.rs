@@ -3,9 +3,17 @@ use std::sync::Arc;
 use async_trait::async_trait;
 
 use crate::domain::{
-    cluster::models::{ClusterDetails, ClusterNode, ClusterSummary, UpdateNodeStatusRequest},
+    accounting::models::UsageRecord,
+    cluster::models::{
+        normalize_image_reference, ClusterDetails, ClusterNode, ClusterSummary, NodeId, NodeStatus,
+        UpdateNodeStatusRequest,
+    },
+    notification::{
+        models::NotificationEvent,
+        service::{publish_event, NotificationService},
+    },
     training_job::{
-        models::{TrainingJob, TrainingJobStatus},
+        models::{FailureReason, ResourceUsageSample, TrainingJob, TrainingJobStatus},
         ports::TrainingJobRepository,
     },
     user::models::{ApiKey, ApiKeyId},
@@ -109,6 +117,14 @@ pub trait ClusterService: Send + Sync {
         &self,
         req: UpdateNodeStatusRequest,
     ) -> Result<ClusterNode, ClusterServiceError>;
+    /// Removes a node whose agent is shutting down, re-queueing the jobs it
+    /// was assigned or running that haven't finished. Removing a node that
+    /// is already gone succeeds.
+    async fn deregister_node(
+        &self,
+        node_id: &NodeId,
+        cluster_id: &ClusterId,
+    ) -> Result<(), ClusterServiceError>;
     async fn authenticate_by_api_key(
         &self,
         key: &SecretString,
@@ -134,6 +150,13 @@ pub trait ClusterService: Send + Sync {
         &self,
         node_id: &super::models::NodeId,
     ) -> Result<ClusterNode, ClusterServiceError>;
+    /// Lists the images an idle node should pull ahead of time: those of the
+    /// jobs at the head of the queues targeting its cluster that it doesn't
+    /// have cached yet.
+    async fn get_prefetch_images(
+        &self,
+        node: &ClusterNode,
+    ) -> Result<Vec<String>, ClusterServiceError>;
 }
 
 #[derive(Clone)]
@@ -143,15 +166,26 @@ pub struct ClusterServiceImpl<
 > {
     cluster_repo: Arc<R>,
     training_job_repo: Arc<T>,
+    notification_service: Arc<dyn NotificationService>,
+    /// How many jobs at the head of each queue have their images pulled by
+    /// idle nodes ahead of time. 0 disables pre-pulling.
+    image_prefetch_depth: u32,
 }
 
 impl<R: ClusterRepository + ClusterApiKeyRepository, T: TrainingJobRepository>
     ClusterServiceImpl<R, T>
 {
-    pub fn new(cluster_repo: Arc<R>, training_job_repo: Arc<T>) -> Self {
+    pub fn new(
+        cluster_repo: Arc<R>,
+        training_job_repo: Arc<T>,
+        notification_service: Arc<dyn NotificationService>,
+        image_prefetch_depth: u32,
+    ) -> Self {
         Self {
             cluster_repo,
             training_job_repo,
+            notification_service,
+            image_prefetch_depth,
         }
     }
 }
@@ -218,6 +252,30 @@ impl<R: ClusterRepository + ClusterApiKeyRepository, T: TrainingJobRepository> C
                     .update_status(&job_id, job_info.status.clone())
                     .await?;
 
+                if !job_info.assigned_gpus.is_empty() && job_info.assigned_gpus != job.assigned_gpus
+                {
+                    self.training_job_repo
+                        .set_assigned_gpus(&job_id, &job_info.assigned_gpus)
+                        .await?;
+                }
+
+                if let Some(usage) = &job_info.usage {
+                    // Telemetry is best effort; losing a sample must not fail
+                    // the heartbeat and the status updates that come with it.
+                    if let Err(e) = self
+                        .training_job_repo
+                        .record_usage(&ResourceUsageSample {
+                            job_id,
+                            node_id: req.node_id,
+                            sampled_at: req.heartbeat_timestamp,
+                            usage: usage.clone(),
+                        })
+                        .await
+                    {
+                        tracing::warn!("Failed to record usage of job {}: {}", job_id, e);
+                    }
+                }
+
                 if matches!(
                     job_info.status,
                     TrainingJobStatus::Succeeded | TrainingJobStatus::Failed
@@ -225,6 +283,34 @@ impl<R: ClusterRepository + ClusterApiKeyRepository, T: TrainingJobRepository> C
                     self.cluster_repo
                         .clear_assigned_job_id(&req.node_id)
                         .await?;
+
+                    if job_info.status == TrainingJobStatus::Failed {
+                        // Agents that predate failure reasons only report the status.
+                        let reason = job_info.failure_reason.unwrap_or(FailureReason::Error);
+                        self.training_job_repo
+                            .set_failure_reason(&job_id, reason)
+                            .await?;
+                    }
+
+                    match self.cluster_repo.get_cluster_node_by_id(&req.node_id).await {
+                        Ok(node) => {
+                            if let Some(record) =
+                                UsageRecord::for_run(&job, &node, req.heartbeat_timestamp)
+                            {
+                                self.training_job_repo.create_usage_record(&record).await?;
+                            }
+                        }
+                        Err(e) => tracing::warn!(
+                            "Not recording usage of job {}, node {} could not be loaded: {}",
+                            job_id,
+                            req.node_id,
+                            e
+                        ),
+                    }
+
+                    if let Some(event) = NotificationEvent::job_finished(&job, &job_info.status) {
+                        publish_event(self.notification_service.as_ref(), event).await;
+                    }
                 }
             }
         }
@@ -245,6 +331,46 @@ impl<R: ClusterRepository + ClusterApiKeyRepository, T: TrainingJobRepository> C
         Ok(node)
     }
 
+    async fn deregister_node(
+        &self,
+        node_id: &NodeId,
+        cluster_id: &ClusterId,
+    ) -> Result<(), ClusterServiceError> {
+        let node = match self.cluster_repo.get_cluster_node_by_id(node_id).await {
+            Ok(node) => node,
+            Err(ClusterRepositoryError::NotFound(_)) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if node.cluster_id != *cluster_id {
+            return Err(ClusterServiceError::InvalidPermissions);
+        }
+
+        let mut job_ids = Vec::new();
+        job_ids.extend(node.assigned_job_id);
+        job_ids.extend(
+            node.reported_job_id
+                .filter(|id| Some(*id) != node.assigned_job_id),
+        );
+        for job_id in job_ids {
+            let job = self
+                .training_job_repo
+                .get_training_job_by_id(&job_id)
+                .await?;
+            // A job the node reported finished was recorded when it finished.
+            if job.node_id != Some(node.id) || job.is_finished() {
+                continue;
+            }
+            tracing::info!(node_id = %node.id, job_id = %job_id, "Re-queueing job of deregistered node");
+            if let Some(record) = UsageRecord::for_run(&job, &node, Utc::now()) {
+                self.training_job_repo.create_usage_record(&record).await?;
+            }
+            self.training_job_repo.reset_job_status(&job_id).await?;
+        }
+
+        self.cluster_repo.delete_cluster_node(&node.id).await?;
+        Ok(())
+    }
+
     async fn list_cluster_nodes(
         &self,
         cluster_id: &ClusterId,
@@ -347,4 +473,29 @@ impl<R: ClusterRepository + ClusterApiKeyRepository, T: TrainingJobRepository> C
         let node = self.cluster_repo.get_cluster_node_by_id(node_id).await?;
         Ok(node)
     }
+
+    async fn get_prefetch_images(
+        &self,
+        node: &ClusterNode,
+    ) -> Result<Vec<String>, ClusterServiceError> {
+        let idle = node.node_status == NodeStatus::Available
+            && node.assigned_job_id.is_none()
+            && !node.draining;
+        if self.image_prefetch_depth == 0 || !idle {
+            return Ok(Vec::new());
+        }
+
+        let images = self
+            .training_job_repo
+            .get_queue_head_definitions(&node.cluster_id, self.image_prefetch_depth.into())
+            .await?;
+        let mut missing: Vec<String> = Vec::new();
+        for image in images {
+            let normalized = normalize_image_reference(&image);
+            if !node.has_image(&normalized) && !missing.contains(&normalized) {
+                missing.push(normalized);
+            }
+        }
+        Ok(missing)
+    }
 }
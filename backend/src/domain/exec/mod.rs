@@ -0,0 +1,342 @@
+pub mod models;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{ExecControl, ExecMessage, ExecRequest, ExecTarget},
+        service::{ExecService, ExecServiceError, ExecServiceImpl},
+    };
+    use crate::domain::{
+        cluster::{
+            models::{ClusterId, ClusterNode},
+            ports::MockClusterRepository,
+        },
+        training_job::{
+            models::{JobKind, SessionSettings, TrainingJob, TrainingJobStatus},
+            ports::MockTrainingJobRepository,
+        },
+        user::models::User,
+    };
+    use mockall::predicate::*;
+    use std::{sync::Arc, time::Duration};
+
+    fn running_job(user: &User, node: &ClusterNode) -> TrainingJob {
+        TrainingJob {
+            status: TrainingJobStatus::Running,
+            user_id: Some(user.id),
+            node_id: Some(node.id),
+            ..TrainingJob::new_mock()
+        }
+    }
+
+    fn bash() -> ExecRequest {
+        ExecRequest {
+            command: vec!["bash".to_string()],
+            tty: true,
+        }
+    }
+
+    fn service(
+        job: TrainingJob,
+        node: ClusterNode,
+        admin_usernames: Vec<String>,
+        attach_timeout: Duration,
+    ) -> ExecServiceImpl {
+        let mut mock_job_repo = MockTrainingJobRepository::new();
+        mock_job_repo
+            .expect_get_training_job_by_id()
+            .with(eq(job.id))
+            .returning(move |_| Ok(job.clone()));
+        mock_job_repo
+            .expect_record_session_activity()
+            .returning(|_, _| Ok(()));
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        mock_cluster_repo
+            .expect_get_cluster_node_by_id()
+            .with(eq(node.id))
+            .returning(move |_| Ok(node.clone()));
+
+        ExecServiceImpl::new(
+            Arc::new(mock_job_repo),
+            Arc::new(mock_cluster_repo),
+            admin_usernames,
+            attach_timeout,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_session_is_relayed_between_user_and_agent() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&user, &node);
+        let service = service(job.clone(), node.clone(), vec![], Duration::from_secs(30));
+
+        let (session, mut user_end) = service.open_session(&job.id, &user, bash()).await.unwrap();
+        assert_eq!(session.node_id, node.id);
+        assert_eq!(session.target, ExecTarget::Command(bash()));
+
+        let offered = service
+            .wait_for_sessions(&node.id, &node.cluster_id, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(offered, vec![session.clone()]);
+
+        let mut agent_end = service
+            .attach_agent(&session.id, &node.cluster_id)
+            .await
+            .unwrap();
+
+        user_end
+            .tx
+            .send(ExecMessage::Data(b"ls\n".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(
+            agent_end.rx.recv().await,
+            Some(ExecMessage::Data(b"ls\n".to_vec()))
+        );
+        agent_end
+            .tx
+            .send(ExecMessage::Control(ExecControl::Exit { code: 0 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            user_end.rx.recv().await,
+            Some(ExecMessage::Control(ExecControl::Exit { code: 0 }))
+        );
+
+        // A session can only be attached to once.
+        assert!(matches!(
+            service.attach_agent(&session.id, &node.cluster_id).await,
+            Err(ExecServiceError::SessionNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_waiting_agent_is_woken_by_new_session() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&user, &node);
+        let service = Arc::new(service(
+            job.clone(),
+            node.clone(),
+            vec![],
+            Duration::from_secs(30),
+        ));
+
+        let waiter = {
+            let service = service.clone();
+            let node = node.clone();
+            tokio::spawn(async move {
+                service
+                    .wait_for_sessions(&node.id, &node.cluster_id, Duration::from_secs(10))
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+
+        let (session, _user_end) = service.open_session(&job.id, &user, bash()).await.unwrap();
+
+        let offered = tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("agent to be woken before its poll times out")
+            .unwrap()
+            .unwrap();
+        assert_eq!(offered, vec![session]);
+    }
+
+    #[tokio::test]
+    async fn test_wakers_of_nodes_that_stopped_polling_are_dropped() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let other_node = ClusterNode::new_mock();
+        let job = running_job(&user, &node);
+        let service = service(job, node.clone(), vec![], Duration::from_secs(30));
+
+        service
+            .wait_for_sessions(&node.id, &node.cluster_id, Duration::from_millis(10))
+            .await
+            .unwrap();
+        service
+            .wait_for_sessions(
+                &other_node.id,
+                &other_node.cluster_id,
+                Duration::from_millis(10),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(service.node_waker_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_only_offered_to_their_cluster() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&user, &node);
+        let service = service(job.clone(), node.clone(), vec![], Duration::from_secs(30));
+
+        let (session, _user_end) = service.open_session(&job.id, &user, bash()).await.unwrap();
+        let other_cluster = ClusterId::generate();
+
+        let offered = service
+            .wait_for_sessions(&node.id, &other_cluster, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(offered.is_empty());
+        assert!(matches!(
+            service.attach_agent(&session.id, &other_cluster).await,
+            Err(ExecServiceError::SessionNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_open_session_requires_owner_or_admin() {
+        let owner = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&owner, &node);
+        let admin = User {
+            username: "admin".to_string(),
+            ..User::new_mock()
+        };
+        let service = service(
+            job.clone(),
+            node,
+            vec!["admin".to_string()],
+            Duration::from_secs(30),
+        );
+
+        let stranger = User {
+            username: "stranger".to_string(),
+            ..User::new_mock()
+        };
+        assert!(matches!(
+            service.open_session(&job.id, &stranger, bash()).await,
+            Err(ExecServiceError::InvalidPermissions)
+        ));
+        assert!(service.open_session(&job.id, &admin, bash()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_open_session_requires_running_job() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = TrainingJob {
+            status: TrainingJobStatus::Queued,
+            node_id: None,
+            ..running_job(&user, &node)
+        };
+        let service = service(job.clone(), node, vec![], Duration::from_secs(30));
+
+        assert!(matches!(
+            service.open_session(&job.id, &user, bash()).await,
+            Err(ExecServiceError::JobNotRunning(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_open_session_requires_command() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&user, &node);
+        let service = service(job.clone(), node, vec![], Duration::from_secs(30));
+
+        let request = ExecRequest {
+            command: vec![],
+            tty: false,
+        };
+        assert!(matches!(
+            service.open_session(&job.id, &user, request).await,
+            Err(ExecServiceError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_open_tunnel_to_exposed_port() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = TrainingJob {
+            exposed_ports: vec![6006],
+            ..running_job(&user, &node)
+        };
+        let service = service(job.clone(), node.clone(), vec![], Duration::from_secs(30));
+
+        let (session, _user_end) = service.open_tunnel(&job.id, &user, 6006).await.unwrap();
+        assert_eq!(session.target, ExecTarget::Port(6006));
+
+        let offered = service
+            .wait_for_sessions(&node.id, &node.cluster_id, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(offered, vec![session]);
+    }
+
+    #[tokio::test]
+    async fn test_open_tunnel_requires_exposed_port() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = TrainingJob {
+            exposed_ports: vec![6006],
+            ..running_job(&user, &node)
+        };
+        let service = service(job.clone(), node, vec![], Duration::from_secs(30));
+
+        assert!(matches!(
+            service.open_tunnel(&job.id, &user, 22).await,
+            Err(ExecServiceError::PortNotExposed(22))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_open_session_tunnel_with_access_token() {
+        let node = ClusterNode::new_mock();
+        let job = TrainingJob {
+            kind: JobKind::Interactive,
+            session: Some(SessionSettings {
+                port: 8888,
+                idle_timeout_minutes: 60,
+                access_token: "secret".to_string(),
+            }),
+            exposed_ports: vec![6006, 8888],
+            ..running_job(&User::new_mock(), &node)
+        };
+        let service = service(job.clone(), node, vec![], Duration::from_secs(30));
+
+        let (session, _user_end) = service
+            .open_session_tunnel(&job.id, "secret", 8888)
+            .await
+            .unwrap();
+        assert_eq!(session.target, ExecTarget::Port(8888));
+
+        assert!(matches!(
+            service.open_session_tunnel(&job.id, "guess", 8888).await,
+            Err(ExecServiceError::InvalidAccessToken)
+        ));
+        // The token doesn't grant access to the job's other services.
+        assert!(matches!(
+            service.open_session_tunnel(&job.id, "secret", 6006).await,
+            Err(ExecServiceError::InvalidAccessToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_session_expires_if_agent_does_not_attach() {
+        let user = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&user, &node);
+        let service = service(job.clone(), node.clone(), vec![], Duration::from_millis(20));
+
+        let (session, mut user_end) = service.open_session(&job.id, &user, bash()).await.unwrap();
+
+        assert!(matches!(
+            user_end.rx.recv().await,
+            Some(ExecMessage::Control(ExecControl::Error { .. }))
+        ));
+        assert_eq!(user_end.rx.recv().await, None);
+        assert!(matches!(
+            service.attach_agent(&session.id, &node.cluster_id).await,
+            Err(ExecServiceError::SessionNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::{
+    domain::{
+        cluster::models::{ClusterId, NodeId},
+        training_job::models::JobId,
+    },
+    identifier,
+};
+
+identifier!(ExecSessionId);
+
+/// How many messages each direction of an exec session buffers before
+/// applying backpressure.
+const CHANNEL_BUFFER: usize = 64;
+
+/// A command to run inside a job's container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecRequest {
+    pub command: Vec<String>,
+    /// Whether to allocate a pseudo-terminal for the command.
+    pub tty: bool,
+}
+
+/// What the agent connects a session to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecTarget {
+    /// Runs a command in the job's container.
+    Command(ExecRequest),
+    /// Tunnels a TCP connection to a port the job exposes. Data messages
+    /// carry the connection's bytes in either direction.
+    Port(u16),
+}
+
+/// An exec session waiting for, or relayed through, the agent of the node
+/// running the job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecSession {
+    pub id: ExecSessionId,
+    pub job_id: JobId,
+    pub node_id: NodeId,
+    pub cluster_id: ClusterId,
+    pub target: ExecTarget,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A control message of an exec session. These are sent as JSON text frames;
+/// terminal input and output are sent as binary frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ExecControl {
+    /// The client's terminal was resized.
+    Resize { cols: u16, rows: u16 },
+    /// The client's input ended, e.g. because it was piped from a file.
+    Eof,
+    /// The command exited.
+    Exit { code: i64 },
+    /// The session could not be started or was interrupted.
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecMessage {
+    /// Terminal input from the client, or output of the command.
+    Data(Vec<u8>),
+    Control(ExecControl),
+}
+
+/// One end of an exec session. Messages sent on `tx` are received by the
+/// other end; the session ends once either end is dropped.
+#[derive(Debug)]
+pub struct ExecChannel {
+    pub tx: mpsc::Sender<ExecMessage>,
+    pub rx: mpsc::Receiver<ExecMessage>,
+}
+
+impl ExecChannel {
+    /// Creates the two connected ends of a session.
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, a_rx) = mpsc::channel(CHANNEL_BUFFER);
+        let (b_tx, b_rx) = mpsc::channel(CHANNEL_BUFFER);
+        (Self { tx: a_tx, rx: b_rx }, Self { tx: b_tx, rx: a_rx })
+    }
+}
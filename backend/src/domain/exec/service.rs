@@ -0,0 +1,425 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use tokio::{sync::Notify, time::Instant};
+
+use super::models::{
+    ExecChannel, ExecControl, ExecMessage, ExecRequest, ExecSession, ExecSessionId, ExecTarget,
+};
+use crate::domain::{
+    cluster::{
+        models::{ClusterId, NodeId},
+        ports::{ClusterRepository, ClusterRepositoryError},
+    },
+    training_job::{
+        models::{JobId, TrainingJobStatus},
+        ports::{TrainingJobRepository, TrainingJobRepositoryError},
+    },
+    user::models::User,
+};
+
+#[derive(Debug, Error)]
+pub enum ExecServiceError {
+    #[error("training job {0} not found")]
+    JobNotFound(String),
+    #[error("training job {0} is not running")]
+    JobNotRunning(String),
+    #[error("only the owner of a job or an administrator can exec into it")]
+    InvalidPermissions,
+    #[error("invalid exec request: {0}")]
+    InvalidRequest(String),
+    #[error("invalid session access token")]
+    InvalidAccessToken,
+    #[error("port {0} is not exposed by the job")]
+    PortNotExposed(u16),
+    #[error("exec session {0} not found")]
+    SessionNotFound(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<TrainingJobRepositoryError> for ExecServiceError {
+    fn from(err: TrainingJobRepositoryError) -> Self {
+        match err {
+            TrainingJobRepositoryError::NotFound(id) => ExecServiceError::JobNotFound(id),
+            e => ExecServiceError::Unknown(e.into()),
+        }
+    }
+}
+
+/// Relays interactive sessions between users and the agents running their
+/// jobs. Agents can't be reached by the control plane, so a session is
+/// parked until the agent of the job's node picks it up and attaches to it.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ExecService: Send + Sync {
+    /// Starts a session running `request` in the container of a running job.
+    /// Returns the user's end of the session; it is connected once the agent
+    /// attaches, or receives an error if the agent doesn't in time.
+    async fn open_session(
+        &self,
+        job_id: &JobId,
+        user: &User,
+        request: ExecRequest,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError>;
+    /// Starts a session tunneling a TCP connection to one of a running job's
+    /// exposed ports, like [ExecService::open_session].
+    async fn open_tunnel(
+        &self,
+        job_id: &JobId,
+        user: &User,
+        port: u16,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError>;
+    /// Starts a tunnel to the server of an interactive job for a holder of
+    /// its access token, like [ExecService::open_tunnel]. The token grants
+    /// access to the server's port only.
+    async fn open_session_tunnel(
+        &self,
+        job_id: &JobId,
+        access_token: &str,
+        port: u16,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError>;
+    /// Waits up to `timeout` for sessions to be opened on a node, and returns
+    /// those not yet handed to its agent.
+    async fn wait_for_sessions(
+        &self,
+        node_id: &NodeId,
+        cluster_id: &ClusterId,
+        timeout: Duration,
+    ) -> Result<Vec<ExecSession>, ExecServiceError>;
+    /// Returns the agent's end of a pending session.
+    async fn attach_agent(
+        &self,
+        session_id: &ExecSessionId,
+        cluster_id: &ClusterId,
+    ) -> Result<ExecChannel, ExecServiceError>;
+}
+
+/// How often traffic through a tunnel to an interactive job is recorded as
+/// activity of its session.
+const SESSION_ACTIVITY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Who is opening a session.
+enum Opener<'a> {
+    User(&'a User),
+    /// Someone holding the access token of an interactive job.
+    SessionToken(&'a str),
+}
+
+struct PendingSession {
+    session: ExecSession,
+    agent_end: ExecChannel,
+    /// Whether the session was returned to the agent already.
+    offered: bool,
+}
+
+#[derive(Default)]
+struct Sessions {
+    pending: HashMap<ExecSessionId, PendingSession>,
+    /// Wakes the agents polling for sessions of a node. Only pollers hold
+    /// on to a waker, so those of nodes that stopped polling, e.g. because
+    /// they were removed, are dropped.
+    node_wakers: HashMap<NodeId, Weak<Notify>>,
+}
+
+pub struct ExecServiceImpl {
+    training_job_repo: Arc<dyn TrainingJobRepository>,
+    cluster_repo: Arc<dyn ClusterRepository>,
+    admin_usernames: Vec<String>,
+    attach_timeout: Duration,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl ExecServiceImpl {
+    pub fn new(
+        training_job_repo: Arc<dyn TrainingJobRepository>,
+        cluster_repo: Arc<dyn ClusterRepository>,
+        admin_usernames: Vec<String>,
+        attach_timeout: Duration,
+    ) -> Self {
+        Self {
+            training_job_repo,
+            cluster_repo,
+            admin_usernames,
+            attach_timeout,
+            sessions: Arc::new(Mutex::new(Sessions::default())),
+        }
+    }
+
+    fn node_waker(&self, node_id: &NodeId) -> Arc<Notify> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(waker) = sessions.node_wakers.get(node_id).and_then(Weak::upgrade) {
+            return waker;
+        }
+        sessions
+            .node_wakers
+            .retain(|_, waker| waker.strong_count() > 0);
+        let waker = Arc::new(Notify::new());
+        sessions
+            .node_wakers
+            .insert(*node_id, Arc::downgrade(&waker));
+        waker
+    }
+
+    /// Wakes the agents polling for sessions of `node_id`, if any are.
+    fn wake_node(&self, node_id: &NodeId) {
+        let waker = self
+            .sessions
+            .lock()
+            .unwrap()
+            .node_wakers
+            .get(node_id)
+            .and_then(Weak::upgrade);
+        if let Some(waker) = waker {
+            waker.notify_waiters();
+        }
+    }
+
+    /// How many nodes there are wakers for.
+    #[cfg(test)]
+    pub(super) fn node_waker_count(&self) -> usize {
+        self.sessions.lock().unwrap().node_wakers.len()
+    }
+
+    fn take_offers(&self, node_id: &NodeId, cluster_id: &ClusterId) -> Vec<ExecSession> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .pending
+            .values_mut()
+            .filter(|p| {
+                !p.offered && p.session.node_id == *node_id && p.session.cluster_id == *cluster_id
+            })
+            .map(|p| {
+                p.offered = true;
+                p.session.clone()
+            })
+            .collect()
+    }
+
+    /// Relays the user's end of a session to the interactive job `job_id`,
+    /// recording traffic through it as activity of the job's session at most
+    /// once per [SESSION_ACTIVITY_INTERVAL], so that a job in use isn't
+    /// stopped as idle.
+    fn track_session_activity(&self, job_id: JobId, mut user_end: ExecChannel) -> ExecChannel {
+        let (outer, mut inner) = ExecChannel::pair();
+        let training_job_repo = self.training_job_repo.clone();
+        tokio::spawn(async move {
+            let mut last_recorded = Instant::now();
+            loop {
+                let forwarded = tokio::select! {
+                    message = inner.rx.recv() => match message {
+                        Some(message) => user_end.tx.send(message).await.is_ok(),
+                        None => false,
+                    },
+                    message = user_end.rx.recv() => match message {
+                        Some(message) => inner.tx.send(message).await.is_ok(),
+                        None => false,
+                    },
+                };
+                if !forwarded {
+                    break;
+                }
+                if last_recorded.elapsed() >= SESSION_ACTIVITY_INTERVAL {
+                    last_recorded = Instant::now();
+                    let training_job_repo = training_job_repo.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = training_job_repo
+                            .record_session_activity(&job_id, Utc::now())
+                            .await
+                        {
+                            tracing::warn!(
+                                "Failed to record session activity of job {}: {}",
+                                job_id,
+                                e
+                            );
+                        }
+                    });
+                }
+            }
+        });
+        outer
+    }
+
+    /// Parks a session for the agent of the node running a job, once the
+    /// opener is allowed to open it.
+    async fn open(
+        &self,
+        job_id: &JobId,
+        opener: Opener<'_>,
+        target: ExecTarget,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError> {
+        let job = self
+            .training_job_repo
+            .get_training_job_by_id(job_id)
+            .await?;
+        match opener {
+            Opener::User(user) => {
+                if job.user_id != Some(user.id) && !self.admin_usernames.contains(&user.username) {
+                    return Err(ExecServiceError::InvalidPermissions);
+                }
+            }
+            Opener::SessionToken(token) => {
+                let valid = job.session.as_ref().is_some_and(|session| {
+                    bool::from(session.access_token.as_bytes().ct_eq(token.as_bytes()))
+                        && target == ExecTarget::Port(session.port)
+                });
+                if !valid {
+                    return Err(ExecServiceError::InvalidAccessToken);
+                }
+            }
+        }
+        if let ExecTarget::Port(port) = target {
+            if !job.exposed_ports.contains(&port) {
+                return Err(ExecServiceError::PortNotExposed(port));
+            }
+        }
+        let node_id = match (&job.status, job.node_id) {
+            (TrainingJobStatus::Running, Some(node_id)) => node_id,
+            _ => return Err(ExecServiceError::JobNotRunning(job_id.to_string())),
+        };
+        let node = match self.cluster_repo.get_cluster_node_by_id(&node_id).await {
+            Ok(node) => node,
+            Err(ClusterRepositoryError::NotFound(_)) => {
+                return Err(ExecServiceError::JobNotRunning(job_id.to_string()))
+            }
+            Err(e) => return Err(ExecServiceError::Unknown(e.into())),
+        };
+        if job.session.is_some() {
+            self.training_job_repo
+                .record_session_activity(job_id, Utc::now())
+                .await?;
+        }
+
+        let session = ExecSession {
+            id: ExecSessionId::generate(),
+            job_id: *job_id,
+            node_id,
+            cluster_id: node.cluster_id,
+            target,
+            created_at: Utc::now(),
+        };
+        let (mut user_end, agent_end) = ExecChannel::pair();
+        if job.session.is_some() {
+            user_end = self.track_session_activity(*job_id, user_end);
+        }
+        self.sessions.lock().unwrap().pending.insert(
+            session.id,
+            PendingSession {
+                session: session.clone(),
+                agent_end,
+                offered: false,
+            },
+        );
+        self.wake_node(&node_id);
+
+        // Give up on the session if the agent doesn't attach in time, telling
+        // the user why before their end is closed.
+        let sessions = self.sessions.clone();
+        let session_id = session.id;
+        let attach_timeout = self.attach_timeout;
+        tokio::spawn(async move {
+            tokio::time::sleep(attach_timeout).await;
+            let expired = sessions.lock().unwrap().pending.remove(&session_id);
+            if let Some(expired) = expired {
+                let message = "the agent of the job's node did not pick up the session";
+                let _ = expired
+                    .agent_end
+                    .tx
+                    .send(ExecMessage::Control(ExecControl::Error {
+                        message: message.to_string(),
+                    }))
+                    .await;
+            }
+        });
+
+        Ok((session, user_end))
+    }
+}
+
+#[async_trait]
+impl ExecService for ExecServiceImpl {
+    async fn open_session(
+        &self,
+        job_id: &JobId,
+        user: &User,
+        request: ExecRequest,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError> {
+        if request.command.is_empty() {
+            return Err(ExecServiceError::InvalidRequest(
+                "a command is required".to_string(),
+            ));
+        }
+        self.open(job_id, Opener::User(user), ExecTarget::Command(request))
+            .await
+    }
+
+    async fn open_tunnel(
+        &self,
+        job_id: &JobId,
+        user: &User,
+        port: u16,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError> {
+        self.open(job_id, Opener::User(user), ExecTarget::Port(port))
+            .await
+    }
+
+    async fn open_session_tunnel(
+        &self,
+        job_id: &JobId,
+        access_token: &str,
+        port: u16,
+    ) -> Result<(ExecSession, ExecChannel), ExecServiceError> {
+        self.open(
+            job_id,
+            Opener::SessionToken(access_token),
+            ExecTarget::Port(port),
+        )
+        .await
+    }
+
+    async fn wait_for_sessions(
+        &self,
+        node_id: &NodeId,
+        cluster_id: &ClusterId,
+        timeout: Duration,
+    ) -> Result<Vec<ExecSession>, ExecServiceError> {
+        // Register interest before looking, so that a session opened in
+        // between isn't missed.
+        let waker = self.node_waker(node_id);
+        let notified = waker.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        let sessions = self.take_offers(node_id, cluster_id);
+        if !sessions.is_empty() {
+            return Ok(sessions);
+        }
+
+        let _ = tokio::time::timeout(timeout, notified).await;
+        Ok(self.take_offers(node_id, cluster_id))
+    }
+
+    async fn attach_agent(
+        &self,
+        session_id: &ExecSessionId,
+        cluster_id: &ClusterId,
+    ) -> Result<ExecChannel, ExecServiceError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.pending.get(session_id) {
+            Some(pending) if pending.session.cluster_id == *cluster_id => Ok(sessions
+                .pending
+                .remove(session_id)
+                .expect("session to be pending")
+                .agent_end),
+            _ => Err(ExecServiceError::SessionNotFound(session_id.to_string())),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+pub mod models;
+pub mod ports;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{fingerprint, IdempotencyRecord, StoredResponse},
+        ports::MockIdempotencyRepository,
+        service::{IdempotencyService, IdempotencyServiceError, IdempotencyServiceImpl},
+    };
+    use chrono::{Duration, Utc};
+    use mockall::predicate::*;
+    use std::sync::Arc;
+
+    fn stored_record(request_hash: &str, response: Option<StoredResponse>) -> IdempotencyRecord {
+        IdempotencyRecord {
+            scope: "scope".to_string(),
+            key: "key-1".to_string(),
+            request_hash: request_hash.to_string(),
+            response,
+            created_at: Utc::now() - Duration::minutes(5),
+        }
+    }
+
+    fn created_response() -> StoredResponse {
+        StoredResponse {
+            status: 201,
+            content_type: Some("application/json".to_string()),
+            body: br#"{"id":"1"}"#.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_begin_new_key() {
+        let mut mock_repo = MockIdempotencyRepository::new();
+        mock_repo
+            .expect_insert_record()
+            .withf(|record| record.key == "key-1" && record.response.is_none())
+            .times(1)
+            .returning(|_| Ok(true));
+
+        let service = IdempotencyServiceImpl::new(Arc::new(mock_repo), Duration::hours(24));
+        let result = service.begin("scope", "key-1", "hash").await;
+
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_begin_replays_completed_request() {
+        let mut mock_repo = MockIdempotencyRepository::new();
+        mock_repo.expect_insert_record().returning(|_| Ok(false));
+        mock_repo
+            .expect_get_record()
+            .with(eq("scope"), eq("key-1"))
+            .returning(|_, _| Ok(Some(stored_record("hash", Some(created_response())))));
+
+        let service = IdempotencyServiceImpl::new(Arc::new(mock_repo), Duration::hours(24));
+        let result = service.begin("scope", "key-1", "hash").await;
+
+        assert_eq!(result.unwrap(), Some(created_response()));
+    }
+
+    #[tokio::test]
+    async fn test_begin_with_different_request() {
+        let mut mock_repo = MockIdempotencyRepository::new();
+        mock_repo.expect_insert_record().returning(|_| Ok(false));
+        mock_repo
+            .expect_get_record()
+            .returning(|_, _| Ok(Some(stored_record("hash", Some(created_response())))));
+
+        let service = IdempotencyServiceImpl::new(Arc::new(mock_repo), Duration::hours(24));
+        let result = service.begin("scope", "key-1", "other-hash").await;
+
+        assert!(matches!(result, Err(IdempotencyServiceError::KeyReused(_))));
+    }
+
+    #[tokio::test]
+    async fn test_begin_while_in_progress() {
+        let mut mock_repo = MockIdempotencyRepository::new();
+        mock_repo.expect_insert_record().returning(|_| Ok(false));
+        mock_repo
+            .expect_get_record()
+            .returning(|_, _| Ok(Some(stored_record("hash", None))));
+
+        let service = IdempotencyServiceImpl::new(Arc::new(mock_repo), Duration::hours(24));
+        let result = service.begin("scope", "key-1", "hash").await;
+
+        assert!(matches!(
+            result,
+            Err(IdempotencyServiceError::InProgress(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_begin_reuses_expired_key() {
+        let mut mock_repo = MockIdempotencyRepository::new();
+        let mut inserted = false;
+        mock_repo
+            .expect_insert_record()
+            .times(2)
+            .returning(move |_| {
+                let first = !inserted;
+                inserted = true;
+                Ok(!first)
+            });
+        mock_repo
+            .expect_get_record()
+            .times(1)
+            .returning(|_, _| Ok(Some(stored_record("old-hash", Some(created_response())))));
+        mock_repo
+            .expect_delete_record()
+            .with(eq("scope"), eq("key-1"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = IdempotencyServiceImpl::new(Arc::new(mock_repo), Duration::minutes(1));
+        let result = service.begin("scope", "key-1", "hash").await;
+
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_begin_reclaims_abandoned_key() {
+        let mut mock_repo = MockIdempotencyRepository::new();
+        let mut inserted = false;
+        mock_repo
+            .expect_insert_record()
+            .times(2)
+            .returning(move |_| {
+                let first = !inserted;
+                inserted = true;
+                Ok(!first)
+            });
+        mock_repo.expect_get_record().times(1).returning(|_, _| {
+            Ok(Some(IdempotencyRecord {
+                created_at: Utc::now() - Duration::hours(1),
+                ..stored_record("hash", None)
+            }))
+        });
+        mock_repo
+            .expect_delete_record()
+            .with(eq("scope"), eq("key-1"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = IdempotencyServiceImpl::new(Arc::new(mock_repo), Duration::hours(24));
+        let result = service.begin("scope", "key-1", "hash").await;
+
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_begin_with_invalid_key() {
+        let service = IdempotencyServiceImpl::new(
+            Arc::new(MockIdempotencyRepository::new()),
+            Duration::hours(24),
+        );
+
+        let result = service.begin("scope", "has spaces", "hash").await;
+
+        assert!(matches!(
+            result,
+            Err(IdempotencyServiceError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_fingerprint_separates_parts() {
+        assert_ne!(
+            fingerprint(&[b"POST", b"/a", b"bc"]),
+            fingerprint(&[b"POST", b"/ab", b"c"])
+        );
+        assert_eq!(
+            fingerprint(&[b"POST", b"/a", b"bc"]),
+            fingerprint(&[b"POST", b"/a", b"bc"])
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// The longest `Idempotency-Key` header value that is accepted.
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// A response that was returned for an idempotent request and is replayed
+/// when the request is retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// A request made with an `Idempotency-Key`. Keys are scoped to the
+/// credentials they were used with, so two callers can't collide.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyRecord {
+    pub scope: String,
+    pub key: String,
+    /// Fingerprint of the method, path and body of the original request.
+    pub request_hash: String,
+    /// None while the original request is still being processed.
+    pub response: Option<StoredResponse>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Hex-encoded SHA-256 of `parts`, each prefixed with its length so that
+/// different splits of the same bytes hash differently.
+pub fn fingerprint(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::models::{IdempotencyRecord, StoredResponse};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IdempotencyRepositoryError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Stores `record` unless a record with the same scope and key exists.
+    /// Returns whether it was stored.
+    async fn insert_record(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<bool, IdempotencyRepositoryError>;
+    async fn get_record(
+        &self,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, IdempotencyRepositoryError>;
+    async fn set_response(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), IdempotencyRepositoryError>;
+    async fn delete_record(&self, scope: &str, key: &str)
+        -> Result<(), IdempotencyRepositoryError>;
+    /// Deletes all records created before `before` and returns how many there were.
+    async fn delete_records_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, IdempotencyRepositoryError>;
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use thiserror::Error;
+
+use super::{
+    models::{IdempotencyRecord, StoredResponse, MAX_IDEMPOTENCY_KEY_LENGTH},
+    ports::{IdempotencyRepository, IdempotencyRepositoryError},
+};
+
+/// How long a request may hold its key before retries treat it as abandoned,
+/// e.g. because the server restarted while processing it.
+const IN_PROGRESS_TIMEOUT_MINUTES: i64 = 10;
+
+#[derive(Debug, Error)]
+pub enum IdempotencyServiceError {
+    #[error("invalid idempotency key: {0}")]
+    InvalidKey(String),
+    #[error("idempotency key {0} was already used for a different request")]
+    KeyReused(String),
+    #[error("a request with idempotency key {0} is still being processed")]
+    InProgress(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<IdempotencyRepositoryError> for IdempotencyServiceError {
+    fn from(err: IdempotencyRepositoryError) -> Self {
+        match err {
+            IdempotencyRepositoryError::Unknown(err) => IdempotencyServiceError::Unknown(err),
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait IdempotencyService: Send + Sync {
+    /// Claims `key` for a request with the given fingerprint. Returns the
+    /// stored response if the same request was already completed, or None if
+    /// the caller should process the request and then call `complete` or
+    /// `release`. Claims that are never completed or released expire after
+    /// a few minutes.
+    async fn begin(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<Option<StoredResponse>, IdempotencyServiceError>;
+    /// Stores the response to replay for retries of the request.
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<(), IdempotencyServiceError>;
+    /// Forgets the key, so that the request can be retried. Used when the
+    /// request failed in a way that should not be replayed.
+    async fn release(&self, scope: &str, key: &str) -> Result<(), IdempotencyServiceError>;
+    /// Deletes keys older than the retention window.
+    async fn purge_expired(&self) -> Result<u64, IdempotencyServiceError>;
+}
+
+pub struct IdempotencyServiceImpl {
+    repository: Arc<dyn IdempotencyRepository>,
+    retention: Duration,
+}
+
+impl IdempotencyServiceImpl {
+    pub fn new(repository: Arc<dyn IdempotencyRepository>, retention: Duration) -> Self {
+        Self {
+            repository,
+            retention,
+        }
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), IdempotencyServiceError> {
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        return Err(IdempotencyServiceError::InvalidKey(format!(
+            "must be between 1 and {MAX_IDEMPOTENCY_KEY_LENGTH} characters"
+        )));
+    }
+    if !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(IdempotencyServiceError::InvalidKey(
+            "must contain only printable ASCII characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl IdempotencyService for IdempotencyServiceImpl {
+    async fn begin(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<Option<StoredResponse>, IdempotencyServiceError> {
+        validate_key(key)?;
+
+        let now = Utc::now();
+        let record = IdempotencyRecord {
+            scope: scope.to_string(),
+            key: key.to_string(),
+            request_hash: request_hash.to_string(),
+            response: None,
+            created_at: now,
+        };
+
+        // The second attempt covers an expired record having been deleted in
+        // between, or another request having released the key.
+        for _ in 0..2 {
+            if self.repository.insert_record(&record).await? {
+                return Ok(None);
+            }
+
+            let Some(existing) = self.repository.get_record(scope, key).await? else {
+                continue;
+            };
+            let abandoned = existing.response.is_none()
+                && existing.created_at < now - Duration::minutes(IN_PROGRESS_TIMEOUT_MINUTES);
+            if abandoned || existing.created_at < now - self.retention {
+                self.repository.delete_record(scope, key).await?;
+                continue;
+            }
+            if existing.request_hash != request_hash {
+                return Err(IdempotencyServiceError::KeyReused(key.to_string()));
+            }
+            return match existing.response {
+                Some(response) => Ok(Some(response)),
+                None => Err(IdempotencyServiceError::InProgress(key.to_string())),
+            };
+        }
+
+        Err(IdempotencyServiceError::InProgress(key.to_string()))
+    }
+
+    async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        response: StoredResponse,
+    ) -> Result<(), IdempotencyServiceError> {
+        Ok(self.repository.set_response(scope, key, &response).await?)
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<(), IdempotencyServiceError> {
+        Ok(self.repository.delete_record(scope, key).await?)
+    }
+
+    async fn purge_expired(&self) -> Result<u64, IdempotencyServiceError> {
+        Ok(self
+            .repository
+            .delete_records_before(Utc::now() - self.retention)
+            .await?)
+    }
+}
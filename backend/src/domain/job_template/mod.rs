@@ -0,0 +1,202 @@
+pub mod models;
+pub mod ports;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{CreateJobTemplateRequest, JobTemplate, JobTemplateOverrides},
+        ports::MockJobTemplateRepository,
+        service::{JobTemplateService, JobTemplateServiceError, JobTemplateServiceImpl},
+    };
+    use crate::domain::{
+        queue::models::QueueId,
+        training_job::{
+            models::{CreatedTrainingJob, ResourceRequirements, TrainingJob},
+            service::MockTrainingJobService,
+        },
+        user::models::UserId,
+    };
+    use mockall::predicate::*;
+    use std::{collections::HashMap, sync::Arc};
+
+    fn create_request() -> CreateJobTemplateRequest {
+        CreateJobTemplateRequest {
+            name: "resnet-finetune".to_string(),
+            shared: false,
+            definition: "ghcr.io/acme/resnet:latest".to_string(),
+            queue_id: Some(QueueId::generate()),
+            resource_requirements: ResourceRequirements {
+                cpu_millicores: 4000,
+                memory_mb: 16384,
+                gpus: None,
+            },
+            env: HashMap::from([("EPOCHS".to_string(), "10".to_string())]),
+            labels: HashMap::from([("project".to_string(), "vision".to_string())]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_template() {
+        let owner_id = UserId::generate();
+        let mut mock_repo = MockJobTemplateRepository::new();
+        mock_repo
+            .expect_create_template()
+            .withf(move |template| {
+                template.owner_id == owner_id && template.name == "resnet-finetune"
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = JobTemplateServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockTrainingJobService::new()),
+        );
+        let result = service.create_template(create_request(), &owner_id).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_template_invalid_env() {
+        let service = JobTemplateServiceImpl::new(
+            Arc::new(MockJobTemplateRepository::new()),
+            Arc::new(MockTrainingJobService::new()),
+        );
+        let request = CreateJobTemplateRequest {
+            env: HashMap::from([("1BAD-NAME".to_string(), "x".to_string())]),
+            ..create_request()
+        };
+
+        let result = service.create_template(request, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(JobTemplateServiceError::InvalidTemplate(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_private_template_of_other_user() {
+        let template = JobTemplate::new_mock();
+        let id = template.id;
+        let mut mock_repo = MockJobTemplateRepository::new();
+        mock_repo
+            .expect_get_template_by_id()
+            .with(eq(id))
+            .returning(move |_| Ok(template.clone()));
+
+        let service = JobTemplateServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockTrainingJobService::new()),
+        );
+        let result = service.get_template(&id, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(JobTemplateServiceError::TemplateNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_delete_shared_template_of_other_user() {
+        let template = JobTemplate {
+            shared: true,
+            ..JobTemplate::new_mock()
+        };
+        let id = template.id;
+        let mut mock_repo = MockJobTemplateRepository::new();
+        mock_repo
+            .expect_get_template_by_id()
+            .returning(move |_| Ok(template.clone()));
+        mock_repo.expect_delete_template().never();
+
+        let service = JobTemplateServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockTrainingJobService::new()),
+        );
+        let result = service.delete_template(&id, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(JobTemplateServiceError::InvalidPermissions)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_submit_from_template_with_overrides() {
+        let user_id = UserId::generate();
+        let template_queue = QueueId::generate();
+        let override_queue = QueueId::generate();
+        let template = JobTemplate {
+            shared: true,
+            queue_id: Some(template_queue),
+            env: HashMap::from([
+                ("EPOCHS".to_string(), "10".to_string()),
+                ("LR".to_string(), "0.1".to_string()),
+            ]),
+            labels: HashMap::from([("project".to_string(), "vision".to_string())]),
+            ..JobTemplate::new_mock()
+        };
+        let id = template.id;
+        let mut mock_repo = MockJobTemplateRepository::new();
+        mock_repo
+            .expect_get_template_by_id()
+            .returning(move |_| Ok(template.clone()));
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_create()
+            .withf(move |request, submitter| {
+                *submitter == user_id
+                    && request.name == "default-template"
+                    && request.queue_id == override_queue
+                    && request.env["EPOCHS"] == "20"
+                    && request.env["LR"] == "0.1"
+                    && request.labels["project"] == "vision"
+                    && request.labels["experiment"] == "long-run"
+            })
+            .times(1)
+            .returning(|_, _| {
+                Ok(CreatedTrainingJob {
+                    job: TrainingJob::new_mock(),
+                    warnings: vec![],
+                })
+            });
+
+        let service = JobTemplateServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_job_service));
+        let overrides = JobTemplateOverrides {
+            queue_id: Some(override_queue),
+            env: HashMap::from([("EPOCHS".to_string(), "20".to_string())]),
+            labels: HashMap::from([("experiment".to_string(), "long-run".to_string())]),
+            ..Default::default()
+        };
+        let result = service.submit_from_template(&id, &user_id, overrides).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_submit_from_template_without_queue() {
+        let template = JobTemplate::new_mock();
+        let owner_id = template.owner_id;
+        let id = template.id;
+        let mut mock_repo = MockJobTemplateRepository::new();
+        mock_repo
+            .expect_get_template_by_id()
+            .returning(move |_| Ok(template.clone()));
+
+        let service = JobTemplateServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockTrainingJobService::new()),
+        );
+        let result = service
+            .submit_from_template(&id, &owner_id, Default::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(JobTemplateServiceError::InvalidTemplate(_))
+        ));
+    }
+}
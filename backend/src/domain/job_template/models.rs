@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        queue::models::QueueId, training_job::models::ResourceRequirements, user::models::UserId,
+    },
+    identifier,
+};
+
+identifier!(JobTemplateId);
+
+/// A stored job specification that can be submitted again and again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobTemplate {
+    pub id: JobTemplateId,
+    pub name: String,
+    pub owner_id: UserId,
+    /// Whether users other than the owner can see and submit the template.
+    pub shared: bool,
+    pub definition: String,
+    /// The queue jobs are submitted to unless the submission names another one.
+    pub queue_id: Option<QueueId>,
+    pub resource_requirements: ResourceRequirements,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JobTemplate {
+    pub fn is_visible_to(&self, user_id: &UserId) -> bool {
+        self.shared || self.owner_id == *user_id
+    }
+}
+
+/// DTO for creating a new job template.
+#[derive(Debug, Clone)]
+pub struct CreateJobTemplateRequest {
+    pub name: String,
+    pub shared: bool,
+    pub definition: String,
+    pub queue_id: Option<QueueId>,
+    pub resource_requirements: ResourceRequirements,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+}
+
+/// Changes to apply to a template's spec when submitting a job from it.
+/// Fields that are set replace the template's; `env`, `labels` and
+/// `annotations` are merged into it. Templates don't expose ports, so
+/// `exposed_ports` is used as is.
+#[derive(Debug, Clone, Default)]
+pub struct JobTemplateOverrides {
+    pub name: Option<String>,
+    pub definition: Option<String>,
+    pub queue_id: Option<QueueId>,
+    pub resource_requirements: Option<ResourceRequirements>,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    pub exposed_ports: Vec<u16>,
+}
+
+#[cfg(test)]
+impl JobTemplate {
+    pub fn new_mock() -> Self {
+        Self {
+            id: JobTemplateId::generate(),
+            name: "default-template".to_string(),
+            owner_id: UserId::generate(),
+            shared: false,
+            definition: "default-uri".to_string(),
+            queue_id: None,
+            resource_requirements: ResourceRequirements {
+                cpu_millicores: 1000,
+                memory_mb: 1024,
+                gpus: None,
+            },
+            env: HashMap::new(),
+            labels: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+
+use super::models::{JobTemplate, JobTemplateId};
+use crate::domain::user::models::UserId;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JobTemplateRepositoryError {
+    #[error("job template with {field} {value} already exists")]
+    Duplicate { field: String, value: String },
+    #[error("job template {0} not found")]
+    NotFound(String),
+    #[error("queue {0} not found")]
+    QueueNotFound(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait JobTemplateRepository: Send + Sync {
+    async fn create_template(
+        &self,
+        template: &JobTemplate,
+    ) -> Result<(), JobTemplateRepositoryError>;
+    async fn get_template_by_id(
+        &self,
+        id: &JobTemplateId,
+    ) -> Result<JobTemplate, JobTemplateRepositoryError>;
+    /// Lists the templates owned by `user_id` and all shared templates.
+    async fn list_templates_visible_to(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<JobTemplate>, JobTemplateRepositoryError>;
+    async fn delete_template(&self, id: &JobTemplateId) -> Result<(), JobTemplateRepositoryError>;
+}
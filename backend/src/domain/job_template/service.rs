@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use super::{
+    models::{CreateJobTemplateRequest, JobTemplate, JobTemplateId, JobTemplateOverrides},
+    ports::{JobTemplateRepository, JobTemplateRepositoryError},
+};
+use crate::{
+    domain::{
+        training_job::{
+            models::{validate_env, validate_labels, CreatedTrainingJob, JobKind},
+            service::{TrainingJobService, TrainingJobServiceError},
+        },
+        user::models::UserId,
+    },
+    inbound::http::routes::training_jobs::models::CreateTrainingJobRequest,
+};
+
+#[derive(Debug, Error)]
+pub enum JobTemplateServiceError {
+    #[error("job template with {field} {value} already exists")]
+    TemplateExists { field: String, value: String },
+    #[error("job template {0} not found")]
+    TemplateNotFound(String),
+    #[error("invalid job template: {0}")]
+    InvalidTemplate(String),
+    #[error("user does not have permission to perform this action")]
+    InvalidPermissions,
+    #[error(transparent)]
+    TrainingJob(#[from] TrainingJobServiceError),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<JobTemplateRepositoryError> for JobTemplateServiceError {
+    fn from(err: JobTemplateRepositoryError) -> Self {
+        match err {
+            JobTemplateRepositoryError::Duplicate { field, value } => {
+                JobTemplateServiceError::TemplateExists { field, value }
+            }
+            JobTemplateRepositoryError::NotFound(id) => {
+                JobTemplateServiceError::TemplateNotFound(id)
+            }
+            JobTemplateRepositoryError::QueueNotFound(id) => {
+                JobTemplateServiceError::InvalidTemplate(format!("queue {id} does not exist"))
+            }
+            JobTemplateRepositoryError::Unknown(err) => JobTemplateServiceError::Unknown(err),
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait JobTemplateService: Send + Sync {
+    async fn create_template(
+        &self,
+        request: CreateJobTemplateRequest,
+        owner_id: &UserId,
+    ) -> Result<JobTemplate, JobTemplateServiceError>;
+    async fn list_templates(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<JobTemplate>, JobTemplateServiceError>;
+    async fn get_template(
+        &self,
+        id: &JobTemplateId,
+        user_id: &UserId,
+    ) -> Result<JobTemplate, JobTemplateServiceError>;
+    async fn delete_template(
+        &self,
+        id: &JobTemplateId,
+        user_id: &UserId,
+    ) -> Result<(), JobTemplateServiceError>;
+    /// Queues a new job built from the template, with `overrides` applied.
+    async fn submit_from_template(
+        &self,
+        id: &JobTemplateId,
+        user_id: &UserId,
+        overrides: JobTemplateOverrides,
+    ) -> Result<CreatedTrainingJob, JobTemplateServiceError>;
+}
+
+pub struct JobTemplateServiceImpl {
+    repository: Arc<dyn JobTemplateRepository>,
+    training_job_service: Arc<dyn TrainingJobService>,
+}
+
+impl JobTemplateServiceImpl {
+    pub fn new(
+        repository: Arc<dyn JobTemplateRepository>,
+        training_job_service: Arc<dyn TrainingJobService>,
+    ) -> Self {
+        Self {
+            repository,
+            training_job_service,
+        }
+    }
+}
+
+fn validate_template(request: &CreateJobTemplateRequest) -> Result<(), JobTemplateServiceError> {
+    if request.name.trim().is_empty() {
+        return Err(JobTemplateServiceError::InvalidTemplate(
+            "name must not be empty".to_string(),
+        ));
+    }
+    request
+        .resource_requirements
+        .validate()
+        .and_then(|_| validate_labels(&request.labels))
+        .and_then(|_| validate_env(request.env.keys()))
+        .map_err(JobTemplateServiceError::InvalidTemplate)
+}
+
+#[async_trait]
+impl JobTemplateService for JobTemplateServiceImpl {
+    async fn create_template(
+        &self,
+        request: CreateJobTemplateRequest,
+        owner_id: &UserId,
+    ) -> Result<JobTemplate, JobTemplateServiceError> {
+        validate_template(&request)?;
+
+        let now = chrono::Utc::now();
+        let template = JobTemplate {
+            id: JobTemplateId::generate(),
+            name: request.name,
+            owner_id: *owner_id,
+            shared: request.shared,
+            definition: request.definition,
+            queue_id: request.queue_id,
+            resource_requirements: request.resource_requirements,
+            env: request.env,
+            labels: request.labels,
+            created_at: now,
+            updated_at: now,
+        };
+        self.repository.create_template(&template).await?;
+
+        Ok(template)
+    }
+
+    async fn list_templates(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<JobTemplate>, JobTemplateServiceError> {
+        Ok(self.repository.list_templates_visible_to(user_id).await?)
+    }
+
+    async fn get_template(
+        &self,
+        id: &JobTemplateId,
+        user_id: &UserId,
+    ) -> Result<JobTemplate, JobTemplateServiceError> {
+        let template = self.repository.get_template_by_id(id).await?;
+        // Don't reveal that another user's private template exists.
+        if !template.is_visible_to(user_id) {
+            return Err(JobTemplateServiceError::TemplateNotFound(id.to_string()));
+        }
+        Ok(template)
+    }
+
+    async fn delete_template(
+        &self,
+        id: &JobTemplateId,
+        user_id: &UserId,
+    ) -> Result<(), JobTemplateServiceError> {
+        let template = self.get_template(id, user_id).await?;
+        if template.owner_id != *user_id {
+            return Err(JobTemplateServiceError::InvalidPermissions);
+        }
+        Ok(self.repository.delete_template(id).await?)
+    }
+
+    async fn submit_from_template(
+        &self,
+        id: &JobTemplateId,
+        user_id: &UserId,
+        overrides: JobTemplateOverrides,
+    ) -> Result<CreatedTrainingJob, JobTemplateServiceError> {
+        let template = self.get_template(id, user_id).await?;
+
+        let queue_id = overrides.queue_id.or(template.queue_id).ok_or_else(|| {
+            JobTemplateServiceError::InvalidTemplate(format!(
+                "template '{}' has no queue, so one must be given when submitting it",
+                template.name
+            ))
+        })?;
+        let resource_requirements = overrides
+            .resource_requirements
+            .unwrap_or(template.resource_requirements);
+        let mut env = template.env;
+        env.extend(overrides.env);
+        let mut labels = template.labels;
+        labels.extend(overrides.labels);
+
+        let request = CreateTrainingJobRequest {
+            name: overrides.name.unwrap_or(template.name),
+            definition: overrides.definition.unwrap_or(template.definition),
+            queue_id,
+            resource_requirements: serde_json::to_value(resource_requirements)
+                .map_err(|e| anyhow::anyhow!(e))?,
+            labels,
+            annotations: overrides.annotations,
+            env,
+            exposed_ports: overrides.exposed_ports,
+            kind: JobKind::Batch,
+            session: None,
+            termination_grace_period_secs: None,
+        };
+
+        Ok(self.training_job_service.create(request, user_id).await?)
+    }
+}
@@ -1,9 +1,16 @@
 use secrecy::{ExposeSecret, SecretString};
 
+pub mod accounting;
 pub mod auth;
 pub mod cluster;
+pub mod exec;
+pub mod idempotency;
+pub mod job_template;
+pub mod notification;
 pub mod queue;
+pub mod quota;
 pub mod scheduler;
+pub mod sweep;
 pub mod training_job;
 pub mod user;
 
@@ -0,0 +1,373 @@
+pub mod models;
+pub mod ports;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{
+            sign_webhook, CreateNotificationChannel, CreateSubscriptionRequest, Delivery,
+            DeliveryStatus, EventKind, NotificationChannel, NotificationEvent, Subscription,
+            SubscriptionScope,
+        },
+        ports::{
+            MockNotificationRepository, MockNotificationSender, NotificationRepositoryError,
+            NotificationSenderError,
+        },
+        service::{NotificationService, NotificationServiceError, NotificationServiceImpl},
+    };
+    use crate::domain::{
+        queue::models::Queue,
+        training_job::models::{TrainingJob, TrainingJobStatus},
+        user::models::UserId,
+    };
+    use mockall::predicate::*;
+    use std::sync::Arc;
+
+    fn service(
+        repo: MockNotificationRepository,
+        sender: MockNotificationSender,
+    ) -> NotificationServiceImpl {
+        NotificationServiceImpl::new(Arc::new(repo), Arc::new(sender), 3)
+    }
+
+    fn webhook_request() -> CreateSubscriptionRequest {
+        CreateSubscriptionRequest {
+            scope: SubscriptionScope::User,
+            event_kinds: vec![EventKind::JobFailed],
+            channel: CreateNotificationChannel::Webhook {
+                url: "https://example.com/hooks/lilac".to_string(),
+            },
+            backlog_threshold: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_webhook_subscription_generates_secret() {
+        let owner_id = UserId::generate();
+        let mut mock_repo = MockNotificationRepository::new();
+        mock_repo
+            .expect_create_subscription()
+            .withf(move |s| s.owner_id == owner_id)
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut mock_sender = MockNotificationSender::new();
+        mock_sender
+            .expect_check_webhook_url()
+            .with(eq("https://example.com/hooks/lilac"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = service(mock_repo, mock_sender);
+        let subscription = service
+            .create_subscription(webhook_request(), &owner_id)
+            .await
+            .unwrap();
+
+        match subscription.channel {
+            NotificationChannel::Webhook { url, secret } => {
+                assert_eq!(url, "https://example.com/hooks/lilac");
+                assert!(secret.starts_with("whsec_"));
+            }
+            other => panic!("expected a webhook channel, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_subscription_rejects_invalid_requests() {
+        let owner_id = UserId::generate();
+        let mut mock_sender = MockNotificationSender::new();
+        mock_sender.expect_email_enabled().returning(|| false);
+        mock_sender
+            .expect_check_webhook_url()
+            .with(eq("http://169.254.169.254/latest/meta-data"))
+            .returning(|url| {
+                Err(NotificationSenderError::DeliveryFailed(format!(
+                    "webhooks can't be delivered to {url}"
+                )))
+            });
+        let service = service(MockNotificationRepository::new(), mock_sender);
+
+        let invalid = [
+            CreateSubscriptionRequest {
+                event_kinds: vec![],
+                ..webhook_request()
+            },
+            CreateSubscriptionRequest {
+                event_kinds: vec![EventKind::QueueBacklog],
+                backlog_threshold: Some(10),
+                ..webhook_request()
+            },
+            CreateSubscriptionRequest {
+                backlog_threshold: Some(10),
+                ..webhook_request()
+            },
+            CreateSubscriptionRequest {
+                channel: CreateNotificationChannel::Webhook {
+                    url: "ftp://example.com".to_string(),
+                },
+                ..webhook_request()
+            },
+            CreateSubscriptionRequest {
+                channel: CreateNotificationChannel::Webhook {
+                    url: "http://169.254.169.254/latest/meta-data".to_string(),
+                },
+                ..webhook_request()
+            },
+            CreateSubscriptionRequest {
+                channel: CreateNotificationChannel::Email {
+                    address: "ml-team@example.com".to_string(),
+                },
+                ..webhook_request()
+            },
+        ];
+        for request in invalid {
+            let result = service.create_subscription(request, &owner_id).await;
+            assert!(
+                matches!(
+                    result,
+                    Err(NotificationServiceError::InvalidSubscription(_))
+                ),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_subscription_of_other_user_is_not_found() {
+        let subscription = Subscription::new_mock();
+        let subscription_id = subscription.id;
+        let mut mock_repo = MockNotificationRepository::new();
+        mock_repo
+            .expect_get_subscription()
+            .with(eq(subscription_id))
+            .times(1)
+            .returning(move |_| Ok(subscription.clone()));
+
+        let service = service(mock_repo, MockNotificationSender::new());
+        let result = service
+            .get_subscription(&subscription_id, &UserId::generate())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(NotificationServiceError::SubscriptionNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_publish_queues_deliveries_for_matching_subscriptions() {
+        let user_id = UserId::generate();
+        let job = TrainingJob {
+            user_id: Some(user_id),
+            ..TrainingJob::new_mock()
+        };
+        let mine = Subscription {
+            owner_id: user_id,
+            ..Subscription::new_mock()
+        };
+        let someone_elses = Subscription::new_mock();
+        let mine_id = mine.id;
+
+        let mut mock_repo = MockNotificationRepository::new();
+        mock_repo
+            .expect_list_subscriptions_for_event()
+            .with(eq(EventKind::JobFailed))
+            .times(1)
+            .returning(move |_| Ok(vec![mine.clone(), someone_elses.clone()]));
+        mock_repo
+            .expect_create_delivery()
+            .withf(move |d| d.subscription_id == mine_id && d.event.kind == EventKind::JobFailed)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = service(mock_repo, MockNotificationSender::new());
+        let event = NotificationEvent::job_finished(&job, &TrainingJobStatus::Failed).unwrap();
+
+        assert!(service.publish(event).await.is_ok());
+    }
+
+    #[test]
+    fn test_job_finished_ignores_non_terminal_statuses() {
+        let job = TrainingJob::new_mock();
+        assert!(NotificationEvent::job_finished(&job, &TrainingJobStatus::Running).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_check_queue_backlog_notifies_once_per_crossing() {
+        let queue = Queue::new_mock();
+        let subscription = Subscription {
+            scope: SubscriptionScope::Queue { queue_id: queue.id },
+            event_kinds: vec![EventKind::QueueBacklog],
+            backlog_threshold: Some(5),
+            ..Subscription::new_mock()
+        };
+        let active = Subscription {
+            backlog_active: true,
+            ..subscription.clone()
+        };
+        let subscription_id = subscription.id;
+
+        let mut mock_repo = MockNotificationRepository::new();
+        let mut seq = mockall::Sequence::new();
+        mock_repo
+            .expect_list_subscriptions_for_event()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_| Ok(vec![subscription.clone()]));
+        mock_repo
+            .expect_create_delivery()
+            .withf(|d| d.event.kind == EventKind::QueueBacklog)
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        mock_repo
+            .expect_set_backlog_active()
+            .with(eq(subscription_id), eq(true))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _| Ok(()));
+        let still_active = active.clone();
+        mock_repo
+            .expect_list_subscriptions_for_event()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_| Ok(vec![still_active.clone()]));
+        mock_repo
+            .expect_list_subscriptions_for_event()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_| Ok(vec![active.clone()]));
+        mock_repo
+            .expect_set_backlog_active()
+            .with(eq(subscription_id), eq(false))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _| Ok(()));
+
+        let service = service(mock_repo, MockNotificationSender::new());
+
+        // Crossing the threshold notifies, staying over it doesn't, and
+        // dropping below it re-arms the subscription.
+        assert!(service.check_queue_backlog(&queue, 5).await.is_ok());
+        assert!(service.check_queue_backlog(&queue, 8).await.is_ok());
+        assert!(service.check_queue_backlog(&queue, 2).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_due_retries_then_fails() {
+        let subscription = Subscription::new_mock();
+        let subscription_id = subscription.id;
+        let delivery = Delivery {
+            attempts: 1,
+            ..Delivery::new(subscription_id, NotificationEvent::test(&subscription_id))
+        };
+        let last_attempt = Delivery {
+            attempts: 2,
+            ..delivery.clone()
+        };
+
+        let mut mock_repo = MockNotificationRepository::new();
+        mock_repo
+            .expect_list_due_deliveries()
+            .times(1)
+            .returning(move |_, _| Ok(vec![delivery.clone(), last_attempt.clone()]));
+        mock_repo
+            .expect_get_subscription()
+            .returning(move |_| Ok(subscription.clone()));
+        mock_repo
+            .expect_update_delivery()
+            .withf(|d| {
+                d.attempts == 2
+                    && d.status == DeliveryStatus::Pending
+                    && d.next_attempt_at.is_some()
+                    && d.last_error.as_deref() == Some("connection refused")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_repo
+            .expect_update_delivery()
+            .withf(|d| {
+                d.attempts == 3 && d.status == DeliveryStatus::Failed && d.next_attempt_at.is_none()
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut mock_sender = MockNotificationSender::new();
+        mock_sender.expect_send().times(2).returning(|_, _| {
+            Err(NotificationSenderError::DeliveryFailed(
+                "connection refused".to_string(),
+            ))
+        });
+
+        let service = service(mock_repo, mock_sender);
+
+        assert_eq!(service.dispatch_due().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_due_skips_deleted_subscriptions() {
+        let subscription_id = Subscription::new_mock().id;
+        let delivery = Delivery::new(subscription_id, NotificationEvent::test(&subscription_id));
+
+        let mut mock_repo = MockNotificationRepository::new();
+        mock_repo
+            .expect_list_due_deliveries()
+            .times(1)
+            .returning(move |_, _| Ok(vec![delivery.clone()]));
+        mock_repo
+            .expect_get_subscription()
+            .returning(|id| Err(NotificationRepositoryError::NotFound(id.to_string())));
+        mock_repo.expect_update_delivery().never();
+
+        let service = service(mock_repo, MockNotificationSender::new());
+
+        assert_eq!(service.dispatch_due().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_due_continues_after_failed_delivery() {
+        let subscription = Subscription::new_mock();
+        let subscription_id = subscription.id;
+        let failing = Delivery::new(subscription_id, NotificationEvent::test(&subscription_id));
+        let delivery = Delivery::new(subscription_id, NotificationEvent::test(&subscription_id));
+        let delivery_id = delivery.id;
+
+        let mut mock_repo = MockNotificationRepository::new();
+        mock_repo
+            .expect_list_due_deliveries()
+            .times(1)
+            .returning(move |_, _| Ok(vec![failing.clone(), delivery.clone()]));
+        mock_repo
+            .expect_get_subscription()
+            .times(1)
+            .returning(|_| Err(anyhow::anyhow!("connection reset").into()));
+        mock_repo
+            .expect_get_subscription()
+            .times(1)
+            .returning(move |_| Ok(subscription.clone()));
+        mock_repo
+            .expect_update_delivery()
+            .withf(move |d| d.id == delivery_id && d.status == DeliveryStatus::Succeeded)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut mock_sender = MockNotificationSender::new();
+        mock_sender.expect_send().times(1).returning(|_, _| Ok(()));
+
+        let service = service(mock_repo, mock_sender);
+
+        assert_eq!(service.dispatch_due().await.unwrap(), 2);
+    }
+
+    #[test]
+    fn test_sign_webhook() {
+        let signature = sign_webhook("whsec_test", 1_700_000_000, br#"{"kind":"test"}"#);
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_ne!(
+            signature,
+            sign_webhook("whsec_other", 1_700_000_000, br#"{"kind":"test"}"#)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    domain::{
+        cluster::models::{ClusterId, ClusterNode, NodeId},
+        queue::models::{Queue, QueueId},
+        training_job::models::{JobId, TrainingJob, TrainingJobStatus},
+        user::models::UserId,
+    },
+    identifier,
+};
+
+identifier!(SubscriptionId);
+identifier!(DeliveryId);
+identifier!(EventId);
+
+/// Something that happened that users can subscribe to.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EventKind {
+    JobSucceeded,
+    JobFailed,
+    JobCancelled,
+    /// A job was stopped for exceeding a limit, such as an interactive
+    /// session going unused for longer than its idle timeout.
+    JobTimedOut,
+    /// A node stopped sending heartbeats and was removed.
+    NodeDied,
+    /// More jobs are waiting in a queue than a subscription's threshold.
+    QueueBacklog,
+    /// Sent on request to check that a subscription's channel works.
+    Test,
+}
+
+/// An event as it is sent to subscribers. Webhooks receive it as their JSON body.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationEvent {
+    pub id: EventId,
+    pub kind: EventKind,
+    pub occurred_at: DateTime<Utc>,
+    /// A one-line, human-readable description, used as the subject of emails.
+    pub summary: String,
+    /// The user who submitted the job the event is about, if any.
+    pub user_id: Option<UserId>,
+    pub queue_id: Option<QueueId>,
+    pub job_id: Option<JobId>,
+    pub cluster_id: Option<ClusterId>,
+    pub node_id: Option<NodeId>,
+    /// Event specific details, such as the name of the job.
+    pub details: serde_json::Value,
+}
+
+impl NotificationEvent {
+    fn new(kind: EventKind, summary: String) -> Self {
+        Self {
+            id: EventId::generate(),
+            kind,
+            occurred_at: Utc::now(),
+            summary,
+            user_id: None,
+            queue_id: None,
+            job_id: None,
+            cluster_id: None,
+            node_id: None,
+            details: serde_json::json!({}),
+        }
+    }
+
+    /// The event for `job` reaching `status`, or None if the status isn't one
+    /// that is notified about.
+    pub fn job_finished(job: &TrainingJob, status: &TrainingJobStatus) -> Option<Self> {
+        let (kind, verb) = match status {
+            TrainingJobStatus::Succeeded => (EventKind::JobSucceeded, "succeeded"),
+            TrainingJobStatus::Failed => (EventKind::JobFailed, "failed"),
+            TrainingJobStatus::Cancelled => (EventKind::JobCancelled, "was cancelled"),
+            _ => return None,
+        };
+
+        Some(Self {
+            user_id: job.user_id,
+            queue_id: job.queue_id,
+            job_id: Some(job.id),
+            node_id: job.node_id,
+            details: serde_json::json!({
+                "job_name": job.name,
+                "status": status,
+                "labels": job.labels,
+                "started_at": job.started_at,
+            }),
+            ..Self::new(kind, format!("Training job {} {verb}", job.name))
+        })
+    }
+
+    /// The event for the interactive `job` being stopped after going unused
+    /// for longer than its idle timeout.
+    pub fn job_timed_out(job: &TrainingJob) -> Self {
+        let idle_timeout_minutes = job
+            .session
+            .as_ref()
+            .map(|session| session.idle_timeout_minutes);
+        Self {
+            user_id: job.user_id,
+            queue_id: job.queue_id,
+            job_id: Some(job.id),
+            node_id: job.node_id,
+            details: serde_json::json!({
+                "job_name": job.name,
+                "labels": job.labels,
+                "started_at": job.started_at,
+                "last_activity_at": job.last_activity_at,
+                "idle_timeout_minutes": idle_timeout_minutes,
+            }),
+            ..Self::new(
+                EventKind::JobTimedOut,
+                format!("Training job {} was stopped after going idle", job.name),
+            )
+        }
+    }
+
+    /// The event for `node` dying while `job`, if any, was placed on it.
+    pub fn node_died(node: &ClusterNode, job: Option<&TrainingJob>) -> Self {
+        Self {
+            user_id: job.and_then(|j| j.user_id),
+            queue_id: job.and_then(|j| j.queue_id),
+            job_id: job.map(|j| j.id),
+            cluster_id: Some(node.cluster_id),
+            node_id: Some(node.id),
+            details: serde_json::json!({
+                "last_heartbeat": node.heartbeat_timestamp,
+                "job_name": job.map(|j| &j.name),
+            }),
+            ..Self::new(
+                EventKind::NodeDied,
+                format!("Node {} stopped responding", node.id),
+            )
+        }
+    }
+
+    pub fn queue_backlog(queue: &Queue, queued_jobs: usize, threshold: i32) -> Self {
+        Self {
+            queue_id: Some(queue.id),
+            details: serde_json::json!({
+                "queue_name": queue.name,
+                "queued_jobs": queued_jobs,
+                "threshold": threshold,
+            }),
+            ..Self::new(
+                EventKind::QueueBacklog,
+                format!("{queued_jobs} jobs are waiting in queue {}", queue.name),
+            )
+        }
+    }
+
+    pub fn test(subscription_id: &SubscriptionId) -> Self {
+        Self {
+            details: serde_json::json!({ "subscription_id": subscription_id }),
+            ..Self::new(EventKind::Test, "Test notification from Lilac".to_string())
+        }
+    }
+}
+
+/// Which events of the subscribed kinds a subscription receives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum SubscriptionScope {
+    /// Events about the subscriber's own jobs.
+    User,
+    Queue {
+        queue_id: QueueId,
+    },
+    Job {
+        job_id: JobId,
+    },
+}
+
+/// Where a subscription's notifications are sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum NotificationChannel {
+    /// An HTTP endpoint that events are POSTed to, signed with `secret`.
+    Webhook {
+        url: String,
+        secret: String,
+    },
+    Email {
+        address: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub id: SubscriptionId,
+    pub owner_id: UserId,
+    pub scope: SubscriptionScope,
+    pub event_kinds: Vec<EventKind>,
+    pub channel: NotificationChannel,
+    /// How many queued jobs trigger a `queue_backlog` event.
+    pub backlog_threshold: Option<i32>,
+    /// Whether the queue is currently over the backlog threshold.
+    pub backlog_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Subscription {
+    pub fn matches(&self, event: &NotificationEvent) -> bool {
+        if !self.event_kinds.contains(&event.kind) {
+            return false;
+        }
+        match &self.scope {
+            SubscriptionScope::User => event.user_id == Some(self.owner_id),
+            SubscriptionScope::Queue { queue_id } => event.queue_id == Some(*queue_id),
+            SubscriptionScope::Job { job_id } => event.job_id == Some(*job_id),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Subscription {
+    pub fn new_mock() -> Self {
+        Self {
+            id: SubscriptionId::generate(),
+            owner_id: UserId::generate(),
+            scope: SubscriptionScope::User,
+            event_kinds: vec![EventKind::JobSucceeded, EventKind::JobFailed],
+            channel: NotificationChannel::Webhook {
+                url: "https://example.com/hooks/lilac".to_string(),
+                secret: "whsec_test".to_string(),
+            },
+            backlog_threshold: None,
+            backlog_active: false,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// DTO for creating a new subscription. Webhook secrets are generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateSubscriptionRequest {
+    pub scope: SubscriptionScope,
+    pub event_kinds: Vec<EventKind>,
+    pub channel: CreateNotificationChannel,
+    pub backlog_threshold: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum CreateNotificationChannel {
+    Webhook { url: String },
+    Email { address: String },
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, strum::EnumString, strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    /// Every attempt failed; the delivery won't be retried.
+    Failed,
+}
+
+/// One event sent, or to be sent, to one subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub id: DeliveryId,
+    pub subscription_id: SubscriptionId,
+    pub event: NotificationEvent,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When the next attempt is due. None once the delivery is done.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Delivery {
+    pub fn new(subscription_id: SubscriptionId, event: NotificationEvent) -> Self {
+        let now = Utc::now();
+        Self {
+            id: DeliveryId::generate(),
+            subscription_id,
+            event,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: Some(now),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// The value of the `X-Lilac-Signature` header of a webhook request: the
+/// hex-encoded HMAC-SHA256 of `{timestamp}.{body}`, keyed with the
+/// subscription's secret.
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::models::{
+    Delivery, EventKind, NotificationChannel, NotificationEvent, Subscription, SubscriptionId,
+};
+use crate::domain::user::models::UserId;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationRepositoryError {
+    #[error("subscription {0} not found")]
+    NotFound(String),
+    /// The queue or job a subscription is scoped to doesn't exist.
+    #[error("subscription scope {0} not found")]
+    ScopeNotFound(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    async fn create_subscription(
+        &self,
+        subscription: &Subscription,
+    ) -> Result<(), NotificationRepositoryError>;
+    async fn get_subscription(
+        &self,
+        id: &SubscriptionId,
+    ) -> Result<Subscription, NotificationRepositoryError>;
+    async fn list_subscriptions_for_owner(
+        &self,
+        owner_id: &UserId,
+    ) -> Result<Vec<Subscription>, NotificationRepositoryError>;
+    /// Lists the subscriptions to events of `kind`, whatever their scope.
+    async fn list_subscriptions_for_event(
+        &self,
+        kind: EventKind,
+    ) -> Result<Vec<Subscription>, NotificationRepositoryError>;
+    async fn delete_subscription(
+        &self,
+        id: &SubscriptionId,
+    ) -> Result<(), NotificationRepositoryError>;
+    async fn set_backlog_active(
+        &self,
+        id: &SubscriptionId,
+        active: bool,
+    ) -> Result<(), NotificationRepositoryError>;
+    async fn create_delivery(&self, delivery: &Delivery)
+        -> Result<(), NotificationRepositoryError>;
+    /// Updates the status, attempts, error and next attempt of a delivery.
+    async fn update_delivery(&self, delivery: &Delivery)
+        -> Result<(), NotificationRepositoryError>;
+    /// Lists up to `limit` pending deliveries whose next attempt is due at `now`.
+    async fn list_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Delivery>, NotificationRepositoryError>;
+    /// Lists the latest `limit` deliveries of a subscription, newest first.
+    async fn list_deliveries(
+        &self,
+        subscription_id: &SubscriptionId,
+        limit: i64,
+    ) -> Result<Vec<Delivery>, NotificationRepositoryError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationSenderError {
+    #[error("email notifications are not configured")]
+    EmailNotConfigured,
+    #[error("{0}")]
+    DeliveryFailed(String),
+}
+
+/// Sends events to webhooks and mailboxes.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait NotificationSender: Send + Sync {
+    async fn send(
+        &self,
+        channel: &NotificationChannel,
+        event: &NotificationEvent,
+    ) -> Result<(), NotificationSenderError>;
+    /// Whether email channels can be delivered to.
+    fn email_enabled(&self) -> bool;
+    /// Checks that webhooks can be delivered to `url`, which they can't if it
+    /// points into the control plane's own network.
+    async fn check_webhook_url(&self, url: &str) -> Result<(), NotificationSenderError>;
+}
@@ -0,0 +1,392 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use thiserror::Error;
+
+use super::{
+    models::{
+        CreateNotificationChannel, CreateSubscriptionRequest, Delivery, DeliveryStatus, EventKind,
+        NotificationChannel, NotificationEvent, Subscription, SubscriptionId, SubscriptionScope,
+    },
+    ports::{NotificationRepository, NotificationRepositoryError, NotificationSender},
+};
+use crate::domain::{queue::models::Queue, user::models::UserId};
+
+/// How many due deliveries are attempted per dispatch.
+const DISPATCH_BATCH_SIZE: i64 = 100;
+/// How many deliveries of a subscription are returned by the delivery log.
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+#[derive(Debug, Error)]
+pub enum NotificationServiceError {
+    #[error("subscription {0} not found")]
+    SubscriptionNotFound(String),
+    #[error("invalid subscription: {0}")]
+    InvalidSubscription(String),
+    #[error("subscription scope {0} not found")]
+    ScopeNotFound(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<NotificationRepositoryError> for NotificationServiceError {
+    fn from(err: NotificationRepositoryError) -> Self {
+        match err {
+            NotificationRepositoryError::NotFound(id) => {
+                NotificationServiceError::SubscriptionNotFound(id)
+            }
+            NotificationRepositoryError::ScopeNotFound(id) => {
+                NotificationServiceError::ScopeNotFound(id)
+            }
+            NotificationRepositoryError::Unknown(err) => NotificationServiceError::Unknown(err),
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait NotificationService: Send + Sync {
+    async fn create_subscription(
+        &self,
+        request: CreateSubscriptionRequest,
+        owner_id: &UserId,
+    ) -> Result<Subscription, NotificationServiceError>;
+    async fn list_subscriptions(
+        &self,
+        owner_id: &UserId,
+    ) -> Result<Vec<Subscription>, NotificationServiceError>;
+    async fn get_subscription(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Subscription, NotificationServiceError>;
+    async fn delete_subscription(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<(), NotificationServiceError>;
+    /// Lists the latest deliveries of a subscription, newest first.
+    async fn list_deliveries(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Vec<Delivery>, NotificationServiceError>;
+    /// Queues a test event for the subscription.
+    async fn send_test(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Delivery, NotificationServiceError>;
+    /// Queues a delivery of `event` for every subscription it matches.
+    async fn publish(&self, event: NotificationEvent) -> Result<(), NotificationServiceError>;
+    /// Notifies the queue's backlog subscriptions whose threshold
+    /// `queued_jobs` has just reached.
+    async fn check_queue_backlog(
+        &self,
+        queue: &Queue,
+        queued_jobs: usize,
+    ) -> Result<(), NotificationServiceError>;
+    /// Attempts the deliveries that are due and returns how many there were.
+    async fn dispatch_due(&self) -> Result<usize, NotificationServiceError>;
+}
+
+/// Publishes `event`, logging rather than returning any error, so that a
+/// notification problem never fails the operation that caused the event.
+pub async fn publish_event(service: &dyn NotificationService, event: NotificationEvent) {
+    let kind = event.kind;
+    if let Err(e) = service.publish(event).await {
+        tracing::error!(error = ?e, %kind, "failed to publish notification event");
+    }
+}
+
+pub struct NotificationServiceImpl {
+    repository: Arc<dyn NotificationRepository>,
+    sender: Arc<dyn NotificationSender>,
+    max_attempts: i32,
+}
+
+impl NotificationServiceImpl {
+    pub fn new(
+        repository: Arc<dyn NotificationRepository>,
+        sender: Arc<dyn NotificationSender>,
+        max_attempts: i32,
+    ) -> Self {
+        Self {
+            repository,
+            sender,
+            max_attempts,
+        }
+    }
+
+    async fn get_owned_subscription(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Subscription, NotificationServiceError> {
+        let subscription = self.repository.get_subscription(id).await?;
+        // Other users' subscriptions are reported as missing rather than
+        // forbidden, so that their IDs can't be probed.
+        if subscription.owner_id != *owner_id {
+            return Err(NotificationServiceError::SubscriptionNotFound(
+                id.to_string(),
+            ));
+        }
+        Ok(subscription)
+    }
+
+    async fn validate(&self, request: &CreateSubscriptionRequest) -> Result<(), String> {
+        if request.event_kinds.is_empty() {
+            return Err("at least one event kind is required".to_string());
+        }
+        if request.event_kinds.contains(&EventKind::Test) {
+            return Err("test events can't be subscribed to".to_string());
+        }
+
+        let wants_backlog = request.event_kinds.contains(&EventKind::QueueBacklog);
+        if wants_backlog && !matches!(request.scope, SubscriptionScope::Queue { .. }) {
+            return Err("queue_backlog events require a queue scope".to_string());
+        }
+        match request.backlog_threshold {
+            None if wants_backlog => {
+                return Err("queue_backlog events require a backlog_threshold".to_string())
+            }
+            Some(_) if !wants_backlog => {
+                return Err("backlog_threshold is only used by queue_backlog events".to_string())
+            }
+            Some(threshold) if threshold < 1 => {
+                return Err("backlog_threshold must be at least 1".to_string())
+            }
+            _ => {}
+        }
+
+        match &request.channel {
+            CreateNotificationChannel::Webhook { url } => {
+                let url = url::Url::parse(url).map_err(|e| format!("invalid webhook url: {e}"))?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return Err("webhook url must use http or https".to_string());
+                }
+                self.sender
+                    .check_webhook_url(url.as_str())
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            CreateNotificationChannel::Email { address } => {
+                if !self.sender.email_enabled() {
+                    return Err("email notifications are not configured".to_string());
+                }
+                let valid = address.split_once('@').is_some_and(|(user, domain)| {
+                    !user.is_empty()
+                        && domain.contains('.')
+                        && !address.contains(char::is_whitespace)
+                });
+                if !valid {
+                    return Err(format!("invalid email address '{address}'"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// How long to wait before attempt number `attempts + 1`.
+    fn retry_delay(attempts: i32) -> Duration {
+        let exponent = attempts.clamp(1, 8) - 1;
+        std::cmp::min(
+            Duration::seconds(30 * 2_i64.pow(exponent as u32)),
+            Duration::hours(1),
+        )
+    }
+
+    async fn attempt(&self, mut delivery: Delivery) -> Result<(), NotificationServiceError> {
+        let subscription = match self
+            .repository
+            .get_subscription(&delivery.subscription_id)
+            .await
+        {
+            Ok(subscription) => subscription,
+            // The delivery goes with the subscription.
+            Err(NotificationRepositoryError::NotFound(_)) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        delivery.attempts += 1;
+        match self
+            .sender
+            .send(&subscription.channel, &delivery.event)
+            .await
+        {
+            Ok(()) => {
+                delivery.status = DeliveryStatus::Succeeded;
+                delivery.last_error = None;
+                delivery.next_attempt_at = None;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    delivery_id = %delivery.id,
+                    attempts = delivery.attempts,
+                    error = %e,
+                    "notification delivery failed"
+                );
+                delivery.last_error = Some(e.to_string());
+                if delivery.attempts >= self.max_attempts {
+                    delivery.status = DeliveryStatus::Failed;
+                    delivery.next_attempt_at = None;
+                } else {
+                    delivery.next_attempt_at =
+                        Some(Utc::now() + Self::retry_delay(delivery.attempts));
+                }
+            }
+        }
+
+        Ok(self.repository.update_delivery(&delivery).await?)
+    }
+}
+
+#[async_trait]
+impl NotificationService for NotificationServiceImpl {
+    async fn create_subscription(
+        &self,
+        request: CreateSubscriptionRequest,
+        owner_id: &UserId,
+    ) -> Result<Subscription, NotificationServiceError> {
+        self.validate(&request)
+            .await
+            .map_err(NotificationServiceError::InvalidSubscription)?;
+
+        let channel = match request.channel {
+            CreateNotificationChannel::Webhook { url } => NotificationChannel::Webhook {
+                url,
+                secret: format!("whsec_{}", nanoid::nanoid!(32)),
+            },
+            CreateNotificationChannel::Email { address } => NotificationChannel::Email { address },
+        };
+        let subscription = Subscription {
+            id: SubscriptionId::generate(),
+            owner_id: *owner_id,
+            scope: request.scope,
+            event_kinds: request.event_kinds,
+            channel,
+            backlog_threshold: request.backlog_threshold,
+            backlog_active: false,
+            created_at: Utc::now(),
+        };
+        self.repository.create_subscription(&subscription).await?;
+
+        Ok(subscription)
+    }
+
+    async fn list_subscriptions(
+        &self,
+        owner_id: &UserId,
+    ) -> Result<Vec<Subscription>, NotificationServiceError> {
+        Ok(self
+            .repository
+            .list_subscriptions_for_owner(owner_id)
+            .await?)
+    }
+
+    async fn get_subscription(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Subscription, NotificationServiceError> {
+        self.get_owned_subscription(id, owner_id).await
+    }
+
+    async fn delete_subscription(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<(), NotificationServiceError> {
+        self.get_owned_subscription(id, owner_id).await?;
+        Ok(self.repository.delete_subscription(id).await?)
+    }
+
+    async fn list_deliveries(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Vec<Delivery>, NotificationServiceError> {
+        self.get_owned_subscription(id, owner_id).await?;
+        Ok(self
+            .repository
+            .list_deliveries(id, DELIVERY_LOG_LIMIT)
+            .await?)
+    }
+
+    async fn send_test(
+        &self,
+        id: &SubscriptionId,
+        owner_id: &UserId,
+    ) -> Result<Delivery, NotificationServiceError> {
+        self.get_owned_subscription(id, owner_id).await?;
+        let delivery = Delivery::new(*id, NotificationEvent::test(id));
+        self.repository.create_delivery(&delivery).await?;
+        Ok(delivery)
+    }
+
+    async fn publish(&self, event: NotificationEvent) -> Result<(), NotificationServiceError> {
+        let subscriptions = self
+            .repository
+            .list_subscriptions_for_event(event.kind)
+            .await?;
+        for subscription in subscriptions.iter().filter(|s| s.matches(&event)) {
+            self.repository
+                .create_delivery(&Delivery::new(subscription.id, event.clone()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn check_queue_backlog(
+        &self,
+        queue: &Queue,
+        queued_jobs: usize,
+    ) -> Result<(), NotificationServiceError> {
+        let subscriptions = self
+            .repository
+            .list_subscriptions_for_event(EventKind::QueueBacklog)
+            .await?;
+        for subscription in subscriptions {
+            if subscription.scope != (SubscriptionScope::Queue { queue_id: queue.id }) {
+                continue;
+            }
+            let Some(threshold) = subscription.backlog_threshold else {
+                continue;
+            };
+
+            let over = queued_jobs >= threshold as usize;
+            if over && !subscription.backlog_active {
+                let event = NotificationEvent::queue_backlog(queue, queued_jobs, threshold);
+                self.repository
+                    .create_delivery(&Delivery::new(subscription.id, event))
+                    .await?;
+                self.repository
+                    .set_backlog_active(&subscription.id, true)
+                    .await?;
+            } else if !over && subscription.backlog_active {
+                self.repository
+                    .set_backlog_active(&subscription.id, false)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch_due(&self) -> Result<usize, NotificationServiceError> {
+        let deliveries = self
+            .repository
+            .list_due_deliveries(Utc::now(), DISPATCH_BATCH_SIZE)
+            .await?;
+        let count = deliveries.len();
+        // One delivery failing to be recorded doesn't hold up the others.
+        for delivery in deliveries {
+            let delivery_id = delivery.id;
+            if let Err(e) = self.attempt(delivery).await {
+                tracing::error!(%delivery_id, error = ?e, "failed to attempt notification delivery");
+            }
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,206 @@
+pub mod models;
+pub mod ports;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{QuotaLedger, QuotaSubject, ResourceQuota},
+        ports::MockQuotaRepository,
+        service::{QuotaService, QuotaServiceError, QuotaServiceImpl},
+    };
+    use crate::domain::{
+        accounting::models::UsageRecord,
+        cluster::models::{ClusterId, NodeId},
+        queue::models::QueueId,
+        training_job::{
+            models::{
+                GpuRequirement, JobId, JobKind, ResourceRequirements, TrainingJob,
+                TrainingJobStatus,
+            },
+            ports::MockTrainingJobRepository,
+        },
+        user::models::UserId,
+    };
+    use chrono::{Duration, TimeZone, Utc};
+    use std::{collections::HashMap, sync::Arc};
+
+    fn gpu_job(user_id: UserId, queue_id: QueueId, gpus: i32) -> TrainingJob {
+        TrainingJob {
+            user_id: Some(user_id),
+            queue_id: Some(queue_id),
+            resource_requirements: ResourceRequirements {
+                cpu_millicores: 1000,
+                memory_mb: 1024,
+                gpus: Some(GpuRequirement {
+                    count: gpus,
+                    model: None,
+                    memory_gb: None,
+                }),
+            },
+            ..TrainingJob::new_mock()
+        }
+    }
+
+    #[test]
+    fn test_ledger_enforces_gpu_limit() {
+        let user_id = UserId::generate();
+        let queue_id = QueueId::generate();
+        let now = Utc::now();
+        let running = gpu_job(user_id, queue_id, 4);
+        let mut ledger = QuotaLedger::new(&[running], &[], now);
+        let quotas = HashMap::from([(
+            QuotaSubject::User(user_id),
+            ResourceQuota {
+                max_gpus: Some(8),
+                ..Default::default()
+            },
+        )]);
+
+        let next = gpu_job(user_id, queue_id, 4);
+        assert_eq!(ledger.check(&next, &quotas), None);
+
+        ledger.add_job(&next);
+        let reason = ledger
+            .check(&gpu_job(user_id, queue_id, 1), &quotas)
+            .unwrap();
+        assert_eq!(
+            reason,
+            format!(
+                "quota exceeded: user {user_id} GPU limit of 8 reached (8 in use, 1 requested)"
+            )
+        );
+        // Jobs in other queues from other users are unaffected.
+        assert_eq!(
+            ledger.check(&gpu_job(UserId::generate(), queue_id, 1), &quotas),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ledger_enforces_queue_session_limit_per_user() {
+        let user_id = UserId::generate();
+        let queue_id = QueueId::generate();
+        let session = |user_id| TrainingJob {
+            kind: JobKind::Interactive,
+            ..gpu_job(user_id, queue_id, 1)
+        };
+        let ledger = QuotaLedger::new(&[session(user_id)], &[], Utc::now());
+        let quotas = HashMap::from([(
+            QuotaSubject::Queue(queue_id),
+            ResourceQuota {
+                max_sessions: Some(1),
+                ..Default::default()
+            },
+        )]);
+
+        assert_eq!(
+            ledger.check(&session(user_id), &quotas),
+            Some(format!(
+                "quota exceeded: queue {queue_id} interactive session limit of 1 per user reached"
+            ))
+        );
+        // Other users of the queue and batch jobs are unaffected.
+        assert_eq!(ledger.check(&session(UserId::generate()), &quotas), None);
+        assert_eq!(ledger.check(&gpu_job(user_id, queue_id, 1), &quotas), None);
+    }
+
+    #[test]
+    fn test_ledger_enforces_monthly_gpu_hours() {
+        let user_id = UserId::generate();
+        let queue_id = QueueId::generate();
+        let now = Utc.with_ymd_and_hms(2025, 8, 15, 12, 0, 0).unwrap();
+        // 10 hours on 2 GPUs, 4 of which fell in the previous month.
+        let record = UsageRecord {
+            job_id: JobId::generate(),
+            user_id: Some(user_id),
+            queue_id: Some(queue_id),
+            cluster_id: ClusterId::generate(),
+            node_id: NodeId::generate(),
+            started_at: Utc.with_ymd_and_hms(2025, 7, 31, 20, 0, 0).unwrap(),
+            ended_at: Utc.with_ymd_and_hms(2025, 8, 1, 6, 0, 0).unwrap(),
+            cpu_millicores: 1000,
+            memory_mb: 1024,
+            gpu_count: 2,
+            gpu_model: None,
+        };
+        let running = TrainingJob {
+            status: TrainingJobStatus::Running,
+            started_at: Some(now - Duration::hours(1)),
+            ..gpu_job(user_id, queue_id, 1)
+        };
+
+        let ledger = QuotaLedger::new(&[running], &[record], now);
+
+        let usage = ledger.usage(&QuotaSubject::Queue(queue_id));
+        assert_eq!(usage.gpu_hours_this_month, 13.0);
+        assert_eq!(usage.running_jobs, 1);
+
+        let quotas = HashMap::from([(
+            QuotaSubject::Queue(queue_id),
+            ResourceQuota {
+                monthly_gpu_hours: Some(13.0),
+                ..Default::default()
+            },
+        )]);
+        assert!(ledger
+            .check(&gpu_job(user_id, queue_id, 1), &quotas)
+            .unwrap()
+            .contains("monthly budget of 13 GPU hours used up"));
+    }
+
+    #[tokio::test]
+    async fn test_get_quota_status() {
+        let user_id = UserId::generate();
+        let queue_id = QueueId::generate();
+        let subject = QuotaSubject::User(user_id);
+
+        let mut quota_repo = MockQuotaRepository::new();
+        quota_repo.expect_get_quota().times(1).returning(|_| {
+            Ok(Some(ResourceQuota {
+                max_running_jobs: Some(2),
+                ..Default::default()
+            }))
+        });
+        let mut job_repo = MockTrainingJobRepository::new();
+        job_repo
+            .expect_get_jobs_by_status()
+            .returning(move |status| {
+                Ok(match status {
+                    TrainingJobStatus::Running => vec![gpu_job(user_id, queue_id, 2)],
+                    _ => vec![],
+                })
+            });
+        job_repo
+            .expect_list_usage_records()
+            .times(1)
+            .returning(|_, _| Ok(vec![]));
+
+        let service = QuotaServiceImpl::new(Arc::new(quota_repo), Arc::new(job_repo));
+        let status = service.get_quota_status(&subject).await.unwrap();
+
+        assert_eq!(status.quota.unwrap().max_running_jobs, Some(2));
+        assert_eq!(status.usage.running_jobs, 1);
+        assert_eq!(status.usage.gpus, 2);
+        assert_eq!(status.usage.cpu_millicores, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_set_quota_rejects_negative_limits() {
+        let quota_repo = MockQuotaRepository::new();
+        let job_repo = MockTrainingJobRepository::new();
+        let service = QuotaServiceImpl::new(Arc::new(quota_repo), Arc::new(job_repo));
+
+        let result = service
+            .set_quota(
+                &QuotaSubject::Queue(QueueId::generate()),
+                ResourceQuota {
+                    max_memory_mb: Some(-1),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(matches!(result, Err(QuotaServiceError::InvalidQuota(_))));
+    }
+}
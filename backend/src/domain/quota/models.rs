@@ -0,0 +1,262 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    accounting::models::UsageRecord,
+    queue::models::QueueId,
+    training_job::models::{JobKind, ResourceRequirements, TrainingJob},
+    user::models::UserId,
+};
+
+/// Limits on how much of the fleet a user or queue may hold at once. Limits
+/// that are not set are not enforced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourceQuota {
+    pub max_gpus: Option<i32>,
+    pub max_cpu_millicores: Option<i32>,
+    pub max_memory_mb: Option<i32>,
+    /// The maximum number of jobs that may be starting or running at once.
+    pub max_running_jobs: Option<i32>,
+    /// The maximum number of interactive sessions that may be starting or
+    /// running at once. On a queue, this applies to each of its users.
+    pub max_sessions: Option<i32>,
+    /// The GPU hours that may be consumed per calendar month (UTC). Once used
+    /// up, no further jobs requesting GPUs are started until the next month.
+    pub monthly_gpu_hours: Option<f64>,
+}
+
+impl ResourceQuota {
+    /// Returns why starting a job with `requirements` on top of `usage` would
+    /// exceed this quota, or None if it would not.
+    pub fn check(&self, usage: &QuotaUsage, requirements: &ResourceRequirements) -> Option<String> {
+        let gpus = requirements.gpu_count();
+
+        if let Some(max) = self.max_running_jobs {
+            if usage.running_jobs + 1 > max {
+                return Some(format!("running job limit of {max} reached"));
+            }
+        }
+        if let Some(max) = self.max_gpus {
+            if usage.gpus + gpus > max {
+                return Some(format!(
+                    "GPU limit of {max} reached ({} in use, {gpus} requested)",
+                    usage.gpus
+                ));
+            }
+        }
+        if let Some(max) = self.max_cpu_millicores {
+            if usage.cpu_millicores + requirements.cpu_millicores > max {
+                return Some(format!(
+                    "CPU limit of {max}m reached ({}m in use, {}m requested)",
+                    usage.cpu_millicores, requirements.cpu_millicores
+                ));
+            }
+        }
+        if let Some(max) = self.max_memory_mb {
+            if usage.memory_mb + requirements.memory_mb > max {
+                return Some(format!(
+                    "memory limit of {max} MB reached ({} MB in use, {} MB requested)",
+                    usage.memory_mb, requirements.memory_mb
+                ));
+            }
+        }
+        if let Some(budget) = self.monthly_gpu_hours {
+            if gpus > 0 && usage.gpu_hours_this_month >= budget {
+                return Some(format!(
+                    "monthly budget of {budget} GPU hours used up ({:.1} used)",
+                    usage.gpu_hours_this_month
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+/// Who a quota applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuotaSubject {
+    User(UserId),
+    Queue(QueueId),
+}
+
+impl QuotaSubject {
+    /// The subjects whose quotas apply to `job`.
+    pub fn for_job(job: &TrainingJob) -> Vec<Self> {
+        job.user_id
+            .map(Self::User)
+            .into_iter()
+            .chain(job.queue_id.map(Self::Queue))
+            .collect()
+    }
+}
+
+impl fmt::Display for QuotaSubject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaSubject::User(id) => write!(f, "user {id}"),
+            QuotaSubject::Queue(id) => write!(f, "queue {id}"),
+        }
+    }
+}
+
+/// What a user or queue currently holds, in the units quotas are expressed in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QuotaUsage {
+    pub running_jobs: i32,
+    /// Interactive sessions among the running jobs.
+    #[serde(default)]
+    pub sessions: i32,
+    pub gpus: i32,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub gpu_hours_this_month: f64,
+}
+
+impl QuotaUsage {
+    fn add_allocation(&mut self, requirements: &ResourceRequirements) {
+        self.running_jobs += 1;
+        self.gpus += requirements.gpu_count();
+        self.cpu_millicores += requirements.cpu_millicores;
+        self.memory_mb += requirements.memory_mb;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuotaStatus {
+    /// None if no quota is configured.
+    pub quota: Option<ResourceQuota>,
+    pub usage: QuotaUsage,
+}
+
+pub fn start_of_month(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .expect("the first of the month is a valid date")
+}
+
+/// The usage of every user and queue, used to enforce quotas.
+#[derive(Debug, Default)]
+pub struct QuotaLedger {
+    usage: HashMap<QuotaSubject, QuotaUsage>,
+    /// The interactive sessions of each user in each queue, which queue
+    /// session limits apply to.
+    queue_sessions: HashMap<(QueueId, UserId), i32>,
+}
+
+impl QuotaLedger {
+    /// Builds the ledger from the jobs that currently hold resources and the
+    /// usage records of runs that ended this month.
+    pub fn new(
+        active_jobs: &[TrainingJob],
+        usage_records: &[UsageRecord],
+        now: DateTime<Utc>,
+    ) -> Self {
+        let month_start = start_of_month(now);
+        let mut ledger = Self::default();
+
+        for record in usage_records {
+            let gpu_hours = record.hours_between(month_start, now) * record.gpu_count as f64;
+            let subjects = record
+                .user_id
+                .map(QuotaSubject::User)
+                .into_iter()
+                .chain(record.queue_id.map(QuotaSubject::Queue));
+            for subject in subjects {
+                ledger
+                    .usage
+                    .entry(subject)
+                    .or_default()
+                    .gpu_hours_this_month += gpu_hours;
+            }
+        }
+
+        for job in active_jobs {
+            ledger.add_job(job);
+            if let Some(started_at) = job.started_at {
+                let hours = (now - started_at.max(month_start))
+                    .num_milliseconds()
+                    .max(0) as f64
+                    / 3_600_000.0;
+                let gpus = job.resource_requirements.gpu_count();
+                for subject in QuotaSubject::for_job(job) {
+                    ledger
+                        .usage
+                        .entry(subject)
+                        .or_default()
+                        .gpu_hours_this_month += hours * gpus as f64;
+                }
+            }
+        }
+
+        ledger
+    }
+
+    pub fn usage(&self, subject: &QuotaSubject) -> QuotaUsage {
+        self.usage.get(subject).cloned().unwrap_or_default()
+    }
+
+    /// Adds the resources allocated to `job` to the usage of its user and queue.
+    pub fn add_job(&mut self, job: &TrainingJob) {
+        let session = job.kind == JobKind::Interactive;
+        for subject in QuotaSubject::for_job(job) {
+            let usage = self.usage.entry(subject).or_default();
+            usage.add_allocation(&job.resource_requirements);
+            if session {
+                usage.sessions += 1;
+            }
+        }
+        if let (true, Some(queue_id), Some(user_id)) = (session, job.queue_id, job.user_id) {
+            *self.queue_sessions.entry((queue_id, user_id)).or_default() += 1;
+        }
+    }
+
+    /// Returns why starting another interactive session of the user of
+    /// `job` would exceed the session limit of `subject`, if it would.
+    fn check_sessions(
+        &self,
+        job: &TrainingJob,
+        subject: &QuotaSubject,
+        quota: &ResourceQuota,
+    ) -> Option<String> {
+        let max = quota.max_sessions?;
+        if job.kind != JobKind::Interactive {
+            return None;
+        }
+        let sessions = match subject {
+            QuotaSubject::User(_) => self.usage(subject).sessions,
+            QuotaSubject::Queue(queue_id) => job.user_id.map_or(0, |user_id| {
+                self.queue_sessions
+                    .get(&(*queue_id, user_id))
+                    .copied()
+                    .unwrap_or(0)
+            }),
+        };
+        if sessions + 1 > max {
+            return Some(match subject {
+                QuotaSubject::User(_) => format!("interactive session limit of {max} reached"),
+                QuotaSubject::Queue(_) => {
+                    format!("interactive session limit of {max} per user reached")
+                }
+            });
+        }
+        None
+    }
+
+    /// Returns the pending reason for `job` if starting it would exceed the
+    /// quota of its user or queue.
+    pub fn check(
+        &self,
+        job: &TrainingJob,
+        quotas: &HashMap<QuotaSubject, ResourceQuota>,
+    ) -> Option<String> {
+        QuotaSubject::for_job(job).into_iter().find_map(|subject| {
+            let quota = quotas.get(&subject)?;
+            self.check_sessions(job, &subject, quota)
+                .or_else(|| quota.check(&self.usage(&subject), &job.resource_requirements))
+                .map(|reason| format!("quota exceeded: {subject} {reason}"))
+        })
+    }
+}
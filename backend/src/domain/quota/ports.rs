@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+
+use super::models::{QuotaSubject, ResourceQuota};
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaRepositoryError {
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait QuotaRepository: Send + Sync {
+    async fn get_quota(
+        &self,
+        subject: &QuotaSubject,
+    ) -> Result<Option<ResourceQuota>, QuotaRepositoryError>;
+    /// Creates or replaces the quota of `subject`.
+    async fn set_quota(
+        &self,
+        subject: &QuotaSubject,
+        quota: &ResourceQuota,
+    ) -> Result<(), QuotaRepositoryError>;
+    async fn delete_quota(&self, subject: &QuotaSubject) -> Result<(), QuotaRepositoryError>;
+    async fn list_quotas(&self)
+        -> Result<Vec<(QuotaSubject, ResourceQuota)>, QuotaRepositoryError>;
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use super::{
+    models::{start_of_month, QuotaLedger, QuotaStatus, QuotaSubject, ResourceQuota},
+    ports::{QuotaRepository, QuotaRepositoryError},
+};
+use crate::domain::training_job::{
+    models::TrainingJobStatus,
+    ports::{TrainingJobRepository, TrainingJobRepositoryError},
+};
+
+#[derive(Debug, Error)]
+pub enum QuotaServiceError {
+    #[error("{0} not found")]
+    SubjectNotFound(String),
+    #[error("invalid quota: {0}")]
+    InvalidQuota(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<QuotaRepositoryError> for QuotaServiceError {
+    fn from(err: QuotaRepositoryError) -> Self {
+        match err {
+            QuotaRepositoryError::NotFound(subject) => QuotaServiceError::SubjectNotFound(subject),
+            QuotaRepositoryError::Unknown(err) => QuotaServiceError::Unknown(err),
+        }
+    }
+}
+
+impl From<TrainingJobRepositoryError> for QuotaServiceError {
+    fn from(err: TrainingJobRepositoryError) -> Self {
+        match err {
+            TrainingJobRepositoryError::Unknown(err) => QuotaServiceError::Unknown(err),
+            _ => QuotaServiceError::Unknown(anyhow::anyhow!(err)),
+        }
+    }
+}
+
+/// Builds the quota ledger from the jobs that currently hold resources and
+/// this month's usage records.
+pub async fn load_quota_ledger(
+    job_repo: &dyn TrainingJobRepository,
+    now: DateTime<Utc>,
+) -> Result<QuotaLedger, TrainingJobRepositoryError> {
+    let mut active_jobs = job_repo
+        .get_jobs_by_status(TrainingJobStatus::Starting)
+        .await?;
+    active_jobs.extend(
+        job_repo
+            .get_jobs_by_status(TrainingJobStatus::Running)
+            .await?,
+    );
+    let usage_records = job_repo
+        .list_usage_records(start_of_month(now), now)
+        .await?;
+
+    Ok(QuotaLedger::new(&active_jobs, &usage_records, now))
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait QuotaService: Send + Sync {
+    async fn get_quota_status(
+        &self,
+        subject: &QuotaSubject,
+    ) -> Result<QuotaStatus, QuotaServiceError>;
+    async fn set_quota(
+        &self,
+        subject: &QuotaSubject,
+        quota: ResourceQuota,
+    ) -> Result<ResourceQuota, QuotaServiceError>;
+    async fn delete_quota(&self, subject: &QuotaSubject) -> Result<(), QuotaServiceError>;
+}
+
+pub struct QuotaServiceImpl {
+    repository: Arc<dyn QuotaRepository>,
+    job_repo: Arc<dyn TrainingJobRepository>,
+}
+
+impl QuotaServiceImpl {
+    pub fn new(
+        repository: Arc<dyn QuotaRepository>,
+        job_repo: Arc<dyn TrainingJobRepository>,
+    ) -> Self {
+        Self {
+            repository,
+            job_repo,
+        }
+    }
+}
+
+fn validate_quota(quota: &ResourceQuota) -> Result<(), QuotaServiceError> {
+    let limits = [
+        ("max_gpus", quota.max_gpus),
+        ("max_cpu_millicores", quota.max_cpu_millicores),
+        ("max_memory_mb", quota.max_memory_mb),
+        ("max_running_jobs", quota.max_running_jobs),
+        ("max_sessions", quota.max_sessions),
+    ];
+    for (name, limit) in limits {
+        if limit.is_some_and(|limit| limit < 0) {
+            return Err(QuotaServiceError::InvalidQuota(format!(
+                "{name} must not be negative"
+            )));
+        }
+    }
+    if quota
+        .monthly_gpu_hours
+        .is_some_and(|hours| !hours.is_finite() || hours < 0.0)
+    {
+        return Err(QuotaServiceError::InvalidQuota(
+            "monthly_gpu_hours must be a non-negative number".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl QuotaService for QuotaServiceImpl {
+    async fn get_quota_status(
+        &self,
+        subject: &QuotaSubject,
+    ) -> Result<QuotaStatus, QuotaServiceError> {
+        let quota = self.repository.get_quota(subject).await?;
+        let ledger = load_quota_ledger(self.job_repo.as_ref(), Utc::now()).await?;
+
+        Ok(QuotaStatus {
+            quota,
+            usage: ledger.usage(subject),
+        })
+    }
+
+    async fn set_quota(
+        &self,
+        subject: &QuotaSubject,
+        quota: ResourceQuota,
+    ) -> Result<ResourceQuota, QuotaServiceError> {
+        validate_quota(&quota)?;
+        self.repository.set_quota(subject, &quota).await?;
+        Ok(quota)
+    }
+
+    async fn delete_quota(&self, subject: &QuotaSubject) -> Result<(), QuotaServiceError> {
+        Ok(self.repository.delete_quota(subject).await?)
+    }
+}
@@ -1,11 +1,21 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use tracing::{error, info};
 
 use crate::{
     domain::{
-        cluster::ports::ClusterRepository, queue::ports::QueueRepository,
-        training_job::ports::TrainingJobRepository,
+        accounting::models::UsageRecord,
+        cluster::{models::ClusterNode, ports::ClusterRepository},
+        notification::{
+            models::NotificationEvent,
+            service::{publish_event, NotificationService},
+        },
+        queue::ports::QueueRepository,
+        quota::{ports::QuotaRepository, service::load_quota_ledger},
+        training_job::{
+            models::{generate_job_token, JobId, TrainingJob, TrainingJobStatus},
+            ports::TrainingJobRepository,
+        },
     },
     outbound::scheduler::agent_adapter::{AgentSchedulerAdapter, AgentSchedulerError},
 };
@@ -14,7 +24,7 @@ use thiserror::Error;
 
 use crate::domain::{
     cluster::ports::ClusterRepositoryError, queue::ports::QueueRepositoryError,
-    training_job::ports::TrainingJobRepositoryError,
+    quota::ports::QuotaRepositoryError, training_job::ports::TrainingJobRepositoryError,
 };
 
 #[derive(Debug, Error)]
@@ -26,6 +36,8 @@ pub enum SchedulerServiceError {
     #[error(transparent)]
     Cluster(#[from] ClusterRepositoryError),
     #[error(transparent)]
+    Quota(#[from] QuotaRepositoryError),
+    #[error(transparent)]
     Agent(#[from] AgentSchedulerError),
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
@@ -35,7 +47,9 @@ pub struct SchedulerService {
     job_repo: Arc<dyn TrainingJobRepository>,
     queue_repo: Arc<dyn QueueRepository>,
     cluster_repo: Arc<dyn ClusterRepository>,
+    quota_repo: Arc<dyn QuotaRepository>,
     agent_adapter: Arc<AgentSchedulerAdapter>,
+    notification_service: Arc<dyn NotificationService>,
 }
 
 impl SchedulerService {
@@ -43,16 +57,43 @@ impl SchedulerService {
         job_repo: Arc<dyn TrainingJobRepository>,
         queue_repo: Arc<dyn QueueRepository>,
         cluster_repo: Arc<dyn ClusterRepository>,
+        quota_repo: Arc<dyn QuotaRepository>,
         agent_adapter: Arc<AgentSchedulerAdapter>,
+        notification_service: Arc<dyn NotificationService>,
     ) -> Self {
         Self {
             job_repo,
             queue_repo,
             cluster_repo,
+            quota_repo,
             agent_adapter,
+            notification_service,
         }
     }
 
+    async fn publish_job_cancelled(&self, job: &TrainingJob) {
+        if let Some(event) = NotificationEvent::job_finished(job, &TrainingJobStatus::Cancelled) {
+            publish_event(self.notification_service.as_ref(), event).await;
+        }
+    }
+
+    /// Records the usage of the job's current run on `node` before it is re-queued.
+    async fn record_usage(
+        &self,
+        job_id: &JobId,
+        node: &ClusterNode,
+    ) -> Result<(), SchedulerServiceError> {
+        let job = self.job_repo.get_training_job_by_id(job_id).await?;
+        // Finished runs were recorded when they finished.
+        if job.node_id != Some(node.id) || job.is_finished() {
+            return Ok(());
+        }
+        if let Some(record) = UsageRecord::for_run(&job, node, Utc::now()) {
+            self.job_repo.create_usage_record(&record).await?;
+        }
+        Ok(())
+    }
+
     async fn cleanup_dead_nodes(&self) -> Result<(), SchedulerServiceError> {
         info!("Running dead node cleanup...");
         let nodes = self.cluster_repo.list_all_nodes().await?;
@@ -61,19 +102,34 @@ impl SchedulerService {
             if since_heartbeat > chrono::Duration::seconds(90) {
                 info!("Found dead node {}. Cleaning up.", node.id);
 
+                let job = match node.assigned_job_id.or(node.reported_job_id) {
+                    Some(job_id) => self.job_repo.get_training_job_by_id(&job_id).await.ok(),
+                    None => None,
+                };
+                publish_event(
+                    self.notification_service.as_ref(),
+                    NotificationEvent::node_died(&node, job.as_ref()),
+                )
+                .await;
+
                 if let Some(job_id) = node.assigned_job_id {
                     info!(
                         "Re-queueing assigned job {} from dead node {}",
                         job_id, node.id
                     );
+                    self.record_usage(&job_id, &node).await?;
                     self.job_repo.reset_job_status(&job_id).await?;
                 }
 
-                if let Some(job_id) = node.reported_job_id {
+                if let Some(job_id) = node
+                    .reported_job_id
+                    .filter(|id| Some(*id) != node.assigned_job_id)
+                {
                     info!(
                         "Re-queueing reported job {} from dead node {}",
                         job_id, node.id
                     );
+                    self.record_usage(&job_id, &node).await?;
                     self.job_repo.reset_job_status(&job_id).await?;
                 }
 
@@ -126,6 +182,7 @@ impl SchedulerService {
                         super::super::training_job::models::TrainingJobStatus::Cancelled,
                     )
                     .await?;
+                self.publish_job_cancelled(&job).await;
             } else if requeue {
                 self.job_repo.reset_job_status(&job.id).await?;
             }
@@ -153,6 +210,7 @@ impl SchedulerService {
                             "Found preempted job {} on node {}. Re-queueing.",
                             job.id, node.id
                         );
+                        self.record_usage(&job.id, &node).await?;
                         self.job_repo.reset_job_status(&job.id).await?;
                     }
                 }
@@ -176,6 +234,7 @@ impl SchedulerService {
                         super::super::training_job::models::TrainingJobStatus::Cancelled,
                     )
                     .await?;
+                self.publish_job_cancelled(&job).await;
             }
         }
         Ok(())
@@ -198,12 +257,22 @@ impl SchedulerService {
         }
 
         let queues = self.queue_repo.get_all_queues_sorted().await?;
+        let quotas: HashMap<_, _> = self.quota_repo.list_quotas().await?.into_iter().collect();
+        let mut ledger = load_quota_ledger(self.job_repo.as_ref(), Utc::now()).await?;
 
         info!("Processing {} queues", queues.len());
 
         for queue in queues {
             let queued_jobs = self.job_repo.get_queued_jobs_for_queue(&queue.id).await?;
 
+            if let Err(e) = self
+                .notification_service
+                .check_queue_backlog(&queue, queued_jobs.len())
+                .await
+            {
+                error!("Error checking backlog of queue '{}': {}", queue.name, e);
+            }
+
             if queued_jobs.is_empty() {
                 continue;
             }
@@ -216,17 +285,39 @@ impl SchedulerService {
 
             for job in queued_jobs {
                 info!("Processing job {}", job.id);
+
+                if let Some(reason) = ledger.check(&job, &quotas) {
+                    info!("Holding job {}: {}", job.id, reason);
+                    if job.pending_reason.as_ref() != Some(&reason) {
+                        self.job_repo
+                            .set_pending_reason(&job.id, Some(reason))
+                            .await?;
+                    }
+                    continue;
+                }
+                if job.pending_reason.is_some() {
+                    self.job_repo.set_pending_reason(&job.id, None).await?;
+                }
+
                 let mut scheduled = false;
 
                 for cluster_id in &queue.cluster_targets {
                     match self
                         .agent_adapter
-                        .find_and_allocate_job(&job.id, cluster_id, &job.resource_requirements)
+                        .find_and_allocate_job(
+                            &job.id,
+                            cluster_id,
+                            &job.resource_requirements,
+                            &job.definition,
+                        )
                         .await
                     {
                         Ok(Some(node_id)) => {
                             info!("Successfully allocated job {} to node {}", job.id, node_id);
-                            self.job_repo.mark_as_starting(&job.id, &node_id).await?;
+                            self.job_repo
+                                .mark_as_starting(&job.id, &node_id, &generate_job_token())
+                                .await?;
+                            ledger.add_job(&job);
                             scheduled = true;
                             break; // Break from cluster loop, move to next job
                         }
@@ -0,0 +1,385 @@
+pub mod models;
+pub mod ports;
+pub mod service;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        models::{
+            CreateSweepRequest, EarlyStopping, Goal, ParameterSpace, SearchStrategy, Sweep,
+            SweepStatus, Trial, TrialStatus,
+        },
+        ports::MockSweepRepository,
+        service::{SweepService, SweepServiceError, SweepServiceImpl},
+    };
+    use crate::domain::{
+        training_job::{
+            models::{CreatedTrainingJob, JobId, JobMetric, TrainingJob, TrainingJobStatus},
+            ports::MockTrainingJobRepository,
+            service::MockTrainingJobService,
+        },
+        user::models::UserId,
+    };
+    use mockall::predicate::*;
+    use serde_json::{json, Value};
+    use std::{
+        collections::{BTreeMap, HashSet},
+        sync::Arc,
+    };
+
+    fn trial(sweep: &Sweep, number: i32, parameters: BTreeMap<String, Value>) -> Trial {
+        Trial {
+            sweep_id: sweep.id,
+            number,
+            job_id: JobId::generate(),
+            parameters,
+            status: TrialStatus::Running,
+            objective_value: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn metric(job_id: JobId, step: i64, value: f64) -> JobMetric {
+        JobMetric {
+            job_id,
+            name: "val_loss".to_string(),
+            step,
+            value,
+            reported_at: chrono::Utc::now(),
+        }
+    }
+
+    fn create_request() -> CreateSweepRequest {
+        let sweep = Sweep::new_mock();
+        CreateSweepRequest {
+            name: sweep.name,
+            definition: sweep.definition,
+            queue_id: sweep.queue_id,
+            resource_requirements: sweep.resource_requirements,
+            env: sweep.env,
+            labels: sweep.labels,
+            parameters: sweep.parameters,
+            objective: sweep.objective,
+            strategy: sweep.strategy,
+            early_stopping: sweep.early_stopping,
+            max_trials: sweep.max_trials,
+            max_parallel: sweep.max_parallel,
+        }
+    }
+
+    #[test]
+    fn test_grid_search_tries_every_combination_once() {
+        let sweep = Sweep {
+            strategy: SearchStrategy::Grid,
+            parameters: BTreeMap::from([
+                (
+                    "optimizer".to_string(),
+                    ParameterSpace::Categorical {
+                        values: vec![json!("adam"), json!("sgd")],
+                    },
+                ),
+                ("layers".to_string(), ParameterSpace::Int { min: 1, max: 3 }),
+            ]),
+            max_trials: 100,
+            ..Sweep::new_mock()
+        };
+
+        assert_eq!(sweep.trial_limit(), 6);
+        let points = (0..6)
+            .map(|number| serde_json::to_string(&sweep.suggest(number, &[])).unwrap())
+            .collect::<HashSet<_>>();
+        assert_eq!(points.len(), 6);
+    }
+
+    #[test]
+    fn test_random_search_is_repeatable_and_in_bounds() {
+        let sweep = Sweep::new_mock();
+
+        for number in 0..20 {
+            let point = sweep.suggest(number, &[]);
+            let lr = point["lr"].as_f64().unwrap();
+            assert!((1e-5..=1e-1).contains(&lr));
+            assert_eq!(point, sweep.suggest(number, &[]));
+        }
+    }
+
+    #[test]
+    fn test_tpe_favors_values_near_the_best_trials() {
+        let sweep = Sweep {
+            strategy: SearchStrategy::Tpe,
+            parameters: BTreeMap::from([(
+                "x".to_string(),
+                ParameterSpace::Uniform {
+                    min: 0.0,
+                    max: 10.0,
+                },
+            )]),
+            ..Sweep::new_mock()
+        };
+        let trials = (0..12)
+            .map(|number| {
+                let x = number as f64 * 10.0 / 11.0;
+                Trial {
+                    status: TrialStatus::Succeeded,
+                    objective_value: Some((x - 2.0).powi(2)),
+                    ..trial(
+                        &sweep,
+                        number,
+                        BTreeMap::from([("x".to_string(), json!(x))]),
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mean = (12..32)
+            .map(|number| sweep.suggest(number, &trials)["x"].as_f64().unwrap())
+            .sum::<f64>()
+            / 20.0;
+
+        assert!((mean - 2.0).abs() < 1.5, "mean suggestion was {mean}");
+    }
+
+    #[test]
+    fn test_median_stopping() {
+        let early_stopping = EarlyStopping::Median {
+            min_steps: 2,
+            min_trials: 2,
+        };
+        let others: Vec<&[(i64, f64)]> =
+            vec![&[(1, 0.5), (2, 0.3), (3, 0.2)], &[(1, 0.6), (2, 0.4)]];
+
+        // Worse than both running averages at step 2 (0.4 and 0.5).
+        assert!(early_stopping.should_stop(Goal::Minimize, &[(1, 0.9), (2, 0.8)], &others));
+        assert!(!early_stopping.should_stop(Goal::Minimize, &[(1, 0.5), (2, 0.35)], &others));
+        // Too early to judge.
+        assert!(!early_stopping.should_stop(Goal::Minimize, &[(1, 0.9)], &others));
+        // Only one other trial has got as far.
+        assert!(!early_stopping.should_stop(
+            Goal::Minimize,
+            &[(1, 0.9), (2, 0.9), (3, 0.9)],
+            &others
+        ));
+    }
+
+    #[test]
+    fn test_hyperband_keeps_the_best_trials_at_each_rung() {
+        let early_stopping = EarlyStopping::Hyperband {
+            min_steps: 2,
+            max_steps: 8,
+            reduction_factor: 2,
+        };
+        let others: Vec<&[(i64, f64)]> = vec![&[(2, 0.9)], &[(2, 0.7), (4, 0.6)], &[(2, 0.8)]];
+
+        // Second best of four at the rung at step 2, so it continues.
+        assert!(!early_stopping.should_stop(Goal::Maximize, &[(2, 0.85), (3, 0.8)], &others));
+        assert!(early_stopping.should_stop(Goal::Maximize, &[(2, 0.75), (3, 0.8)], &others));
+        // Not at a rung yet.
+        assert!(!early_stopping.should_stop(Goal::Maximize, &[(1, 0.1)], &others));
+    }
+
+    #[tokio::test]
+    async fn test_create_sweep_submits_first_trials() {
+        let owner_id = UserId::generate();
+        let mut mock_repo = MockSweepRepository::new();
+        let created = Arc::new(std::sync::Mutex::new(None::<Sweep>));
+        let stored = created.clone();
+        mock_repo
+            .expect_create_sweep()
+            .times(1)
+            .returning(move |sweep| {
+                *stored.lock().unwrap() = Some(sweep.clone());
+                Ok(())
+            });
+        mock_repo.expect_list_trials().returning(|_| Ok(vec![]));
+        mock_repo
+            .expect_create_trial()
+            .withf(|trial| trial.parameters.contains_key("lr"))
+            .times(2)
+            .returning(|_| Ok(()));
+        let stored = created.clone();
+        mock_repo
+            .expect_get_sweep_by_id()
+            .returning(move |_| Ok(stored.lock().unwrap().clone().unwrap()));
+
+        let mut mock_job_repo = MockTrainingJobRepository::new();
+        mock_job_repo
+            .expect_get_metric_for_jobs()
+            .returning(|_, _| Ok(vec![]));
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_create()
+            .withf(move |request, user_id| {
+                *user_id == owner_id
+                    && request.env.contains_key("LILAC_PARAM_LR")
+                    && request.env.contains_key("LILAC_PARAMS")
+                    && request.labels.contains_key("lilac.sweep")
+            })
+            .times(2)
+            .returning(|_, _| {
+                Ok(CreatedTrainingJob {
+                    job: TrainingJob::new_mock(),
+                    warnings: vec![],
+                })
+            });
+
+        let service = SweepServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_job_repo),
+            Arc::new(mock_job_service),
+        );
+        let details = service
+            .create_sweep(create_request(), &owner_id)
+            .await
+            .unwrap();
+
+        assert_eq!(details.sweep.user_id, owner_id);
+    }
+
+    #[tokio::test]
+    async fn test_create_grid_sweep_with_continuous_parameter() {
+        let service = SweepServiceImpl::new(
+            Arc::new(MockSweepRepository::new()),
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockTrainingJobService::new()),
+        );
+        let request = CreateSweepRequest {
+            strategy: SearchStrategy::Grid,
+            ..create_request()
+        };
+
+        let result = service.create_sweep(request, &UserId::generate()).await;
+
+        assert!(matches!(result, Err(SweepServiceError::InvalidSweep(_))));
+    }
+
+    #[tokio::test]
+    async fn test_advance_sweeps_stops_underperformers_and_submits_new_trials() {
+        let sweep = Sweep {
+            early_stopping: Some(EarlyStopping::Median {
+                min_steps: 1,
+                min_trials: 1,
+            }),
+            ..Sweep::new_mock()
+        };
+        let behind = trial(&sweep, 0, BTreeMap::new());
+        let ahead = trial(&sweep, 1, BTreeMap::new());
+        let behind_job_id = behind.job_id;
+        let ahead_job_id = ahead.job_id;
+
+        let mut mock_repo = MockSweepRepository::new();
+        let running = vec![sweep.clone()];
+        mock_repo
+            .expect_list_sweeps()
+            .with(eq(Some(SweepStatus::Running)))
+            .returning(move |_| Ok(running.clone()));
+        let trials = vec![behind, ahead];
+        mock_repo
+            .expect_list_trials()
+            .returning(move |_| Ok(trials.clone()));
+        mock_repo
+            .expect_update_trial()
+            .withf(|trial| trial.status == TrialStatus::Running && trial.objective_value.is_some())
+            .times(2)
+            .returning(|_| Ok(()));
+        mock_repo
+            .expect_update_trial()
+            .withf(move |trial| {
+                trial.job_id == behind_job_id && trial.status == TrialStatus::Stopped
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_repo
+            .expect_create_trial()
+            .withf(|trial| trial.number == 2)
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_repo.expect_update_sweep_status().never();
+
+        let mut mock_job_repo = MockTrainingJobRepository::new();
+        mock_job_repo
+            .expect_get_metric_for_jobs()
+            .returning(move |_, _| {
+                Ok(vec![
+                    metric(behind_job_id, 1, 0.9),
+                    metric(ahead_job_id, 1, 0.5),
+                    metric(behind_job_id, 2, 0.8),
+                    metric(ahead_job_id, 2, 0.3),
+                ])
+            });
+        mock_job_repo
+            .expect_get_training_job_by_id()
+            .returning(|id| {
+                Ok(TrainingJob {
+                    id: *id,
+                    status: TrainingJobStatus::Running,
+                    ..TrainingJob::new_mock()
+                })
+            });
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_cancel()
+            .with(eq(behind_job_id))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_job_service.expect_create().times(1).returning(|_, _| {
+            Ok(CreatedTrainingJob {
+                job: TrainingJob::new_mock(),
+                warnings: vec![],
+            })
+        });
+
+        let service = SweepServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_job_repo),
+            Arc::new(mock_job_service),
+        );
+
+        service.advance_sweeps().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_advance_sweeps_completes_finished_sweep() {
+        let sweep = Sweep {
+            max_trials: 2,
+            ..Sweep::new_mock()
+        };
+        let sweep_id = sweep.id;
+        let trials = (0..2)
+            .map(|number| Trial {
+                status: TrialStatus::Succeeded,
+                objective_value: Some(0.5),
+                ..trial(&sweep, number, BTreeMap::new())
+            })
+            .collect::<Vec<_>>();
+
+        let mut mock_repo = MockSweepRepository::new();
+        let running = vec![sweep];
+        mock_repo
+            .expect_list_sweeps()
+            .returning(move |_| Ok(running.clone()));
+        mock_repo
+            .expect_list_trials()
+            .returning(move |_| Ok(trials.clone()));
+        mock_repo
+            .expect_update_sweep_status()
+            .with(eq(sweep_id), eq(SweepStatus::Completed))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut mock_job_repo = MockTrainingJobRepository::new();
+        mock_job_repo
+            .expect_get_metric_for_jobs()
+            .returning(|_, _| Ok(vec![]));
+
+        let service = SweepServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_job_repo),
+            Arc::new(MockTrainingJobService::new()),
+        );
+
+        service.advance_sweeps().await.unwrap();
+    }
+}
@@ -0,0 +1,687 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    domain::{
+        queue::models::QueueId,
+        training_job::models::{JobId, ResourceRequirements, TrainingJobStatus},
+        user::models::UserId,
+    },
+    identifier,
+};
+
+identifier!(SweepId);
+
+/// The values a hyperparameter can take.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParameterSpace {
+    /// One of a fixed list of values, such as optimizer names.
+    Categorical { values: Vec<Value> },
+    /// A float drawn uniformly from `[min, max]`.
+    Uniform { min: f64, max: f64 },
+    /// A float whose logarithm is uniform over `[ln min, ln max]`, for
+    /// parameters such as learning rates that span orders of magnitude.
+    LogUniform { min: f64, max: f64 },
+    /// An integer from `[min, max]`, both inclusive.
+    Int { min: i64, max: i64 },
+}
+
+impl ParameterSpace {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Categorical { values } if values.is_empty() => {
+                Err("categorical parameters need at least one value".to_string())
+            }
+            Self::Uniform { min, max } if !(min.is_finite() && max.is_finite() && min < max) => {
+                Err("uniform parameters need finite bounds with min < max".to_string())
+            }
+            Self::LogUniform { min, max } if !(max.is_finite() && *min > 0.0 && min < max) => {
+                Err("log-uniform parameters need finite bounds with 0 < min < max".to_string())
+            }
+            Self::Int { min, max } if min > max => {
+                Err("int parameters need min <= max".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The number of distinct values, or None for continuous spaces.
+    pub fn grid_size(&self) -> Option<usize> {
+        match self {
+            Self::Categorical { values } => Some(values.len()),
+            Self::Int { min, max } => usize::try_from(max.abs_diff(*min)).ok()?.checked_add(1),
+            Self::Uniform { .. } | Self::LogUniform { .. } => None,
+        }
+    }
+
+    fn grid_value(&self, index: usize) -> Value {
+        match self {
+            Self::Categorical { values } => values[index].clone(),
+            Self::Int { min, .. } => Value::from(min + index as i64),
+            Self::Uniform { .. } | Self::LogUniform { .. } => {
+                unreachable!("continuous parameters are rejected for grid search")
+            }
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Value {
+        match self {
+            Self::Categorical { values } => values[rng.random_range(0..values.len())].clone(),
+            Self::Int { min, max } => Value::from(rng.random_range(*min..=*max)),
+            Self::Uniform { .. } | Self::LogUniform { .. } => {
+                let (low, high) = self.bounds();
+                self.value_from_internal(rng.random_range(low..=high))
+            }
+        }
+    }
+
+    /// The bounds of the space that TPE models numeric parameters in: log
+    /// scale for log-uniform parameters, and widened by half a step on either
+    /// side for integers so that every integer gets the same share.
+    fn bounds(&self) -> (f64, f64) {
+        match self {
+            Self::Uniform { min, max } => (*min, *max),
+            Self::LogUniform { min, max } => (min.ln(), max.ln()),
+            Self::Int { min, max } => (*min as f64 - 0.5, *max as f64 + 0.5),
+            Self::Categorical { .. } => unreachable!("categorical parameters have no bounds"),
+        }
+    }
+
+    fn to_internal(&self, value: &Value) -> Option<f64> {
+        let value = value.as_f64()?;
+        match self {
+            Self::LogUniform { .. } => Some(value.ln()),
+            _ => Some(value),
+        }
+    }
+
+    fn value_from_internal(&self, x: f64) -> Value {
+        match self {
+            Self::Uniform { min, max } => Value::from(x.clamp(*min, *max)),
+            Self::LogUniform { min, max } => Value::from(x.exp().clamp(*min, *max)),
+            Self::Int { min, max } => Value::from((x.round() as i64).clamp(*min, *max)),
+            Self::Categorical { .. } => unreachable!("categorical parameters have no bounds"),
+        }
+    }
+}
+
+/// How the parameters of each new trial are chosen.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchStrategy {
+    /// Tries every combination of values in turn. Only categorical and int
+    /// parameters can be searched this way.
+    Grid,
+    /// Draws every parameter independently and uniformly.
+    Random,
+    /// Tree-structured Parzen Estimator: once a few trials have finished,
+    /// favors values that are more common among the best trials than among
+    /// the rest.
+    Tpe,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Goal {
+    Minimize,
+    Maximize,
+}
+
+impl Goal {
+    /// Whether `a` is strictly better than `b`.
+    pub fn is_better(&self, a: f64, b: f64) -> bool {
+        match self {
+            Self::Minimize => a < b,
+            Self::Maximize => a > b,
+        }
+    }
+
+    pub fn best(&self, values: impl IntoIterator<Item = f64>) -> Option<f64> {
+        values.into_iter().reduce(|best, value| {
+            if self.is_better(value, best) {
+                value
+            } else {
+                best
+            }
+        })
+    }
+
+    /// Orders values from best to worst.
+    fn order(&self, a: f64, b: f64) -> std::cmp::Ordering {
+        match self {
+            Self::Minimize => a.total_cmp(&b),
+            Self::Maximize => b.total_cmp(&a),
+        }
+    }
+}
+
+/// The metric that trials report and the sweep optimizes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Objective {
+    pub metric: String,
+    pub goal: Goal,
+}
+
+/// The values of a trial's objective metric, as `(step, value)` pairs
+/// ordered by step.
+pub type Curve = [(i64, f64)];
+
+/// When to stop trials that are unlikely to beat the others.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EarlyStopping {
+    /// Stops a trial once its best value is worse than the median of the
+    /// running averages of the other trials that got as far.
+    Median {
+        /// Trials are never stopped before reporting this step.
+        #[serde(default)]
+        min_steps: i64,
+        /// How many other trials must have reached the same step first.
+        #[serde(default = "EarlyStopping::default_min_trials")]
+        min_trials: usize,
+    },
+    /// Asynchronous successive halving, the building block of Hyperband.
+    /// Trials are compared at rungs at `min_steps`, `min_steps *
+    /// reduction_factor`, ... below `max_steps`, and only the best
+    /// `1 / reduction_factor` of the trials at a rung continue past it.
+    Hyperband {
+        min_steps: i64,
+        max_steps: i64,
+        #[serde(default = "EarlyStopping::default_reduction_factor")]
+        reduction_factor: i64,
+    },
+}
+
+impl EarlyStopping {
+    fn default_min_trials() -> usize {
+        3
+    }
+
+    fn default_reduction_factor() -> i64 {
+        3
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Median {
+                min_steps,
+                min_trials,
+            } => {
+                if *min_steps < 0 {
+                    return Err("min_steps must not be negative".to_string());
+                }
+                if *min_trials == 0 {
+                    return Err("min_trials must be greater than 0".to_string());
+                }
+            }
+            Self::Hyperband {
+                min_steps,
+                max_steps,
+                reduction_factor,
+            } => {
+                if *min_steps <= 0 {
+                    return Err("min_steps must be greater than 0".to_string());
+                }
+                if max_steps <= min_steps {
+                    return Err("max_steps must be greater than min_steps".to_string());
+                }
+                if *reduction_factor < 2 {
+                    return Err("reduction_factor must be at least 2".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a trial whose objective has followed `curve` should be
+    /// stopped, given the curves of the other trials of the sweep.
+    pub fn should_stop(&self, goal: Goal, curve: &Curve, others: &[&Curve]) -> bool {
+        let Some(&(step, _)) = curve.last() else {
+            return false;
+        };
+
+        match *self {
+            Self::Median {
+                min_steps,
+                min_trials,
+            } => {
+                if step < min_steps {
+                    return false;
+                }
+                let Some(best) = best_until(goal, curve, step) else {
+                    return false;
+                };
+                let mut averages = others
+                    .iter()
+                    .filter(|other| reached(other, step))
+                    .filter_map(|other| average_until(other, step))
+                    .collect::<Vec<_>>();
+                if averages.len() < min_trials {
+                    return false;
+                }
+                averages.sort_by(f64::total_cmp);
+                let mid = averages.len() / 2;
+                let median = if averages.len() % 2 == 0 {
+                    (averages[mid - 1] + averages[mid]) / 2.0
+                } else {
+                    averages[mid]
+                };
+                goal.is_better(median, best)
+            }
+            Self::Hyperband {
+                min_steps,
+                max_steps,
+                reduction_factor,
+            } => {
+                let mut rung = None;
+                let mut next = min_steps;
+                while next <= step && next < max_steps {
+                    rung = Some(next);
+                    next = next.saturating_mul(reduction_factor);
+                }
+                let Some(rung) = rung else {
+                    return false;
+                };
+                let Some(own) = best_until(goal, curve, rung) else {
+                    return false;
+                };
+                let mut values = others
+                    .iter()
+                    .filter(|other| reached(other, rung))
+                    .filter_map(|other| best_until(goal, other, rung))
+                    .collect::<Vec<_>>();
+                values.push(own);
+                // Too few trials at the rung to single any of them out yet.
+                let reduction_factor = reduction_factor as usize;
+                if values.len() < reduction_factor {
+                    return false;
+                }
+                values.sort_by(|a, b| goal.order(*a, *b));
+                let keep = values.len() / reduction_factor;
+                goal.is_better(values[keep - 1], own)
+            }
+        }
+    }
+}
+
+fn reached(curve: &Curve, step: i64) -> bool {
+    curve.last().is_some_and(|&(last, _)| last >= step)
+}
+
+fn best_until(goal: Goal, curve: &Curve, step: i64) -> Option<f64> {
+    goal.best(
+        curve
+            .iter()
+            .take_while(|(s, _)| *s <= step)
+            .map(|(_, value)| *value),
+    )
+}
+
+fn average_until(curve: &Curve, step: i64) -> Option<f64> {
+    let values = curve
+        .iter()
+        .take_while(|(s, _)| *s <= step)
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepStatus {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+/// A search over hyperparameters, run as a series of training jobs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sweep {
+    pub id: SweepId,
+    pub name: String,
+    pub user_id: UserId,
+    pub queue_id: QueueId,
+    /// The container image every trial runs.
+    pub definition: String,
+    pub resource_requirements: ResourceRequirements,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    pub parameters: BTreeMap<String, ParameterSpace>,
+    pub objective: Objective,
+    pub strategy: SearchStrategy,
+    pub early_stopping: Option<EarlyStopping>,
+    pub max_trials: i32,
+    /// How many trials may be queued or running at once.
+    pub max_parallel: i32,
+    pub status: SweepStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How many finished trials TPE needs before it stops drawing at random.
+const TPE_STARTUP_TRIALS: usize = 5;
+/// The share of finished trials that TPE considers good.
+const TPE_GOOD_FRACTION: f64 = 0.25;
+/// How many candidates TPE draws for each parameter before picking one.
+const TPE_CANDIDATES: usize = 24;
+
+impl Sweep {
+    /// How many trials the sweep runs in total. Grid searches stop early
+    /// once every combination has been tried.
+    pub fn trial_limit(&self) -> i32 {
+        match self.strategy {
+            SearchStrategy::Grid => {
+                let size = grid_size(&self.parameters).unwrap_or(usize::MAX);
+                self.max_trials.min(i32::try_from(size).unwrap_or(i32::MAX))
+            }
+            SearchStrategy::Random | SearchStrategy::Tpe => self.max_trials,
+        }
+    }
+
+    /// Chooses the parameters of trial `number`, given the trials so far.
+    /// The choice only depends on the sweep, the number and the finished
+    /// trials, so it can be repeated.
+    pub fn suggest(&self, number: i32, trials: &[Trial]) -> BTreeMap<String, Value> {
+        let mut rng = StdRng::seed_from_u64(seed(&self.id, number));
+        match self.strategy {
+            SearchStrategy::Grid => grid_point(&self.parameters, number as usize),
+            SearchStrategy::Random => random_point(&self.parameters, &mut rng),
+            SearchStrategy::Tpe => {
+                tpe_point(&self.parameters, self.objective.goal, trials, &mut rng)
+            }
+        }
+    }
+
+    /// The finished or running trial with the best objective value so far.
+    pub fn best_trial<'a>(&self, trials: &'a [Trial]) -> Option<&'a Trial> {
+        trials
+            .iter()
+            .filter(|trial| trial.objective_value.is_some())
+            .min_by(|a, b| {
+                self.objective
+                    .goal
+                    .order(a.objective_value.unwrap(), b.objective_value.unwrap())
+            })
+    }
+}
+
+/// The number of combinations of a grid search, or None if a parameter is
+/// continuous.
+pub fn grid_size(parameters: &BTreeMap<String, ParameterSpace>) -> Option<usize> {
+    parameters.values().try_fold(1usize, |size, space| {
+        Some(size.saturating_mul(space.grid_size()?))
+    })
+}
+
+fn seed(id: &SweepId, number: i32) -> u64 {
+    let bytes = id.inner().as_bytes();
+    let high = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+    let low = u64::from_le_bytes(bytes[8..].try_into().unwrap());
+    (high ^ low) ^ (number as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+/// Decodes `index` as a mixed-radix number, with one digit per parameter.
+fn grid_point(
+    parameters: &BTreeMap<String, ParameterSpace>,
+    mut index: usize,
+) -> BTreeMap<String, Value> {
+    parameters
+        .iter()
+        .map(|(name, space)| {
+            let size = space.grid_size().unwrap_or(1);
+            let value = space.grid_value(index % size);
+            index /= size;
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+fn random_point(
+    parameters: &BTreeMap<String, ParameterSpace>,
+    rng: &mut StdRng,
+) -> BTreeMap<String, Value> {
+    parameters
+        .iter()
+        .map(|(name, space)| (name.clone(), space.sample(rng)))
+        .collect()
+}
+
+fn tpe_point(
+    parameters: &BTreeMap<String, ParameterSpace>,
+    goal: Goal,
+    trials: &[Trial],
+    rng: &mut StdRng,
+) -> BTreeMap<String, Value> {
+    let mut finished = trials
+        .iter()
+        .filter(|trial| trial.status != TrialStatus::Running)
+        .filter_map(|trial| Some((trial.objective_value?, &trial.parameters)))
+        .collect::<Vec<_>>();
+    if finished.len() < TPE_STARTUP_TRIALS {
+        return random_point(parameters, rng);
+    }
+
+    finished.sort_by(|a, b| goal.order(a.0, b.0));
+    let good_count = ((finished.len() as f64 * TPE_GOOD_FRACTION).ceil() as usize).max(1);
+    let (good, bad) = finished.split_at(good_count);
+
+    parameters
+        .iter()
+        .map(|(name, space)| {
+            let good = good
+                .iter()
+                .filter_map(|(_, params)| params.get(name))
+                .collect::<Vec<_>>();
+            let bad = bad
+                .iter()
+                .filter_map(|(_, params)| params.get(name))
+                .collect::<Vec<_>>();
+            let value = match space {
+                ParameterSpace::Categorical { values } => tpe_categorical(values, &good, &bad, rng),
+                _ => tpe_numeric(space, &good, &bad, rng),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Picks the value whose smoothed frequency among the good trials is
+/// highest relative to its frequency among the bad ones, among candidates
+/// drawn from the good trials' distribution.
+fn tpe_categorical(values: &[Value], good: &[&Value], bad: &[&Value], rng: &mut StdRng) -> Value {
+    let weights = |observed: &[&Value]| {
+        values
+            .iter()
+            .map(|value| {
+                let count = observed.iter().filter(|seen| **seen == value).count();
+                (count as f64 + 1.0) / (observed.len() + values.len()) as f64
+            })
+            .collect::<Vec<_>>()
+    };
+    let good_weights = weights(good);
+    let bad_weights = weights(bad);
+
+    (0..TPE_CANDIDATES)
+        .map(|_| {
+            let mut target = rng.random::<f64>();
+            let mut index = values.len() - 1;
+            for (i, weight) in good_weights.iter().enumerate() {
+                if target < *weight {
+                    index = i;
+                    break;
+                }
+                target -= weight;
+            }
+            index
+        })
+        .max_by(|a, b| {
+            (good_weights[*a] / bad_weights[*a]).total_cmp(&(good_weights[*b] / bad_weights[*b]))
+        })
+        .map(|index| values[index].clone())
+        .unwrap_or_else(|| values[0].clone())
+}
+
+/// Draws candidates from a Parzen estimator over the good trials' values
+/// and keeps the one most likely under it relative to the estimator over
+/// the bad trials' values.
+fn tpe_numeric(space: &ParameterSpace, good: &[&Value], bad: &[&Value], rng: &mut StdRng) -> Value {
+    let (low, high) = space.bounds();
+    let good = good
+        .iter()
+        .filter_map(|value| space.to_internal(value))
+        .collect::<Vec<_>>();
+    let bad = bad
+        .iter()
+        .filter_map(|value| space.to_internal(value))
+        .collect::<Vec<_>>();
+    let good_width = bandwidth(low, high, good.len());
+    let bad_width = bandwidth(low, high, bad.len());
+
+    (0..TPE_CANDIDATES)
+        .map(|_| {
+            // Every observation is a component of the mixture, and so is a
+            // uniform prior over the whole space.
+            let component = rng.random_range(0..=good.len());
+            match good.get(component) {
+                Some(center) => (center + good_width * standard_normal(rng)).clamp(low, high),
+                None => rng.random_range(low..=high),
+            }
+        })
+        .map(|x| {
+            let score = parzen_density(x, &good, good_width, low, high)
+                / parzen_density(x, &bad, bad_width, low, high);
+            (x, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(x, _)| space.value_from_internal(x))
+        .unwrap_or_else(|| space.value_from_internal((low + high) / 2.0))
+}
+
+/// A kernel width that shrinks as observations accumulate.
+fn bandwidth(low: f64, high: f64, observations: usize) -> f64 {
+    (high - low) / (2.0 * (observations as f64 + 1.0).sqrt())
+}
+
+fn parzen_density(x: f64, centers: &[f64], width: f64, low: f64, high: f64) -> f64 {
+    let kernels = centers
+        .iter()
+        .map(|center| {
+            let z = (x - center) / width;
+            (-0.5 * z * z).exp() / (width * (2.0 * std::f64::consts::PI).sqrt())
+        })
+        .sum::<f64>();
+    (kernels + 1.0 / (high - low)) / (centers.len() as f64 + 1.0)
+}
+
+/// Box-Muller transform.
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1 = 1.0 - rng.random::<f64>();
+    let u2 = rng.random::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrialStatus {
+    /// The trial's job is queued or running.
+    Running,
+    Succeeded,
+    Failed,
+    /// The trial's job was cancelled, by early stopping or by hand.
+    Stopped,
+}
+
+impl From<&TrainingJobStatus> for TrialStatus {
+    fn from(status: &TrainingJobStatus) -> Self {
+        match status {
+            TrainingJobStatus::Queued
+            | TrainingJobStatus::Starting
+            | TrainingJobStatus::Running => Self::Running,
+            TrainingJobStatus::Succeeded => Self::Succeeded,
+            TrainingJobStatus::Failed => Self::Failed,
+            TrainingJobStatus::Cancelled => Self::Stopped,
+        }
+    }
+}
+
+/// One training job of a sweep, with the parameters it was given.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Trial {
+    pub sweep_id: SweepId,
+    /// Numbers start at 0 and increase by one with each trial.
+    pub number: i32,
+    pub job_id: JobId,
+    pub parameters: BTreeMap<String, Value>,
+    pub status: TrialStatus,
+    /// The best value of the objective metric the trial has reported.
+    pub objective_value: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A sweep along with all of its trials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepDetails {
+    pub sweep: Sweep,
+    pub trials: Vec<Trial>,
+}
+
+/// DTO for creating a new sweep.
+#[derive(Debug, Clone)]
+pub struct CreateSweepRequest {
+    pub name: String,
+    pub definition: String,
+    pub queue_id: QueueId,
+    pub resource_requirements: ResourceRequirements,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    pub parameters: BTreeMap<String, ParameterSpace>,
+    pub objective: Objective,
+    pub strategy: SearchStrategy,
+    pub early_stopping: Option<EarlyStopping>,
+    pub max_trials: i32,
+    pub max_parallel: i32,
+}
+
+#[cfg(test)]
+impl Sweep {
+    pub fn new_mock() -> Self {
+        Self {
+            id: SweepId::generate(),
+            name: "lr-search".to_string(),
+            user_id: UserId::generate(),
+            queue_id: QueueId::generate(),
+            definition: "ghcr.io/acme/train:latest".to_string(),
+            resource_requirements: ResourceRequirements {
+                cpu_millicores: 1000,
+                memory_mb: 1024,
+                gpus: None,
+            },
+            env: HashMap::new(),
+            labels: HashMap::new(),
+            parameters: BTreeMap::from([(
+                "lr".to_string(),
+                ParameterSpace::LogUniform {
+                    min: 1e-5,
+                    max: 1e-1,
+                },
+            )]),
+            objective: Objective {
+                metric: "val_loss".to_string(),
+                goal: Goal::Minimize,
+            },
+            strategy: SearchStrategy::Random,
+            early_stopping: None,
+            max_trials: 4,
+            max_parallel: 2,
+            status: SweepStatus::Running,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
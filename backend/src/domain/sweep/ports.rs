@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+
+use super::models::{Sweep, SweepId, SweepStatus, Trial};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SweepRepositoryError {
+    #[error("sweep {0} not found")]
+    NotFound(String),
+    #[error("queue {0} not found")]
+    QueueNotFound(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait SweepRepository: Send + Sync {
+    async fn create_sweep(&self, sweep: &Sweep) -> Result<(), SweepRepositoryError>;
+    async fn get_sweep_by_id(&self, id: &SweepId) -> Result<Sweep, SweepRepositoryError>;
+    /// Lists all sweeps, or only those with `status`, newest first.
+    async fn list_sweeps(
+        &self,
+        status: Option<SweepStatus>,
+    ) -> Result<Vec<Sweep>, SweepRepositoryError>;
+    async fn update_sweep_status(
+        &self,
+        id: &SweepId,
+        status: SweepStatus,
+    ) -> Result<(), SweepRepositoryError>;
+    async fn delete_sweep(&self, id: &SweepId) -> Result<(), SweepRepositoryError>;
+    async fn create_trial(&self, trial: &Trial) -> Result<(), SweepRepositoryError>;
+    /// Lists the trials of a sweep, ordered by number.
+    async fn list_trials(&self, sweep_id: &SweepId) -> Result<Vec<Trial>, SweepRepositoryError>;
+    /// Stores a trial's status and objective value.
+    async fn update_trial(&self, trial: &Trial) -> Result<(), SweepRepositoryError>;
+}
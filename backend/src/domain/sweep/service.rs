@@ -0,0 +1,405 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{
+    models::{
+        grid_size, CreateSweepRequest, SearchStrategy, Sweep, SweepDetails, SweepId, SweepStatus,
+        Trial, TrialStatus,
+    },
+    ports::{SweepRepository, SweepRepositoryError},
+};
+use crate::{
+    domain::{
+        training_job::{
+            models::{validate_env, validate_labels, validate_metric_name, JobId, JobKind},
+            ports::{TrainingJobRepository, TrainingJobRepositoryError},
+            service::{TrainingJobService, TrainingJobServiceError},
+        },
+        user::models::UserId,
+    },
+    inbound::http::routes::training_jobs::models::CreateTrainingJobRequest,
+};
+
+/// The most trials a single sweep can run.
+const MAX_TRIALS: i32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum SweepServiceError {
+    #[error("sweep {0} not found")]
+    SweepNotFound(String),
+    #[error("invalid sweep: {0}")]
+    InvalidSweep(String),
+    #[error("sweep {0} is not running")]
+    NotRunning(String),
+    #[error("user does not have permission to perform this action")]
+    InvalidPermissions,
+    #[error(transparent)]
+    TrainingJob(#[from] TrainingJobServiceError),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl From<SweepRepositoryError> for SweepServiceError {
+    fn from(err: SweepRepositoryError) -> Self {
+        match err {
+            SweepRepositoryError::NotFound(id) => SweepServiceError::SweepNotFound(id),
+            SweepRepositoryError::QueueNotFound(id) => {
+                SweepServiceError::InvalidSweep(format!("queue {id} does not exist"))
+            }
+            SweepRepositoryError::Unknown(err) => SweepServiceError::Unknown(err),
+        }
+    }
+}
+
+impl From<TrainingJobRepositoryError> for SweepServiceError {
+    fn from(err: TrainingJobRepositoryError) -> Self {
+        SweepServiceError::TrainingJob(err.into())
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait SweepService: Send + Sync {
+    /// Stores the sweep and submits its first trials.
+    async fn create_sweep(
+        &self,
+        request: CreateSweepRequest,
+        user_id: &UserId,
+    ) -> Result<SweepDetails, SweepServiceError>;
+    async fn list_sweeps(&self) -> Result<Vec<Sweep>, SweepServiceError>;
+    async fn get_sweep(&self, id: &SweepId) -> Result<SweepDetails, SweepServiceError>;
+    /// Stops the sweep and cancels its running trials. Only the sweep's
+    /// owner can cancel it.
+    async fn cancel_sweep(&self, id: &SweepId, user_id: &UserId) -> Result<(), SweepServiceError>;
+    /// Moves every running sweep forward: records how its trials are doing,
+    /// stops the ones that early stopping gives up on, submits new trials in
+    /// their place and completes the sweep once it has run them all.
+    async fn advance_sweeps(&self) -> Result<(), SweepServiceError>;
+}
+
+pub struct SweepServiceImpl {
+    repository: Arc<dyn SweepRepository>,
+    training_job_repo: Arc<dyn TrainingJobRepository>,
+    training_job_service: Arc<dyn TrainingJobService>,
+}
+
+impl SweepServiceImpl {
+    pub fn new(
+        repository: Arc<dyn SweepRepository>,
+        training_job_repo: Arc<dyn TrainingJobRepository>,
+        training_job_service: Arc<dyn TrainingJobService>,
+    ) -> Self {
+        Self {
+            repository,
+            training_job_repo,
+            training_job_service,
+        }
+    }
+
+    async fn advance(&self, sweep: &Sweep) -> Result<(), SweepServiceError> {
+        let mut trials = self.repository.list_trials(&sweep.id).await?;
+        let goal = sweep.objective.goal;
+
+        let job_ids = trials.iter().map(|trial| trial.job_id).collect::<Vec<_>>();
+        let mut curves: HashMap<JobId, Vec<(i64, f64)>> = HashMap::new();
+        for metric in self
+            .training_job_repo
+            .get_metric_for_jobs(&job_ids, &sweep.objective.metric)
+            .await?
+        {
+            curves
+                .entry(metric.job_id)
+                .or_default()
+                .push((metric.step, metric.value));
+        }
+
+        for trial in trials
+            .iter_mut()
+            .filter(|trial| trial.status == TrialStatus::Running)
+        {
+            let status = match self
+                .training_job_repo
+                .get_training_job_by_id(&trial.job_id)
+                .await
+            {
+                Ok(job) => TrialStatus::from(&job.status),
+                Err(TrainingJobRepositoryError::NotFound(_)) => TrialStatus::Failed,
+                Err(e) => return Err(e.into()),
+            };
+            let objective_value = curves
+                .get(&trial.job_id)
+                .and_then(|curve| goal.best(curve.iter().map(|(_, value)| *value)));
+            if status != trial.status || objective_value != trial.objective_value {
+                trial.status = status;
+                trial.objective_value = objective_value;
+                self.repository.update_trial(trial).await?;
+            }
+        }
+
+        if let Some(early_stopping) = &sweep.early_stopping {
+            let stopped = trials
+                .iter()
+                .enumerate()
+                .filter(|(_, trial)| trial.status == TrialStatus::Running)
+                .filter(|(_, trial)| {
+                    let Some(curve) = curves.get(&trial.job_id) else {
+                        return false;
+                    };
+                    let others = trials
+                        .iter()
+                        .filter(|other| other.number != trial.number)
+                        .filter_map(|other| curves.get(&other.job_id))
+                        .map(Vec::as_slice)
+                        .collect::<Vec<_>>();
+                    early_stopping.should_stop(goal, curve, &others)
+                })
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            for index in stopped {
+                let trial = &mut trials[index];
+                self.training_job_service.cancel(&trial.job_id).await?;
+                trial.status = TrialStatus::Stopped;
+                self.repository.update_trial(trial).await?;
+            }
+        }
+
+        let limit = sweep.trial_limit();
+        let mut running = trials
+            .iter()
+            .filter(|trial| trial.status == TrialStatus::Running)
+            .count();
+        let mut number = trials.len() as i32;
+        while number < limit && running < sweep.max_parallel as usize {
+            let parameters = sweep.suggest(number, &trials);
+            let created = self
+                .training_job_service
+                .create(trial_request(sweep, number, &parameters)?, &sweep.user_id)
+                .await?;
+
+            let now = chrono::Utc::now();
+            let trial = Trial {
+                sweep_id: sweep.id,
+                number,
+                job_id: created.job.id,
+                parameters,
+                status: TrialStatus::Running,
+                objective_value: None,
+                created_at: now,
+                updated_at: now,
+            };
+            self.repository.create_trial(&trial).await?;
+            trials.push(trial);
+            running += 1;
+            number += 1;
+        }
+
+        if running == 0 && number >= limit {
+            self.repository
+                .update_sweep_status(&sweep.id, SweepStatus::Completed)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_sweep(request: &CreateSweepRequest) -> Result<(), SweepServiceError> {
+    let invalid = |msg: String| SweepServiceError::InvalidSweep(msg);
+
+    if request.name.trim().is_empty() {
+        return Err(invalid("name must not be empty".to_string()));
+    }
+    request
+        .resource_requirements
+        .validate()
+        .and_then(|_| validate_labels(&request.labels))
+        .and_then(|_| validate_env(request.env.keys()))
+        .and_then(|_| validate_metric_name(&request.objective.metric))
+        .map_err(invalid)?;
+
+    if request.parameters.is_empty() {
+        return Err(invalid("at least one parameter is required".to_string()));
+    }
+    let mut env_names = HashSet::new();
+    for (name, space) in &request.parameters {
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(invalid(format!(
+                "parameter name '{name}' must start with a letter or '_' and contain only letters, digits and '_'"
+            )));
+        }
+        if !env_names.insert(name.to_ascii_uppercase()) {
+            return Err(invalid(format!(
+                "parameter names must differ in more than case, but '{name}' does not"
+            )));
+        }
+        space
+            .validate()
+            .map_err(|e| invalid(format!("parameter '{name}': {e}")))?;
+    }
+    if request.strategy == SearchStrategy::Grid && grid_size(&request.parameters).is_none() {
+        return Err(invalid(
+            "grid search only supports categorical and int parameters".to_string(),
+        ));
+    }
+
+    if let Some(early_stopping) = &request.early_stopping {
+        early_stopping
+            .validate()
+            .map_err(|e| invalid(format!("early stopping: {e}")))?;
+    }
+    if !(1..=MAX_TRIALS).contains(&request.max_trials) {
+        return Err(invalid(format!(
+            "max_trials must be between 1 and {MAX_TRIALS}"
+        )));
+    }
+    if request.max_parallel < 1 {
+        return Err(invalid("max_parallel must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+/// The job that runs trial `number` of the sweep. Trials learn their
+/// parameters from `LILAC_PARAMS`, a JSON object, or from one
+/// `LILAC_PARAM_<NAME>` variable per parameter.
+fn trial_request(
+    sweep: &Sweep,
+    number: i32,
+    parameters: &BTreeMap<String, Value>,
+) -> Result<CreateTrainingJobRequest, SweepServiceError> {
+    let mut env = sweep.env.clone();
+    env.insert("LILAC_SWEEP_ID".to_string(), sweep.id.to_string());
+    env.insert("LILAC_TRIAL_NUMBER".to_string(), number.to_string());
+    env.insert(
+        "LILAC_PARAMS".to_string(),
+        serde_json::to_string(parameters).map_err(|e| anyhow::anyhow!(e))?,
+    );
+    for (name, value) in parameters {
+        let value = match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        env.insert(format!("LILAC_PARAM_{}", name.to_ascii_uppercase()), value);
+    }
+
+    let mut labels = sweep.labels.clone();
+    labels.insert("lilac.sweep".to_string(), sweep.id.to_string());
+    labels.insert("lilac.trial".to_string(), number.to_string());
+
+    Ok(CreateTrainingJobRequest {
+        name: format!("{}-{number}", sweep.name),
+        definition: sweep.definition.clone(),
+        queue_id: sweep.queue_id,
+        resource_requirements: serde_json::to_value(&sweep.resource_requirements)
+            .map_err(|e| anyhow::anyhow!(e))?,
+        labels,
+        annotations: HashMap::new(),
+        env,
+        exposed_ports: Vec::new(),
+        kind: JobKind::Batch,
+        session: None,
+        termination_grace_period_secs: None,
+    })
+}
+
+#[async_trait]
+impl SweepService for SweepServiceImpl {
+    async fn create_sweep(
+        &self,
+        request: CreateSweepRequest,
+        user_id: &UserId,
+    ) -> Result<SweepDetails, SweepServiceError> {
+        validate_sweep(&request)?;
+
+        let now = chrono::Utc::now();
+        let sweep = Sweep {
+            id: SweepId::generate(),
+            name: request.name,
+            user_id: *user_id,
+            queue_id: request.queue_id,
+            definition: request.definition,
+            resource_requirements: request.resource_requirements,
+            env: request.env,
+            labels: request.labels,
+            parameters: request.parameters,
+            objective: request.objective,
+            strategy: request.strategy,
+            early_stopping: request.early_stopping,
+            max_trials: request.max_trials,
+            max_parallel: request.max_parallel,
+            status: SweepStatus::Running,
+            created_at: now,
+            updated_at: now,
+        };
+        self.repository.create_sweep(&sweep).await?;
+
+        if let Err(err) = self.advance(&sweep).await {
+            // A sweep whose first trial can't be submitted would never get
+            // anywhere, so report why instead of keeping it.
+            if self.repository.list_trials(&sweep.id).await?.is_empty() {
+                self.repository.delete_sweep(&sweep.id).await?;
+                return Err(err);
+            }
+            tracing::warn!(sweep_id = %sweep.id, error = %err, "failed to submit all initial trials");
+        }
+
+        self.get_sweep(&sweep.id).await
+    }
+
+    async fn list_sweeps(&self) -> Result<Vec<Sweep>, SweepServiceError> {
+        Ok(self.repository.list_sweeps(None).await?)
+    }
+
+    async fn get_sweep(&self, id: &SweepId) -> Result<SweepDetails, SweepServiceError> {
+        let sweep = self.repository.get_sweep_by_id(id).await?;
+        let trials = self.repository.list_trials(id).await?;
+        Ok(SweepDetails { sweep, trials })
+    }
+
+    async fn cancel_sweep(&self, id: &SweepId, user_id: &UserId) -> Result<(), SweepServiceError> {
+        let sweep = self.repository.get_sweep_by_id(id).await?;
+        if sweep.user_id != *user_id {
+            return Err(SweepServiceError::InvalidPermissions);
+        }
+        if sweep.status != SweepStatus::Running {
+            return Err(SweepServiceError::NotRunning(id.to_string()));
+        }
+
+        for mut trial in self.repository.list_trials(id).await? {
+            if trial.status != TrialStatus::Running {
+                continue;
+            }
+            match self.training_job_service.cancel(&trial.job_id).await {
+                Ok(()) | Err(TrainingJobServiceError::TrainingJobNotFound(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+            trial.status = TrialStatus::Stopped;
+            self.repository.update_trial(&trial).await?;
+        }
+
+        Ok(self
+            .repository
+            .update_sweep_status(id, SweepStatus::Cancelled)
+            .await?)
+    }
+
+    async fn advance_sweeps(&self) -> Result<(), SweepServiceError> {
+        let sweeps = self
+            .repository
+            .list_sweeps(Some(SweepStatus::Running))
+            .await?;
+        for sweep in sweeps {
+            if let Err(e) = self.advance(&sweep).await {
+                tracing::warn!(sweep_id = %sweep.id, error = %e, "failed to advance sweep");
+            }
+        }
+        Ok(())
+    }
+}
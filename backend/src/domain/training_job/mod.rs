@@ -5,54 +5,351 @@ pub mod service;
 #[cfg(test)]
 mod tests {
     use super::{
-        models::{GetTrainingJobsFilters, TrainingJobStatus},
-        ports::MockTrainingJobRepository,
-        service::TrainingJobServiceImpl,
+        models::{
+            BulkJobAction, BulkJobOutcome, BulkJobSelection, GetTrainingJobsFilters,
+            GpuRequirement, JobKind, LabelRequirement, LabelSelector, ReportedMetric,
+            ResourceRequirements, ResourceUsage, ResourceUsagePeaks, ResourceUsageSample,
+            SessionSettings, TrainingJob, TrainingJobMetadataPatch, TrainingJobStatus,
+        },
+        ports::{MockTrainingJobRepository, TrainingJobRepositoryError},
+        service::{TrainingJobServiceError, TrainingJobServiceImpl},
     };
     use crate::{
         domain::{
-            cluster::ports::MockClusterRepository,
-            queue::models::QueueId,
+            cluster::{
+                models::{ClusterId, NodeCapacity, NodeId},
+                ports::MockClusterRepository,
+            },
+            notification::{models::EventKind, service::MockNotificationService},
+            queue::{
+                models::{Queue, QueueId},
+                ports::{MockQueueRepository, QueueRepositoryError},
+            },
             training_job::{models::JobId, service::TrainingJobService},
+            user::models::UserId,
+        },
+        inbound::http::routes::training_jobs::models::{
+            CreateSessionRequest, CreateTrainingJobRequest,
         },
-        inbound::http::routes::training_jobs::models::CreateTrainingJobRequest,
     };
     use mockall::predicate::*;
-    use std::sync::Arc;
+    use std::{collections::HashMap, sync::Arc};
 
-    #[tokio::test]
-    async fn test_create_training_job() {
-        let mut mock_repo = MockTrainingJobRepository::new();
-        let mock_cluster_repo = MockClusterRepository::new();
-        let queue_id = QueueId::generate();
-        let request = CreateTrainingJobRequest {
+    fn queue_targeting(queue_id: QueueId, cluster_id: ClusterId) -> Queue {
+        Queue {
+            id: queue_id,
+            name: "default".to_string(),
+            priority: 0,
+            cluster_targets: vec![cluster_id],
+        }
+    }
+
+    fn node_capacity(cluster_id: ClusterId, gpu_count: i32) -> NodeCapacity {
+        NodeCapacity {
+            node_id: NodeId::generate(),
+            cluster_id,
+            cpu_millicores: 8000,
+            memory_mb: 32768,
+            gpu_count,
+            last_seen_at: chrono::Utc::now(),
+        }
+    }
+
+    fn create_request(queue_id: QueueId, gpus: i32) -> CreateTrainingJobRequest {
+        CreateTrainingJobRequest {
             name: "test".to_string(),
             definition: "definition".to_string(),
             queue_id,
             resource_requirements: serde_json::json!({
                 "cpu_millicores": 1000,
                 "memory_mb": 1024,
-                "gpus": null
+                "gpus": { "count": gpus, "model": null, "memory_gb": null }
             }),
-        };
+            labels: HashMap::from([("project".to_string(), "vision".to_string())]),
+            annotations: HashMap::new(),
+            env: HashMap::from([("EPOCHS".to_string(), "10".to_string())]),
+            exposed_ports: vec![6006],
+            kind: JobKind::Batch,
+            session: None,
+            termination_grace_period_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        let mut mock_queue_repo = MockQueueRepository::new();
+        let queue_id = QueueId::generate();
+        let cluster_id = ClusterId::generate();
+        let user_id = UserId::generate();
+        let request = create_request(queue_id, 2);
 
+        mock_queue_repo
+            .expect_get_queue_by_id()
+            .with(eq(queue_id))
+            .times(1)
+            .returning(move |_| Ok(queue_targeting(queue_id, cluster_id)));
+        mock_cluster_repo
+            .expect_list_node_capacities()
+            .with(eq(cluster_id))
+            .times(1)
+            .returning(move |_| Ok(vec![node_capacity(cluster_id, 8)]));
+        // The node that was big enough has gone away.
+        mock_cluster_repo
+            .expect_list_cluster_nodes()
+            .with(eq(cluster_id))
+            .times(1)
+            .returning(|_| Ok(vec![]));
         mock_repo
             .expect_create()
-            .withf(move |job| job.name == "test" && job.queue_id == Some(queue_id))
+            .withf(move |job| {
+                job.name == "test"
+                    && job.queue_id == Some(queue_id)
+                    && job.user_id == Some(user_id)
+                    && job.labels.get("project").map(String::as_str) == Some("vision")
+            })
             .times(1)
             .returning(|_| Ok(()));
 
-        let service = TrainingJobServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_cluster_repo));
-        let result = service.create(request).await;
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(mock_queue_repo),
+            Arc::new(MockNotificationService::new()),
+        );
+        let result = service.create(request, &user_id).await;
 
         assert!(result.is_ok());
-        let training_job = result.unwrap();
+        let created = result.unwrap();
+        assert_eq!(created.warnings.len(), 1);
+        let training_job = created.job;
         assert_eq!(training_job.name, "test");
         assert_eq!(training_job.definition, "definition");
         assert_eq!(training_job.status, TrainingJobStatus::Queued);
         assert_eq!(training_job.queue_id, Some(queue_id));
     }
 
+    #[tokio::test]
+    async fn test_create_training_job_larger_than_any_node() {
+        let mock_repo = MockTrainingJobRepository::new();
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        let mut mock_queue_repo = MockQueueRepository::new();
+        let queue_id = QueueId::generate();
+        let cluster_id = ClusterId::generate();
+
+        mock_queue_repo
+            .expect_get_queue_by_id()
+            .returning(move |_| Ok(queue_targeting(queue_id, cluster_id)));
+        mock_cluster_repo
+            .expect_list_node_capacities()
+            .returning(move |_| Ok(vec![node_capacity(cluster_id, 8)]));
+        mock_cluster_repo
+            .expect_list_cluster_nodes()
+            .returning(|_| Ok(vec![]));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(mock_queue_repo),
+            Arc::new(MockNotificationService::new()),
+        );
+        let result = service
+            .create(create_request(queue_id, 64), &UserId::generate())
+            .await;
+
+        match result {
+            Err(TrainingJobServiceError::Unschedulable(reason)) => {
+                assert!(reason.contains("64 GPUs requested, but the largest node has 8"))
+            }
+            other => panic!("expected Unschedulable, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job_invalid_requirements() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let request = CreateTrainingJobRequest {
+            resource_requirements: serde_json::json!({
+                "cpu_millicores": 1000,
+                "memory_mb": -1,
+                "gpus": null
+            }),
+            ..create_request(QueueId::generate(), 1)
+        };
+
+        let result = service.create(request, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::Unschedulable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job_invalid_labels() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let request = CreateTrainingJobRequest {
+            labels: HashMap::from([("git sha".to_string(), "abc123".to_string())]),
+            ..create_request(QueueId::generate(), 1)
+        };
+
+        let result = service.create(request, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidMetadata(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job_duplicate_exposed_port() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let request = CreateTrainingJobRequest {
+            exposed_ports: vec![6006, 6006],
+            ..create_request(QueueId::generate(), 1)
+        };
+
+        let result = service.create(request, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidMetadata(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_interactive_job() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        let mut mock_queue_repo = MockQueueRepository::new();
+        let queue_id = QueueId::generate();
+        let cluster_id = ClusterId::generate();
+
+        mock_queue_repo
+            .expect_get_queue_by_id()
+            .returning(move |_| Ok(queue_targeting(queue_id, cluster_id)));
+        mock_cluster_repo
+            .expect_list_node_capacities()
+            .returning(move |_| Ok(vec![node_capacity(cluster_id, 8)]));
+        mock_cluster_repo
+            .expect_list_cluster_nodes()
+            .returning(|_| Ok(vec![]));
+        mock_repo.expect_create().times(1).returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(mock_queue_repo),
+            Arc::new(MockNotificationService::new()),
+        );
+        let request = CreateTrainingJobRequest {
+            kind: JobKind::Interactive,
+            session: Some(CreateSessionRequest {
+                port: None,
+                idle_timeout_minutes: Some(30),
+            }),
+            ..create_request(queue_id, 1)
+        };
+
+        let job = service
+            .create(request, &UserId::generate())
+            .await
+            .unwrap()
+            .job;
+
+        let session = job.session.as_ref().unwrap();
+        assert_eq!(session.port, SessionSettings::DEFAULT_PORT);
+        assert_eq!(session.idle_timeout_minutes, 30);
+        assert_eq!(session.access_token.len(), 32);
+        // The session's port is exposed alongside the requested ones.
+        assert_eq!(job.exposed_ports, vec![6006, 8888]);
+        assert_eq!(
+            job.session_env().get("JUPYTER_TOKEN"),
+            Some(&session.access_token)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_batch_job_with_session_settings() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let request = CreateTrainingJobRequest {
+            session: Some(CreateSessionRequest::default()),
+            ..create_request(QueueId::generate(), 1)
+        };
+
+        let result = service.create(request, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidSession(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job_invalid_grace_period() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let request = CreateTrainingJobRequest {
+            termination_grace_period_secs: Some(-1),
+            ..create_request(QueueId::generate(), 1)
+        };
+
+        let result = service.create(request, &UserId::generate()).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidGracePeriod(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job_unknown_queue() {
+        let mut mock_queue_repo = MockQueueRepository::new();
+        mock_queue_repo
+            .expect_get_queue_by_id()
+            .returning(|id| Err(QueueRepositoryError::NotFound(id.to_string())));
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(mock_queue_repo),
+            Arc::new(MockNotificationService::new()),
+        );
+
+        let result = service
+            .create(create_request(QueueId::generate(), 1), &UserId::generate())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::QueueNotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_get_training_jobs() {
         let mut mock_repo = MockTrainingJobRepository::new();
@@ -68,12 +365,277 @@ mod tests {
             .times(1)
             .returning(|_| Ok(vec![]));
 
-        let service = TrainingJobServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_cluster_repo));
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
         let result = service.get_training_jobs(filters).await;
 
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_resubmit() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        let mut mock_queue_repo = MockQueueRepository::new();
+        let queue_id = QueueId::generate();
+        let cluster_id = ClusterId::generate();
+        let submitter = UserId::generate();
+        let original = TrainingJob {
+            status: TrainingJobStatus::Failed,
+            queue_id: Some(queue_id),
+            user_id: Some(UserId::generate()),
+            resource_requirements: ResourceRequirements {
+                cpu_millicores: 1000,
+                memory_mb: 1024,
+                gpus: None,
+            },
+            env: HashMap::from([("EPOCHS".to_string(), "10".to_string())]),
+            termination_grace_period_secs: 300,
+            restart_count: 2,
+            ..TrainingJob::new_mock()
+        };
+        let original_id = original.id;
+
+        mock_repo
+            .expect_get_training_job_by_id()
+            .with(eq(original_id))
+            .times(1)
+            .returning(move |_| Ok(original.clone()));
+        mock_queue_repo
+            .expect_get_queue_by_id()
+            .returning(move |_| Ok(queue_targeting(queue_id, cluster_id)));
+        mock_cluster_repo
+            .expect_list_node_capacities()
+            .returning(move |_| Ok(vec![node_capacity(cluster_id, 0)]));
+        mock_cluster_repo
+            .expect_list_cluster_nodes()
+            .returning(|_| Ok(vec![]));
+        mock_repo
+            .expect_create()
+            .withf(move |job| {
+                job.id != original_id
+                    && job.resubmitted_from == Some(original_id)
+                    && job.user_id == Some(submitter)
+                    && job.status == TrainingJobStatus::Queued
+                    && job.env["EPOCHS"] == "10"
+                    && job.termination_grace_period_secs == 300
+                    && job.restart_count == 0
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(mock_queue_repo),
+            Arc::new(MockNotificationService::new()),
+        );
+        // Administrators can resubmit anyone's jobs.
+        let result = service.resubmit(&original_id, &submitter, None).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_resubmit_unfinished_job() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let job = TrainingJob {
+            status: TrainingJobStatus::Running,
+            ..TrainingJob::new_mock()
+        };
+        let id = job.id;
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |_| Ok(job.clone()));
+        mock_repo.expect_create().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let result = service.resubmit(&id, &UserId::generate(), None).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::NotFinished(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resubmit_other_users_job() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let job = TrainingJob {
+            status: TrainingJobStatus::Succeeded,
+            user_id: Some(UserId::generate()),
+            ..TrainingJob::new_mock()
+        };
+        let id = job.id;
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |_| Ok(job.clone()));
+        mock_repo.expect_create().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let caller = UserId::generate();
+        let result = service.resubmit(&id, &caller, Some(caller)).await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidPermissions)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let id = JobId::generate();
+        let patch = TrainingJobMetadataPatch {
+            labels: HashMap::from([
+                ("experiment".to_string(), Some("lr-sweep".to_string())),
+                ("owner".to_string(), None),
+            ]),
+            annotations: HashMap::from([(
+                "notes".to_string(),
+                Some("Rerun with the fixed tokenizer, see #42.".to_string()),
+            )]),
+        };
+
+        mock_repo
+            .expect_update_metadata()
+            .with(eq(id), eq(patch.clone()))
+            .times(1)
+            .returning(|_, _| Ok(TrainingJob::new_mock()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let result = service.update_metadata(&id, patch, None).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata_of_other_users_job() {
+        let job = TrainingJob {
+            user_id: Some(UserId::generate()),
+            ..TrainingJob::new_mock()
+        };
+        let id = job.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_get_training_job_by_id()
+            .with(eq(id))
+            .returning(move |_| Ok(job.clone()));
+        mock_repo.expect_update_metadata().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let patch = TrainingJobMetadataPatch {
+            labels: HashMap::from([("sweep".to_string(), Some("lr".to_string()))]),
+            ..Default::default()
+        };
+        let result = service
+            .update_metadata(&id, patch, Some(UserId::generate()))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidPermissions)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata_invalid_label_value() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let patch = TrainingJobMetadataPatch {
+            labels: HashMap::from([("dataset".to_string(), Some("v1, v2".to_string()))]),
+            ..Default::default()
+        };
+
+        let result = service
+            .update_metadata(&JobId::generate(), patch, None)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidMetadata(_))
+        ));
+    }
+
+    #[test]
+    fn test_label_selector() {
+        let selector: LabelSelector = "project=vision, owner!=bot,git_sha,!archived"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            selector.requirements,
+            vec![
+                LabelRequirement::Equals {
+                    key: "project".to_string(),
+                    value: "vision".to_string(),
+                },
+                LabelRequirement::NotEquals {
+                    key: "owner".to_string(),
+                    value: "bot".to_string(),
+                },
+                LabelRequirement::Exists("git_sha".to_string()),
+                LabelRequirement::NotExists("archived".to_string()),
+            ]
+        );
+
+        let labels = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(selector.matches(&labels(&[("project", "vision"), ("git_sha", "abc123")])));
+        assert!(selector.matches(&labels(&[
+            ("project", "vision"),
+            ("owner", "alice"),
+            ("git_sha", "abc123"),
+        ])));
+        assert!(!selector.matches(&labels(&[
+            ("project", "vision"),
+            ("owner", "bot"),
+            ("git_sha", "abc123"),
+        ])));
+        assert!(!selector.matches(&labels(&[("project", "nlp"), ("git_sha", "abc123")])));
+        assert!(!selector.matches(&labels(&[
+            ("project", "vision"),
+            ("git_sha", "abc123"),
+            ("archived", "true"),
+        ])));
+
+        assert!("project=".parse::<LabelSelector>().is_ok());
+        assert!("=vision".parse::<LabelSelector>().is_err());
+        assert!("project=a b".parse::<LabelSelector>().is_err());
+    }
+
     #[tokio::test]
     async fn test_update_status() {
         let mut mock_repo = MockTrainingJobRepository::new();
@@ -87,12 +649,55 @@ mod tests {
             .times(1)
             .returning(|_, _| Ok(()));
 
-        let service = TrainingJobServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_cluster_repo));
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
         let result = service.update_status(&id, status).await;
 
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_update_status_to_terminal_publishes_event() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let job = TrainingJob {
+            status: TrainingJobStatus::Running,
+            ..TrainingJob::new_mock()
+        };
+        let id = job.id;
+
+        mock_repo
+            .expect_get_training_job_by_id()
+            .with(eq(id))
+            .times(1)
+            .returning(move |_| Ok(job.clone()));
+        mock_repo
+            .expect_update_status()
+            .with(eq(id), eq(TrainingJobStatus::Failed))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut mock_notification_service = MockNotificationService::new();
+        mock_notification_service
+            .expect_publish()
+            .withf(move |event| event.kind == EventKind::JobFailed && event.job_id == Some(id))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(mock_notification_service),
+        );
+        let result = service.update_status(&id, TrainingJobStatus::Failed).await;
+
+        assert!(result.is_ok());
+    }
+
     #[tokio::test]
     async fn test_post_logs() {
         let mut mock_repo = MockTrainingJobRepository::new();
@@ -105,9 +710,606 @@ mod tests {
             .times(1)
             .returning(|_, _| Ok(()));
 
-        let service = TrainingJobServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_cluster_repo));
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
         let result = service.post_logs(&id, "logs".to_string()).await;
 
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_get_usage() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let mock_cluster_repo = MockClusterRepository::new();
+        let job = TrainingJob::new_mock();
+        let id = job.id;
+        let sample = ResourceUsageSample {
+            job_id: id,
+            node_id: NodeId::generate(),
+            sampled_at: chrono::Utc::now(),
+            usage: ResourceUsage {
+                memory_rss_mb: 9216,
+                ..Default::default()
+            },
+        };
+        let peaks = ResourceUsagePeaks {
+            memory_mb: 9216,
+            ..Default::default()
+        };
+
+        mock_repo
+            .expect_get_training_job_by_id()
+            .with(eq(id))
+            .times(1)
+            .returning(move |_| Ok(job.clone()));
+        mock_repo
+            .expect_get_usage_peaks()
+            .with(eq(id))
+            .times(1)
+            .returning(move |_| Ok(Some(peaks.clone())));
+        mock_repo
+            .expect_get_usage_samples()
+            .with(eq(id), eq(None), eq(1000))
+            .times(1)
+            .returning(move |_, _, _| Ok(vec![sample.clone()]));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let result = service.get_usage(&id, None).await;
+
+        assert!(result.is_ok());
+        let usage = result.unwrap();
+        assert_eq!(usage.peak.unwrap().memory_mb, 9216);
+        assert_eq!(usage.samples.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_dry_run() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let queued = TrainingJob::new_mock();
+        let finished = TrainingJob {
+            status: TrainingJobStatus::Succeeded,
+            ..TrainingJob::new_mock()
+        };
+        let filters = GetTrainingJobsFilters {
+            selector: Some("sweep=lr".parse().unwrap()),
+            ..Default::default()
+        };
+        let jobs = vec![queued.clone(), finished.clone()];
+        mock_repo
+            .expect_get_training_jobs()
+            .with(eq(filters.clone()))
+            .times(1)
+            .returning(move |_| Ok(jobs.clone()));
+        mock_repo.expect_update_status().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let results = service
+            .bulk_update(
+                BulkJobSelection::Filter(filters),
+                BulkJobAction::Cancel,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].job_id, queued.id);
+        assert_eq!(results[0].outcome, BulkJobOutcome::WouldApply);
+        assert_eq!(results[1].job_id, finished.id);
+        assert_eq!(results[1].outcome, BulkJobOutcome::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_dry_run_requeue_skips_finished_jobs() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let jobs: Vec<_> = [
+            TrainingJobStatus::Running,
+            TrainingJobStatus::Failed,
+            TrainingJobStatus::Succeeded,
+            TrainingJobStatus::Cancelled,
+        ]
+        .into_iter()
+        .map(|status| TrainingJob {
+            status,
+            ..TrainingJob::new_mock()
+        })
+        .collect();
+        let ids = jobs.iter().map(|job| job.id).collect();
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |id| Ok(jobs.iter().find(|job| job.id == *id).unwrap().clone()));
+        mock_repo.expect_update_status().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let results = service
+            .bulk_update(
+                BulkJobSelection::Ids(ids),
+                BulkJobAction::Requeue,
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let outcomes: Vec<_> = results.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                BulkJobOutcome::WouldApply,
+                BulkJobOutcome::WouldApply,
+                BulkJobOutcome::Skipped,
+                BulkJobOutcome::Skipped,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_move_by_ids() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        let mut mock_queue_repo = MockQueueRepository::new();
+        let target = QueueId::generate();
+        let cluster_id = ClusterId::generate();
+        let queued = TrainingJob {
+            queue_id: Some(QueueId::generate()),
+            ..TrainingJob::new_mock()
+        };
+        let too_big = TrainingJob {
+            queue_id: Some(QueueId::generate()),
+            resource_requirements: ResourceRequirements {
+                cpu_millicores: 1000,
+                memory_mb: 1024,
+                gpus: Some(GpuRequirement {
+                    count: 64,
+                    model: None,
+                    memory_gb: None,
+                }),
+            },
+            ..TrainingJob::new_mock()
+        };
+        let running = TrainingJob {
+            status: TrainingJobStatus::Running,
+            ..TrainingJob::new_mock()
+        };
+        let missing = JobId::generate();
+        let (queued_id, too_big_id, running_id) = (queued.id, too_big.id, running.id);
+
+        mock_queue_repo
+            .expect_get_queue_by_id()
+            .with(eq(target))
+            .times(1)
+            .returning(move |_| Ok(queue_targeting(target, cluster_id)));
+        mock_cluster_repo
+            .expect_list_node_capacities()
+            .returning(move |_| Ok(vec![node_capacity(cluster_id, 8)]));
+        mock_cluster_repo
+            .expect_list_cluster_nodes()
+            .returning(|_| Ok(vec![]));
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |id| match *id {
+                id if id == queued_id => Ok(queued.clone()),
+                id if id == too_big_id => Ok(too_big.clone()),
+                id if id == running_id => Ok(running.clone()),
+                id => Err(TrainingJobRepositoryError::NotFound(id.to_string())),
+            });
+        mock_repo
+            .expect_update_queue()
+            .with(eq(queued_id), eq(target))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(mock_cluster_repo),
+            Arc::new(mock_queue_repo),
+            Arc::new(MockNotificationService::new()),
+        );
+        let results = service
+            .bulk_update(
+                BulkJobSelection::Ids(vec![queued_id, too_big_id, running_id, missing]),
+                BulkJobAction::Move { queue_id: target },
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let outcomes: Vec<_> = results.iter().map(|r| (r.job_id, r.outcome)).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (queued_id, BulkJobOutcome::Applied),
+                (too_big_id, BulkJobOutcome::Skipped),
+                (running_id, BulkJobOutcome::Skipped),
+                (missing, BulkJobOutcome::Failed),
+            ]
+        );
+        assert!(results[1].message.as_deref().unwrap().contains("GPU"));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_skips_other_users_jobs() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let owner = UserId::generate();
+        let own = TrainingJob {
+            user_id: Some(owner),
+            ..TrainingJob::new_mock()
+        };
+        let other = TrainingJob {
+            user_id: Some(UserId::generate()),
+            ..TrainingJob::new_mock()
+        };
+        let (own_id, other_id) = (own.id, other.id);
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |id| {
+                Ok(if *id == own_id {
+                    own.clone()
+                } else {
+                    other.clone()
+                })
+            });
+        mock_repo
+            .expect_delete()
+            .with(eq(own_id))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let results = service
+            .bulk_update(
+                BulkJobSelection::Ids(vec![own_id, other_id]),
+                BulkJobAction::Delete,
+                false,
+                Some(owner),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].outcome, BulkJobOutcome::Applied);
+        assert_eq!(results[1].job_id, other_id);
+        assert_eq!(results[1].outcome, BulkJobOutcome::Skipped);
+        assert_eq!(results[1].job_name, None);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_requires_a_filter() {
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo.expect_get_training_jobs().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let result = service
+            .bulk_update(
+                BulkJobSelection::Filter(GetTrainingJobsFilters::default()),
+                BulkJobAction::Delete,
+                false,
+                None,
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidBulkSelection(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stop_idle_sessions() {
+        let now = chrono::Utc::now();
+        let session = |idle_minutes: i64| TrainingJob {
+            status: TrainingJobStatus::Running,
+            kind: JobKind::Interactive,
+            session: Some(SessionSettings {
+                port: 8888,
+                idle_timeout_minutes: 60,
+                access_token: "token".to_string(),
+            }),
+            started_at: Some(now - chrono::Duration::hours(3)),
+            last_activity_at: Some(now - chrono::Duration::minutes(idle_minutes)),
+            ..TrainingJob::new_mock()
+        };
+        let idle = session(61);
+        let active = session(5);
+        // Batch jobs are never idle, however long they run.
+        let batch = TrainingJob {
+            status: TrainingJobStatus::Running,
+            started_at: Some(now - chrono::Duration::hours(3)),
+            ..TrainingJob::new_mock()
+        };
+        let idle_id = idle.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let running = vec![idle, active, batch];
+        mock_repo
+            .expect_get_jobs_by_status()
+            .with(eq(TrainingJobStatus::Running))
+            .times(1)
+            .returning(move |_| Ok(running.clone()));
+        mock_repo
+            .expect_update_status()
+            .with(eq(idle_id), eq(TrainingJobStatus::Cancelled))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut mock_notification_service = MockNotificationService::new();
+        mock_notification_service
+            .expect_publish()
+            .withf(move |event| {
+                event.kind == EventKind::JobTimedOut && event.job_id == Some(idle_id)
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(mock_notification_service),
+        );
+        let stopped = service.stop_idle_sessions().await.unwrap();
+
+        assert_eq!(stopped, vec![idle_id]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_idle_sessions_continues_past_failures() {
+        let now = chrono::Utc::now();
+        let session = || TrainingJob {
+            status: TrainingJobStatus::Running,
+            kind: JobKind::Interactive,
+            session: Some(SessionSettings {
+                port: 8888,
+                idle_timeout_minutes: 60,
+                access_token: "token".to_string(),
+            }),
+            started_at: Some(now - chrono::Duration::hours(3)),
+            last_activity_at: Some(now - chrono::Duration::hours(2)),
+            ..TrainingJob::new_mock()
+        };
+        let failing = session();
+        let idle = session();
+        let failing_id = failing.id;
+        let idle_id = idle.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        let running = vec![failing, idle];
+        mock_repo
+            .expect_get_jobs_by_status()
+            .returning(move |_| Ok(running.clone()));
+        mock_repo
+            .expect_update_status()
+            .with(eq(failing_id), eq(TrainingJobStatus::Cancelled))
+            .times(1)
+            .returning(|_, _| {
+                Err(TrainingJobRepositoryError::Unknown(anyhow::anyhow!(
+                    "connection reset"
+                )))
+            });
+        mock_repo
+            .expect_update_status()
+            .with(eq(idle_id), eq(TrainingJobStatus::Cancelled))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut mock_notification_service = MockNotificationService::new();
+        mock_notification_service
+            .expect_publish()
+            .withf(move |event| event.job_id == Some(idle_id))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(mock_notification_service),
+        );
+        let stopped = service.stop_idle_sessions().await.unwrap();
+
+        assert_eq!(stopped, vec![idle_id]);
+    }
+
+    #[tokio::test]
+    async fn test_report_metrics() {
+        let owner_id = UserId::generate();
+        let job = TrainingJob {
+            user_id: Some(owner_id),
+            ..TrainingJob::new_mock()
+        };
+        let job_id = job.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_get_training_job_by_id()
+            .with(eq(job_id))
+            .times(1)
+            .returning(move |_| Ok(job.clone()));
+        mock_repo
+            .expect_record_metrics()
+            .withf(move |metrics| {
+                metrics.len() == 1
+                    && metrics[0].job_id == job_id
+                    && metrics[0].name == "val_loss"
+                    && metrics[0].step == 100
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let metrics = vec![ReportedMetric {
+            name: "val_loss".to_string(),
+            step: 100,
+            value: 0.42,
+        }];
+        let result = service.report_metrics(&job_id, &owner_id, metrics).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_report_metrics_for_job_of_other_user() {
+        let job = TrainingJob {
+            user_id: Some(UserId::generate()),
+            ..TrainingJob::new_mock()
+        };
+        let job_id = job.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |_| Ok(job.clone()));
+        mock_repo.expect_record_metrics().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let metrics = vec![ReportedMetric {
+            name: "val_loss".to_string(),
+            step: 100,
+            value: 0.42,
+        }];
+        let result = service
+            .report_metrics(&job_id, &UserId::generate(), metrics)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidPermissions)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_report_metrics_with_job_token() {
+        let job = TrainingJob {
+            status: TrainingJobStatus::Running,
+            job_token: Some("lilac_job_current".to_string()),
+            ..TrainingJob::new_mock()
+        };
+        let job_id = job.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |_| Ok(job.clone()));
+        mock_repo
+            .expect_record_metrics()
+            .withf(move |metrics| metrics.len() == 1 && metrics[0].job_id == job_id)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let metrics = vec![ReportedMetric {
+            name: "val_loss".to_string(),
+            step: 100,
+            value: 0.42,
+        }];
+        let result = service
+            .report_metrics_with_job_token(&job_id, "lilac_job_current", metrics)
+            .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_report_metrics_with_token_of_earlier_run() {
+        let job = TrainingJob {
+            status: TrainingJobStatus::Running,
+            job_token: Some("lilac_job_current".to_string()),
+            ..TrainingJob::new_mock()
+        };
+        let job_id = job.id;
+
+        let mut mock_repo = MockTrainingJobRepository::new();
+        mock_repo
+            .expect_get_training_job_by_id()
+            .returning(move |_| Ok(job.clone()));
+        mock_repo.expect_record_metrics().never();
+
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(mock_repo),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let metrics = vec![ReportedMetric {
+            name: "val_loss".to_string(),
+            step: 100,
+            value: 0.42,
+        }];
+        let result = service
+            .report_metrics_with_job_token(&job_id, "lilac_job_earlier", metrics)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidJobToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_report_metrics_not_finite() {
+        let service = TrainingJobServiceImpl::new(
+            Arc::new(MockTrainingJobRepository::new()),
+            Arc::new(MockClusterRepository::new()),
+            Arc::new(MockQueueRepository::new()),
+            Arc::new(MockNotificationService::new()),
+        );
+        let metrics = vec![ReportedMetric {
+            name: "val_loss".to_string(),
+            step: 3,
+            value: f64::NAN,
+        }];
+        let result = service
+            .report_metrics(&JobId::generate(), &UserId::generate(), metrics)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TrainingJobServiceError::InvalidMetrics(_))
+        ));
+    }
 }
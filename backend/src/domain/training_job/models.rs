@@ -1,8 +1,10 @@
+use std::{collections::HashMap, str::FromStr};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    domain::{cluster::models::NodeId, queue::models::QueueId},
+    domain::{cluster::models::NodeId, queue::models::QueueId, user::models::UserId},
     identifier,
 };
 
@@ -19,6 +21,54 @@ pub enum TrainingJobStatus {
     Cancelled,
 }
 
+/// Why a job failed, as reported by the agent that ran it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The job exited with a non-zero code, or could not be run at all.
+    Error,
+    /// The job was killed for using more memory than it asked for.
+    OutOfMemory,
+}
+
+/// A GPU of a node that was handed to a job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AssignedGpu {
+    /// The GPU's index on its node.
+    pub index: u32,
+    pub uuid: Option<String>,
+}
+
+/// What kind of workload a job runs.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    /// Runs to completion without anyone attached to it.
+    #[default]
+    Batch,
+    /// Runs a server, such as a Jupyter notebook, that its owner works in
+    /// through the control plane until it goes idle.
+    Interactive,
+}
+
+/// The server of an interactive job and how it is reached.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionSettings {
+    /// The port the session's server listens on. Always one of the job's
+    /// exposed ports.
+    pub port: u16,
+    /// How long the session may go without being used before it is stopped.
+    pub idle_timeout_minutes: i32,
+    /// Lets the server be opened in a browser without an API key. Passed to
+    /// the server as `LILAC_SESSION_TOKEN` and `JUPYTER_TOKEN`.
+    pub access_token: String,
+}
+
+impl SessionSettings {
+    pub const DEFAULT_PORT: u16 = 8888;
+    pub const DEFAULT_IDLE_TIMEOUT_MINUTES: i32 = 60;
+}
+
 /// Describes a specific requirement for a GPU.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuRequirement {
@@ -41,6 +91,31 @@ pub struct ResourceRequirements {
     pub gpus: Option<GpuRequirement>,
 }
 
+impl ResourceRequirements {
+    /// Checks that the requirements describe a job that could run at all.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cpu_millicores <= 0 {
+            return Err("cpu_millicores must be greater than 0".to_string());
+        }
+        if self.memory_mb <= 0 {
+            return Err("memory_mb must be greater than 0".to_string());
+        }
+        if let Some(gpus) = &self.gpus {
+            if gpus.count <= 0 {
+                return Err("gpus.count must be greater than 0".to_string());
+            }
+            if gpus.memory_gb.is_some_and(|memory_gb| memory_gb <= 0) {
+                return Err("gpus.memory_gb must be greater than 0".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn gpu_count(&self) -> i32 {
+        self.gpus.as_ref().map_or(0, |gpus| gpus.count)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrainingJob {
     pub id: JobId,
@@ -50,16 +125,588 @@ pub struct TrainingJob {
     pub status: TrainingJobStatus,
     pub node_id: Option<NodeId>,
     pub queue_id: Option<QueueId>,
+    /// The user who submitted the job. None for jobs submitted before owners were tracked.
+    pub user_id: Option<UserId>,
     pub resource_requirements: ResourceRequirements,
+    /// When the job last started running. Cleared when the job is re-queued.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job was last placed on a node. Cleared when the job is re-queued.
+    pub assigned_at: Option<DateTime<Utc>>,
+    /// Why the scheduler is holding the job back, if it is.
+    pub pending_reason: Option<String>,
+    /// Short, queryable tags such as `project` or `git_sha`.
+    pub labels: HashMap<String, String>,
+    /// Free-form notes that are stored with the job but can't be queried.
+    pub annotations: HashMap<String, String>,
+    /// Environment variables set in the job's container.
+    pub env: HashMap<String, String>,
+    /// Ports of services in the job's container, such as TensorBoard, that
+    /// its owner can reach through the control plane.
+    pub exposed_ports: Vec<u16>,
+    pub kind: JobKind,
+    /// Set for interactive jobs only.
+    pub session: Option<SessionSettings>,
+    /// When the owner last connected to the job through the control plane.
+    pub last_activity_at: Option<DateTime<Utc>>,
+    /// The job this one is a resubmission of, if any.
+    pub resubmitted_from: Option<JobId>,
+    /// How long the job has to save a checkpoint and exit after it is asked
+    /// to stop, before it is killed.
+    pub termination_grace_period_secs: i32,
+    /// How many times the job was re-queued after it had started running,
+    /// e.g. because its node died.
+    pub restart_count: i32,
+    /// Lets the job's own code call back into the API, e.g. to report
+    /// metrics. A new one is issued each time the job is placed on a node.
+    #[serde(skip)]
+    pub job_token: Option<String>,
+    /// Why the job failed. Only set on failed jobs.
+    pub failure_reason: Option<FailureReason>,
+    /// The GPUs of its node the job runs on, as reported by the agent.
+    pub assigned_gpus: Vec<AssignedGpu>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl TrainingJob {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            TrainingJobStatus::Succeeded | TrainingJobStatus::Failed | TrainingJobStatus::Cancelled
+        )
+    }
+
+    /// The path, relative to the API endpoint, that the server of an
+    /// interactive job is proxied under.
+    pub fn session_path(&self) -> Option<String> {
+        self.session
+            .as_ref()
+            .map(|session| format!("/training_jobs/{}/proxy/{}/", self.id, session.port))
+    }
+
+    /// Environment variables set in the job's container in addition to its
+    /// own, telling the server of an interactive job how it is reached.
+    pub fn session_env(&self) -> HashMap<String, String> {
+        let (Some(session), Some(path)) = (&self.session, self.session_path()) else {
+            return HashMap::new();
+        };
+        HashMap::from([
+            ("LILAC_SESSION_PORT".to_string(), session.port.to_string()),
+            ("LILAC_SESSION_BASE_URL".to_string(), path),
+            (
+                "LILAC_SESSION_TOKEN".to_string(),
+                session.access_token.clone(),
+            ),
+            ("JUPYTER_TOKEN".to_string(), session.access_token.clone()),
+        ])
+    }
+
+    /// Environment variables set in the container of a job that was
+    /// re-queued after it had started, telling it to resume from the
+    /// checkpoint its earlier run saved.
+    pub fn resume_env(&self) -> HashMap<String, String> {
+        if self.restart_count == 0 {
+            return HashMap::new();
+        }
+        HashMap::from([
+            ("LILAC_RESUME".to_string(), "1".to_string()),
+            (
+                "LILAC_RESTART_COUNT".to_string(),
+                self.restart_count.to_string(),
+            ),
+        ])
+    }
+
+    /// Whether this is a running interactive job that has gone unused for
+    /// longer than its idle timeout. Time before the job started running
+    /// doesn't count.
+    pub fn is_idle(&self, now: DateTime<Utc>) -> bool {
+        let (Some(session), TrainingJobStatus::Running) = (&self.session, &self.status) else {
+            return false;
+        };
+        let Some(since) = self.started_at.max(self.last_activity_at) else {
+            return false;
+        };
+        now - since > chrono::Duration::minutes(session.idle_timeout_minutes.into())
+    }
+}
+
+/// A newly created job, along with anything its submitter should know about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedTrainingJob {
+    pub job: TrainingJob,
+    /// Problems that don't prevent the job from being queued, such as there
+    /// being no live node that can currently run it.
+    pub warnings: Vec<String>,
+}
+
+/// A point-in-time sample of the resources consumed by a running job, as
+/// reported by the agent alongside its heartbeat.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_millicores: i32,
+    pub memory_rss_mb: i32,
+    /// The highest memory usage observed by the container runtime so far.
+    pub memory_peak_mb: i32,
+    /// Cumulative bytes received over the network since the job started.
+    pub network_rx_bytes: i64,
+    /// Cumulative bytes sent over the network since the job started.
+    pub network_tx_bytes: i64,
+    pub block_read_bytes: i64,
+    pub block_write_bytes: i64,
+    /// Per-GPU usage of the job's processes. Empty if the job uses no GPUs.
+    #[serde(default)]
+    pub gpus: Vec<GpuUsage>,
+}
+
+impl ResourceUsage {
+    pub fn total_gpu_memory_mb(&self) -> i32 {
+        self.gpus.iter().map(|gpu| gpu.memory_used_mb).sum()
+    }
+
+    pub fn mean_gpu_utilization_percent(&self) -> i32 {
+        if self.gpus.is_empty() {
+            return 0;
+        }
+        self.gpus
+            .iter()
+            .map(|gpu| gpu.utilization_percent)
+            .sum::<i32>()
+            / self.gpus.len() as i32
+    }
+}
+
+/// Usage of a single GPU by the processes of a job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GpuUsage {
+    pub index: i32,
+    pub utilization_percent: i32,
+    pub memory_used_mb: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResourceUsageSample {
+    pub job_id: JobId,
+    pub node_id: NodeId,
+    pub sampled_at: DateTime<Utc>,
+    pub usage: ResourceUsage,
+}
+
+/// The highest values observed across all usage samples of a job.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourceUsagePeaks {
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    /// Summed across all GPUs assigned to the job.
+    pub gpu_memory_mb: i32,
+    /// Averaged across all GPUs assigned to the job.
+    pub gpu_utilization_percent: i32,
+    pub network_rx_bytes: i64,
+    pub network_tx_bytes: i64,
+    pub block_read_bytes: i64,
+    pub block_write_bytes: i64,
+    pub last_sampled_at: DateTime<Utc>,
+}
+
+/// What a job asked for, next to what it actually used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingJobUsage {
+    pub job_id: JobId,
+    pub requested: ResourceRequirements,
+    /// None if the agent has not reported any usage for the job yet.
+    pub peak: Option<ResourceUsagePeaks>,
+    pub samples: Vec<ResourceUsageSample>,
+}
+
+/// A value of one of a job's metrics, such as its validation loss, at a
+/// training step.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobMetric {
+    pub job_id: JobId,
+    pub name: String,
+    pub step: i64,
+    pub value: f64,
+    pub reported_at: DateTime<Utc>,
+}
+
+/// A metric value as reported by a job. Reporting the same metric for the
+/// same step again replaces the earlier value.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReportedMetric {
+    pub name: String,
+    pub step: i64,
+    pub value: f64,
+}
+
+impl ReportedMetric {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_metric_name(&self.name)?;
+        if self.step < 0 {
+            return Err(format!(
+                "step of metric '{}' must not be negative",
+                self.name
+            ));
+        }
+        if !self.value.is_finite() {
+            return Err(format!("value of metric '{}' must be finite", self.name));
+        }
+        Ok(())
+    }
+}
+
+const MAX_LABEL_KEY_LENGTH: usize = 63;
+const MAX_LABEL_VALUE_LENGTH: usize = 63;
+const MAX_ANNOTATION_VALUE_LENGTH: usize = 4096;
+
+fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.len() > MAX_LABEL_KEY_LENGTH {
+        return Err(format!(
+            "key '{key}' must be between 1 and {MAX_LABEL_KEY_LENGTH} characters"
+        ));
+    }
+    if !key.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+    {
+        return Err(format!(
+            "key '{key}' must start with a letter or digit and contain only letters, digits, '-', '_', '.' and '/'"
+        ));
+    }
+    Ok(())
+}
+
+fn validate_label_value(key: &str, value: &str) -> Result<(), String> {
+    if value.len() > MAX_LABEL_VALUE_LENGTH {
+        return Err(format!(
+            "value of label '{key}' must be at most {MAX_LABEL_VALUE_LENGTH} characters"
+        ));
+    }
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "value of label '{key}' must contain only letters, digits, '-', '_' and '.'"
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that every label has a well-formed key and a short, selector-safe value.
+pub fn validate_labels<'a>(
+    labels: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> Result<(), String> {
+    for (key, value) in labels {
+        validate_key(key)?;
+        validate_label_value(key, value)?;
+    }
+    Ok(())
+}
+
+/// Checks that every annotation has a well-formed key. Values may contain
+/// anything, up to a size limit.
+pub fn validate_annotations<'a>(
+    annotations: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> Result<(), String> {
+    for (key, value) in annotations {
+        validate_key(key)?;
+        if value.len() > MAX_ANNOTATION_VALUE_LENGTH {
+            return Err(format!(
+                "value of annotation '{key}' must be at most {MAX_ANNOTATION_VALUE_LENGTH} bytes"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every environment variable name is a valid shell identifier.
+/// Metric names follow the same rules as label keys.
+pub fn validate_metric_name(name: &str) -> Result<(), String> {
+    validate_key(name)
+}
+
+pub fn validate_env<'a>(env: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+    for name in env {
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!(
+                "environment variable name '{name}' must start with a letter or '_' and contain only letters, digits and '_'"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that exposed ports are valid TCP ports and listed only once.
+pub fn validate_exposed_ports(ports: &[u16]) -> Result<(), String> {
+    for (i, port) in ports.iter().enumerate() {
+        if *port == 0 {
+            return Err("exposed ports must be between 1 and 65535".to_string());
+        }
+        if ports[..i].contains(port) {
+            return Err(format!("port {port} is exposed more than once"));
+        }
+    }
+    Ok(())
+}
+
+/// Distinguishes job tokens from user API keys.
+pub const JOB_TOKEN_PREFIX: &str = "lilac_job_";
+
+/// Issues a token for a run of a job. See [TrainingJob::job_token].
+pub fn generate_job_token() -> String {
+    format!("{JOB_TOKEN_PREFIX}{}", nanoid::nanoid!(32))
+}
+
+/// How long a job has to exit after it is asked to stop, if it doesn't say.
+pub const DEFAULT_TERMINATION_GRACE_PERIOD_SECS: i32 = 30;
+const MAX_TERMINATION_GRACE_PERIOD_SECS: i32 = 3600;
+
+pub fn validate_termination_grace_period(secs: i32) -> Result<(), String> {
+    if !(0..=MAX_TERMINATION_GRACE_PERIOD_SECS).contains(&secs) {
+        return Err(format!(
+            "termination_grace_period_secs must be between 0 and {MAX_TERMINATION_GRACE_PERIOD_SECS}"
+        ));
+    }
+    Ok(())
+}
+
+/// Changes to the labels and annotations of a job. A `None` value removes
+/// the key; keys that aren't mentioned are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrainingJobMetadataPatch {
+    #[serde(default)]
+    pub labels: HashMap<String, Option<String>>,
+    #[serde(default)]
+    pub annotations: HashMap<String, Option<String>>,
+}
+
+impl TrainingJobMetadataPatch {
+    pub fn validate(&self) -> Result<(), String> {
+        for key in self.labels.keys().chain(self.annotations.keys()) {
+            validate_key(key)?;
+        }
+        validate_labels(
+            self.labels
+                .iter()
+                .filter_map(|(key, value)| value.as_ref().map(|value| (key, value))),
+        )?;
+        validate_annotations(
+            self.annotations
+                .iter()
+                .filter_map(|(key, value)| value.as_ref().map(|value| (key, value))),
+        )
+    }
+}
+
+/// A single term of a [LabelSelector].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelRequirement {
+    /// `key=value`
+    Equals { key: String, value: String },
+    /// `key!=value`. Also matches jobs that don't have the label at all.
+    NotEquals { key: String, value: String },
+    /// `key`
+    Exists(String),
+    /// `!key`
+    NotExists(String),
+}
+
+impl LabelRequirement {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        match self {
+            Self::Equals { key, value } => labels.get(key) == Some(value),
+            Self::NotEquals { key, value } => labels.get(key) != Some(value),
+            Self::Exists(key) => labels.contains_key(key),
+            Self::NotExists(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+impl FromStr for LabelRequirement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let requirement = if let Some((key, value)) = s.split_once("!=") {
+            Self::NotEquals {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            }
+        } else if let Some((key, value)) = s.split_once("==").or_else(|| s.split_once('=')) {
+            Self::Equals {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            }
+        } else if let Some(key) = s.strip_prefix('!') {
+            Self::NotExists(key.trim().to_string())
+        } else {
+            Self::Exists(s.to_string())
+        };
+
+        match &requirement {
+            Self::Equals { key, value } | Self::NotEquals { key, value } => {
+                validate_key(key)?;
+                validate_label_value(key, value)?;
+            }
+            Self::Exists(key) | Self::NotExists(key) => validate_key(key)?,
+        }
+        Ok(requirement)
+    }
+}
+
+/// A comma-separated list of label requirements that must all hold, e.g.
+/// `project=vision,owner!=bot`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LabelSelector {
+    pub requirements: Vec<LabelRequirement>,
+}
+
+impl LabelSelector {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.requirements
+            .iter()
+            .all(|requirement| requirement.matches(labels))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let requirements = s
+            .split(',')
+            .filter(|term| !term.trim().is_empty())
+            .map(|term| {
+                term.parse()
+                    .map_err(|e| format!("invalid label selector '{s}': {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { requirements })
+    }
+}
+
+impl TryFrom<String> for LabelSelector {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
 #[derive(Debug, Default, Deserialize, PartialEq, Clone)]
 pub struct GetTrainingJobsFilters {
     pub id: Option<JobId>,
     pub name: Option<String>,
     pub status: Option<TrainingJobStatus>,
+    /// Only jobs whose labels match, e.g. `project=vision,owner!=bot`.
+    pub selector: Option<LabelSelector>,
+    pub queue_id: Option<QueueId>,
+    /// Only jobs submitted by this user.
+    pub user_id: Option<UserId>,
+    /// Only jobs created at or after this time.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only jobs created before this time.
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl GetTrainingJobsFilters {
+    /// Whether the filters match every job.
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none()
+            && self.name.is_none()
+            && self.status.is_none()
+            && self
+                .selector
+                .as_ref()
+                .is_none_or(|selector| selector.requirements.is_empty())
+            && self.queue_id.is_none()
+            && self.user_id.is_none()
+            && self.created_after.is_none()
+            && self.created_before.is_none()
+    }
+}
+
+/// An operation applied to many jobs at once.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkJobAction {
+    Cancel,
+    /// Puts the job back in its queue, stopping it first if it is running.
+    /// Only failed and unfinished jobs are requeued, so that jobs which
+    /// succeeded or were cancelled on purpose don't run again.
+    Requeue,
+    /// Moves a queued job to another queue.
+    Move {
+        queue_id: QueueId,
+    },
+    Delete,
+}
+
+impl BulkJobAction {
+    /// Why the action doesn't apply to `job`, if it doesn't.
+    pub fn skip_reason(&self, job: &TrainingJob) -> Option<String> {
+        match self {
+            BulkJobAction::Cancel if job.is_finished() => {
+                Some("job has already finished".to_string())
+            }
+            BulkJobAction::Requeue if job.status == TrainingJobStatus::Queued => {
+                Some("job is already queued".to_string())
+            }
+            BulkJobAction::Requeue if job.status == TrainingJobStatus::Succeeded => {
+                Some("job has already succeeded".to_string())
+            }
+            BulkJobAction::Requeue if job.status == TrainingJobStatus::Cancelled => {
+                Some("job was cancelled".to_string())
+            }
+            BulkJobAction::Move { .. } if job.status != TrainingJobStatus::Queued => {
+                Some("only queued jobs can be moved".to_string())
+            }
+            BulkJobAction::Move { queue_id } if job.queue_id.as_ref() == Some(queue_id) => {
+                Some("job is already in this queue".to_string())
+            }
+            BulkJobAction::Delete
+                if matches!(
+                    job.status,
+                    TrainingJobStatus::Starting | TrainingJobStatus::Running
+                ) =>
+            {
+                Some("job is still running and must be cancelled first".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The jobs a bulk operation applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkJobSelection {
+    Ids(Vec<JobId>),
+    Filter(GetTrainingJobsFilters),
+}
+
+/// What happened to one job in a bulk operation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkJobOutcome {
+    Applied,
+    /// The action would have been applied, but this was a dry run.
+    WouldApply,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkJobResult {
+    pub job_id: JobId,
+    /// The job's name and status before the action, when the job was found.
+    pub job_name: Option<String>,
+    pub job_status: Option<TrainingJobStatus>,
+    pub outcome: BulkJobOutcome,
+    /// Why the job was skipped or the action failed.
+    pub message: Option<String>,
 }
 
 #[cfg(test)]
@@ -72,11 +719,28 @@ impl TrainingJob {
             status: TrainingJobStatus::Queued,
             node_id: None,
             queue_id: None,
+            user_id: None,
             resource_requirements: ResourceRequirements {
                 cpu_millicores: 0,
                 memory_mb: 0,
                 gpus: None,
             },
+            started_at: None,
+            assigned_at: None,
+            pending_reason: None,
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+            env: HashMap::new(),
+            exposed_ports: Vec::new(),
+            kind: JobKind::Batch,
+            session: None,
+            last_activity_at: None,
+            resubmitted_from: None,
+            termination_grace_period_secs: DEFAULT_TERMINATION_GRACE_PERIOD_SECS,
+            restart_count: 0,
+            job_token: None,
+            failure_reason: None,
+            assigned_gpus: Vec::new(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
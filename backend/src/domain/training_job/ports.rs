@@ -1,6 +1,15 @@
-use super::models::{GetTrainingJobsFilters, TrainingJob, TrainingJobStatus};
-use crate::domain::{cluster::models::NodeId, queue::models::QueueId, training_job::models::JobId};
+use super::models::{
+    AssignedGpu, FailureReason, GetTrainingJobsFilters, JobMetric, ResourceUsagePeaks,
+    ResourceUsageSample, TrainingJob, TrainingJobMetadataPatch, TrainingJobStatus,
+};
+use crate::domain::{
+    accounting::models::UsageRecord,
+    cluster::models::{ClusterId, NodeId},
+    queue::models::QueueId,
+    training_job::models::JobId,
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 #[derive(Debug, thiserror::Error)]
 pub enum TrainingJobRepositoryError {
@@ -33,15 +42,107 @@ pub trait TrainingJobRepository: Send + Sync {
         id: &JobId,
         status: TrainingJobStatus,
     ) -> Result<(), TrainingJobRepositoryError>;
+    /// Places the job on a node, issuing a new job token for the run.
     async fn mark_as_starting(
         &self,
         id: &JobId,
         node_id: &NodeId,
+        job_token: &str,
     ) -> Result<(), TrainingJobRepositoryError>;
     async fn post_logs(&self, id: &JobId, logs: String) -> Result<(), TrainingJobRepositoryError>;
+    async fn set_pending_reason(
+        &self,
+        id: &JobId,
+        reason: Option<String>,
+    ) -> Result<(), TrainingJobRepositoryError>;
+    /// Records why the job failed.
+    async fn set_failure_reason(
+        &self,
+        id: &JobId,
+        reason: FailureReason,
+    ) -> Result<(), TrainingJobRepositoryError>;
+    /// Records which GPUs of its node the job runs on.
+    async fn set_assigned_gpus(
+        &self,
+        id: &JobId,
+        gpus: &[AssignedGpu],
+    ) -> Result<(), TrainingJobRepositoryError>;
+    /// Applies `patch` to the job's labels and annotations and returns the updated job.
+    async fn update_metadata(
+        &self,
+        id: &JobId,
+        patch: &TrainingJobMetadataPatch,
+    ) -> Result<TrainingJob, TrainingJobRepositoryError>;
+    /// Records that the owner of an interactive job used it at `at`. Writes
+    /// are coalesced, so the stored time may lag by up to a minute.
+    async fn record_session_activity(
+        &self,
+        id: &JobId,
+        at: DateTime<Utc>,
+    ) -> Result<(), TrainingJobRepositoryError>;
     async fn reset_job_status(&self, job_id: &JobId) -> Result<(), TrainingJobRepositoryError>;
+    async fn update_queue(
+        &self,
+        id: &JobId,
+        queue_id: &QueueId,
+    ) -> Result<(), TrainingJobRepositoryError>;
+    async fn delete(&self, id: &JobId) -> Result<(), TrainingJobRepositoryError>;
     async fn get_jobs_by_status(
         &self,
         status: TrainingJobStatus,
     ) -> Result<Vec<TrainingJob>, TrainingJobRepositoryError>;
+    /// Lists the distinct images of queued jobs.
+    async fn get_queued_definitions(&self) -> Result<Vec<String>, TrainingJobRepositoryError>;
+    /// Lists the distinct images of the first `depth` queued jobs of each
+    /// queue that targets the cluster.
+    async fn get_queue_head_definitions(
+        &self,
+        cluster_id: &ClusterId,
+        depth: i64,
+    ) -> Result<Vec<String>, TrainingJobRepositoryError>;
+    /// Stores a usage sample and folds it into the job's peak usage.
+    async fn record_usage(
+        &self,
+        sample: &ResourceUsageSample,
+    ) -> Result<(), TrainingJobRepositoryError>;
+    async fn get_usage_peaks(
+        &self,
+        id: &JobId,
+    ) -> Result<Option<ResourceUsagePeaks>, TrainingJobRepositoryError>;
+    /// Lists the latest `limit` samples of the job taken after `since`, in
+    /// the order they were taken.
+    async fn get_usage_samples(
+        &self,
+        id: &JobId,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<ResourceUsageSample>, TrainingJobRepositoryError>;
+    /// Deletes the usage samples of jobs that finished before `before`, and
+    /// returns how many were deleted. Peaks and usage records are kept.
+    async fn delete_usage_samples_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, TrainingJobRepositoryError>;
+    async fn create_usage_record(
+        &self,
+        record: &UsageRecord,
+    ) -> Result<(), TrainingJobRepositoryError>;
+    /// Lists the usage records of all runs that overlap `[from, to)`.
+    async fn list_usage_records(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<UsageRecord>, TrainingJobRepositoryError>;
+    /// Stores metric values, replacing any earlier value of the same
+    /// metric at the same step.
+    async fn record_metrics(&self, metrics: &[JobMetric])
+        -> Result<(), TrainingJobRepositoryError>;
+    async fn get_metrics(&self, id: &JobId) -> Result<Vec<JobMetric>, TrainingJobRepositoryError>;
+    /// Lists the values of the metric `name` reported by any of `ids`,
+    /// ordered by step.
+    async fn get_metric_for_jobs(
+        &self,
+        ids: &[JobId],
+        name: &str,
+    ) -> Result<Vec<JobMetric>, TrainingJobRepositoryError>;
 }
@@ -1,22 +1,49 @@
 use std::sync::Arc;
 
 use super::{
-    models::{GetTrainingJobsFilters, TrainingJob, TrainingJobStatus},
+    models::{
+        generate_job_token, validate_annotations, validate_env, validate_exposed_ports,
+        validate_labels, validate_termination_grace_period, BulkJobAction, BulkJobOutcome,
+        BulkJobResult, BulkJobSelection, CreatedTrainingJob, GetTrainingJobsFilters, JobKind,
+        JobMetric, ReportedMetric, ResourceRequirements, SessionSettings, TrainingJob,
+        TrainingJobMetadataPatch, TrainingJobStatus, TrainingJobUsage,
+        DEFAULT_TERMINATION_GRACE_PERIOD_SECS,
+    },
     ports::TrainingJobRepository,
 };
 use crate::{
     domain::{
+        accounting::models::UsageRecord,
         cluster::{
-            models::NodeId,
+            models::{NodeCapacity, NodeId},
             ports::{ClusterRepository, ClusterRepositoryError},
         },
+        notification::{
+            models::NotificationEvent,
+            service::{publish_event, NotificationService},
+        },
+        queue::{
+            models::Queue,
+            ports::{QueueRepository, QueueRepositoryError},
+        },
         training_job::{models::JobId, ports::TrainingJobRepositoryError},
+        user::models::UserId,
+    },
+    inbound::http::routes::training_jobs::models::{
+        CreateSessionRequest, CreateTrainingJobRequest,
     },
-    inbound::http::routes::training_jobs::models::CreateTrainingJobRequest,
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use subtle::ConstantTimeEq;
 use thiserror::Error;
 
+/// How many metric values a job can report in a single request.
+const MAX_METRICS_PER_REPORT: usize = 1000;
+
+/// How many of its latest usage samples are returned with a job's usage.
+const MAX_USAGE_SAMPLES: i64 = 1000;
+
 #[derive(Debug, Error)]
 pub enum TrainingJobServiceError {
     #[error("training job with {field} {value} already exists")]
@@ -25,6 +52,26 @@ pub enum TrainingJobServiceError {
     TrainingJobNotFound(String),
     #[error("invalid training job definition: {0}")]
     InvalidDefinition(#[from] serde_json::Error),
+    #[error("queue {0} not found")]
+    QueueNotFound(String),
+    #[error("training job cannot be scheduled: {0}")]
+    Unschedulable(String),
+    #[error("invalid labels or annotations: {0}")]
+    InvalidMetadata(String),
+    #[error("training job {0} has not finished yet")]
+    NotFinished(String),
+    #[error("invalid session settings: {0}")]
+    InvalidSession(String),
+    #[error("invalid bulk job selection: {0}")]
+    InvalidBulkSelection(String),
+    #[error("invalid termination grace period: {0}")]
+    InvalidGracePeriod(String),
+    #[error("invalid metrics: {0}")]
+    InvalidMetrics(String),
+    #[error("user does not have permission to perform this action")]
+    InvalidPermissions,
+    #[error("invalid job token")]
+    InvalidJobToken,
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
@@ -54,13 +101,24 @@ impl From<ClusterRepositoryError> for TrainingJobServiceError {
     }
 }
 
+impl From<QueueRepositoryError> for TrainingJobServiceError {
+    fn from(err: QueueRepositoryError) -> Self {
+        match err {
+            QueueRepositoryError::NotFound(id) => TrainingJobServiceError::QueueNotFound(id),
+            QueueRepositoryError::Unknown(err) => TrainingJobServiceError::Unknown(err),
+            _ => TrainingJobServiceError::Unknown(anyhow::anyhow!(err)),
+        }
+    }
+}
+
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait TrainingJobService: Send + Sync {
     async fn create(
         &self,
         request: CreateTrainingJobRequest,
-    ) -> Result<TrainingJob, TrainingJobServiceError>;
+        user_id: &UserId,
+    ) -> Result<CreatedTrainingJob, TrainingJobServiceError>;
     async fn get_training_jobs(
         &self,
         filters: GetTrainingJobsFilters,
@@ -74,6 +132,8 @@ pub trait TrainingJobService: Send + Sync {
         &self,
         id: &JobId,
     ) -> Result<TrainingJob, TrainingJobServiceError>;
+    /// Lists the images of queued jobs, which agents keep cached.
+    async fn get_queued_images(&self) -> Result<Vec<String>, TrainingJobServiceError>;
     async fn mark_as_starting(
         &self,
         id: &JobId,
@@ -81,31 +141,253 @@ pub trait TrainingJobService: Send + Sync {
     ) -> Result<(), TrainingJobServiceError>;
     async fn post_logs(&self, id: &JobId, logs: String) -> Result<(), TrainingJobServiceError>;
     async fn cancel(&self, id: &JobId) -> Result<(), TrainingJobServiceError>;
+    /// With an `owner`, jobs submitted by anyone else can't be updated.
+    async fn update_metadata(
+        &self,
+        id: &JobId,
+        patch: TrainingJobMetadataPatch,
+        owner: Option<UserId>,
+    ) -> Result<TrainingJob, TrainingJobServiceError>;
+    /// Queues a copy of a finished job, submitted by `user_id`. With an
+    /// `owner`, jobs submitted by anyone else can't be resubmitted.
+    async fn resubmit(
+        &self,
+        id: &JobId,
+        user_id: &UserId,
+        owner: Option<UserId>,
+    ) -> Result<CreatedTrainingJob, TrainingJobServiceError>;
+    /// Returns the job's requested and peak usage, with the latest samples
+    /// taken after `since`.
+    async fn get_usage(
+        &self,
+        id: &JobId,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<TrainingJobUsage, TrainingJobServiceError>;
+    /// Deletes the usage samples of jobs that finished before `before`, and
+    /// returns how many were deleted.
+    async fn purge_usage_samples(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, TrainingJobServiceError>;
+    /// Applies `action` to every selected job and reports what happened to
+    /// each. With `dry_run`, reports what would happen without changing anything.
+    /// With an `owner`, jobs submitted by anyone else are skipped.
+    async fn bulk_update(
+        &self,
+        selection: BulkJobSelection,
+        action: BulkJobAction,
+        dry_run: bool,
+        owner: Option<UserId>,
+    ) -> Result<Vec<BulkJobResult>, TrainingJobServiceError>;
+    /// Cancels the interactive jobs that have gone unused for longer than
+    /// their idle timeout, and returns their ids.
+    async fn stop_idle_sessions(&self) -> Result<Vec<JobId>, TrainingJobServiceError>;
+    /// Stores metric values reported by the job. Only the job's owner can
+    /// report them.
+    async fn report_metrics(
+        &self,
+        id: &JobId,
+        user_id: &UserId,
+        metrics: Vec<ReportedMetric>,
+    ) -> Result<(), TrainingJobServiceError>;
+    /// Stores metric values reported by the job's own code, authenticated
+    /// with the token of the job's current run.
+    async fn report_metrics_with_job_token(
+        &self,
+        id: &JobId,
+        job_token: &str,
+        metrics: Vec<ReportedMetric>,
+    ) -> Result<(), TrainingJobServiceError>;
+    async fn get_metrics(&self, id: &JobId) -> Result<Vec<JobMetric>, TrainingJobServiceError>;
+}
+
+/// Who is reporting metrics for a job.
+enum Reporter<'a> {
+    User(&'a UserId),
+    /// The job itself, holding the token of its current run.
+    JobToken(&'a str),
 }
 
 pub struct TrainingJobServiceImpl {
     repository: Arc<dyn TrainingJobRepository>,
     cluster_repo: Arc<dyn ClusterRepository>,
+    queue_repo: Arc<dyn QueueRepository>,
+    notification_service: Arc<dyn NotificationService>,
 }
 
 impl TrainingJobServiceImpl {
     pub fn new(
         repository: Arc<dyn TrainingJobRepository>,
         cluster_repo: Arc<dyn ClusterRepository>,
+        queue_repo: Arc<dyn QueueRepository>,
+        notification_service: Arc<dyn NotificationService>,
     ) -> Self {
         Self {
             repository,
             cluster_repo,
+            queue_repo,
+            notification_service,
         }
     }
-}
 
-#[async_trait]
-impl TrainingJobService for TrainingJobServiceImpl {
-    async fn create(
+    /// Frees the node a job was placed on and records the usage of its run
+    /// so far, if it was running.
+    async fn release_node(&self, job: &TrainingJob) -> Result<(), TrainingJobServiceError> {
+        let Some(node_id) = job.node_id else {
+            return Ok(());
+        };
+
+        self.cluster_repo
+            .clear_assigned_job_id(&node_id)
+            .await
+            .map_err(|e| TrainingJobServiceError::Unknown(e.into()))?;
+
+        if !job.is_finished() {
+            match self.cluster_repo.get_cluster_node_by_id(&node_id).await {
+                Ok(node) => {
+                    if let Some(record) = UsageRecord::for_run(job, &node, chrono::Utc::now()) {
+                        self.repository.create_usage_record(&record).await?;
+                    }
+                }
+                Err(e) => tracing::warn!(
+                    "Not recording usage of job {}, node {} could not be loaded: {}",
+                    job.id,
+                    node_id,
+                    e
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn record_reported_metrics(
+        &self,
+        id: &JobId,
+        reporter: Reporter<'_>,
+        metrics: Vec<ReportedMetric>,
+    ) -> Result<(), TrainingJobServiceError> {
+        if metrics.len() > MAX_METRICS_PER_REPORT {
+            return Err(TrainingJobServiceError::InvalidMetrics(format!(
+                "at most {MAX_METRICS_PER_REPORT} values can be reported at once"
+            )));
+        }
+        for metric in &metrics {
+            metric
+                .validate()
+                .map_err(TrainingJobServiceError::InvalidMetrics)?;
+        }
+
+        let job = self.repository.get_training_job_by_id(id).await?;
+        match reporter {
+            Reporter::User(user_id) => {
+                if job.user_id != Some(*user_id) {
+                    return Err(TrainingJobServiceError::InvalidPermissions);
+                }
+            }
+            Reporter::JobToken(token) => {
+                // Tokens of runs that have finished or been re-queued are no
+                // longer valid. Tokens are compared in constant time so that
+                // they can't be guessed from how long the comparison takes.
+                let valid = job.job_token.as_deref().is_some_and(|expected| {
+                    bool::from(expected.as_bytes().ct_eq(token.as_bytes()))
+                });
+                if job.is_finished() || !valid {
+                    return Err(TrainingJobServiceError::InvalidJobToken);
+                }
+            }
+        }
+
+        let now = chrono::Utc::now();
+        let metrics = metrics
+            .into_iter()
+            .map(|metric| JobMetric {
+                job_id: job.id,
+                name: metric.name,
+                step: metric.step,
+                value: metric.value,
+                reported_at: now,
+            })
+            .collect::<Vec<_>>();
+        Ok(self.repository.record_metrics(&metrics).await?)
+    }
+
+    /// Cancels `job` and, unless it had already finished, publishes the
+    /// event `event` builds for it.
+    async fn stop(
+        &self,
+        job: &TrainingJob,
+        event: impl FnOnce(&TrainingJob) -> Option<NotificationEvent> + Send,
+    ) -> Result<(), TrainingJobServiceError> {
+        self.release_node(job).await?;
+
+        self.repository
+            .update_status(&job.id, TrainingJobStatus::Cancelled)
+            .await?;
+
+        if !job.is_finished() {
+            if let Some(event) = event(job) {
+                publish_event(self.notification_service.as_ref(), event).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn apply_bulk_action(
+        &self,
+        job: &TrainingJob,
+        action: &BulkJobAction,
+    ) -> Result<(), TrainingJobServiceError> {
+        match action {
+            BulkJobAction::Cancel => self.cancel(&job.id).await,
+            BulkJobAction::Requeue => {
+                self.release_node(job).await?;
+                Ok(self.repository.reset_job_status(&job.id).await?)
+            }
+            BulkJobAction::Move { queue_id } => {
+                Ok(self.repository.update_queue(&job.id, queue_id).await?)
+            }
+            BulkJobAction::Delete => Ok(self.repository.delete(&job.id).await?),
+        }
+    }
+
+    async fn create_job(
         &self,
         request: CreateTrainingJobRequest,
-    ) -> Result<TrainingJob, TrainingJobServiceError> {
+        user_id: &UserId,
+        resubmitted_from: Option<JobId>,
+    ) -> Result<CreatedTrainingJob, TrainingJobServiceError> {
+        let resource_requirements: ResourceRequirements =
+            serde_json::from_value(request.resource_requirements)?;
+        resource_requirements
+            .validate()
+            .map_err(TrainingJobServiceError::Unschedulable)?;
+        validate_labels(&request.labels).map_err(TrainingJobServiceError::InvalidMetadata)?;
+        validate_annotations(&request.annotations)
+            .map_err(TrainingJobServiceError::InvalidMetadata)?;
+        validate_env(request.env.keys()).map_err(TrainingJobServiceError::InvalidMetadata)?;
+        validate_exposed_ports(&request.exposed_ports)
+            .map_err(TrainingJobServiceError::InvalidMetadata)?;
+        let session = session_settings(request.kind, request.session)
+            .map_err(TrainingJobServiceError::InvalidSession)?;
+        let termination_grace_period_secs = request
+            .termination_grace_period_secs
+            .unwrap_or(DEFAULT_TERMINATION_GRACE_PERIOD_SECS);
+        validate_termination_grace_period(termination_grace_period_secs)
+            .map_err(TrainingJobServiceError::InvalidGracePeriod)?;
+        let mut exposed_ports = request.exposed_ports;
+        if let Some(session) = &session {
+            if !exposed_ports.contains(&session.port) {
+                exposed_ports.push(session.port);
+            }
+        }
+
+        let queue = self.queue_repo.get_queue_by_id(&request.queue_id).await?;
+        let warnings = self
+            .check_schedulable(&queue, &resource_requirements)
+            .await?;
+
         let job_id = JobId::generate();
         let now = chrono::Utc::now();
 
@@ -116,14 +398,174 @@ impl TrainingJobService for TrainingJobServiceImpl {
             status: TrainingJobStatus::Queued,
             node_id: None,
             queue_id: Some(request.queue_id),
-            resource_requirements: serde_json::from_value(request.resource_requirements)?,
+            user_id: Some(*user_id),
+            resource_requirements,
+            started_at: None,
+            assigned_at: None,
+            pending_reason: None,
+            labels: request.labels,
+            annotations: request.annotations,
+            env: request.env,
+            exposed_ports,
+            kind: request.kind,
+            session,
+            last_activity_at: None,
+            resubmitted_from,
+            termination_grace_period_secs,
+            restart_count: 0,
+            job_token: None,
+            failure_reason: None,
+            assigned_gpus: Vec::new(),
             created_at: now,
             updated_at: now,
         };
 
         self.repository.create(&training_job).await?;
 
-        Ok(training_job)
+        Ok(CreatedTrainingJob {
+            job: training_job,
+            warnings,
+        })
+    }
+
+    /// Checks that some node ever seen in the queue's target clusters is big
+    /// enough to run a job with `requirements`, and returns warnings about
+    /// anything that will keep the job queued for now.
+    async fn check_schedulable(
+        &self,
+        queue: &Queue,
+        requirements: &ResourceRequirements,
+    ) -> Result<Vec<String>, TrainingJobServiceError> {
+        if queue.cluster_targets.is_empty() {
+            return Ok(vec![format!(
+                "queue '{}' has no target clusters, so the job will stay queued until one is added",
+                queue.name
+            )]);
+        }
+
+        let mut capacities = Vec::new();
+        let mut live_nodes = Vec::new();
+        for cluster_id in &queue.cluster_targets {
+            capacities.extend(self.cluster_repo.list_node_capacities(cluster_id).await?);
+            live_nodes.extend(self.cluster_repo.list_cluster_nodes(cluster_id).await?);
+        }
+
+        if capacities.is_empty() {
+            return Ok(vec![format!(
+                "no node has joined the target clusters of queue '{}' yet, so the job could not be checked against their capacity",
+                queue.name
+            )]);
+        }
+
+        if !capacities
+            .iter()
+            .any(|capacity| capacity.fits(requirements))
+        {
+            return Err(TrainingJobServiceError::Unschedulable(describe_shortfall(
+                &queue.name,
+                &capacities,
+                requirements,
+            )));
+        }
+
+        if !live_nodes
+            .iter()
+            .any(|node| node.capacity().fits(requirements))
+        {
+            return Ok(vec![format!(
+                "no node currently online in the target clusters of queue '{}' can run this job, so it will stay queued until one joins",
+                queue.name
+            )]);
+        }
+
+        Ok(vec![])
+    }
+}
+
+/// Fills in the server settings of an interactive job, generating its access
+/// token. Batch jobs have none.
+fn session_settings(
+    kind: JobKind,
+    request: Option<CreateSessionRequest>,
+) -> Result<Option<SessionSettings>, String> {
+    let request = match (kind, request) {
+        (JobKind::Batch, None) => return Ok(None),
+        (JobKind::Batch, Some(_)) => {
+            return Err("session settings can only be given for interactive jobs".to_string())
+        }
+        (JobKind::Interactive, request) => request.unwrap_or_default(),
+    };
+
+    let port = request.port.unwrap_or(SessionSettings::DEFAULT_PORT);
+    if port == 0 {
+        return Err("session port must be between 1 and 65535".to_string());
+    }
+    let idle_timeout_minutes = request
+        .idle_timeout_minutes
+        .unwrap_or(SessionSettings::DEFAULT_IDLE_TIMEOUT_MINUTES);
+    if idle_timeout_minutes <= 0 {
+        return Err("idle_timeout_minutes must be greater than 0".to_string());
+    }
+
+    Ok(Some(SessionSettings {
+        port,
+        idle_timeout_minutes,
+        access_token: nanoid::nanoid!(32),
+    }))
+}
+
+/// Explains why none of `capacities` can fit a job with `requirements`.
+fn describe_shortfall(
+    queue_name: &str,
+    capacities: &[NodeCapacity],
+    requirements: &ResourceRequirements,
+) -> String {
+    let max_cpu = capacities
+        .iter()
+        .map(|c| c.cpu_millicores)
+        .max()
+        .unwrap_or(0);
+    let max_memory = capacities.iter().map(|c| c.memory_mb).max().unwrap_or(0);
+    let max_gpus = capacities.iter().map(|c| c.gpu_count).max().unwrap_or(0);
+
+    let mut shortfalls = Vec::new();
+    if requirements.cpu_millicores > max_cpu {
+        shortfalls.push(format!(
+            "{} CPU millicores requested, but the largest node has {max_cpu}",
+            requirements.cpu_millicores
+        ));
+    }
+    if requirements.memory_mb > max_memory {
+        shortfalls.push(format!(
+            "{} MB of memory requested, but the largest node has {max_memory} MB",
+            requirements.memory_mb
+        ));
+    }
+    if requirements.gpu_count() > max_gpus {
+        shortfalls.push(format!(
+            "{} GPUs requested, but the largest node has {max_gpus}",
+            requirements.gpu_count()
+        ));
+    }
+    if shortfalls.is_empty() {
+        shortfalls
+            .push("no single node has enough CPU, memory and GPUs at the same time".to_string());
+    }
+
+    format!(
+        "no node ever seen in the target clusters of queue '{queue_name}' can run this job: {}",
+        shortfalls.join("; ")
+    )
+}
+
+#[async_trait]
+impl TrainingJobService for TrainingJobServiceImpl {
+    async fn create(
+        &self,
+        request: CreateTrainingJobRequest,
+        user_id: &UserId,
+    ) -> Result<CreatedTrainingJob, TrainingJobServiceError> {
+        self.create_job(request, user_id, None).await
     }
 
     async fn get_training_jobs(
@@ -138,7 +580,21 @@ impl TrainingJobService for TrainingJobServiceImpl {
         id: &JobId,
         status: TrainingJobStatus,
     ) -> Result<(), TrainingJobServiceError> {
-        Ok(self.repository.update_status(id, status).await?)
+        if !matches!(
+            status,
+            TrainingJobStatus::Succeeded | TrainingJobStatus::Failed | TrainingJobStatus::Cancelled
+        ) {
+            return Ok(self.repository.update_status(id, status).await?);
+        }
+
+        let job = self.repository.get_training_job_by_id(id).await?;
+        self.repository.update_status(id, status.clone()).await?;
+        if !job.is_finished() {
+            if let Some(event) = NotificationEvent::job_finished(&job, &status) {
+                publish_event(self.notification_service.as_ref(), event).await;
+            }
+        }
+        Ok(())
     }
 
     async fn get_training_job_by_id(
@@ -148,12 +604,19 @@ impl TrainingJobService for TrainingJobServiceImpl {
         Ok(self.repository.get_training_job_by_id(id).await?)
     }
 
+    async fn get_queued_images(&self) -> Result<Vec<String>, TrainingJobServiceError> {
+        Ok(self.repository.get_queued_definitions().await?)
+    }
+
     async fn mark_as_starting(
         &self,
         id: &JobId,
         node_id: &NodeId,
     ) -> Result<(), TrainingJobServiceError> {
-        Ok(self.repository.mark_as_starting(id, node_id).await?)
+        Ok(self
+            .repository
+            .mark_as_starting(id, node_id, &generate_job_token())
+            .await?)
     }
 
     async fn post_logs(&self, id: &JobId, logs: String) -> Result<(), TrainingJobServiceError> {
@@ -162,18 +625,245 @@ impl TrainingJobService for TrainingJobServiceImpl {
 
     async fn cancel(&self, id: &JobId) -> Result<(), TrainingJobServiceError> {
         let job = self.repository.get_training_job_by_id(id).await?;
+        self.stop(&job, |job| {
+            NotificationEvent::job_finished(job, &TrainingJobStatus::Cancelled)
+        })
+        .await
+    }
 
-        if let Some(node_id) = job.node_id {
-            self.cluster_repo
-                .clear_assigned_job_id(&node_id)
-                .await
-                .map_err(|e| TrainingJobServiceError::Unknown(e.into()))?;
+    async fn update_metadata(
+        &self,
+        id: &JobId,
+        patch: TrainingJobMetadataPatch,
+        owner: Option<UserId>,
+    ) -> Result<TrainingJob, TrainingJobServiceError> {
+        patch
+            .validate()
+            .map_err(TrainingJobServiceError::InvalidMetadata)?;
+        if let Some(owner) = owner {
+            let job = self.repository.get_training_job_by_id(id).await?;
+            if job.user_id != Some(owner) {
+                return Err(TrainingJobServiceError::InvalidPermissions);
+            }
         }
 
-        self.repository
-            .update_status(id, TrainingJobStatus::Cancelled)
+        Ok(self.repository.update_metadata(id, &patch).await?)
+    }
+
+    async fn resubmit(
+        &self,
+        id: &JobId,
+        user_id: &UserId,
+        owner: Option<UserId>,
+    ) -> Result<CreatedTrainingJob, TrainingJobServiceError> {
+        let job = self.repository.get_training_job_by_id(id).await?;
+        if owner.is_some_and(|owner| job.user_id != Some(owner)) {
+            return Err(TrainingJobServiceError::InvalidPermissions);
+        }
+        if !job.is_finished() {
+            return Err(TrainingJobServiceError::NotFinished(id.to_string()));
+        }
+        let queue_id = job.queue_id.ok_or_else(|| {
+            TrainingJobServiceError::Unschedulable(format!(
+                "training job {id} is no longer assigned to a queue"
+            ))
+        })?;
+
+        let request = CreateTrainingJobRequest {
+            name: job.name,
+            definition: job.definition,
+            queue_id,
+            resource_requirements: serde_json::to_value(job.resource_requirements)?,
+            labels: job.labels,
+            annotations: job.annotations,
+            env: job.env,
+            exposed_ports: job.exposed_ports,
+            kind: job.kind,
+            session: job.session.map(|session| CreateSessionRequest {
+                port: Some(session.port),
+                idle_timeout_minutes: Some(session.idle_timeout_minutes),
+            }),
+            termination_grace_period_secs: Some(job.termination_grace_period_secs),
+        };
+
+        self.create_job(request, user_id, Some(job.id)).await
+    }
+
+    async fn get_usage(
+        &self,
+        id: &JobId,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<TrainingJobUsage, TrainingJobServiceError> {
+        let job = self.repository.get_training_job_by_id(id).await?;
+        let peak = self.repository.get_usage_peaks(id).await?;
+        let samples = self
+            .repository
+            .get_usage_samples(id, since, MAX_USAGE_SAMPLES)
             .await?;
 
-        Ok(())
+        Ok(TrainingJobUsage {
+            job_id: job.id,
+            requested: job.resource_requirements,
+            peak,
+            samples,
+        })
+    }
+
+    async fn purge_usage_samples(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, TrainingJobServiceError> {
+        Ok(self.repository.delete_usage_samples_before(before).await?)
+    }
+
+    async fn bulk_update(
+        &self,
+        selection: BulkJobSelection,
+        action: BulkJobAction,
+        dry_run: bool,
+        owner: Option<UserId>,
+    ) -> Result<Vec<BulkJobResult>, TrainingJobServiceError> {
+        let target_queue = match &action {
+            BulkJobAction::Move { queue_id } => {
+                Some(self.queue_repo.get_queue_by_id(queue_id).await?)
+            }
+            _ => None,
+        };
+
+        let jobs = match selection {
+            BulkJobSelection::Ids(ids) => {
+                let mut jobs = Vec::with_capacity(ids.len());
+                for id in ids {
+                    match self.repository.get_training_job_by_id(&id).await {
+                        Ok(job) => jobs.push(Ok(job)),
+                        Err(TrainingJobRepositoryError::NotFound(_)) => jobs.push(Err(id)),
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                jobs
+            }
+            BulkJobSelection::Filter(filters) => {
+                if filters.is_empty() {
+                    return Err(TrainingJobServiceError::InvalidBulkSelection(
+                        "at least one filter is required".to_string(),
+                    ));
+                }
+                self.repository
+                    .get_training_jobs(filters)
+                    .await?
+                    .into_iter()
+                    .map(Ok)
+                    .collect()
+            }
+        };
+
+        let mut results = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            let job = match job {
+                Ok(job) => job,
+                Err(job_id) => {
+                    results.push(BulkJobResult {
+                        job_id,
+                        job_name: None,
+                        job_status: None,
+                        outcome: BulkJobOutcome::Failed,
+                        message: Some("training job not found".to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            if owner.is_some() && job.user_id != owner {
+                // Don't reveal anything about other users' jobs.
+                results.push(BulkJobResult {
+                    job_id: job.id,
+                    job_name: None,
+                    job_status: None,
+                    outcome: BulkJobOutcome::Skipped,
+                    message: Some("job belongs to another user".to_string()),
+                });
+                continue;
+            }
+
+            let skip_reason = action.skip_reason(&job);
+            // Moved jobs must fit their new queue just like new jobs do.
+            let check = match (&skip_reason, &target_queue) {
+                (None, Some(queue)) => self
+                    .check_schedulable(queue, &job.resource_requirements)
+                    .await
+                    .map(|_| ()),
+                _ => Ok(()),
+            };
+
+            let (outcome, message) = match (skip_reason, check) {
+                (Some(reason), _) | (None, Err(TrainingJobServiceError::Unschedulable(reason))) => {
+                    (BulkJobOutcome::Skipped, Some(reason))
+                }
+                (None, Err(e)) => (BulkJobOutcome::Failed, Some(e.to_string())),
+                (None, Ok(())) if dry_run => (BulkJobOutcome::WouldApply, None),
+                (None, Ok(())) => match self.apply_bulk_action(&job, &action).await {
+                    Ok(()) => (BulkJobOutcome::Applied, None),
+                    Err(e) => (BulkJobOutcome::Failed, Some(e.to_string())),
+                },
+            };
+
+            results.push(BulkJobResult {
+                job_id: job.id,
+                job_name: Some(job.name),
+                job_status: Some(job.status),
+                outcome,
+                message,
+            });
+        }
+
+        Ok(results)
+    }
+
+    async fn stop_idle_sessions(&self) -> Result<Vec<JobId>, TrainingJobServiceError> {
+        let now = chrono::Utc::now();
+        let idle = self
+            .repository
+            .get_jobs_by_status(TrainingJobStatus::Running)
+            .await?
+            .into_iter()
+            .filter(|job| job.is_idle(now));
+
+        let mut stopped = Vec::new();
+        for job in idle {
+            // One job failing to stop shouldn't keep the others running.
+            match self
+                .stop(&job, |job| Some(NotificationEvent::job_timed_out(job)))
+                .await
+            {
+                Ok(()) => stopped.push(job.id),
+                Err(e) => tracing::warn!("Failed to stop idle session of job {}: {}", job.id, e),
+            }
+        }
+        Ok(stopped)
+    }
+
+    async fn report_metrics(
+        &self,
+        id: &JobId,
+        user_id: &UserId,
+        metrics: Vec<ReportedMetric>,
+    ) -> Result<(), TrainingJobServiceError> {
+        self.record_reported_metrics(id, Reporter::User(user_id), metrics)
+            .await
+    }
+
+    async fn report_metrics_with_job_token(
+        &self,
+        id: &JobId,
+        job_token: &str,
+        metrics: Vec<ReportedMetric>,
+    ) -> Result<(), TrainingJobServiceError> {
+        self.record_reported_metrics(id, Reporter::JobToken(job_token), metrics)
+            .await
+    }
+
+    async fn get_metrics(&self, id: &JobId) -> Result<Vec<JobMetric>, TrainingJobServiceError> {
+        self.repository.get_training_job_by_id(id).await?;
+        Ok(self.repository.get_metrics(id).await?)
     }
 }
@@ -1,7 +1,10 @@
 use crate::domain::{
-    auth::service::AuthServiceError, cluster::service::ClusterServiceError,
-    queue::service::QueueServiceError, training_job::service::TrainingJobServiceError,
-    user::service::UserServiceError,
+    accounting::service::AccountingServiceError, auth::service::AuthServiceError,
+    cluster::service::ClusterServiceError, exec::service::ExecServiceError,
+    idempotency::service::IdempotencyServiceError, job_template::service::JobTemplateServiceError,
+    notification::service::NotificationServiceError, queue::service::QueueServiceError,
+    quota::service::QuotaServiceError, sweep::service::SweepServiceError,
+    training_job::service::TrainingJobServiceError, user::service::UserServiceError,
 };
 
 use axum::{
@@ -20,6 +23,7 @@ pub enum ApiError {
     NotFound(String),
     Unauthorized(String),
     Forbidden,
+    BadGateway(String),
 }
 
 impl From<QueueServiceError> for ApiError {
@@ -93,6 +97,34 @@ impl From<TrainingJobServiceError> for ApiError {
             TrainingJobServiceError::InvalidDefinition(e) => {
                 Self::BadRequest(format!("Invalid job definition: {e}"))
             }
+            TrainingJobServiceError::QueueNotFound(id) => {
+                Self::UnprocessableEntity(format!("Queue {id} does not exist"))
+            }
+            TrainingJobServiceError::Unschedulable(msg) => {
+                Self::UnprocessableEntity(format!("Training job cannot be scheduled: {msg}"))
+            }
+            TrainingJobServiceError::InvalidMetadata(msg) => {
+                Self::BadRequest(format!("Invalid labels or annotations: {msg}"))
+            }
+            TrainingJobServiceError::InvalidSession(msg) => {
+                Self::BadRequest(format!("Invalid session settings: {msg}"))
+            }
+            TrainingJobServiceError::NotFinished(id) => Self::Conflict(format!(
+                "Training job {id} must finish before it can be resubmitted"
+            )),
+            TrainingJobServiceError::InvalidBulkSelection(msg) => {
+                Self::BadRequest(format!("Invalid job selection: {msg}"))
+            }
+            TrainingJobServiceError::InvalidJobToken => {
+                Self::Unauthorized("Invalid job token".to_string())
+            }
+            TrainingJobServiceError::InvalidGracePeriod(msg) => {
+                Self::BadRequest(format!("Invalid termination grace period: {msg}"))
+            }
+            TrainingJobServiceError::InvalidMetrics(msg) => {
+                Self::BadRequest(format!("Invalid metrics: {msg}"))
+            }
+            TrainingJobServiceError::InvalidPermissions => Self::Forbidden,
             TrainingJobServiceError::Unknown(e) => {
                 tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
                 Self::InternalServerError("Something went wrong".to_string())
@@ -101,6 +133,135 @@ impl From<TrainingJobServiceError> for ApiError {
     }
 }
 
+impl From<AccountingServiceError> for ApiError {
+    fn from(err: AccountingServiceError) -> Self {
+        match err {
+            AccountingServiceError::InvalidPeriod(msg) => Self::BadRequest(msg),
+            AccountingServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
+impl From<QuotaServiceError> for ApiError {
+    fn from(err: QuotaServiceError) -> Self {
+        match err {
+            QuotaServiceError::SubjectNotFound(subject) => {
+                Self::NotFound(format!("{subject} not found"))
+            }
+            QuotaServiceError::InvalidQuota(msg) => Self::BadRequest(msg),
+            QuotaServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
+impl From<JobTemplateServiceError> for ApiError {
+    fn from(err: JobTemplateServiceError) -> Self {
+        match err {
+            JobTemplateServiceError::TemplateExists { value, .. } => {
+                Self::Conflict(format!("Job template {value} already exists"))
+            }
+            JobTemplateServiceError::TemplateNotFound(_) => {
+                Self::NotFound("Job template not found".to_string())
+            }
+            JobTemplateServiceError::InvalidTemplate(msg) => Self::BadRequest(msg),
+            JobTemplateServiceError::InvalidPermissions => Self::Forbidden,
+            JobTemplateServiceError::TrainingJob(e) => e.into(),
+            JobTemplateServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
+impl From<SweepServiceError> for ApiError {
+    fn from(err: SweepServiceError) -> Self {
+        match err {
+            SweepServiceError::SweepNotFound(_) => Self::NotFound("Sweep not found".to_string()),
+            SweepServiceError::InvalidSweep(msg) => Self::BadRequest(msg),
+            SweepServiceError::NotRunning(id) => {
+                Self::Conflict(format!("Sweep {id} is not running"))
+            }
+            SweepServiceError::InvalidPermissions => Self::Forbidden,
+            SweepServiceError::TrainingJob(e) => e.into(),
+            SweepServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
+impl From<NotificationServiceError> for ApiError {
+    fn from(err: NotificationServiceError) -> Self {
+        match err {
+            NotificationServiceError::SubscriptionNotFound(_) => {
+                Self::NotFound("Subscription not found".to_string())
+            }
+            NotificationServiceError::InvalidSubscription(msg) => Self::BadRequest(msg),
+            NotificationServiceError::ScopeNotFound(id) => {
+                Self::UnprocessableEntity(format!("Queue or job {id} does not exist"))
+            }
+            NotificationServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
+impl From<ExecServiceError> for ApiError {
+    fn from(err: ExecServiceError) -> Self {
+        match err {
+            ExecServiceError::JobNotFound(_) => {
+                Self::NotFound("Training job not found".to_string())
+            }
+            ExecServiceError::JobNotRunning(_) => {
+                Self::Conflict("Training job is not running".to_string())
+            }
+            ExecServiceError::InvalidPermissions => Self::Forbidden,
+            ExecServiceError::InvalidAccessToken => {
+                Self::Unauthorized("Invalid session access token".to_string())
+            }
+            ExecServiceError::InvalidRequest(msg) => Self::BadRequest(msg),
+            e @ ExecServiceError::PortNotExposed(_) => Self::NotFound(e.to_string()),
+            ExecServiceError::SessionNotFound(_) => {
+                Self::NotFound("Exec session not found".to_string())
+            }
+            ExecServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
+impl From<IdempotencyServiceError> for ApiError {
+    fn from(err: IdempotencyServiceError) -> Self {
+        match err {
+            IdempotencyServiceError::InvalidKey(msg) => {
+                Self::BadRequest(format!("Invalid Idempotency-Key: {msg}"))
+            }
+            IdempotencyServiceError::KeyReused(_) => Self::Conflict(
+                "Idempotency-Key was already used for a different request".to_string(),
+            ),
+            IdempotencyServiceError::InProgress(_) => Self::Conflict(
+                "A request with this Idempotency-Key is still being processed".to_string(),
+            ),
+            IdempotencyServiceError::Unknown(e) => {
+                tracing::error!(error = ?e, backtrace = %e.backtrace(), "unknown error occurred");
+                Self::InternalServerError("Something went wrong".to_string())
+            }
+        }
+    }
+}
+
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
         tracing::error!(error = ?err, "Detailed error: {:?}", err);
@@ -125,6 +286,7 @@ impl IntoResponse for ApiError {
             ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             ApiError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            ApiError::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
         };
 
         let body = Json(json!({ "error": error_message }));
@@ -0,0 +1,239 @@
+use axum::extract::ws::{Message, WebSocket};
+
+use crate::domain::exec::models::{ExecChannel, ExecMessage};
+
+/// Relays an exec session between a WebSocket and one end of the session
+/// until either side closes. Terminal data is sent as binary frames and
+/// control messages as JSON text frames.
+pub async fn relay(mut socket: WebSocket, mut channel: ExecChannel) {
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let message = match incoming {
+                    Some(Ok(Message::Binary(data))) => ExecMessage::Data(data.to_vec()),
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                        Ok(control) => ExecMessage::Control(control),
+                        Err(e) => {
+                            tracing::debug!(error = %e, "ignoring invalid exec control message");
+                            continue;
+                        }
+                    },
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                };
+                if channel.tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+            outgoing = channel.rx.recv() => {
+                let message = match outgoing {
+                    Some(ExecMessage::Data(data)) => Message::Binary(data.into()),
+                    Some(ExecMessage::Control(control)) => Message::Text(
+                        serde_json::to_string(&control)
+                            .expect("exec control messages to serialize")
+                            .into(),
+                    ),
+                    None => {
+                        let _ = socket.send(Message::Close(None)).await;
+                        break;
+                    }
+                };
+                if socket.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            cluster::{
+                models::{Cluster, ClusterNode},
+                ports::MockClusterRepository,
+                service::MockClusterService,
+            },
+            exec::service::ExecServiceImpl,
+            training_job::{
+                models::{TrainingJob, TrainingJobStatus},
+                ports::MockTrainingJobRepository,
+            },
+            user::{models::User, service::MockUserService},
+        },
+        inbound::http::{
+            routes::{clusters, training_jobs},
+            AppState,
+        },
+    };
+    use chrono::Utc;
+    use futures::{SinkExt, StreamExt};
+    use std::{net::SocketAddr, sync::Arc, time::Duration};
+    use tokio_tungstenite::tungstenite::{
+        client::IntoClientRequest, http::StatusCode, Error as WsError, Message,
+    };
+
+    /// Serves the job and cluster routes on a local port, with a real exec
+    /// service in front of `job` running on `node`.
+    async fn serve(owner: User, job: TrainingJob, node: ClusterNode) -> SocketAddr {
+        let mut mock_user_service = MockUserService::new();
+        mock_user_service
+            .expect_authenticate_by_api_key()
+            .returning(move |key| {
+                use secrecy::ExposeSecret;
+                match key.expose_secret() {
+                    "owner-key" => Ok(owner.clone()),
+                    _ => Ok(User::new_mock()),
+                }
+            });
+        let cluster_id = node.cluster_id;
+        let mut mock_cluster_service = MockClusterService::new();
+        mock_cluster_service
+            .expect_authenticate_by_api_key()
+            .returning(move |_| {
+                Ok(Cluster {
+                    id: cluster_id,
+                    name: "test-cluster".to_string(),
+                    description: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+            });
+        let mut mock_job_repo = MockTrainingJobRepository::new();
+        mock_job_repo
+            .expect_get_training_job_by_id()
+            .returning(move |_| Ok(job.clone()));
+        let mut mock_cluster_repo = MockClusterRepository::new();
+        mock_cluster_repo
+            .expect_get_cluster_node_by_id()
+            .returning(move |_| Ok(node.clone()));
+
+        let mut app_state = AppState::new_mock();
+        app_state.user_service = Arc::new(mock_user_service);
+        app_state.cluster_service = Arc::new(mock_cluster_service);
+        app_state.exec_service = Arc::new(ExecServiceImpl::new(
+            Arc::new(mock_job_repo),
+            Arc::new(mock_cluster_repo),
+            vec![],
+            Duration::from_secs(30),
+        ));
+        let app = training_jobs::training_jobs_router()
+            .merge(clusters::router())
+            .with_state(app_state);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    fn ws_request(
+        url: String,
+        key: &str,
+    ) -> tokio_tungstenite::tungstenite::handshake::client::Request {
+        let mut request = url.into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Authorization", format!("Bearer {key}").parse().unwrap());
+        request
+    }
+
+    fn running_job(owner: &User, node: &ClusterNode) -> TrainingJob {
+        TrainingJob {
+            status: TrainingJobStatus::Running,
+            user_id: Some(owner.id),
+            node_id: Some(node.id),
+            ..TrainingJob::new_mock()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exec_session_is_relayed_through_agent() {
+        let owner = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&owner, &node);
+        let addr = serve(owner, job.clone(), node.clone()).await;
+
+        let (mut user_socket, _) = tokio_tungstenite::connect_async(ws_request(
+            format!(
+                "ws://{addr}/training_jobs/{}/exec?command=bash&command=-l&tty=true",
+                job.id
+            ),
+            "owner-key",
+        ))
+        .await
+        .unwrap();
+
+        let sessions: serde_json::Value = reqwest::Client::new()
+            .get(format!("http://{addr}/node/{}/exec_sessions", node.id))
+            .bearer_auth("cluster-key")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(sessions.as_array().unwrap().len(), 1);
+        assert_eq!(sessions[0]["job_id"], job.id.to_string());
+        assert_eq!(sessions[0]["command"], serde_json::json!(["bash", "-l"]));
+        assert_eq!(sessions[0]["tty"], true);
+
+        let (mut agent_socket, _) = tokio_tungstenite::connect_async(ws_request(
+            format!(
+                "ws://{addr}/node/{}/exec_sessions/{}/attach",
+                node.id,
+                sessions[0]["id"].as_str().unwrap()
+            ),
+            "cluster-key",
+        ))
+        .await
+        .unwrap();
+
+        user_socket
+            .send(Message::Binary(b"ls\n".to_vec().into()))
+            .await
+            .unwrap();
+        assert_eq!(
+            agent_socket.next().await.unwrap().unwrap(),
+            Message::Binary(b"ls\n".to_vec().into())
+        );
+
+        agent_socket
+            .send(Message::Text(r#"{"type":"exit","code":3}"#.into()))
+            .await
+            .unwrap();
+        agent_socket.close(None).await.unwrap();
+
+        let Message::Text(exit) = user_socket.next().await.unwrap().unwrap() else {
+            panic!("expected the exit message");
+        };
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&exit).unwrap(),
+            serde_json::json!({"type": "exit", "code": 3})
+        );
+        assert!(matches!(
+            user_socket.next().await,
+            Some(Ok(Message::Close(_))) | None
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_exec_is_forbidden_for_other_users() {
+        let owner = User::new_mock();
+        let node = ClusterNode::new_mock();
+        let job = running_job(&owner, &node);
+        let addr = serve(owner, job.clone(), node).await;
+
+        let result = tokio_tungstenite::connect_async(ws_request(
+            format!("ws://{addr}/training_jobs/{}/exec?command=bash", job.id),
+            "someone-elses-key",
+        ))
+        .await;
+
+        match result {
+            Err(WsError::Http(response)) => assert_eq!(response.status(), StatusCode::FORBIDDEN),
+            other => panic!("expected the upgrade to be refused, got {other:?}"),
+        }
+    }
+}
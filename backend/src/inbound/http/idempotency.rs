@@ -0,0 +1,505 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    domain::{
+        auth::models::Claims,
+        idempotency::{
+            models::{fingerprint, StoredResponse},
+            service::IdempotencyService,
+        },
+    },
+    inbound::http::{errors::ApiError, AppState},
+};
+
+pub static IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+pub static IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Request and response bodies larger than this are not buffered, and
+/// requests carrying them are rejected when they use an `Idempotency-Key`.
+const MAX_BUFFERED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Makes mutating requests that carry an `Idempotency-Key` header safe to
+/// retry. The first request with a key is processed normally and its response
+/// is stored; retries with the same key and the same request get the stored
+/// response back, while a different request reusing the key is rejected.
+///
+/// Keys are scoped to the caller, so callers can't see each other's
+/// responses. Server errors are not stored, so a request that failed with a
+/// 5xx can be retried with the same key, and neither are requests the client
+/// gave up on before they finished. A response too large to store is
+/// replaced by a conflict, as the request must not be processed twice.
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    ) {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let Ok(key) = key.to_str().map(str::to_string) else {
+        return ApiError::BadRequest("Invalid Idempotency-Key: must be ASCII".to_string())
+            .into_response();
+    };
+
+    let idempotency_service = state.idempotency_service.clone();
+
+    let (mut parts, body) = request.into_parts();
+    let scope = caller_scope(&state, &mut parts).await;
+    let Ok(body) = to_bytes(body, MAX_BUFFERED_BODY_BYTES).await else {
+        return ApiError::BadRequest("Request body is too large".to_string()).into_response();
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| parts.uri.path());
+    let request_hash = fingerprint(&[parts.method.as_str().as_bytes(), path.as_bytes(), &body]);
+
+    match idempotency_service.begin(&scope, &key, &request_hash).await {
+        Ok(None) => {}
+        Ok(Some(stored)) => return replay(stored),
+        Err(e) => return ApiError::from(e).into_response(),
+    }
+    let claim = Claim {
+        idempotency_service: idempotency_service.clone(),
+        scope: scope.clone(),
+        key: key.clone(),
+        released: false,
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    if response.status().is_server_error() {
+        claim.release().await;
+        return response;
+    }
+
+    // The request has been processed by now, so the key is kept even if the
+    // response can't be stored, and retries are rejected rather than
+    // processing the request again.
+    let (parts, body) = response.into_parts();
+    if body.size_hint().lower() > MAX_BUFFERED_BODY_BYTES as u64 {
+        claim.complete(not_stored()).await;
+        return Response::from_parts(parts, body);
+    }
+    let body = match to_bytes(body, MAX_BUFFERED_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!(error = ?e, "failed to buffer response for idempotency key");
+            claim.complete(not_stored()).await;
+            return ApiError::InternalServerError("Something went wrong".to_string())
+                .into_response();
+        }
+    };
+
+    claim
+        .complete(StoredResponse {
+            status: parts.status.as_u16(),
+            content_type: parts
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            body: body.to_vec(),
+        })
+        .await;
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Stands in for a response that was too large to store, so that a retry is
+/// rejected rather than processed again.
+fn not_stored() -> StoredResponse {
+    StoredResponse {
+        status: StatusCode::CONFLICT.as_u16(),
+        content_type: Some("application/json".to_string()),
+        body: serde_json::json!({
+            "error": "A request with this Idempotency-Key was already processed, \
+                      but its response was too large to be stored"
+        })
+        .to_string()
+        .into_bytes(),
+    }
+}
+
+/// Identifies the caller of a request. Users are identified by their id
+/// rather than their token, so that a retry made after refreshing a token
+/// still finds the key. The claims are extracted the same way the handler
+/// extracts them, and handed on to it so the token is validated only once.
+/// Other credentials, such as API keys and job tokens, don't change between
+/// retries and are identified by themselves.
+async fn caller_scope(state: &AppState, parts: &mut Parts) -> String {
+    if let Ok(claims) = Claims::from_request_parts(parts, state).await {
+        let scope = format!("user:{}", claims.sub);
+        parts.extensions.insert(claims);
+        return scope;
+    }
+
+    match parts.headers.get(header::AUTHORIZATION) {
+        Some(authorization) => fingerprint(&[authorization.as_bytes()]),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// A key claimed for a request that is being processed. If the request is
+/// abandoned, e.g. because the client disconnected and its handler was
+/// dropped, the claim is released so that a retry doesn't have to wait for
+/// it to expire.
+struct Claim {
+    idempotency_service: Arc<dyn IdempotencyService>,
+    scope: String,
+    key: String,
+    released: bool,
+}
+
+impl Claim {
+    async fn release(mut self) {
+        self.released = true;
+        if let Err(e) = self
+            .idempotency_service
+            .release(&self.scope, &self.key)
+            .await
+        {
+            tracing::error!(error = ?e, "failed to release idempotency key");
+        }
+    }
+
+    /// Stores the response to the request, keeping the key claimed.
+    async fn complete(mut self, response: StoredResponse) {
+        self.released = true;
+        if let Err(e) = self
+            .idempotency_service
+            .complete(&self.scope, &self.key, response)
+            .await
+        {
+            tracing::error!(error = ?e, "failed to store response for idempotency key");
+        }
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let idempotency_service = self.idempotency_service.clone();
+        let scope = std::mem::take(&mut self.scope);
+        let key = std::mem::take(&mut self.key);
+        tokio::spawn(async move {
+            if let Err(e) = idempotency_service.release(&scope, &key).await {
+                tracing::error!(error = ?e, "failed to release abandoned idempotency key");
+            }
+        });
+    }
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|v| HeaderValue::from_str(&v).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(
+        IDEMPOTENT_REPLAYED.clone(),
+        HeaderValue::from_static("true"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        auth::{
+            models::TokenClaims,
+            service::{AuthServiceError, MockAuthService},
+        },
+        idempotency::service::{IdempotencyServiceError, MockIdempotencyService},
+        training_job::models::JOB_TOKEN_PREFIX,
+        user::models::UserId,
+    };
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use mockall::predicate::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(service: MockIdempotencyService, calls: Arc<AtomicUsize>) -> Router {
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .returning(|_| Err(AuthServiceError::InvalidCredentials));
+        app_with_auth(service, auth_service, calls)
+    }
+
+    fn app_with_auth(
+        service: MockIdempotencyService,
+        auth_service: MockAuthService,
+        calls: Arc<AtomicUsize>,
+    ) -> Router {
+        let state = AppState {
+            idempotency_service: Arc::new(service),
+            auth_service: Arc::new(auth_service),
+            ..AppState::new_mock()
+        };
+        Router::new()
+            .route(
+                "/jobs",
+                post(move || {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        (StatusCode::CREATED, "created")
+                    }
+                }),
+            )
+            .layer(from_fn_with_state(state, idempotency_middleware))
+    }
+
+    fn request(key: Option<&str>) -> Request {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/jobs")
+            .header(header::AUTHORIZATION, "Bearer key");
+        if let Some(key) = key {
+            builder = builder.header(&IDEMPOTENCY_KEY, key);
+        }
+        builder.body(Body::from("{}")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_without_key_is_not_tracked() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(MockIdempotencyService::new(), calls.clone());
+
+        let response = app.oneshot(request(None)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_first_request_stores_response() {
+        let mut service = MockIdempotencyService::new();
+        service
+            .expect_begin()
+            .with(always(), eq("key-1"), always())
+            .times(1)
+            .returning(|_, _, _| Ok(None));
+        service
+            .expect_complete()
+            .withf(|_, key, response| {
+                key == "key-1" && response.status == 201 && response.body == b"created"
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(service, calls.clone());
+
+        let response = app.oneshot(request(Some("key-1"))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(!response.headers().contains_key(&IDEMPOTENT_REPLAYED));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_replays_stored_response() {
+        let mut service = MockIdempotencyService::new();
+        service.expect_begin().returning(|_, _, _| {
+            Ok(Some(StoredResponse {
+                status: 201,
+                content_type: Some("application/json".to_string()),
+                body: br#"{"id":"1"}"#.to_vec(),
+            }))
+        });
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(service, calls.clone());
+
+        let response = app.oneshot(request(Some("key-1"))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[&IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"id":"1"}"#);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reused_key_is_rejected() {
+        let mut service = MockIdempotencyService::new();
+        service
+            .expect_begin()
+            .returning(|_, key, _| Err(IdempotencyServiceError::KeyReused(key.to_string())));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(service, calls.clone());
+
+        let response = app.oneshot(request(Some("key-1"))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_to_user_not_token() {
+        let user_id = UserId::generate();
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .with(eq("key"))
+            .returning(move |_| Ok(TokenClaims::new_mock(user_id)));
+        let mut service = MockIdempotencyService::new();
+        let scope = format!("user:{user_id}");
+        service
+            .expect_begin()
+            .withf(move |s, key, _| s == scope && key == "key-1")
+            .times(1)
+            .returning(|_, _, _| Ok(None));
+        service.expect_complete().returning(|_, _, _| Ok(()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app_with_auth(service, auth_service, calls.clone());
+
+        let response = app.oneshot(request(Some("key-1"))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_claims_are_handed_on_to_handler() {
+        let user_id = UserId::generate();
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .times(1)
+            .returning(move |_| Ok(TokenClaims::new_mock(user_id)));
+        let mut service = MockIdempotencyService::new();
+        service.expect_begin().returning(|_, _, _| Ok(None));
+        service.expect_complete().returning(|_, _, _| Ok(()));
+        let state = AppState {
+            idempotency_service: Arc::new(service),
+            auth_service: Arc::new(auth_service),
+            ..AppState::new_mock()
+        };
+        let app = Router::new()
+            .route(
+                "/jobs",
+                post(|claims: Claims| async move { claims.sub.to_string() }),
+            )
+            .layer(from_fn_with_state(state.clone(), idempotency_middleware))
+            .with_state(state);
+
+        let response = app.oneshot(request(Some("key-1"))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, user_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_response_too_large_to_store_is_not_replayed() {
+        let mut service = MockIdempotencyService::new();
+        service.expect_begin().returning(|_, _, _| Ok(None));
+        service
+            .expect_complete()
+            .withf(|_, key, response| key == "key-1" && response.status == 409)
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        service.expect_release().never();
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .returning(|_| Err(AuthServiceError::InvalidCredentials));
+        let state = AppState {
+            idempotency_service: Arc::new(service),
+            auth_service: Arc::new(auth_service),
+            ..AppState::new_mock()
+        };
+        let large = vec![b'x'; MAX_BUFFERED_BODY_BYTES + 1];
+        let app = Router::new()
+            .route("/jobs", post(move || async move { large }))
+            .layer(from_fn_with_state(state, idempotency_middleware));
+
+        let response = app.oneshot(request(Some("key-1"))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), MAX_BUFFERED_BODY_BYTES + 1);
+    }
+
+    #[test]
+    fn test_not_stored_marker_is_replayed_as_conflict() {
+        let response = replay(not_stored());
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(response.headers()[&IDEMPOTENT_REPLAYED], "true");
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_request_releases_key() {
+        let (released_tx, released_rx) = tokio::sync::oneshot::channel();
+        let released_tx = std::sync::Mutex::new(Some(released_tx));
+        let mut service = MockIdempotencyService::new();
+        service.expect_begin().returning(|_, _, _| Ok(None));
+        service
+            .expect_release()
+            .withf(|_, key| key == "key-1")
+            .times(1)
+            .returning(move |_, _| {
+                if let Some(tx) = released_tx.lock().unwrap().take() {
+                    let _ = tx.send(());
+                }
+                Ok(())
+            });
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .returning(|_| Err(AuthServiceError::InvalidCredentials));
+        let state = AppState {
+            idempotency_service: Arc::new(service),
+            auth_service: Arc::new(auth_service),
+            ..AppState::new_mock()
+        };
+        let app = Router::new()
+            .route("/jobs", post(std::future::pending::<StatusCode>))
+            .layer(from_fn_with_state(state, idempotency_middleware));
+
+        // The client gives up while the handler is still running.
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/jobs")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {JOB_TOKEN_PREFIX}token"),
+            )
+            .header(&IDEMPOTENCY_KEY, "key-1")
+            .body(Body::from("{}"))
+            .unwrap();
+        let result =
+            tokio::time::timeout(std::time::Duration::from_millis(50), app.oneshot(request)).await;
+        assert!(result.is_err());
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), released_rx)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
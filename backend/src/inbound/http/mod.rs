@@ -1,7 +1,10 @@
 pub mod errors;
+pub mod exec;
+pub mod idempotency;
+pub mod proxy;
 pub mod routes;
 
-use axum::{extract::FromRef, Router};
+use axum::{extract::FromRef, middleware::from_fn_with_state, Router};
 use http::{HeaderName, Request};
 use std::sync::Arc;
 use tokio::net::TcpListener;
@@ -18,13 +21,21 @@ use uuid::Uuid;
 use crate::{
     config::LilacConfig,
     domain::{
-        auth::service::AuthService, cluster::service::ClusterService, queue::service::QueueService,
+        accounting::service::AccountingService, auth::service::AuthService,
+        cluster::service::ClusterService, exec::service::ExecService,
+        idempotency::service::IdempotencyService, job_template::service::JobTemplateService,
+        notification::service::NotificationService, queue::service::QueueService,
+        quota::service::QuotaService, sweep::service::SweepService,
         training_job::service::TrainingJobService, user::service::UserService,
     },
-    inbound::http::routes::{clusters, queues, training_jobs},
+    inbound::http::routes::{
+        clusters, job_templates, notifications, queues, quotas, sweeps, training_jobs, usage,
+    },
 };
 
+use self::errors::ApiError;
 use self::routes::{auth, users};
+use crate::domain::auth::models::Claims;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -34,6 +45,13 @@ pub struct AppState {
     pub auth_service: Arc<dyn AuthService>,
     pub training_job_service: Arc<dyn TrainingJobService>,
     pub queue_service: Arc<dyn QueueService>,
+    pub accounting_service: Arc<dyn AccountingService>,
+    pub quota_service: Arc<dyn QuotaService>,
+    pub job_template_service: Arc<dyn JobTemplateService>,
+    pub idempotency_service: Arc<dyn IdempotencyService>,
+    pub notification_service: Arc<dyn NotificationService>,
+    pub exec_service: Arc<dyn ExecService>,
+    pub sweep_service: Arc<dyn SweepService>,
 }
 
 impl FromRef<AppState> for Arc<LilacConfig> {
@@ -72,6 +90,57 @@ impl FromRef<AppState> for Arc<dyn QueueService> {
     }
 }
 
+impl FromRef<AppState> for Arc<dyn AccountingService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.accounting_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn QuotaService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.quota_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn JobTemplateService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.job_template_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn NotificationService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.notification_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn ExecService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.exec_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn SweepService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.sweep_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn IdempotencyService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.idempotency_service.clone()
+    }
+}
+
+impl AppState {
+    /// Whether the caller is one of the administrators, who may act on
+    /// everyone's jobs and set quotas.
+    pub(crate) async fn is_admin(&self, claims: &Claims) -> Result<bool, ApiError> {
+        let user = self.user_service.get_user_by_id(&claims.sub).await?;
+        Ok(self.config.admin_usernames.contains(&user.username))
+    }
+}
+
 pub struct HttpServer {
     app: Router,
     listener: TcpListener,
@@ -107,6 +176,15 @@ impl HttpServer {
             .merge(clusters::router())
             .merge(training_jobs::training_jobs_router())
             .merge(queues::routes())
+            .merge(usage::router())
+            .merge(quotas::router())
+            .merge(job_templates::router())
+            .merge(sweeps::router())
+            .merge(notifications::router())
+            .layer(from_fn_with_state(
+                app_state.clone(),
+                idempotency::idempotency_middleware,
+            ))
             .layer(
                 ServiceBuilder::new()
                     .layer(
@@ -144,9 +222,13 @@ impl AppState {
     /// Creates a new mock AppState with the provided configuration.
     pub fn new_mock_with_config(config: LilacConfig) -> Self {
         use crate::domain::{
-            auth::service::MockAuthService, cluster::service::MockClusterService,
-            queue::service::MockQueueService, training_job::service::MockTrainingJobService,
-            user::service::MockUserService,
+            accounting::service::MockAccountingService, auth::service::MockAuthService,
+            cluster::service::MockClusterService, exec::service::MockExecService,
+            idempotency::service::MockIdempotencyService,
+            job_template::service::MockJobTemplateService,
+            notification::service::MockNotificationService, queue::service::MockQueueService,
+            quota::service::MockQuotaService, sweep::service::MockSweepService,
+            training_job::service::MockTrainingJobService, user::service::MockUserService,
         };
 
         Self {
@@ -156,6 +238,13 @@ impl AppState {
             auth_service: Arc::new(MockAuthService::new()),
             training_job_service: Arc::new(MockTrainingJobService::new()),
             queue_service: Arc::new(MockQueueService::new()),
+            accounting_service: Arc::new(MockAccountingService::new()),
+            quota_service: Arc::new(MockQuotaService::new()),
+            job_template_service: Arc::new(MockJobTemplateService::new()),
+            idempotency_service: Arc::new(MockIdempotencyService::new()),
+            notification_service: Arc::new(MockNotificationService::new()),
+            exec_service: Arc::new(MockExecService::new()),
+            sweep_service: Arc::new(MockSweepService::new()),
         }
     }
 
@@ -171,9 +260,13 @@ mod tests {
     use crate::{
         config::LilacConfig,
         domain::{
-            auth::service::MockAuthService, cluster::service::MockClusterService,
-            queue::service::MockQueueService, training_job::service::MockTrainingJobService,
-            user::service::MockUserService,
+            accounting::service::MockAccountingService, auth::service::MockAuthService,
+            cluster::service::MockClusterService, exec::service::MockExecService,
+            idempotency::service::MockIdempotencyService,
+            job_template::service::MockJobTemplateService,
+            notification::service::MockNotificationService, queue::service::MockQueueService,
+            quota::service::MockQuotaService, sweep::service::MockSweepService,
+            training_job::service::MockTrainingJobService, user::service::MockUserService,
         },
     };
     use axum::{
@@ -206,6 +299,13 @@ mod tests {
             auth_service: Arc::new(MockAuthService::new()),
             training_job_service: Arc::new(MockTrainingJobService::new()),
             queue_service: Arc::new(mock_queue_service),
+            accounting_service: Arc::new(MockAccountingService::new()),
+            quota_service: Arc::new(MockQuotaService::new()),
+            job_template_service: Arc::new(MockJobTemplateService::new()),
+            idempotency_service: Arc::new(MockIdempotencyService::new()),
+            notification_service: Arc::new(MockNotificationService::new()),
+            exec_service: Arc::new(MockExecService::new()),
+            sweep_service: Arc::new(MockSweepService::new()),
         };
 
         let session_store = MemoryStore::default();
@@ -0,0 +1,185 @@
+use axum::{body::Body, extract::Request, response::Response};
+use http::{header, HeaderValue, StatusCode, Version};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+use crate::{
+    domain::exec::models::{ExecChannel, ExecControl, ExecMessage},
+    inbound::http::errors::ApiError,
+};
+
+/// How many bytes of a tunneled connection are buffered in each direction.
+const TUNNEL_BUFFER: usize = 64 * 1024;
+
+/// Turns the user's end of a tunnel session into a byte stream, so that it
+/// can be used like a TCP connection to the job's port.
+pub fn tunnel_stream(mut channel: ExecChannel) -> DuplexStream {
+    let (ours, theirs) = tokio::io::duplex(TUNNEL_BUFFER);
+    let (mut reader, mut writer) = tokio::io::split(theirs);
+    tokio::spawn(async move {
+        let mut buf = vec![0; TUNNEL_BUFFER];
+        let mut sending = true;
+        let mut receiving = true;
+        while sending || receiving {
+            tokio::select! {
+                read = reader.read(&mut buf), if sending => {
+                    let message = match read {
+                        Ok(0) | Err(_) => {
+                            sending = false;
+                            ExecMessage::Control(ExecControl::Eof)
+                        }
+                        Ok(n) => ExecMessage::Data(buf[..n].to_vec()),
+                    };
+                    if channel.tx.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                message = channel.rx.recv(), if receiving => match message {
+                    Some(ExecMessage::Data(data)) => {
+                        if writer.write_all(&data).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(ExecMessage::Control(ExecControl::Eof)) => {
+                        receiving = false;
+                        let _ = writer.shutdown().await;
+                    }
+                    Some(ExecMessage::Control(ExecControl::Error { message })) => {
+                        tracing::debug!(%message, "tunnel session failed");
+                        break;
+                    }
+                    Some(ExecMessage::Control(_)) => {}
+                    None => break,
+                },
+            }
+        }
+    });
+    ours
+}
+
+/// Forwards an HTTP request to a job's service over a tunnel session, and
+/// its response back. WebSocket and other upgrades are relayed once the
+/// service accepts them. `path` is the request's path within the service.
+pub async fn forward(
+    channel: ExecChannel,
+    mut request: Request,
+    path: &str,
+) -> Result<Response, ApiError> {
+    let stream = TokioIo::new(tunnel_stream(channel));
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(stream)
+        .await
+        .map_err(bad_gateway)?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.with_upgrades().await {
+            tracing::debug!(error = %e, "proxied connection failed");
+        }
+    });
+
+    let client_upgrade = hyper::upgrade::on(&mut request);
+    let (mut parts, body) = request.into_parts();
+    let path_and_query = match parts.uri.query() {
+        Some(query) => format!("/{path}?{query}"),
+        None => format!("/{path}"),
+    };
+    parts.uri = path_and_query
+        .parse()
+        .map_err(|_| ApiError::BadRequest("Invalid proxy path".to_string()))?;
+    parts.version = Version::HTTP_11;
+    // The API key is for the control plane, not the job's services.
+    parts.headers.remove(header::AUTHORIZATION);
+    if !parts.headers.contains_key(header::HOST) {
+        parts
+            .headers
+            .insert(header::HOST, HeaderValue::from_static("localhost"));
+    }
+
+    let mut response = sender
+        .send_request(Request::from_parts(parts, body))
+        .await
+        .map_err(bad_gateway)?;
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        let service_upgrade = hyper::upgrade::on(&mut response);
+        tokio::spawn(async move {
+            match tokio::try_join!(client_upgrade, service_upgrade) {
+                Ok((client, service)) => {
+                    let _ = tokio::io::copy_bidirectional(
+                        &mut TokioIo::new(client),
+                        &mut TokioIo::new(service),
+                    )
+                    .await;
+                }
+                Err(e) => tracing::debug!(error = %e, "failed to upgrade proxied connection"),
+            }
+        });
+    }
+
+    Ok(response.map(Body::new))
+}
+
+fn bad_gateway(err: hyper::Error) -> ApiError {
+    tracing::debug!(error = %err, "failed to reach job service");
+    ApiError::BadGateway("Could not reach the service in the training job".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{forward, tunnel_stream};
+    use crate::{domain::exec::models::ExecChannel, inbound::http::errors::ApiError};
+    use axum::{body::Body, extract::Request};
+    use http::{header, StatusCode};
+    use http_body_util::BodyExt;
+    use hyper::{server::conn::http1, service::service_fn, Response};
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_forward_relays_request_to_job_service() {
+        let (user_end, agent_end) = ExecChannel::pair();
+
+        // Play the job's service on the agent's end of the tunnel, echoing
+        // what it received.
+        tokio::spawn(async move {
+            let service = service_fn(
+                |request: hyper::Request<hyper::body::Incoming>| async move {
+                    let echo = format!(
+                        "{} {} authorized={}",
+                        request.method(),
+                        request.uri(),
+                        request.headers().contains_key(header::AUTHORIZATION)
+                    );
+                    Ok::<_, Infallible>(Response::new(echo))
+                },
+            );
+            http1::Builder::new()
+                .serve_connection(TokioIo::new(tunnel_stream(agent_end)), service)
+                .await
+                .unwrap();
+        });
+
+        let request = Request::builder()
+            .uri("/training_jobs/1/proxy/6006/data/runs?tag=loss")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = forward(user_end, request, "data/runs").await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"GET /data/runs?tag=loss authorized=false");
+    }
+
+    #[tokio::test]
+    async fn test_forward_fails_if_tunnel_closes() {
+        let (user_end, agent_end) = ExecChannel::pair();
+        drop(agent_end);
+
+        let request = Request::builder()
+            .uri("/training_jobs/1/proxy/6006/")
+            .body(Body::empty())
+            .unwrap();
+        let response = forward(user_end, request, "").await;
+
+        assert!(matches!(response, Err(ApiError::BadGateway(_))));
+    }
+}
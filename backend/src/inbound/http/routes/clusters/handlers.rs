@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, State, WebSocketUpgrade},
+    response::Response,
     Json,
 };
 use chrono::Utc;
@@ -14,15 +15,17 @@ use crate::{
             models::{ClusterId, NodeId, UpdateNodeStatusRequest},
             service::ClusterService,
         },
+        exec::{models::ExecSessionId, service::ExecService},
         training_job::service::TrainingJobService,
         user::models::{ApiKeyId, NewApiKey},
     },
     inbound::http::{
         errors::ApiError,
+        exec::relay,
         routes::clusters::models::{
             CreateClusterHttpRequest, CreateClusterHttpResponse, GetClusterDetailsHttpResponse,
             GetClusterHttpResponse, HttpApiKey, HttpClusterNode, HttpClusterNodeHeartbeat,
-            HttpHeartbeatResponse, HttpJobDetails, ListClusterJobsHttpResponse,
+            HttpExecSession, HttpHeartbeatResponse, HttpJobDetails, ListClusterJobsHttpResponse,
             ListClusterNodesHttpResponse, ListClustersHttpResponse,
         },
     },
@@ -142,17 +145,83 @@ pub async fn cluster_node_heartbeat(
             cpu_info: req.cpu_info,
             gpu_info: req.gpu_info,
             job_info: req.job_info,
+            cached_images: req.cached_images,
+            draining: req.draining,
         })
         .await?;
 
     let assigned_job = if let Some(job_id) = node.assigned_job_id {
         let job_details = training_job_service.get_training_job_by_id(&job_id).await?;
-        Some(HttpJobDetails::from(job_details))
+        Some(HttpJobDetails::new(job_details, cluster.id))
     } else {
         None
     };
 
-    Ok(Json(HttpHeartbeatResponse { assigned_job }))
+    let protected_images = training_job_service.get_queued_images().await?;
+    let prefetch_images = cluster_service.get_prefetch_images(&node).await?;
+
+    Ok(Json(HttpHeartbeatResponse {
+        assigned_job,
+        protected_images,
+        prefetch_images,
+    }))
+}
+
+/// Removes a node whose agent is shutting down, re-queueing its unfinished
+/// jobs.
+#[axum::debug_handler(state = AppState)]
+pub async fn deregister_node(
+    Path(node_id): Path<NodeId>,
+    State(cluster_service): State<Arc<dyn ClusterService>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<(), ApiError> {
+    let cluster = cluster_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    cluster_service
+        .deregister_node(&node_id, &cluster.id)
+        .await?;
+    Ok(())
+}
+
+/// How long an agent's request for exec sessions is held open when there
+/// are none.
+const EXEC_SESSION_POLL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(25);
+
+/// Long-polls for exec sessions opened on the node.
+#[axum::debug_handler(state = AppState)]
+pub async fn list_node_exec_sessions(
+    Path(node_id): Path<NodeId>,
+    State(cluster_service): State<Arc<dyn ClusterService>>,
+    State(exec_service): State<Arc<dyn ExecService>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<Vec<HttpExecSession>>, ApiError> {
+    let cluster = cluster_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let sessions = exec_service
+        .wait_for_sessions(&node_id, &cluster.id, EXEC_SESSION_POLL_TIMEOUT)
+        .await?;
+    Ok(Json(sessions.into_iter().map(Into::into).collect()))
+}
+
+/// Connects the agent to an exec session over a WebSocket.
+#[axum::debug_handler(state = AppState)]
+pub async fn attach_node_exec_session(
+    ws: WebSocketUpgrade,
+    Path((_node_id, session_id)): Path<(NodeId, ExecSessionId)>,
+    State(cluster_service): State<Arc<dyn ClusterService>>,
+    State(exec_service): State<Arc<dyn ExecService>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<Response, ApiError> {
+    let cluster = cluster_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let channel = exec_service.attach_agent(&session_id, &cluster.id).await?;
+    Ok(ws.on_upgrade(move |socket| relay(socket, channel)))
 }
 
 #[axum::debug_handler(state = AppState)]
@@ -28,7 +28,16 @@ pub fn router() -> Router<AppState> {
         )
         .route("/clusters/{cluster_id}/jobs", get(list_cluster_jobs))
         .route("/nodes/{node_id}", get(get_node))
+        .route("/node/{node_id}", delete(deregister_node))
         .route("/node/{node_id}/status", post(cluster_node_heartbeat))
+        .route(
+            "/node/{node_id}/exec_sessions",
+            get(list_node_exec_sessions),
+        )
+        .route(
+            "/node/{node_id}/exec_sessions/{session_id}/attach",
+            get(attach_node_exec_session),
+        )
 }
 
 #[cfg(test)]
@@ -44,7 +53,7 @@ mod tests {
                 service::MockClusterService,
             },
             training_job::{
-                models::{JobId, TrainingJob, TrainingJobStatus},
+                models::{AssignedGpu, FailureReason, JobId, TrainingJob, TrainingJobStatus},
                 service::MockTrainingJobService,
             },
             user::models::{ApiKey, ApiKeyId, NewApiKey, UserId},
@@ -105,6 +114,16 @@ mod tests {
         cluster_service
     }
 
+    /// A job service for heartbeats, with `images` as the images of queued jobs.
+    fn mock_queued_images(images: &[&str]) -> MockTrainingJobService {
+        let images: Vec<String> = images.iter().map(|image| image.to_string()).collect();
+        let mut job_service = MockTrainingJobService::new();
+        job_service
+            .expect_get_queued_images()
+            .returning(move || Ok(images.clone()));
+        job_service
+    }
+
     #[tokio::test]
     async fn test_create_api_key_for_cluster_route() {
         let user_id = UserId::generate();
@@ -477,6 +496,9 @@ mod tests {
             job_info: Some(JobInfo {
                 current_job_id: job_id,
                 status: TrainingJobStatus::Running,
+                usage: None,
+                failure_reason: None,
+                assigned_gpus: Vec::new(),
             }),
             ..HttpClusterNodeHeartbeat::new_mock()
         };
@@ -490,7 +512,10 @@ mod tests {
                     ..ClusterNode::new_mock()
                 })
             });
-        let mut mock_job_service = MockTrainingJobService::new();
+        mock_cluster_service
+            .expect_get_prefetch_images()
+            .returning(|_| Ok(Vec::new()));
+        let mut mock_job_service = mock_queued_images(&[]);
         mock_job_service
             .expect_get_training_job_by_id()
             .with(eq(job_id))
@@ -498,6 +523,8 @@ mod tests {
             .returning(move |_| {
                 Ok(TrainingJob {
                     id: job_id,
+                    termination_grace_period_secs: 120,
+                    restart_count: 1,
                     ..TrainingJob::new_mock()
                 })
             });
@@ -517,7 +544,15 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let response_body: HttpHeartbeatResponse = serde_json::from_slice(&body).unwrap();
-        assert!(response_body.assigned_job.is_some());
+        let assigned_job = response_body.assigned_job.unwrap();
+        assert_eq!(assigned_job.termination_grace_period_secs, 120);
+        assert_eq!(assigned_job.attempt, 2);
+        assert_eq!(assigned_job.cluster_id, cluster_id);
+        // The job was re-queued after it had started, so it should resume.
+        assert_eq!(
+            assigned_job.env.get("LILAC_RESUME").map(String::as_str),
+            Some("1")
+        );
     }
 
     #[tokio::test]
@@ -536,7 +571,10 @@ mod tests {
                     ..ClusterNode::new_mock()
                 })
             });
-        let mock_job_service = MockTrainingJobService::new();
+        mock_cluster_service
+            .expect_get_prefetch_images()
+            .returning(|_| Ok(vec!["ghcr.io/acme/resnet:latest".to_string()]));
+        let mock_job_service = mock_queued_images(&["pytorch/pytorch:2.1.0"]);
         let app = setup_test_app(
             mock_cluster_service,
             crate::domain::auth::service::MockAuthService::new(),
@@ -554,5 +592,173 @@ mod tests {
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let response_body: HttpHeartbeatResponse = serde_json::from_slice(&body).unwrap();
         assert!(response_body.assigned_job.is_none());
+        assert_eq!(
+            response_body.protected_images,
+            vec!["pytorch/pytorch:2.1.0"]
+        );
+        assert_eq!(
+            response_body.prefetch_images,
+            vec!["ghcr.io/acme/resnet:latest"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cluster_node_heartbeat_with_oom_killed_job() {
+        let cluster_id = ClusterId::generate();
+        let node_id = NodeId::generate();
+        let job_id = JobId::generate();
+        let cluster_token = "cluster-api-key";
+        let mut heartbeat_body =
+            serde_json::to_value(HttpClusterNodeHeartbeat::new_mock()).unwrap();
+        heartbeat_body["job_info"] = serde_json::json!({
+            "current_job_id": job_id,
+            "status": "failed",
+            "failure_reason": "out_of_memory",
+        });
+        let mut mock_cluster_service = mock_cluster_auth(cluster_id, cluster_token);
+        mock_cluster_service
+            .expect_update_node_status()
+            .withf(move |req| {
+                req.job_info.as_ref().is_some_and(|job_info| {
+                    job_info.current_job_id == job_id
+                        && job_info.failure_reason == Some(FailureReason::OutOfMemory)
+                })
+            })
+            .times(1)
+            .returning(|_| Ok(ClusterNode::new_mock()));
+        mock_cluster_service
+            .expect_get_prefetch_images()
+            .returning(|_| Ok(Vec::new()));
+        let app = setup_test_app(
+            mock_cluster_service,
+            crate::domain::auth::service::MockAuthService::new(),
+            mock_queued_images(&[]),
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/node/{}/status", node_id))
+            .header("Authorization", format!("Bearer {}", cluster_token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(heartbeat_body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_node_heartbeat_with_assigned_gpus() {
+        let cluster_id = ClusterId::generate();
+        let node_id = NodeId::generate();
+        let job_id = JobId::generate();
+        let cluster_token = "cluster-api-key";
+        let mut heartbeat_body =
+            serde_json::to_value(HttpClusterNodeHeartbeat::new_mock()).unwrap();
+        heartbeat_body["job_info"] = serde_json::json!({
+            "current_job_id": job_id,
+            "status": "running",
+            "assigned_gpus": [{ "index": 2, "uuid": "GPU-8f7e" }, { "index": 3, "uuid": null }],
+        });
+        let mut mock_cluster_service = mock_cluster_auth(cluster_id, cluster_token);
+        mock_cluster_service
+            .expect_update_node_status()
+            .withf(|req| {
+                req.job_info.as_ref().is_some_and(|job_info| {
+                    job_info.assigned_gpus
+                        == vec![
+                            AssignedGpu {
+                                index: 2,
+                                uuid: Some("GPU-8f7e".to_string()),
+                            },
+                            AssignedGpu {
+                                index: 3,
+                                uuid: None,
+                            },
+                        ]
+                })
+            })
+            .times(1)
+            .returning(|_| Ok(ClusterNode::new_mock()));
+        mock_cluster_service
+            .expect_get_prefetch_images()
+            .returning(|_| Ok(Vec::new()));
+        let app = setup_test_app(
+            mock_cluster_service,
+            crate::domain::auth::service::MockAuthService::new(),
+            mock_queued_images(&[]),
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/node/{}/status", node_id))
+            .header("Authorization", format!("Bearer {}", cluster_token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(heartbeat_body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_node_heartbeat_while_draining() {
+        let cluster_id = ClusterId::generate();
+        let node_id = NodeId::generate();
+        let cluster_token = "cluster-api-key";
+        let heartbeat_body = HttpClusterNodeHeartbeat {
+            draining: true,
+            ..HttpClusterNodeHeartbeat::new_mock()
+        };
+        let mut mock_cluster_service = mock_cluster_auth(cluster_id, cluster_token);
+        mock_cluster_service
+            .expect_update_node_status()
+            .withf(move |req| req.node_id == node_id && req.draining)
+            .times(1)
+            .returning(|_| {
+                Ok(ClusterNode {
+                    draining: true,
+                    ..ClusterNode::new_mock()
+                })
+            });
+        mock_cluster_service
+            .expect_get_prefetch_images()
+            .returning(|_| Ok(Vec::new()));
+        let app = setup_test_app(
+            mock_cluster_service,
+            crate::domain::auth::service::MockAuthService::new(),
+            mock_queued_images(&[]),
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/node/{}/status", node_id))
+            .header("Authorization", format!("Bearer {}", cluster_token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&heartbeat_body).unwrap()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_deregister_node_route() {
+        let cluster_id = ClusterId::generate();
+        let node_id = NodeId::generate();
+        let cluster_token = "cluster-api-key";
+        let mut mock_cluster_service = mock_cluster_auth(cluster_id, cluster_token);
+        mock_cluster_service
+            .expect_deregister_node()
+            .with(eq(node_id), eq(cluster_id))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let app = setup_test_app(
+            mock_cluster_service,
+            crate::domain::auth::service::MockAuthService::new(),
+            MockTrainingJobService::new(),
+        );
+        let request = Request::builder()
+            .method("DELETE")
+            .uri(format!("/node/{}", node_id))
+            .header("Authorization", format!("Bearer {}", cluster_token))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -8,7 +10,9 @@ use crate::{
             ClusterMemoryStats, ClusterNode, ClusterSummary, Cpu, CreateClusterRequest, Gpu,
             JobInfo, NodeId, NodeStatus,
         },
-        training_job::models::TrainingJob,
+        exec::models::{ExecSession, ExecSessionId, ExecTarget},
+        queue::models::QueueId,
+        training_job::models::{JobId, ResourceRequirements, TrainingJob},
         user::models::{ApiKey, ApiKeyId},
     },
     inbound::http::routes::training_jobs::models::HttpTrainingJob,
@@ -121,20 +125,49 @@ pub struct HttpClusterNodeHeartbeat {
     pub cpu_info: Cpu,
     pub gpu_info: Option<Gpu>,
     pub job_info: Option<JobInfo>,
+    /// The Docker images the agent keeps cached.
+    #[serde(default)]
+    pub cached_images: Vec<String>,
+    /// Whether the agent is shutting down and takes no new jobs.
+    #[serde(default)]
+    pub draining: bool,
 }
 
-/// The body of a [Cluster] list response.
+/// A job assigned to a node, with everything its agent needs to run it.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HttpJobDetails {
     pub id: String,
     pub docker_uri: String,
+    pub env: HashMap<String, String>,
+    pub exposed_ports: Vec<u16>,
+    pub termination_grace_period_secs: i32,
+    pub name: String,
+    pub queue_id: Option<QueueId>,
+    pub cluster_id: ClusterId,
+    pub resource_requirements: ResourceRequirements,
+    /// 1 for the job's first run, counting up each time it is re-queued
+    /// after it had started.
+    pub attempt: i32,
+    pub job_token: Option<String>,
 }
 
-impl From<TrainingJob> for HttpJobDetails {
-    fn from(job: TrainingJob) -> Self {
+impl HttpJobDetails {
+    pub fn new(job: TrainingJob, cluster_id: ClusterId) -> Self {
+        let mut env = job.session_env();
+        env.extend(job.resume_env());
+        env.extend(job.env);
         Self {
             id: job.id.to_string(),
             docker_uri: job.definition,
+            env,
+            exposed_ports: job.exposed_ports,
+            termination_grace_period_secs: job.termination_grace_period_secs,
+            name: job.name,
+            queue_id: job.queue_id,
+            cluster_id,
+            resource_requirements: job.resource_requirements,
+            attempt: job.restart_count + 1,
+            job_token: job.job_token,
         }
     }
 }
@@ -142,6 +175,39 @@ impl From<TrainingJob> for HttpJobDetails {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HttpHeartbeatResponse {
     pub assigned_job: Option<HttpJobDetails>,
+    /// Images of queued jobs, which the agent must not evict from its cache.
+    pub protected_images: Vec<String>,
+    /// Images of jobs near the head of the queues, which an idle agent
+    /// should pull ahead of time.
+    pub prefetch_images: Vec<String>,
+}
+
+/// An exec session for an agent to attach to. Sessions with a `port` tunnel
+/// a connection to that port instead of running `command`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpExecSession {
+    pub id: ExecSessionId,
+    pub job_id: JobId,
+    pub command: Vec<String>,
+    pub tty: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+impl From<ExecSession> for HttpExecSession {
+    fn from(session: ExecSession) -> Self {
+        let (command, tty, port) = match session.target {
+            ExecTarget::Command(request) => (request.command, request.tty, None),
+            ExecTarget::Port(port) => (Vec::new(), false, Some(port)),
+        };
+        Self {
+            id: session.id,
+            job_id: session.job_id,
+            command,
+            tty,
+            port,
+        }
+    }
 }
 
 /// The body of a [ClusterNode] get request.
@@ -154,6 +220,8 @@ pub struct HttpClusterNode {
     pub memory_mb: i32,
     pub cpu: Cpu,
     pub gpu: Option<Gpu>,
+    pub cached_images: Vec<String>,
+    pub draining: bool,
 }
 
 impl From<ClusterNode> for HttpClusterNode {
@@ -166,6 +234,8 @@ impl From<ClusterNode> for HttpClusterNode {
             memory_mb: value.memory_mb,
             cpu: value.cpu,
             gpu: value.gpu,
+            cached_images: value.cached_images,
+            draining: value.draining,
         }
     }
 }
@@ -240,6 +310,8 @@ impl HttpClusterNodeHeartbeat {
             cpu_info: Cpu::new_mock(),
             gpu_info: None,
             job_info: None,
+            cached_images: Vec::new(),
+            draining: false,
         }
     }
 }
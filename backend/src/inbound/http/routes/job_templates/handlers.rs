@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use secrecy::SecretString;
+
+use crate::{
+    domain::{
+        auth::models::Claims,
+        job_template::{models::JobTemplateId, service::JobTemplateService},
+    },
+    inbound::http::{
+        errors::ApiError, routes::training_jobs::models::CreateTrainingJobResponse, AppState,
+    },
+};
+
+use super::models::{HttpCreateJobTemplateRequest, HttpJobTemplate, HttpSubmitFromTemplateRequest};
+
+pub async fn create_job_template(
+    claims: Claims,
+    State(job_template_service): State<Arc<dyn JobTemplateService>>,
+    Json(request): Json<HttpCreateJobTemplateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let template = job_template_service
+        .create_template(request.into(), &claims.sub)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(HttpJobTemplate::from(template))))
+}
+
+pub async fn list_job_templates(
+    claims: Claims,
+    State(job_template_service): State<Arc<dyn JobTemplateService>>,
+) -> Result<Json<Vec<HttpJobTemplate>>, ApiError> {
+    let templates = job_template_service.list_templates(&claims.sub).await?;
+    Ok(Json(templates.into_iter().map(Into::into).collect()))
+}
+
+pub async fn get_job_template(
+    claims: Claims,
+    State(job_template_service): State<Arc<dyn JobTemplateService>>,
+    Path(template_id): Path<JobTemplateId>,
+) -> Result<Json<HttpJobTemplate>, ApiError> {
+    let template = job_template_service
+        .get_template(&template_id, &claims.sub)
+        .await?;
+    Ok(Json(template.into()))
+}
+
+pub async fn delete_job_template(
+    claims: Claims,
+    State(job_template_service): State<Arc<dyn JobTemplateService>>,
+    Path(template_id): Path<JobTemplateId>,
+) -> Result<(), ApiError> {
+    job_template_service
+        .delete_template(&template_id, &claims.sub)
+        .await?;
+    Ok(())
+}
+
+/// Submits a job from a template. Like job creation, this authenticates with
+/// a user API key so that it can be called from the CLI.
+pub async fn submit_from_job_template(
+    State(state): State<AppState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(template_id): Path<JobTemplateId>,
+    Json(request): Json<HttpSubmitFromTemplateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = state
+        .user_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let created = state
+        .job_template_service
+        .submit_from_template(&template_id, &user.id, request.into())
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateTrainingJobResponse::from(created)),
+    ))
+}
@@ -0,0 +1,184 @@
+pub mod handlers;
+pub mod models;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::inbound::http::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/job_templates",
+            post(handlers::create_job_template).get(handlers::list_job_templates),
+        )
+        .route(
+            "/job_templates/{template_id}",
+            get(handlers::get_job_template).delete(handlers::delete_job_template),
+        )
+        .route(
+            "/job_templates/{template_id}/submit",
+            post(handlers::submit_from_job_template),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            auth::{models::TokenClaims, service::MockAuthService},
+            job_template::{
+                models::{JobTemplate, JobTemplateId},
+                service::{JobTemplateServiceError, MockJobTemplateService},
+            },
+            training_job::models::{CreatedTrainingJob, TrainingJob},
+            user::{models::UserId, service::MockUserService},
+        },
+        inbound::http::{
+            routes::{
+                job_templates::models::HttpJobTemplate,
+                training_jobs::models::CreateTrainingJobResponse,
+            },
+            AppState,
+        },
+    };
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use mockall::predicate::*;
+    use secrecy::ExposeSecret;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn setup_test_app(
+        job_template_service: MockJobTemplateService,
+        user_service: MockUserService,
+        user_id: UserId,
+    ) -> axum::Router {
+        let token_claims = TokenClaims::new_mock(user_id);
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .with(eq("user-token"))
+            .returning(move |_| Ok(token_claims.clone()));
+
+        let mut app_state = AppState::new_mock();
+        app_state.job_template_service = Arc::new(job_template_service);
+        app_state.user_service = Arc::new(user_service);
+        app_state.auth_service = Arc::new(auth_service);
+        super::router().with_state(app_state)
+    }
+
+    #[tokio::test]
+    async fn test_create_job_template() {
+        let user_id = UserId::generate();
+        let mut mock_service = MockJobTemplateService::new();
+        mock_service
+            .expect_create_template()
+            .withf(move |request, owner_id| {
+                *owner_id == user_id && request.name == "resnet" && request.shared
+            })
+            .times(1)
+            .returning(move |request, owner_id| {
+                Ok(JobTemplate {
+                    name: request.name,
+                    owner_id: *owner_id,
+                    shared: request.shared,
+                    ..JobTemplate::new_mock()
+                })
+            });
+
+        let app = setup_test_app(mock_service, MockUserService::new(), user_id);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/job_templates")
+            .header("Authorization", "Bearer user-token")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                r#"{
+                    "name": "resnet",
+                    "shared": true,
+                    "definition": "ghcr.io/acme/resnet:latest",
+                    "resource_requirements": {"cpu_millicores": 1000, "memory_mb": 1024, "gpus": null},
+                    "env": {"EPOCHS": "10"}
+                }"#,
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let template: HttpJobTemplate = serde_json::from_slice(&body).unwrap();
+        assert_eq!(template.owner_id, user_id);
+        assert!(template.shared);
+    }
+
+    #[tokio::test]
+    async fn test_get_job_template_not_found() {
+        let user_id = UserId::generate();
+        let template_id = JobTemplateId::generate();
+        let mut mock_service = MockJobTemplateService::new();
+        mock_service
+            .expect_get_template()
+            .with(eq(template_id), eq(user_id))
+            .times(1)
+            .returning(|id, _| Err(JobTemplateServiceError::TemplateNotFound(id.to_string())));
+
+        let app = setup_test_app(mock_service, MockUserService::new(), user_id);
+        let request = Request::builder()
+            .uri(format!("/job_templates/{template_id}"))
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_submit_from_job_template() {
+        let api_key = "user-api-key";
+        let template_id = JobTemplateId::generate();
+
+        let mut mock_user_service = MockUserService::new();
+        mock_user_service
+            .expect_authenticate_by_api_key()
+            .withf(move |secret| secret.expose_secret() == api_key)
+            .times(1)
+            .returning(|_| Ok(Default::default()));
+
+        let mut mock_service = MockJobTemplateService::new();
+        mock_service
+            .expect_submit_from_template()
+            .withf(move |id, _, overrides| {
+                *id == template_id && overrides.env.get("EPOCHS").map(String::as_str) == Some("20")
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(CreatedTrainingJob {
+                    job: TrainingJob::new_mock(),
+                    warnings: vec![],
+                })
+            });
+
+        let app = setup_test_app(mock_service, mock_user_service, UserId::generate());
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/job_templates/{template_id}/submit"))
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"env": {"EPOCHS": "20"}}"#))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let _: CreateTrainingJobResponse = serde_json::from_slice(&body).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    job_template::models::{
+        CreateJobTemplateRequest, JobTemplate, JobTemplateId, JobTemplateOverrides,
+    },
+    queue::models::QueueId,
+    training_job::models::ResourceRequirements,
+    user::models::UserId,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpCreateJobTemplateRequest {
+    pub name: String,
+    #[serde(default)]
+    pub shared: bool,
+    pub definition: String,
+    pub queue_id: Option<QueueId>,
+    pub resource_requirements: ResourceRequirements,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+impl From<HttpCreateJobTemplateRequest> for CreateJobTemplateRequest {
+    fn from(value: HttpCreateJobTemplateRequest) -> Self {
+        Self {
+            name: value.name,
+            shared: value.shared,
+            definition: value.definition,
+            queue_id: value.queue_id,
+            resource_requirements: value.resource_requirements,
+            env: value.env,
+            labels: value.labels,
+        }
+    }
+}
+
+/// The body of a submit-from-template request. Every field is optional.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HttpSubmitFromTemplateRequest {
+    pub name: Option<String>,
+    pub definition: Option<String>,
+    pub queue_id: Option<QueueId>,
+    pub resource_requirements: Option<ResourceRequirements>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+    #[serde(default)]
+    pub exposed_ports: Vec<u16>,
+}
+
+impl From<HttpSubmitFromTemplateRequest> for JobTemplateOverrides {
+    fn from(value: HttpSubmitFromTemplateRequest) -> Self {
+        Self {
+            name: value.name,
+            definition: value.definition,
+            queue_id: value.queue_id,
+            resource_requirements: value.resource_requirements,
+            env: value.env,
+            labels: value.labels,
+            annotations: value.annotations,
+            exposed_ports: value.exposed_ports,
+        }
+    }
+}
+
+/// An HTTP representation of a [JobTemplate].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpJobTemplate {
+    pub id: JobTemplateId,
+    pub name: String,
+    pub owner_id: UserId,
+    pub shared: bool,
+    pub definition: String,
+    pub queue_id: Option<QueueId>,
+    pub resource_requirements: ResourceRequirements,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<JobTemplate> for HttpJobTemplate {
+    fn from(template: JobTemplate) -> Self {
+        Self {
+            id: template.id,
+            name: template.name,
+            owner_id: template.owner_id,
+            shared: template.shared,
+            definition: template.definition,
+            queue_id: template.queue_id,
+            resource_requirements: template.resource_requirements,
+            env: template.env,
+            labels: template.labels,
+            created_at: template.created_at,
+            updated_at: template.updated_at,
+        }
+    }
+}
@@ -1,5 +1,10 @@
 pub mod auth;
 pub mod clusters;
+pub mod job_templates;
+pub mod notifications;
 pub mod queues;
+pub mod quotas;
+pub mod sweeps;
 pub mod training_jobs;
+pub mod usage;
 pub mod users;
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    domain::{
+        auth::models::Claims,
+        notification::{models::SubscriptionId, service::NotificationService},
+    },
+    inbound::http::errors::ApiError,
+};
+
+use super::models::{
+    HttpCreateSubscriptionRequest, HttpCreatedSubscription, HttpDelivery, HttpSubscription,
+};
+
+pub async fn create_subscription(
+    claims: Claims,
+    State(notification_service): State<Arc<dyn NotificationService>>,
+    Json(request): Json<HttpCreateSubscriptionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let subscription = notification_service
+        .create_subscription(request.into(), &claims.sub)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(HttpCreatedSubscription::from(subscription)),
+    ))
+}
+
+pub async fn list_subscriptions(
+    claims: Claims,
+    State(notification_service): State<Arc<dyn NotificationService>>,
+) -> Result<Json<Vec<HttpSubscription>>, ApiError> {
+    let subscriptions = notification_service.list_subscriptions(&claims.sub).await?;
+    Ok(Json(subscriptions.into_iter().map(Into::into).collect()))
+}
+
+pub async fn get_subscription(
+    claims: Claims,
+    State(notification_service): State<Arc<dyn NotificationService>>,
+    Path(subscription_id): Path<SubscriptionId>,
+) -> Result<Json<HttpSubscription>, ApiError> {
+    let subscription = notification_service
+        .get_subscription(&subscription_id, &claims.sub)
+        .await?;
+    Ok(Json(subscription.into()))
+}
+
+pub async fn delete_subscription(
+    claims: Claims,
+    State(notification_service): State<Arc<dyn NotificationService>>,
+    Path(subscription_id): Path<SubscriptionId>,
+) -> Result<(), ApiError> {
+    notification_service
+        .delete_subscription(&subscription_id, &claims.sub)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_deliveries(
+    claims: Claims,
+    State(notification_service): State<Arc<dyn NotificationService>>,
+    Path(subscription_id): Path<SubscriptionId>,
+) -> Result<Json<Vec<HttpDelivery>>, ApiError> {
+    let deliveries = notification_service
+        .list_deliveries(&subscription_id, &claims.sub)
+        .await?;
+    Ok(Json(deliveries.into_iter().map(Into::into).collect()))
+}
+
+/// Queues a test event for the subscription. It is sent with the next
+/// dispatch, and its outcome shows up in the delivery log.
+pub async fn send_test_notification(
+    claims: Claims,
+    State(notification_service): State<Arc<dyn NotificationService>>,
+    Path(subscription_id): Path<SubscriptionId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let delivery = notification_service
+        .send_test(&subscription_id, &claims.sub)
+        .await?;
+    Ok((StatusCode::ACCEPTED, Json(HttpDelivery::from(delivery))))
+}
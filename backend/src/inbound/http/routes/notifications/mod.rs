@@ -0,0 +1,261 @@
+pub mod handlers;
+pub mod models;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::inbound::http::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/notifications/subscriptions",
+            post(handlers::create_subscription).get(handlers::list_subscriptions),
+        )
+        .route(
+            "/notifications/subscriptions/{subscription_id}",
+            get(handlers::get_subscription).delete(handlers::delete_subscription),
+        )
+        .route(
+            "/notifications/subscriptions/{subscription_id}/deliveries",
+            get(handlers::list_deliveries),
+        )
+        .route(
+            "/notifications/subscriptions/{subscription_id}/test",
+            post(handlers::send_test_notification),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            auth::{models::TokenClaims, service::MockAuthService},
+            notification::{
+                models::{
+                    Delivery, DeliveryStatus, EventKind, NotificationChannel, NotificationEvent,
+                    Subscription, SubscriptionId, SubscriptionScope,
+                },
+                service::{MockNotificationService, NotificationServiceError},
+            },
+            queue::models::QueueId,
+            user::models::UserId,
+        },
+        inbound::http::{
+            routes::notifications::models::{
+                HttpCreatedSubscription, HttpDelivery, HttpSubscription,
+            },
+            AppState,
+        },
+    };
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use mockall::predicate::*;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn setup_test_app(
+        notification_service: MockNotificationService,
+        user_id: UserId,
+    ) -> axum::Router {
+        let token_claims = TokenClaims::new_mock(user_id);
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .with(eq("user-token"))
+            .returning(move |_| Ok(token_claims.clone()));
+
+        let mut app_state = AppState::new_mock();
+        app_state.notification_service = Arc::new(notification_service);
+        app_state.auth_service = Arc::new(auth_service);
+        super::router().with_state(app_state)
+    }
+
+    #[tokio::test]
+    async fn test_create_subscription_returns_secret_once() {
+        let user_id = UserId::generate();
+        let queue_id = QueueId::generate();
+        let mut mock_service = MockNotificationService::new();
+        mock_service
+            .expect_create_subscription()
+            .withf(move |request, owner_id| {
+                *owner_id == user_id
+                    && request.scope == SubscriptionScope::Queue { queue_id }
+                    && request.backlog_threshold == Some(20)
+            })
+            .times(1)
+            .returning(|request, owner_id| {
+                Ok(Subscription {
+                    owner_id: *owner_id,
+                    scope: request.scope,
+                    event_kinds: request.event_kinds,
+                    backlog_threshold: request.backlog_threshold,
+                    ..Subscription::new_mock()
+                })
+            });
+
+        let app = setup_test_app(mock_service, user_id);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/notifications/subscriptions")
+            .header("Authorization", "Bearer user-token")
+            .header("Content-Type", "application/json")
+            .body(Body::from(format!(
+                r#"{{
+                    "scope": {{"type": "queue", "queue_id": "{queue_id}"}},
+                    "event_kinds": ["queue_backlog", "job_failed"],
+                    "channel": {{"type": "webhook", "url": "https://example.com/hooks/lilac"}},
+                    "backlog_threshold": 20
+                }}"#
+            )))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: HttpCreatedSubscription = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created.subscription.owner_id, user_id);
+        assert_eq!(created.webhook_secret.as_deref(), Some("whsec_test"));
+    }
+
+    #[tokio::test]
+    async fn test_list_subscriptions_hides_secret() {
+        let user_id = UserId::generate();
+        let mut mock_service = MockNotificationService::new();
+        mock_service
+            .expect_list_subscriptions()
+            .with(eq(user_id))
+            .times(1)
+            .returning(move |owner_id| {
+                Ok(vec![Subscription {
+                    owner_id: *owner_id,
+                    ..Subscription::new_mock()
+                }])
+            });
+
+        let app = setup_test_app(mock_service, user_id);
+        let request = Request::builder()
+            .uri("/notifications/subscriptions")
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("whsec_test"));
+        let subscriptions: Vec<HttpSubscription> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(subscriptions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_subscription_invalid() {
+        let user_id = UserId::generate();
+        let mut mock_service = MockNotificationService::new();
+        mock_service
+            .expect_create_subscription()
+            .times(1)
+            .returning(|_, _| {
+                Err(NotificationServiceError::InvalidSubscription(
+                    "at least one event kind is required".to_string(),
+                ))
+            });
+
+        let app = setup_test_app(mock_service, user_id);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/notifications/subscriptions")
+            .header("Authorization", "Bearer user-token")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                r#"{
+                    "scope": {"type": "user"},
+                    "event_kinds": [],
+                    "channel": {"type": "email", "address": "ml-team@example.com"}
+                }"#,
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_send_test_notification() {
+        let user_id = UserId::generate();
+        let subscription_id = SubscriptionId::generate();
+        let mut mock_service = MockNotificationService::new();
+        mock_service
+            .expect_send_test()
+            .with(eq(subscription_id), eq(user_id))
+            .times(1)
+            .returning(|id, _| Ok(Delivery::new(*id, NotificationEvent::test(id))));
+
+        let app = setup_test_app(mock_service, user_id);
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!(
+                "/notifications/subscriptions/{subscription_id}/test"
+            ))
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let delivery: HttpDelivery = serde_json::from_slice(&body).unwrap();
+        assert_eq!(delivery.subscription_id, subscription_id);
+        assert_eq!(delivery.event.kind, EventKind::Test);
+        assert_eq!(delivery.status, DeliveryStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_list_deliveries_not_found() {
+        let user_id = UserId::generate();
+        let subscription_id = SubscriptionId::generate();
+        let mut mock_service = MockNotificationService::new();
+        mock_service
+            .expect_list_deliveries()
+            .with(eq(subscription_id), eq(user_id))
+            .times(1)
+            .returning(|id, _| {
+                Err(NotificationServiceError::SubscriptionNotFound(
+                    id.to_string(),
+                ))
+            });
+
+        let app = setup_test_app(mock_service, user_id);
+        let request = Request::builder()
+            .uri(format!(
+                "/notifications/subscriptions/{subscription_id}/deliveries"
+            ))
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_channel_secret_is_not_serialized() {
+        let channel: super::models::HttpNotificationChannel = NotificationChannel::Webhook {
+            url: "https://example.com".to_string(),
+            secret: "whsec_test".to_string(),
+        }
+        .into();
+        assert!(!serde_json::to_string(&channel)
+            .unwrap()
+            .contains("whsec_test"));
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    notification::models::{
+        CreateNotificationChannel, CreateSubscriptionRequest, Delivery, DeliveryId, DeliveryStatus,
+        EventKind, NotificationChannel, NotificationEvent, Subscription, SubscriptionId,
+        SubscriptionScope,
+    },
+    user::models::UserId,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HttpCreateSubscriptionRequest {
+    pub scope: SubscriptionScope,
+    pub event_kinds: Vec<EventKind>,
+    pub channel: CreateNotificationChannel,
+    pub backlog_threshold: Option<i32>,
+}
+
+impl From<HttpCreateSubscriptionRequest> for CreateSubscriptionRequest {
+    fn from(value: HttpCreateSubscriptionRequest) -> Self {
+        Self {
+            scope: value.scope,
+            event_kinds: value.event_kinds,
+            channel: value.channel,
+            backlog_threshold: value.backlog_threshold,
+        }
+    }
+}
+
+/// A [NotificationChannel] without the webhook secret.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum HttpNotificationChannel {
+    Webhook { url: String },
+    Email { address: String },
+}
+
+impl From<NotificationChannel> for HttpNotificationChannel {
+    fn from(channel: NotificationChannel) -> Self {
+        match channel {
+            NotificationChannel::Webhook { url, .. } => Self::Webhook { url },
+            NotificationChannel::Email { address } => Self::Email { address },
+        }
+    }
+}
+
+/// An HTTP representation of a [Subscription].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpSubscription {
+    pub id: SubscriptionId,
+    pub owner_id: UserId,
+    pub scope: SubscriptionScope,
+    pub event_kinds: Vec<EventKind>,
+    pub channel: HttpNotificationChannel,
+    pub backlog_threshold: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Subscription> for HttpSubscription {
+    fn from(subscription: Subscription) -> Self {
+        Self {
+            id: subscription.id,
+            owner_id: subscription.owner_id,
+            scope: subscription.scope,
+            event_kinds: subscription.event_kinds,
+            channel: subscription.channel.into(),
+            backlog_threshold: subscription.backlog_threshold,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+/// The response to creating a subscription. This is the only time the
+/// webhook signing secret is returned.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpCreatedSubscription {
+    #[serde(flatten)]
+    pub subscription: HttpSubscription,
+    pub webhook_secret: Option<String>,
+}
+
+impl From<Subscription> for HttpCreatedSubscription {
+    fn from(subscription: Subscription) -> Self {
+        let webhook_secret = match &subscription.channel {
+            NotificationChannel::Webhook { secret, .. } => Some(secret.clone()),
+            NotificationChannel::Email { .. } => None,
+        };
+        Self {
+            subscription: subscription.into(),
+            webhook_secret,
+        }
+    }
+}
+
+/// An HTTP representation of a [Delivery].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpDelivery {
+    pub id: DeliveryId,
+    pub subscription_id: SubscriptionId,
+    pub event: NotificationEvent,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Delivery> for HttpDelivery {
+    fn from(delivery: Delivery) -> Self {
+        Self {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event: delivery.event,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            last_error: delivery.last_error,
+            next_attempt_at: delivery.next_attempt_at,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::{
+    domain::{
+        auth::models::Claims,
+        queue::models::QueueId,
+        quota::{
+            models::{QuotaStatus, QuotaSubject, ResourceQuota},
+            service::QuotaService,
+        },
+        user::models::UserId,
+    },
+    inbound::http::{errors::ApiError, AppState},
+};
+
+/// Only administrators may change quotas, or users could lift their own.
+async fn require_admin(state: &AppState, claims: &Claims) -> Result<(), ApiError> {
+    if state.is_admin(claims).await? {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden)
+    }
+}
+
+pub async fn get_user_quota(
+    _claims: Claims,
+    State(quota_service): State<Arc<dyn QuotaService>>,
+    Path(user_id): Path<UserId>,
+) -> Result<Json<QuotaStatus>, ApiError> {
+    let status = quota_service
+        .get_quota_status(&QuotaSubject::User(user_id))
+        .await?;
+    Ok(Json(status))
+}
+
+pub async fn set_user_quota(
+    claims: Claims,
+    State(state): State<AppState>,
+    Path(user_id): Path<UserId>,
+    Json(quota): Json<ResourceQuota>,
+) -> Result<Json<ResourceQuota>, ApiError> {
+    require_admin(&state, &claims).await?;
+    let quota = state
+        .quota_service
+        .set_quota(&QuotaSubject::User(user_id), quota)
+        .await?;
+    Ok(Json(quota))
+}
+
+pub async fn delete_user_quota(
+    claims: Claims,
+    State(state): State<AppState>,
+    Path(user_id): Path<UserId>,
+) -> Result<(), ApiError> {
+    require_admin(&state, &claims).await?;
+    state
+        .quota_service
+        .delete_quota(&QuotaSubject::User(user_id))
+        .await?;
+    Ok(())
+}
+
+pub async fn get_queue_quota(
+    _claims: Claims,
+    State(quota_service): State<Arc<dyn QuotaService>>,
+    Path(queue_id): Path<QueueId>,
+) -> Result<Json<QuotaStatus>, ApiError> {
+    let status = quota_service
+        .get_quota_status(&QuotaSubject::Queue(queue_id))
+        .await?;
+    Ok(Json(status))
+}
+
+pub async fn set_queue_quota(
+    claims: Claims,
+    State(state): State<AppState>,
+    Path(queue_id): Path<QueueId>,
+    Json(quota): Json<ResourceQuota>,
+) -> Result<Json<ResourceQuota>, ApiError> {
+    require_admin(&state, &claims).await?;
+    let quota = state
+        .quota_service
+        .set_quota(&QuotaSubject::Queue(queue_id), quota)
+        .await?;
+    Ok(Json(quota))
+}
+
+pub async fn delete_queue_quota(
+    claims: Claims,
+    State(state): State<AppState>,
+    Path(queue_id): Path<QueueId>,
+) -> Result<(), ApiError> {
+    require_admin(&state, &claims).await?;
+    state
+        .quota_service
+        .delete_quota(&QuotaSubject::Queue(queue_id))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,200 @@
+pub mod handlers;
+
+use axum::{routing::get, Router};
+
+use crate::inbound::http::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/users/{id}/quota",
+            get(handlers::get_user_quota)
+                .put(handlers::set_user_quota)
+                .delete(handlers::delete_user_quota),
+        )
+        .route(
+            "/queues/{queue_id}/quota",
+            get(handlers::get_queue_quota)
+                .put(handlers::set_queue_quota)
+                .delete(handlers::delete_queue_quota),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::LilacConfig,
+        domain::{
+            auth::{models::TokenClaims, service::MockAuthService},
+            queue::models::QueueId,
+            quota::{
+                models::{QuotaStatus, QuotaSubject, QuotaUsage, ResourceQuota},
+                service::{MockQuotaService, QuotaServiceError},
+            },
+            user::{
+                models::{User, UserId},
+                service::MockUserService,
+            },
+        },
+        inbound::http::AppState,
+    };
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use mockall::predicate::*;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    /// An app where the caller, authenticated with `user-token`, is named
+    /// `username`, and `admin` is the one administrator.
+    fn setup_test_app(quota_service: MockQuotaService, username: &str) -> axum::Router {
+        let user_id = UserId::generate();
+        let token_claims = TokenClaims::new_mock(user_id);
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .with(eq("user-token"))
+            .returning(move |_| Ok(token_claims.clone()));
+        let user = User {
+            id: user_id,
+            username: username.to_string(),
+            ..User::new_mock()
+        };
+        let mut user_service = MockUserService::new();
+        user_service
+            .expect_get_user_by_id()
+            .with(eq(user_id))
+            .returning(move |_| Ok(user.clone()));
+
+        let mut app_state = AppState::new_mock_with_config(LilacConfig {
+            admin_usernames: vec!["admin".to_string()],
+            ..Default::default()
+        });
+        app_state.quota_service = Arc::new(quota_service);
+        app_state.auth_service = Arc::new(auth_service);
+        app_state.user_service = Arc::new(user_service);
+        super::router().with_state(app_state)
+    }
+
+    #[tokio::test]
+    async fn test_get_queue_quota() {
+        let queue_id = QueueId::generate();
+        let mut quota_service = MockQuotaService::new();
+        quota_service
+            .expect_get_quota_status()
+            .with(eq(QuotaSubject::Queue(queue_id)))
+            .times(1)
+            .returning(|_| {
+                Ok(QuotaStatus {
+                    quota: Some(ResourceQuota {
+                        max_gpus: Some(8),
+                        ..Default::default()
+                    }),
+                    usage: QuotaUsage {
+                        running_jobs: 1,
+                        gpus: 4,
+                        ..Default::default()
+                    },
+                })
+            });
+
+        let app = setup_test_app(quota_service, "alice");
+        let request = Request::builder()
+            .uri(format!("/queues/{queue_id}/quota"))
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: QuotaStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.quota.unwrap().max_gpus, Some(8));
+        assert_eq!(status.usage.gpus, 4);
+    }
+
+    #[tokio::test]
+    async fn test_set_user_quota_rejects_invalid_quota() {
+        let user_id = UserId::generate();
+        let mut quota_service = MockQuotaService::new();
+        quota_service
+            .expect_set_quota()
+            .with(eq(QuotaSubject::User(user_id)), always())
+            .times(1)
+            .returning(|_, _| {
+                Err(QuotaServiceError::InvalidQuota(
+                    "max_gpus must not be negative".to_string(),
+                ))
+            });
+
+        let app = setup_test_app(quota_service, "admin");
+        let request = Request::builder()
+            .method("PUT")
+            .uri(format!("/users/{user_id}/quota"))
+            .header("Authorization", "Bearer user-token")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"max_gpus": -1}"#))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_non_admin_cannot_change_quotas() {
+        let user_id = UserId::generate();
+        let queue_id = QueueId::generate();
+        let requests = [
+            ("PUT", format!("/users/{user_id}/quota")),
+            ("DELETE", format!("/users/{user_id}/quota")),
+            ("PUT", format!("/queues/{queue_id}/quota")),
+            ("DELETE", format!("/queues/{queue_id}/quota")),
+        ];
+
+        for (method, uri) in requests {
+            let mut quota_service = MockQuotaService::new();
+            quota_service.expect_set_quota().never();
+            quota_service.expect_delete_quota().never();
+            let app = setup_test_app(quota_service, "alice");
+            let request = Request::builder()
+                .method(method)
+                .uri(&uri)
+                .header("Authorization", "Bearer user-token")
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"max_gpus": 64}"#))
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{method} {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_admin_can_change_max_sessions() {
+        let user_id = UserId::generate();
+        for (username, status) in [("alice", StatusCode::FORBIDDEN), ("admin", StatusCode::OK)] {
+            let mut quota_service = MockQuotaService::new();
+            quota_service
+                .expect_set_quota()
+                .withf(|_, quota| quota.max_sessions == Some(100))
+                .times(usize::from(username == "admin"))
+                .returning(|_, quota| Ok(quota));
+            let app = setup_test_app(quota_service, username);
+            let request = Request::builder()
+                .method("PUT")
+                .uri(format!("/users/{user_id}/quota"))
+                .header("Authorization", "Bearer user-token")
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"max_sessions": 100}"#))
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+
+            assert_eq!(response.status(), status, "{username}");
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use secrecy::SecretString;
+
+use crate::{
+    domain::{
+        auth::models::Claims,
+        sweep::{models::SweepId, service::SweepService},
+    },
+    inbound::http::{errors::ApiError, AppState},
+};
+
+use super::models::{HttpCreateSweepRequest, HttpSweep, HttpSweepDetails};
+
+/// Creates a sweep and submits its first trials. Like job creation, this
+/// authenticates with a user API key so that it can be called from the CLI.
+pub async fn create_sweep(
+    State(state): State<AppState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Json(request): Json<HttpCreateSweepRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = state
+        .user_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let details = state
+        .sweep_service
+        .create_sweep(request.into(), &user.id)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(HttpSweepDetails::from(details))))
+}
+
+pub async fn list_sweeps(
+    _claims: Claims,
+    State(sweep_service): State<Arc<dyn SweepService>>,
+) -> Result<Json<Vec<HttpSweep>>, ApiError> {
+    let sweeps = sweep_service.list_sweeps().await?;
+    Ok(Json(sweeps.into_iter().map(Into::into).collect()))
+}
+
+pub async fn get_sweep(
+    _claims: Claims,
+    State(sweep_service): State<Arc<dyn SweepService>>,
+    Path(sweep_id): Path<SweepId>,
+) -> Result<Json<HttpSweepDetails>, ApiError> {
+    let details = sweep_service.get_sweep(&sweep_id).await?;
+    Ok(Json(details.into()))
+}
+
+pub async fn cancel_sweep(
+    claims: Claims,
+    State(sweep_service): State<Arc<dyn SweepService>>,
+    Path(sweep_id): Path<SweepId>,
+) -> Result<impl IntoResponse, ApiError> {
+    sweep_service.cancel_sweep(&sweep_id, &claims.sub).await?;
+    Ok((StatusCode::OK, Json(())))
+}
@@ -0,0 +1,196 @@
+pub mod handlers;
+pub mod models;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::inbound::http::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/sweeps",
+            post(handlers::create_sweep).get(handlers::list_sweeps),
+        )
+        .route("/sweeps/{sweep_id}", get(handlers::get_sweep))
+        .route("/sweeps/{sweep_id}/cancel", post(handlers::cancel_sweep))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            auth::{models::TokenClaims, service::MockAuthService},
+            sweep::{
+                models::{Sweep, SweepDetails, SweepId, Trial, TrialStatus},
+                service::{MockSweepService, SweepServiceError},
+            },
+            training_job::models::JobId,
+            user::{models::UserId, service::MockUserService},
+        },
+        inbound::http::{routes::sweeps::models::HttpSweepDetails, AppState},
+    };
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use mockall::predicate::*;
+    use secrecy::ExposeSecret;
+    use std::{collections::BTreeMap, sync::Arc};
+    use tower::ServiceExt;
+
+    fn setup_test_app(
+        sweep_service: MockSweepService,
+        user_service: MockUserService,
+        user_id: UserId,
+    ) -> axum::Router {
+        let token_claims = TokenClaims::new_mock(user_id);
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .with(eq("user-token"))
+            .returning(move |_| Ok(token_claims.clone()));
+
+        let mut app_state = AppState::new_mock();
+        app_state.sweep_service = Arc::new(sweep_service);
+        app_state.user_service = Arc::new(user_service);
+        app_state.auth_service = Arc::new(auth_service);
+        super::router().with_state(app_state)
+    }
+
+    fn trial(sweep: &Sweep, number: i32, objective_value: Option<f64>) -> Trial {
+        Trial {
+            sweep_id: sweep.id,
+            number,
+            job_id: JobId::generate(),
+            parameters: BTreeMap::new(),
+            status: TrialStatus::Succeeded,
+            objective_value,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_sweep() {
+        let api_key = "user-api-key";
+        let mut mock_user_service = MockUserService::new();
+        mock_user_service
+            .expect_authenticate_by_api_key()
+            .withf(move |secret| secret.expose_secret() == api_key)
+            .times(1)
+            .returning(|_| Ok(Default::default()));
+
+        let mut mock_service = MockSweepService::new();
+        mock_service
+            .expect_create_sweep()
+            .withf(|request, _| {
+                request.name == "lr-search" && request.max_parallel == 1 && request.max_trials == 8
+            })
+            .times(1)
+            .returning(|request, user_id| {
+                Ok(SweepDetails {
+                    sweep: Sweep {
+                        name: request.name,
+                        user_id: *user_id,
+                        ..Sweep::new_mock()
+                    },
+                    trials: vec![],
+                })
+            });
+
+        let app = setup_test_app(mock_service, mock_user_service, UserId::generate());
+        let request = Request::builder()
+            .method("POST")
+            .uri("/sweeps")
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("Content-Type", "application/json")
+            .body(Body::from(format!(
+                r#"{{
+                    "name": "lr-search",
+                    "definition": "ghcr.io/acme/train:latest",
+                    "queue_id": "{}",
+                    "resource_requirements": {{"cpu_millicores": 1000, "memory_mb": 1024, "gpus": null}},
+                    "parameters": {{
+                        "lr": {{"type": "log_uniform", "min": 0.00001, "max": 0.1}},
+                        "optimizer": {{"type": "categorical", "values": ["adam", "sgd"]}}
+                    }},
+                    "objective": {{"metric": "val_loss", "goal": "minimize"}},
+                    "strategy": "tpe",
+                    "early_stopping": {{"type": "median", "min_steps": 5}},
+                    "max_trials": 8
+                }}"#,
+                uuid::Uuid::new_v4()
+            )))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_get_sweep_reports_best_trial() {
+        let user_id = UserId::generate();
+        let sweep = Sweep::new_mock();
+        let sweep_id = sweep.id;
+        let trials = vec![
+            trial(&sweep, 0, Some(0.9)),
+            trial(&sweep, 1, Some(0.4)),
+            trial(&sweep, 2, None),
+        ];
+
+        let mut mock_service = MockSweepService::new();
+        mock_service
+            .expect_get_sweep()
+            .with(eq(sweep_id))
+            .times(1)
+            .returning(move |_| {
+                Ok(SweepDetails {
+                    sweep: sweep.clone(),
+                    trials: trials.clone(),
+                })
+            });
+
+        let app = setup_test_app(mock_service, MockUserService::new(), user_id);
+        let request = Request::builder()
+            .uri(format!("/sweeps/{sweep_id}"))
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let details: HttpSweepDetails = serde_json::from_slice(&body).unwrap();
+        assert_eq!(details.best_trial, Some(1));
+        assert_eq!(details.trials.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_sweep_of_other_user() {
+        let user_id = UserId::generate();
+        let sweep_id = SweepId::generate();
+        let mut mock_service = MockSweepService::new();
+        mock_service
+            .expect_cancel_sweep()
+            .with(eq(sweep_id), eq(user_id))
+            .times(1)
+            .returning(|_, _| Err(SweepServiceError::InvalidPermissions));
+
+        let app = setup_test_app(mock_service, MockUserService::new(), user_id);
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/sweeps/{sweep_id}/cancel"))
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
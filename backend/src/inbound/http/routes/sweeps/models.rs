@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::domain::{
+    queue::models::QueueId,
+    sweep::models::{
+        CreateSweepRequest, EarlyStopping, Objective, ParameterSpace, SearchStrategy, Sweep,
+        SweepDetails, SweepId, SweepStatus, Trial, TrialStatus,
+    },
+    training_job::models::{JobId, ResourceRequirements},
+    user::models::UserId,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpCreateSweepRequest {
+    pub name: String,
+    pub definition: String,
+    pub queue_id: QueueId,
+    pub resource_requirements: ResourceRequirements,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    pub parameters: BTreeMap<String, ParameterSpace>,
+    pub objective: Objective,
+    pub strategy: SearchStrategy,
+    #[serde(default)]
+    pub early_stopping: Option<EarlyStopping>,
+    pub max_trials: i32,
+    #[serde(default = "HttpCreateSweepRequest::default_max_parallel")]
+    pub max_parallel: i32,
+}
+
+impl HttpCreateSweepRequest {
+    fn default_max_parallel() -> i32 {
+        1
+    }
+}
+
+impl From<HttpCreateSweepRequest> for CreateSweepRequest {
+    fn from(value: HttpCreateSweepRequest) -> Self {
+        Self {
+            name: value.name,
+            definition: value.definition,
+            queue_id: value.queue_id,
+            resource_requirements: value.resource_requirements,
+            env: value.env,
+            labels: value.labels,
+            parameters: value.parameters,
+            objective: value.objective,
+            strategy: value.strategy,
+            early_stopping: value.early_stopping,
+            max_trials: value.max_trials,
+            max_parallel: value.max_parallel,
+        }
+    }
+}
+
+/// An HTTP representation of a [Sweep].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpSweep {
+    pub id: SweepId,
+    pub name: String,
+    pub user_id: UserId,
+    pub queue_id: QueueId,
+    pub definition: String,
+    pub resource_requirements: ResourceRequirements,
+    pub env: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    pub parameters: BTreeMap<String, ParameterSpace>,
+    pub objective: Objective,
+    pub strategy: SearchStrategy,
+    pub early_stopping: Option<EarlyStopping>,
+    pub max_trials: i32,
+    pub max_parallel: i32,
+    pub status: SweepStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Sweep> for HttpSweep {
+    fn from(sweep: Sweep) -> Self {
+        Self {
+            id: sweep.id,
+            name: sweep.name,
+            user_id: sweep.user_id,
+            queue_id: sweep.queue_id,
+            definition: sweep.definition,
+            resource_requirements: sweep.resource_requirements,
+            env: sweep.env,
+            labels: sweep.labels,
+            parameters: sweep.parameters,
+            objective: sweep.objective,
+            strategy: sweep.strategy,
+            early_stopping: sweep.early_stopping,
+            max_trials: sweep.max_trials,
+            max_parallel: sweep.max_parallel,
+            status: sweep.status,
+            created_at: sweep.created_at,
+            updated_at: sweep.updated_at,
+        }
+    }
+}
+
+/// An HTTP representation of a [Trial].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpTrial {
+    pub number: i32,
+    pub job_id: JobId,
+    pub parameters: BTreeMap<String, Value>,
+    pub status: TrialStatus,
+    pub objective_value: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Trial> for HttpTrial {
+    fn from(trial: Trial) -> Self {
+        Self {
+            number: trial.number,
+            job_id: trial.job_id,
+            parameters: trial.parameters,
+            status: trial.status,
+            objective_value: trial.objective_value,
+            created_at: trial.created_at,
+            updated_at: trial.updated_at,
+        }
+    }
+}
+
+/// A sweep along with its trials.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpSweepDetails {
+    #[serde(flatten)]
+    pub sweep: HttpSweep,
+    /// The number of the trial with the best objective value so far.
+    pub best_trial: Option<i32>,
+    pub trials: Vec<HttpTrial>,
+}
+
+impl From<SweepDetails> for HttpSweepDetails {
+    fn from(details: SweepDetails) -> Self {
+        let best_trial = details
+            .sweep
+            .best_trial(&details.trials)
+            .map(|trial| trial.number);
+        Self {
+            sweep: details.sweep.into(),
+            best_trial,
+            trials: details.trials.into_iter().map(Into::into).collect(),
+        }
+    }
+}
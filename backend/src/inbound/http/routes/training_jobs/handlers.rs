@@ -1,42 +1,53 @@
 use std::sync::Arc;
 
 use super::models::{
-    CreateTrainingJobRequest, CreateTrainingJobResponse, PostLogsRequest,
-    UpdateTrainingJobStatusRequest,
+    BulkMoveTrainingJobsRequest, BulkTrainingJobsRequest, BulkTrainingJobsResponse,
+    CreateTrainingJobRequest, CreateTrainingJobResponse, ExecTrainingJobQuery, PostLogsRequest,
+    ProxyTrainingJobPath, ProxyTrainingJobQuery, ReportMetricsRequest, TrainingJobUsageQuery,
+    UpdateTrainingJobRequest, UpdateTrainingJobStatusRequest,
+};
+use crate::domain::training_job::models::{
+    BulkJobAction, BulkJobSelection, GetTrainingJobsFilters, JOB_TOKEN_PREFIX,
 };
-use crate::domain::training_job::models::GetTrainingJobsFilters;
 use crate::domain::training_job::service::TrainingJobService;
-use crate::inbound::http::routes::training_jobs::models::HttpTrainingJob;
+use crate::inbound::http::routes::training_jobs::models::{
+    HttpTrainingJob, HttpTrainingJobMetrics, HttpTrainingJobUsage,
+};
 use crate::{
     domain::{auth::models::Claims, training_job::models::JobId},
     inbound::http::{
-        errors::ApiError, routes::training_jobs::models::ListTrainingJobsHttpResponse, AppState,
+        errors::ApiError, exec::relay, proxy,
+        routes::training_jobs::models::ListTrainingJobsHttpResponse, AppState,
     },
 };
 use axum::extract::Path;
 use axum::{
-    extract::{Query, State},
+    extract::{Query, Request, State, WebSocketUpgrade},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use axum_extra::{
+    extract::cookie::{Cookie, CookieJar, SameSite},
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
 use secrecy::SecretString;
 
+/// The cookie holding the access token of an interactive session.
+const SESSION_COOKIE: &str = "lilac_session_token";
+
 pub async fn create_training_job(
     State(state): State<AppState>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Json(request): Json<CreateTrainingJobRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
+    let user = state
         .user_service
         .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
         .await?;
 
-    let training_job_with_targets = state.training_job_service.create(request).await?;
+    let training_job_with_targets = state.training_job_service.create(request, &user.id).await?;
 
     Ok((
         StatusCode::CREATED,
@@ -44,26 +55,70 @@ pub async fn create_training_job(
     ))
 }
 
+/// Queues a copy of a finished job. Like job creation, this authenticates
+/// with a user API key so that it can be called from the CLI. Administrators
+/// can resubmit anyone's jobs, everyone else only their own.
+pub async fn resubmit_training_job(
+    State(state): State<AppState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(job_id): Path<JobId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = state
+        .user_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let owner = (!state.config.admin_usernames.contains(&user.username)).then_some(user.id);
+    let created = state
+        .training_job_service
+        .resubmit(&job_id, &user.id, owner)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateTrainingJobResponse::from(created)),
+    ))
+}
+
 #[axum::debug_handler(state = AppState)]
 pub async fn get_training_job(
-    _claims: Claims,
+    claims: Claims,
     State(training_job_service): State<Arc<dyn TrainingJobService>>,
     Path(job_id): Path<JobId>,
 ) -> Result<Json<HttpTrainingJob>, ApiError> {
     let training_job = training_job_service.get_training_job_by_id(&job_id).await?;
 
-    Ok(Json(training_job.into()))
+    Ok(Json(HttpTrainingJob::for_caller(training_job, &claims.sub)))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn update_training_job(
+    claims: Claims,
+    State(state): State<AppState>,
+    Path(job_id): Path<JobId>,
+    Json(request): Json<UpdateTrainingJobRequest>,
+) -> Result<Json<HttpTrainingJob>, ApiError> {
+    let owner = (!state.is_admin(&claims).await?).then_some(claims.sub);
+    let training_job = state
+        .training_job_service
+        .update_metadata(&job_id, request.into(), owner)
+        .await?;
+
+    Ok(Json(HttpTrainingJob::for_caller(training_job, &claims.sub)))
 }
 
 #[axum::debug_handler]
 pub async fn list_training_jobs(
-    _claims: Claims,
+    claims: Claims,
     State(state): State<AppState>,
     Query(params): Query<GetTrainingJobsFilters>,
 ) -> Result<Json<ListTrainingJobsHttpResponse>, ApiError> {
     let training_jobs = state.training_job_service.get_training_jobs(params).await?;
 
-    Ok(Json(training_jobs.into()))
+    Ok(Json(ListTrainingJobsHttpResponse::for_caller(
+        training_jobs,
+        &claims.sub,
+    )))
 }
 
 pub async fn update_training_job_status(
@@ -97,3 +152,221 @@ pub async fn cancel_training_job(
     state.training_job_service.cancel(&job_id).await?;
     Ok((StatusCode::OK, Json(())))
 }
+
+#[axum::debug_handler(state = AppState)]
+pub async fn get_training_job_usage(
+    _claims: Claims,
+    State(training_job_service): State<Arc<dyn TrainingJobService>>,
+    Path(job_id): Path<JobId>,
+    Query(query): Query<TrainingJobUsageQuery>,
+) -> Result<Json<HttpTrainingJobUsage>, ApiError> {
+    let usage = training_job_service.get_usage(&job_id, query.since).await?;
+
+    Ok(Json(usage.into()))
+}
+
+/// Stores metric values reported by a job. This authenticates with the API
+/// key of the job's owner so that training code can call it directly.
+pub async fn report_training_job_metrics(
+    State(state): State<AppState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(job_id): Path<JobId>,
+    Json(request): Json<ReportMetricsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Jobs report their own metrics with their job token, users with an API key.
+    if auth.token().starts_with(JOB_TOKEN_PREFIX) {
+        state
+            .training_job_service
+            .report_metrics_with_job_token(&job_id, auth.token(), request.metrics)
+            .await?;
+        return Ok((StatusCode::OK, Json(())));
+    }
+
+    let user = state
+        .user_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    state
+        .training_job_service
+        .report_metrics(&job_id, &user.id, request.metrics)
+        .await?;
+
+    Ok((StatusCode::OK, Json(())))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn get_training_job_metrics(
+    _claims: Claims,
+    State(training_job_service): State<Arc<dyn TrainingJobService>>,
+    Path(job_id): Path<JobId>,
+) -> Result<Json<HttpTrainingJobMetrics>, ApiError> {
+    let metrics = training_job_service.get_metrics(&job_id).await?;
+
+    Ok(Json(HttpTrainingJobMetrics {
+        job_id,
+        metrics: metrics.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Applies a bulk action for the caller. Administrators can act on anyone's
+/// jobs, everyone else only on their own.
+async fn bulk_update(
+    state: &AppState,
+    claims: &Claims,
+    request: BulkTrainingJobsRequest,
+    action: BulkJobAction,
+) -> Result<Json<BulkTrainingJobsResponse>, ApiError> {
+    let dry_run = request.dry_run;
+    let selection = BulkJobSelection::try_from(request).map_err(ApiError::BadRequest)?;
+    let owner = (!state.is_admin(claims).await?).then_some(claims.sub);
+    let results = state
+        .training_job_service
+        .bulk_update(selection, action, dry_run, owner)
+        .await?;
+
+    Ok(Json(BulkTrainingJobsResponse { dry_run, results }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn bulk_cancel_training_jobs(
+    claims: Claims,
+    State(state): State<AppState>,
+    Json(request): Json<BulkTrainingJobsRequest>,
+) -> Result<Json<BulkTrainingJobsResponse>, ApiError> {
+    bulk_update(&state, &claims, request, BulkJobAction::Cancel).await
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn bulk_requeue_training_jobs(
+    claims: Claims,
+    State(state): State<AppState>,
+    Json(request): Json<BulkTrainingJobsRequest>,
+) -> Result<Json<BulkTrainingJobsResponse>, ApiError> {
+    bulk_update(&state, &claims, request, BulkJobAction::Requeue).await
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn bulk_move_training_jobs(
+    claims: Claims,
+    State(state): State<AppState>,
+    Json(request): Json<BulkMoveTrainingJobsRequest>,
+) -> Result<Json<BulkTrainingJobsResponse>, ApiError> {
+    let action = BulkJobAction::Move {
+        queue_id: request.queue_id,
+    };
+    bulk_update(&state, &claims, request.jobs, action).await
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn bulk_delete_training_jobs(
+    claims: Claims,
+    State(state): State<AppState>,
+    Json(request): Json<BulkTrainingJobsRequest>,
+) -> Result<Json<BulkTrainingJobsResponse>, ApiError> {
+    bulk_update(&state, &claims, request, BulkJobAction::Delete).await
+}
+
+/// Opens an interactive session in the container of a running job and
+/// relays it over a WebSocket. Like job creation, this authenticates with a
+/// user API key so that it can be called from the CLI.
+pub async fn exec_training_job(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(job_id): Path<JobId>,
+    axum_extra::extract::Query(query): axum_extra::extract::Query<ExecTrainingJobQuery>,
+) -> Result<Response, ApiError> {
+    let user = state
+        .user_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let (session, channel) = state
+        .exec_service
+        .open_session(&job_id, &user, query.into())
+        .await?;
+    tracing::info!(session_id = %session.id, %job_id, user_id = %user.id, "opened exec session");
+
+    Ok(ws.on_upgrade(move |socket| relay(socket, channel)))
+}
+
+pub async fn port_forward_training_job(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path((job_id, port)): Path<(JobId, u16)>,
+) -> Result<Response, ApiError> {
+    let user = state
+        .user_service
+        .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+        .await?;
+
+    let (session, channel) = state.exec_service.open_tunnel(&job_id, &user, port).await?;
+    tracing::info!(session_id = %session.id, %job_id, port, user_id = %user.id, "opened port forward");
+
+    Ok(ws.on_upgrade(move |socket| relay(socket, channel)))
+}
+
+/// Proxies a request to a service in a running job. Besides a user API key,
+/// the server of an interactive job accepts its access token, given as a
+/// `token` query parameter or, once the browser has been handed one, a
+/// cookie scoped to the server's path.
+pub async fn proxy_training_job(
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    jar: CookieJar,
+    Path(path): Path<ProxyTrainingJobPath>,
+    axum_extra::extract::Query(query): axum_extra::extract::Query<ProxyTrainingJobQuery>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let query_token = query.token.filter(|_| auth.is_none());
+    let (_, channel) = if let Some(TypedHeader(auth)) = auth {
+        let user = state
+            .user_service
+            .authenticate_by_api_key(&SecretString::from(auth.token().to_string()))
+            .await?;
+        state
+            .exec_service
+            .open_tunnel(&path.job_id, &user, path.port)
+            .await?
+    } else {
+        let token = query_token
+            .clone()
+            .or_else(|| jar.get(SESSION_COOKIE).map(|c| c.value().to_string()))
+            .ok_or_else(|| ApiError::Unauthorized("Missing credentials".to_string()))?;
+        state
+            .exec_service
+            .open_session_tunnel(&path.job_id, &token, path.port)
+            .await?
+    };
+
+    // The server of an interactive session is told the path it is proxied
+    // under, so it receives the full path rather than the one within it.
+    let job = state
+        .training_job_service
+        .get_training_job_by_id(&path.job_id)
+        .await?;
+    let session_path = job
+        .session
+        .as_ref()
+        .filter(|session| session.port == path.port)
+        .and(job.session_path());
+    let service_path = match &session_path {
+        Some(_) => request.uri().path().trim_start_matches('/').to_string(),
+        None => path.path.unwrap_or_default(),
+    };
+    let response = proxy::forward(channel, request, &service_path).await?;
+
+    match (query_token, session_path) {
+        (Some(token), Some(session_path)) => {
+            let cookie = Cookie::build((SESSION_COOKIE, token))
+                .path(session_path)
+                .http_only(true)
+                .secure(true)
+                .same_site(SameSite::Lax);
+            Ok((jar.add(cookie), response).into_response())
+        }
+        _ => Ok(response),
+    }
+}
@@ -1,13 +1,16 @@
 use axum::{
-    routing::{get, patch, post},
+    routing::{any, get, patch, post},
     Router,
 };
 
 use crate::inbound::http::AppState;
 
 use self::handlers::{
-    cancel_training_job, create_training_job, get_training_job, list_training_jobs, post_logs,
-    update_training_job_status,
+    bulk_cancel_training_jobs, bulk_delete_training_jobs, bulk_move_training_jobs,
+    bulk_requeue_training_jobs, cancel_training_job, create_training_job, exec_training_job,
+    get_training_job, get_training_job_metrics, get_training_job_usage, list_training_jobs,
+    port_forward_training_job, post_logs, proxy_training_job, report_training_job_metrics,
+    resubmit_training_job, update_training_job, update_training_job_status,
 };
 
 pub mod handlers;
@@ -17,13 +20,51 @@ pub fn training_jobs_router() -> Router<AppState> {
     Router::new()
         .route("/training_jobs", post(create_training_job))
         .route("/training_jobs", get(list_training_jobs))
-        .route("/training_jobs/{job_id}", get(get_training_job))
+        .route(
+            "/training_jobs/bulk/cancel",
+            post(bulk_cancel_training_jobs),
+        )
+        .route(
+            "/training_jobs/bulk/requeue",
+            post(bulk_requeue_training_jobs),
+        )
+        .route("/training_jobs/bulk/move", post(bulk_move_training_jobs))
+        .route(
+            "/training_jobs/bulk/delete",
+            post(bulk_delete_training_jobs),
+        )
+        .route(
+            "/training_jobs/{job_id}",
+            get(get_training_job).patch(update_training_job),
+        )
         .route(
             "/training_jobs/{job_id}/status",
             patch(update_training_job_status),
         )
         .route("/training_jobs/{job_id}/logs", post(post_logs))
         .route("/training_jobs/{job_id}/cancel", post(cancel_training_job))
+        .route(
+            "/training_jobs/{job_id}/resubmit",
+            post(resubmit_training_job),
+        )
+        .route("/training_jobs/{job_id}/usage", get(get_training_job_usage))
+        .route(
+            "/training_jobs/{job_id}/metrics",
+            get(get_training_job_metrics).post(report_training_job_metrics),
+        )
+        .route("/training_jobs/{job_id}/exec", get(exec_training_job))
+        .route(
+            "/training_jobs/{job_id}/port_forward/{port}",
+            get(port_forward_training_job),
+        )
+        .route(
+            "/training_jobs/{job_id}/proxy/{port}/",
+            any(proxy_training_job),
+        )
+        .route(
+            "/training_jobs/{job_id}/proxy/{port}/{*path}",
+            any(proxy_training_job),
+        )
 }
 
 #[cfg(test)]
@@ -31,15 +72,24 @@ mod tests {
     use crate::{
         domain::{
             auth::models::TokenClaims,
+            queue::models::QueueId,
             training_job::{
-                models::{JobId, TrainingJob, TrainingJobStatus},
-                service::MockTrainingJobService,
+                models::{
+                    BulkJobAction, BulkJobOutcome, BulkJobResult, BulkJobSelection,
+                    CreatedTrainingJob, JobId, LabelSelector, ResourceUsagePeaks, TrainingJob,
+                    TrainingJobStatus, TrainingJobUsage,
+                },
+                service::{MockTrainingJobService, TrainingJobServiceError},
+            },
+            user::{
+                models::{User, UserId},
+                service::MockUserService,
             },
-            user::{models::UserId, service::MockUserService},
         },
         inbound::http::{
             routes::training_jobs::models::{
-                CreateTrainingJobRequest, HttpTrainingJob, ListTrainingJobsHttpResponse,
+                BulkTrainingJobsResponse, CreateTrainingJobRequest, CreateTrainingJobResponse,
+                HttpTrainingJob, HttpTrainingJobUsage, ListTrainingJobsHttpResponse,
                 UpdateTrainingJobStatusRequest,
             },
             AppState,
@@ -51,7 +101,7 @@ mod tests {
     };
     use mockall::predicate::*;
     use secrecy::ExposeSecret;
-    use std::sync::Arc;
+    use std::{collections::HashMap, sync::Arc};
     use tower::ServiceExt;
 
     fn setup_test_app(
@@ -79,6 +129,20 @@ mod tests {
         auth_service
     }
 
+    fn mock_user(user_id: UserId, username: &str) -> MockUserService {
+        let user = User {
+            id: user_id,
+            username: username.to_string(),
+            ..User::new_mock()
+        };
+        let mut user_service = MockUserService::new();
+        user_service
+            .expect_get_user_by_id()
+            .with(eq(user_id))
+            .returning(move |_| Ok(user.clone()));
+        user_service
+    }
+
     #[tokio::test]
     async fn test_create_training_job_route() {
         let api_key = "cluster-api-key";
@@ -87,6 +151,13 @@ mod tests {
             definition: "test-uri".to_string(),
             queue_id: Default::default(),
             resource_requirements: serde_json::Value::Null,
+            labels: Default::default(),
+            annotations: Default::default(),
+            env: Default::default(),
+            exposed_ports: Default::default(),
+            kind: Default::default(),
+            session: None,
+            termination_grace_period_secs: None,
         };
 
         let mut mock_user_service = MockUserService::new();
@@ -97,10 +168,12 @@ mod tests {
             .returning(|_| Ok(Default::default()));
 
         let mut mock_job_service = MockTrainingJobService::new();
-        mock_job_service
-            .expect_create()
-            .times(1)
-            .returning(|_| Ok(TrainingJob::new_mock()));
+        mock_job_service.expect_create().times(1).returning(|_, _| {
+            Ok(CreatedTrainingJob {
+                job: TrainingJob::new_mock(),
+                warnings: vec!["no node currently online".to_string()],
+            })
+        });
 
         let app = setup_test_app(mock_job_service, mock_user_service, Default::default());
 
@@ -115,6 +188,53 @@ mod tests {
         let response = app.oneshot(request).await.unwrap();
 
         assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: CreateTrainingJobResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_body.warnings, vec!["no node currently online"]);
+    }
+
+    #[tokio::test]
+    async fn test_create_training_job_route_unschedulable() {
+        let api_key = "user-api-key";
+        let request_body = CreateTrainingJobRequest {
+            name: "test-job".to_string(),
+            definition: "test-uri".to_string(),
+            queue_id: Default::default(),
+            resource_requirements: serde_json::Value::Null,
+            labels: Default::default(),
+            annotations: Default::default(),
+            env: Default::default(),
+            exposed_ports: Default::default(),
+            kind: Default::default(),
+            session: None,
+            termination_grace_period_secs: None,
+        };
+
+        let mut mock_user_service = MockUserService::new();
+        mock_user_service
+            .expect_authenticate_by_api_key()
+            .returning(|_| Ok(Default::default()));
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service.expect_create().times(1).returning(|_, _| {
+            Err(TrainingJobServiceError::Unschedulable(
+                "64 GPUs requested, but the largest node has 8".to_string(),
+            ))
+        });
+
+        let app = setup_test_app(mock_job_service, mock_user_service, Default::default());
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/training_jobs")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&request_body).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
     }
 
     #[tokio::test]
@@ -148,11 +268,91 @@ mod tests {
         let _: ListTrainingJobsHttpResponse = serde_json::from_slice(&body).unwrap();
     }
 
+    #[tokio::test]
+    async fn test_list_training_jobs_route_with_selector() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+        let expected_selector: LabelSelector = "project=vision,owner!=bot".parse().unwrap();
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_get_training_jobs()
+            .withf(move |filters| filters.selector.as_ref() == Some(&expected_selector))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let app = setup_test_app(
+            mock_job_service,
+            Default::default(),
+            mock_user_auth(user_id, token),
+        );
+
+        let request = Request::builder()
+            .uri("/training_jobs?selector=project%3Dvision%2Cowner!%3Dbot")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_update_training_job_route() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+        let mut job_to_return = TrainingJob::new_mock();
+        job_to_return
+            .labels
+            .insert("experiment".to_string(), "lr-sweep".to_string());
+        let job_id = job_to_return.id;
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_update_metadata()
+            .withf(move |id, patch, owner| {
+                *id == job_id
+                    && patch.labels.get("experiment") == Some(&Some("lr-sweep".to_string()))
+                    && patch.labels.get("owner") == Some(&None)
+                    && *owner == Some(user_id)
+            })
+            .times(1)
+            .returning(move |_, _, _| Ok(job_to_return.clone()));
+
+        let app = setup_test_app(
+            mock_job_service,
+            mock_user(user_id, "alice"),
+            mock_user_auth(user_id, token),
+        );
+
+        let request = Request::builder()
+            .method("PATCH")
+            .uri(format!("/training_jobs/{}", job_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                r#"{"labels": {"experiment": "lr-sweep", "owner": null}}"#,
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: HttpTrainingJob = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_body.labels["experiment"], "lr-sweep");
+    }
+
     #[tokio::test]
     async fn test_get_training_job_route() {
         let user_id = UserId::generate();
         let token = "user-token";
-        let job_to_return = TrainingJob::new_mock();
+        let job_to_return = TrainingJob {
+            user_id: Some(UserId::generate()),
+            env: HashMap::from([("WANDB_API_KEY".to_string(), "secret".to_string())]),
+            ..TrainingJob::new_mock()
+        };
         let job_id = job_to_return.id;
 
         let mut mock_job_service = MockTrainingJobService::new();
@@ -180,6 +380,51 @@ mod tests {
         let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let response_body: HttpTrainingJob = serde_json::from_slice(&body).unwrap();
         assert_eq!(response_body.job_id, job_id);
+        // Only the job's owner sees its environment.
+        assert_eq!(response_body.env, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_training_jobs_route_shows_env_to_owner() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+        let env = HashMap::from([("WANDB_API_KEY".to_string(), "secret".to_string())]);
+        let own_job = TrainingJob {
+            user_id: Some(user_id),
+            env: env.clone(),
+            ..TrainingJob::new_mock()
+        };
+        let other_job = TrainingJob {
+            user_id: Some(UserId::generate()),
+            env: env.clone(),
+            ..TrainingJob::new_mock()
+        };
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_get_training_jobs()
+            .times(1)
+            .returning(move |_| Ok(vec![own_job.clone(), other_job.clone()]));
+
+        let app = setup_test_app(
+            mock_job_service,
+            Default::default(),
+            mock_user_auth(user_id, token),
+        );
+
+        let request = Request::builder()
+            .uri("/training_jobs")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_body["jobs"][0]["env"]["WANDB_API_KEY"], "secret");
+        assert!(response_body["jobs"][1].get("env").is_none());
     }
 
     #[tokio::test]
@@ -259,4 +504,242 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_resubmit_training_job_route() {
+        let api_key = "user-api-key";
+        let original_id = JobId::generate();
+
+        let mut mock_user_service = MockUserService::new();
+        mock_user_service
+            .expect_authenticate_by_api_key()
+            .withf(move |secret| secret.expose_secret() == api_key)
+            .times(1)
+            .returning(|_| Ok(Default::default()));
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_resubmit()
+            .withf(move |id, user_id, owner| *id == original_id && *owner == Some(*user_id))
+            .times(1)
+            .returning(move |_, _, _| {
+                Ok(CreatedTrainingJob {
+                    job: TrainingJob {
+                        resubmitted_from: Some(original_id),
+                        ..TrainingJob::new_mock()
+                    },
+                    warnings: vec![],
+                })
+            });
+
+        let app = setup_test_app(mock_job_service, mock_user_service, Default::default());
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/training_jobs/{}/resubmit", original_id))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: CreateTrainingJobResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_body.job.resubmitted_from, Some(original_id));
+    }
+
+    #[tokio::test]
+    async fn test_get_training_job_usage_route() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+        let job = TrainingJob::new_mock();
+        let job_id = job.id;
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_get_usage()
+            .with(eq(job_id), eq(None))
+            .times(1)
+            .returning(move |_, _| {
+                Ok(TrainingJobUsage {
+                    job_id,
+                    requested: job.resource_requirements.clone(),
+                    peak: Some(ResourceUsagePeaks {
+                        memory_mb: 9216,
+                        ..Default::default()
+                    }),
+                    samples: vec![],
+                })
+            });
+
+        let app = setup_test_app(
+            mock_job_service,
+            Default::default(),
+            mock_user_auth(user_id, token),
+        );
+
+        let request = Request::builder()
+            .uri(format!("/training_jobs/{}/usage", job_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: HttpTrainingJobUsage = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_body.job_id, job_id);
+        assert_eq!(response_body.peak.unwrap().memory_mb, 9216);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_cancel_training_jobs_route_dry_run() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+        let job_id = JobId::generate();
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_bulk_update()
+            .withf(move |selection, action, dry_run, owner| {
+                matches!(selection, BulkJobSelection::Filter(filters)
+                    if filters.status == Some(TrainingJobStatus::Queued)
+                        && filters.selector == "sweep=lr".parse().ok())
+                    && *action == BulkJobAction::Cancel
+                    && *dry_run
+                    && *owner == Some(user_id)
+            })
+            .times(1)
+            .returning(move |_, _, _, _| {
+                Ok(vec![BulkJobResult {
+                    job_id,
+                    job_name: Some("sweep-1".to_string()),
+                    job_status: Some(TrainingJobStatus::Queued),
+                    outcome: BulkJobOutcome::WouldApply,
+                    message: None,
+                }])
+            });
+
+        let app = setup_test_app(
+            mock_job_service,
+            mock_user(user_id, "alice"),
+            mock_user_auth(user_id, token),
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/training_jobs/bulk/cancel")
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "filter": { "status": "queued", "selector": "sweep=lr" },
+                    "dry_run": true,
+                })
+                .to_string(),
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: BulkTrainingJobsResponse = serde_json::from_slice(&body).unwrap();
+        assert!(response_body.dry_run);
+        assert_eq!(response_body.results.len(), 1);
+        assert_eq!(response_body.results[0].job_id, job_id);
+        assert_eq!(response_body.results[0].outcome, BulkJobOutcome::WouldApply);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_move_training_jobs_route() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+        let job_id = JobId::generate();
+        let queue_id = QueueId::generate();
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service
+            .expect_bulk_update()
+            .with(
+                eq(BulkJobSelection::Ids(vec![job_id])),
+                eq(BulkJobAction::Move { queue_id }),
+                eq(false),
+                eq(None),
+            )
+            .times(1)
+            .returning(move |_, _, _, _| {
+                Ok(vec![BulkJobResult {
+                    job_id,
+                    job_name: Some("job".to_string()),
+                    job_status: Some(TrainingJobStatus::Queued),
+                    outcome: BulkJobOutcome::Applied,
+                    message: None,
+                }])
+            });
+
+        // Administrators can move anyone's jobs.
+        let mut app_state = AppState::new_mock_with_config(crate::config::LilacConfig {
+            admin_usernames: vec!["admin".to_string()],
+            ..Default::default()
+        });
+        app_state.training_job_service = Arc::new(mock_job_service);
+        app_state.user_service = Arc::new(mock_user(user_id, "admin"));
+        app_state.auth_service = Arc::new(mock_user_auth(user_id, token));
+        let app = crate::inbound::http::routes::training_jobs::training_jobs_router()
+            .with_state(app_state);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/training_jobs/bulk/move")
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "queue_id": queue_id, "job_ids": [job_id] }).to_string(),
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let response_body: BulkTrainingJobsResponse = serde_json::from_slice(&body).unwrap();
+        assert!(!response_body.dry_run);
+        assert_eq!(response_body.results[0].outcome, BulkJobOutcome::Applied);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_delete_training_jobs_route_requires_one_selection() {
+        let user_id = UserId::generate();
+        let token = "user-token";
+
+        let mut mock_job_service = MockTrainingJobService::new();
+        mock_job_service.expect_bulk_update().never();
+
+        let app = setup_test_app(
+            mock_job_service,
+            Default::default(),
+            mock_user_auth(user_id, token),
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/training_jobs/bulk/delete")
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "job_ids": [JobId::generate()],
+                    "filter": { "status": "failed" },
+                })
+                .to_string(),
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -1,10 +1,19 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::domain::{
     cluster::models::NodeId,
+    exec::models::ExecRequest,
     queue::models::QueueId,
-    training_job::models::{JobId, ResourceRequirements, TrainingJob, TrainingJobStatus},
+    training_job::models::{
+        AssignedGpu, BulkJobResult, BulkJobSelection, CreatedTrainingJob, FailureReason,
+        GetTrainingJobsFilters, JobId, JobKind, JobMetric, ReportedMetric, ResourceRequirements,
+        ResourceUsagePeaks, ResourceUsageSample, TrainingJob, TrainingJobMetadataPatch,
+        TrainingJobStatus, TrainingJobUsage,
+    },
+    user::models::UserId,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,15 +22,124 @@ pub struct CreateTrainingJobRequest {
     pub definition: String,
     pub queue_id: QueueId,
     pub resource_requirements: serde_json::Value,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub exposed_ports: Vec<u16>,
+    #[serde(default)]
+    pub kind: JobKind,
+    /// Settings of an interactive job's server. Defaults are used if omitted.
+    #[serde(default)]
+    pub session: Option<CreateSessionRequest>,
+    /// How long the job has to save a checkpoint and exit after it is asked
+    /// to stop. Defaults to 30 seconds.
+    #[serde(default)]
+    pub termination_grace_period_secs: Option<i32>,
 }
 
-pub type CreateTrainingJobResponse = TrainingJob;
+/// The server settings of an interactive job.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateSessionRequest {
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub idle_timeout_minutes: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTrainingJobResponse {
+    #[serde(flatten)]
+    pub job: TrainingJob,
+    /// Where the server of an interactive job can be opened in a browser,
+    /// relative to the API endpoint. Includes the session's access token, so
+    /// it is only returned when the job is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl From<CreatedTrainingJob> for CreateTrainingJobResponse {
+    fn from(created: CreatedTrainingJob) -> Self {
+        let session_url = created
+            .job
+            .session
+            .as_ref()
+            .zip(created.job.session_path())
+            .map(|(session, path)| format!("{path}?token={}", session.access_token));
+        Self {
+            job: created.job,
+            session_url,
+            warnings: created.warnings,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateTrainingJobStatusRequest {
     pub status: TrainingJobStatus,
 }
 
+/// The body of a [TrainingJob] PATCH request. Keys set to `null` are removed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTrainingJobRequest {
+    #[serde(default)]
+    pub labels: HashMap<String, Option<String>>,
+    #[serde(default)]
+    pub annotations: HashMap<String, Option<String>>,
+}
+
+impl From<UpdateTrainingJobRequest> for TrainingJobMetadataPatch {
+    fn from(value: UpdateTrainingJobRequest) -> Self {
+        Self {
+            labels: value.labels,
+            annotations: value.annotations,
+        }
+    }
+}
+
+/// The body of a bulk request. Jobs are selected either by `job_ids` or by
+/// `filter`, but not both.
+#[derive(Debug, Deserialize)]
+pub struct BulkTrainingJobsRequest {
+    #[serde(default)]
+    pub job_ids: Option<Vec<JobId>>,
+    #[serde(default)]
+    pub filter: Option<GetTrainingJobsFilters>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl TryFrom<BulkTrainingJobsRequest> for BulkJobSelection {
+    type Error = String;
+
+    fn try_from(value: BulkTrainingJobsRequest) -> Result<Self, Self::Error> {
+        match (value.job_ids, value.filter) {
+            (Some(ids), None) => Ok(Self::Ids(ids)),
+            (None, Some(filter)) => Ok(Self::Filter(filter)),
+            _ => Err("exactly one of job_ids and filter must be given".to_string()),
+        }
+    }
+}
+
+/// The body of a bulk move request.
+#[derive(Debug, Deserialize)]
+pub struct BulkMoveTrainingJobsRequest {
+    pub queue_id: QueueId,
+    #[serde(flatten)]
+    pub jobs: BulkTrainingJobsRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkTrainingJobsResponse {
+    pub dry_run: bool,
+    pub results: Vec<BulkJobResult>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PostLogsRequest {
     pub logs: String,
@@ -35,26 +153,83 @@ pub struct HttpTrainingJob {
     pub job_status: TrainingJobStatus,
     pub node_id: Option<NodeId>,
     pub queue_id: Option<QueueId>,
+    pub user_id: Option<UserId>,
     pub resource_requirements: ResourceRequirements,
+    pub started_at: Option<DateTime<Utc>>,
+    pub pending_reason: Option<String>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    /// Environment variables often hold credentials, so only the job's owner
+    /// sees them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+    pub exposed_ports: Vec<u16>,
+    pub kind: JobKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<HttpSession>,
+    pub resubmitted_from: Option<JobId>,
+    pub termination_grace_period_secs: i32,
+    pub restart_count: i32,
+    pub failure_reason: Option<FailureReason>,
+    pub assigned_gpus: Vec<AssignedGpu>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// The server of an interactive job, without its access token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpSession {
+    pub port: u16,
+    pub idle_timeout_minutes: i32,
+    /// The path the server is proxied under, relative to the API endpoint.
+    pub path: String,
+    pub last_activity_at: Option<DateTime<Utc>>,
+}
+
 impl From<TrainingJob> for HttpTrainingJob {
     fn from(job: TrainingJob) -> Self {
+        let session_path = job.session_path();
+        let session = job.session;
         Self {
             job_id: job.id,
             job_name: job.name,
             job_status: job.status,
             node_id: job.node_id,
             queue_id: job.queue_id,
+            user_id: job.user_id,
             resource_requirements: job.resource_requirements,
+            started_at: job.started_at,
+            pending_reason: job.pending_reason,
+            labels: job.labels,
+            annotations: job.annotations,
+            env: None,
+            exposed_ports: job.exposed_ports,
+            kind: job.kind,
+            session: session.map(|session| HttpSession {
+                port: session.port,
+                idle_timeout_minutes: session.idle_timeout_minutes,
+                path: session_path.unwrap_or_default(),
+                last_activity_at: job.last_activity_at,
+            }),
+            resubmitted_from: job.resubmitted_from,
+            termination_grace_period_secs: job.termination_grace_period_secs,
+            restart_count: job.restart_count,
+            failure_reason: job.failure_reason,
+            assigned_gpus: job.assigned_gpus,
             created_at: job.created_at,
             updated_at: job.updated_at,
         }
     }
 }
 
+impl HttpTrainingJob {
+    /// The job as `caller` sees it, with its environment if they own it.
+    pub fn for_caller(job: TrainingJob, caller: &UserId) -> Self {
+        let env = (job.user_id == Some(*caller)).then(|| job.env.clone());
+        Self { env, ..job.into() }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListTrainingJobsHttpResponse {
     jobs: Vec<HttpTrainingJob>,
@@ -67,3 +242,110 @@ impl From<Vec<TrainingJob>> for ListTrainingJobsHttpResponse {
         }
     }
 }
+
+impl ListTrainingJobsHttpResponse {
+    /// The jobs as `caller` sees them, see [HttpTrainingJob::for_caller].
+    pub fn for_caller(jobs: Vec<TrainingJob>, caller: &UserId) -> Self {
+        Self {
+            jobs: jobs
+                .into_iter()
+                .map(|job| HttpTrainingJob::for_caller(job, caller))
+                .collect(),
+        }
+    }
+}
+
+/// The body of a [TrainingJob] usage response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpTrainingJobUsage {
+    pub job_id: JobId,
+    pub requested: ResourceRequirements,
+    pub peak: Option<ResourceUsagePeaks>,
+    pub samples: Vec<ResourceUsageSample>,
+}
+
+impl From<TrainingJobUsage> for HttpTrainingJobUsage {
+    fn from(value: TrainingJobUsage) -> Self {
+        Self {
+            job_id: value.job_id,
+            requested: value.requested,
+            peak: value.peak,
+            samples: value.samples,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportMetricsRequest {
+    pub metrics: Vec<ReportedMetric>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpJobMetric {
+    pub name: String,
+    pub step: i64,
+    pub value: f64,
+    pub reported_at: DateTime<Utc>,
+}
+
+impl From<JobMetric> for HttpJobMetric {
+    fn from(value: JobMetric) -> Self {
+        Self {
+            name: value.name,
+            step: value.step,
+            value: value.value,
+            reported_at: value.reported_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpTrainingJobMetrics {
+    pub job_id: JobId,
+    /// Ordered by metric name, then step.
+    pub metrics: Vec<HttpJobMetric>,
+}
+
+/// The query of a usage request, e.g. `?since=2025-08-05T12:00:00Z` to only
+/// fetch the samples taken since the last poll.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrainingJobUsageQuery {
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// The query of an exec request, e.g. `?command=bash&command=-l&tty=true`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecTrainingJobQuery {
+    #[serde(default)]
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub tty: bool,
+}
+
+/// The path of a proxied request, e.g. `/training_jobs/{job_id}/proxy/6006/data/runs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyTrainingJobPath {
+    pub job_id: JobId,
+    pub port: u16,
+    /// The path within the job's service. Absent for the service's root.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// The query of a proxied request. Other parameters are passed on to the
+/// job's service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyTrainingJobQuery {
+    /// The access token of an interactive session.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl From<ExecTrainingJobQuery> for ExecRequest {
+    fn from(value: ExecTrainingJobQuery) -> Self {
+        Self {
+            command: value.command,
+            tty: value.tty,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+
+use super::models::{
+    usage_records_to_csv, usage_report_to_csv, HttpUsageRecordsResponse, UsageExportFormat,
+    UsageQueryParams,
+};
+use crate::{
+    domain::{
+        accounting::{models::UsageReportFilters, service::AccountingService},
+        auth::models::Claims,
+    },
+    inbound::http::errors::ApiError,
+};
+
+const DEFAULT_REPORT_PERIOD_DAYS: i64 = 30;
+
+fn report_period(params: &UsageQueryParams) -> (DateTime<Utc>, DateTime<Utc>) {
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params
+        .from
+        .unwrap_or_else(|| to - Duration::days(DEFAULT_REPORT_PERIOD_DAYS));
+    (from, to)
+}
+
+fn csv_response(filename: &str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[axum::debug_handler(state = crate::inbound::http::AppState)]
+pub async fn list_usage_records(
+    _claims: Claims,
+    State(accounting_service): State<Arc<dyn AccountingService>>,
+    Query(params): Query<UsageQueryParams>,
+) -> Result<Response, ApiError> {
+    let (from, to) = report_period(&params);
+    let records = accounting_service.get_usage_records(from, to).await?;
+
+    Ok(match params.format {
+        UsageExportFormat::Json => {
+            Json(HttpUsageRecordsResponse { from, to, records }).into_response()
+        }
+        UsageExportFormat::Csv => csv_response("usage-records.csv", usage_records_to_csv(&records)),
+    })
+}
+
+#[axum::debug_handler(state = crate::inbound::http::AppState)]
+pub async fn get_usage_report(
+    _claims: Claims,
+    State(accounting_service): State<Arc<dyn AccountingService>>,
+    Query(params): Query<UsageQueryParams>,
+) -> Result<Response, ApiError> {
+    let (from, to) = report_period(&params);
+    let report = accounting_service
+        .get_usage_report(UsageReportFilters {
+            from,
+            to,
+            group_by: params.group_by,
+            daily: params.daily,
+        })
+        .await?;
+
+    Ok(match params.format {
+        UsageExportFormat::Json => Json(report).into_response(),
+        UsageExportFormat::Csv => csv_response("usage-report.csv", usage_report_to_csv(&report)),
+    })
+}
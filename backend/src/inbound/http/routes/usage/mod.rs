@@ -0,0 +1,105 @@
+pub mod handlers;
+pub mod models;
+
+use axum::{routing::get, Router};
+
+use crate::inbound::http::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/usage/records", get(handlers::list_usage_records))
+        .route("/usage/report", get(handlers::get_usage_report))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            accounting::{
+                models::{UsageGroupBy, UsageReport, UsageReportRow},
+                service::MockAccountingService,
+            },
+            auth::{models::TokenClaims, service::MockAuthService},
+            user::models::UserId,
+        },
+        inbound::http::AppState,
+    };
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+    };
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn setup_test_app(accounting_service: MockAccountingService) -> axum::Router {
+        let token_claims = TokenClaims::new_mock(UserId::generate());
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_validate_token()
+            .with(eq("user-token"))
+            .returning(move |_| Ok(token_claims.clone()));
+
+        let mut app_state = AppState::new_mock();
+        app_state.accounting_service = Arc::new(accounting_service);
+        app_state.auth_service = Arc::new(auth_service);
+        super::router().with_state(app_state)
+    }
+
+    #[tokio::test]
+    async fn test_get_usage_report_csv() {
+        let from = Utc.with_ymd_and_hms(2025, 8, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap();
+
+        let mut accounting_service = MockAccountingService::new();
+        accounting_service
+            .expect_get_usage_report()
+            .withf(move |filters| {
+                filters.from == from
+                    && filters.to == to
+                    && filters.group_by == UsageGroupBy::Queue
+                    && !filters.daily
+            })
+            .times(1)
+            .returning(move |filters| {
+                Ok(UsageReport {
+                    from: filters.from,
+                    to: filters.to,
+                    group_by: filters.group_by,
+                    rows: vec![UsageReportRow {
+                        key: "research".to_string(),
+                        day: None,
+                        gpu_model: Some("A100".to_string()),
+                        job_count: 2,
+                        gpu_hours: 16.0,
+                        cpu_core_hours: 32.0,
+                        memory_gb_hours: 128.0,
+                        cost: Some(40.0),
+                    }],
+                })
+            });
+
+        let app = setup_test_app(accounting_service);
+
+        let request = Request::builder()
+            .uri("/usage/report?from=2025-08-01T00:00:00Z&to=2025-09-01T00:00:00Z&group_by=queue&format=csv")
+            .header("Authorization", "Bearer user-token")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            "queue,day,gpu_model,job_count,gpu_hours,cpu_core_hours,memory_gb_hours,cost\n\
+             research,,A100,2,16.0000,32.0000,128.0000,40.00\n"
+        );
+    }
+}
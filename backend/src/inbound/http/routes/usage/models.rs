@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::accounting::models::{UsageGroupBy, UsageRecord, UsageReport};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Query parameters shared by the usage endpoints. The period defaults to the
+/// 30 days leading up to now.
+#[derive(Debug, Default, Deserialize)]
+pub struct UsageQueryParams {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub group_by: UsageGroupBy,
+    #[serde(default)]
+    pub daily: bool,
+    #[serde(default)]
+    pub format: UsageExportFormat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpUsageRecordsResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub records: Vec<UsageRecord>,
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub fn usage_records_to_csv(records: &[UsageRecord]) -> String {
+    let mut csv = String::from(
+        "job_id,user_id,queue_id,cluster_id,node_id,started_at,ended_at,hours,cpu_millicores,memory_mb,gpu_count,gpu_model\n",
+    );
+    for record in records {
+        csv.push_str(&csv_line(&[
+            record.job_id.to_string(),
+            optional(record.user_id),
+            optional(record.queue_id),
+            record.cluster_id.to_string(),
+            record.node_id.to_string(),
+            record.started_at.to_rfc3339(),
+            record.ended_at.to_rfc3339(),
+            format!(
+                "{:.4}",
+                record.hours_between(record.started_at, record.ended_at)
+            ),
+            record.cpu_millicores.to_string(),
+            record.memory_mb.to_string(),
+            record.gpu_count.to_string(),
+            optional(record.gpu_model.as_ref()),
+        ]));
+    }
+    csv
+}
+
+pub fn usage_report_to_csv(report: &UsageReport) -> String {
+    let mut csv = format!(
+        "{},day,gpu_model,job_count,gpu_hours,cpu_core_hours,memory_gb_hours,cost\n",
+        report.group_by
+    );
+    for row in &report.rows {
+        csv.push_str(&csv_line(&[
+            row.key.clone(),
+            optional(row.day),
+            optional(row.gpu_model.as_ref()),
+            row.job_count.to_string(),
+            format!("{:.4}", row.gpu_hours),
+            format!("{:.4}", row.cpu_core_hours),
+            format!("{:.4}", row.memory_gb_hours),
+            optional(row.cost.map(|cost| format!("{cost:.2}"))),
+        ]));
+    }
+    csv
+}
@@ -1,3 +1,4 @@
 pub mod jwt;
+pub mod notification;
 pub mod persistence;
 pub mod scheduler;
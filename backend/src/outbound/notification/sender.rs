@@ -0,0 +1,539 @@
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use secrecy::ExposeSecret;
+
+use crate::{
+    config::{SmtpConfig, SmtpSecurity},
+    domain::notification::{
+        models::{sign_webhook, NotificationChannel, NotificationEvent},
+        ports::{NotificationSender, NotificationSenderError},
+    },
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_WEBHOOK_REDIRECTS: usize = 5;
+
+struct SmtpSink {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+/// Whether webhooks may be delivered to `ip`. Loopback, link-local,
+/// private and other non-public addresses could reach services inside the
+/// control plane's network, so they are refused.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space, used by carrier-grade NAT.
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// The hosts webhooks may be delivered to despite being on a non-public
+/// network.
+#[derive(Clone)]
+struct AllowedHosts(Arc<HashSet<String>>);
+
+impl AllowedHosts {
+    fn contains(&self, host: &str) -> bool {
+        self.0.contains(&host.to_ascii_lowercase())
+    }
+
+    /// Checks a webhook URL whose host is an IP address. Other hosts are
+    /// checked when they are resolved, so that a host can't pass the check
+    /// and then resolve to a different address when it is connected to.
+    fn check_url(&self, url: &reqwest::Url) -> Result<(), String> {
+        let ip = match url.host() {
+            Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+            Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+            Some(url::Host::Domain(_)) => return Ok(()),
+            None => return Err("webhook url has no host".to_string()),
+        };
+        if is_public(ip) || self.contains(url.host_str().unwrap_or_default()) {
+            Ok(())
+        } else {
+            Err(format!("webhooks can't be delivered to {ip}"))
+        }
+    }
+}
+
+/// Resolves webhook hosts, refusing those that resolve to a non-public
+/// address unless they are allowed.
+struct WebhookResolver {
+    allowed_hosts: AllowedHosts,
+}
+
+impl WebhookResolver {
+    async fn resolve_host(
+        allowed_hosts: &AllowedHosts,
+        host: &str,
+    ) -> Result<Vec<SocketAddr>, String> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|e| format!("failed to resolve {host}: {e}"))?
+            .collect();
+        if !allowed_hosts.contains(host) {
+            if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                return Err(format!(
+                    "webhooks can't be delivered to {host}, it resolves to {}",
+                    addr.ip()
+                ));
+            }
+        }
+        Ok(addrs)
+    }
+}
+
+impl reqwest::dns::Resolve for WebhookResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let allowed_hosts = self.allowed_hosts.clone();
+        Box::pin(async move {
+            let addrs = Self::resolve_host(&allowed_hosts, name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Delivers events to webhooks over HTTP and to mailboxes over SMTP.
+pub struct ChannelNotificationSender {
+    http: reqwest::Client,
+    smtp: Option<SmtpSink>,
+    allowed_hosts: AllowedHosts,
+}
+
+impl ChannelNotificationSender {
+    /// Creates a sender. Without `smtp`, email deliveries fail. Webhooks are
+    /// only delivered to public addresses and to `allowed_webhook_hosts`.
+    pub fn new(
+        smtp: Option<&SmtpConfig>,
+        allowed_webhook_hosts: Vec<String>,
+    ) -> anyhow::Result<Self> {
+        let allowed_hosts = AllowedHosts(Arc::new(
+            allowed_webhook_hosts
+                .iter()
+                .map(|host| host.to_ascii_lowercase())
+                .collect(),
+        ));
+        let redirect_hosts = allowed_hosts.clone();
+        let http = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            // A proxy would resolve the host itself, bypassing the resolver.
+            .no_proxy()
+            .dns_resolver(Arc::new(WebhookResolver {
+                allowed_hosts: allowed_hosts.clone(),
+            }))
+            // Redirects to IP addresses aren't resolved, so they are checked here.
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_WEBHOOK_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+                match redirect_hosts.check_url(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                }
+            }))
+            .build()?;
+        let smtp = smtp.map(Self::smtp_sink).transpose()?;
+
+        Ok(Self {
+            http,
+            smtp,
+            allowed_hosts,
+        })
+    }
+
+    fn smtp_sink(config: &SmtpConfig) -> anyhow::Result<SmtpSink> {
+        let builder = match config.security {
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            }
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        };
+        let mut builder = builder.port(config.port);
+        if let Some(username) = &config.username {
+            let password = config
+                .password
+                .as_ref()
+                .map(|p| p.expose_secret().to_string())
+                .unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        Ok(SmtpSink {
+            transport: builder.build(),
+            from: config.from.parse()?,
+        })
+    }
+
+    async fn send_webhook(
+        &self,
+        url: &str,
+        secret: &str,
+        event: &NotificationEvent,
+    ) -> Result<(), NotificationSenderError> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+        self.allowed_hosts
+            .check_url(&url)
+            .map_err(NotificationSenderError::DeliveryFailed)?;
+        let body = serde_json::to_vec(event)
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Lilac-Event", event.kind.to_string())
+            .header("X-Lilac-Event-Id", event.id.to_string())
+            .header("X-Lilac-Timestamp", timestamp.to_string())
+            .header("X-Lilac-Signature", sign_webhook(secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(NotificationSenderError::DeliveryFailed(format!(
+                "webhook responded with {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn send_email(
+        &self,
+        address: &str,
+        event: &NotificationEvent,
+    ) -> Result<(), NotificationSenderError> {
+        let smtp = self
+            .smtp
+            .as_ref()
+            .ok_or(NotificationSenderError::EmailNotConfigured)?;
+        let to: Mailbox = address
+            .parse()
+            .map_err(|e| NotificationSenderError::DeliveryFailed(format!("{e}")))?;
+        let details = serde_json::to_string_pretty(event)
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+
+        let message = Message::builder()
+            .from(smtp.from.clone())
+            .to(to)
+            .subject(format!("[Lilac] {}", event.summary))
+            .header(ContentType::TEXT_PLAIN)
+            .body(format!("{}\n\n{details}\n", event.summary))
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+
+        smtp.transport
+            .send(message)
+            .await
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationSender for ChannelNotificationSender {
+    async fn send(
+        &self,
+        channel: &NotificationChannel,
+        event: &NotificationEvent,
+    ) -> Result<(), NotificationSenderError> {
+        match channel {
+            NotificationChannel::Webhook { url, secret } => {
+                self.send_webhook(url, secret, event).await
+            }
+            NotificationChannel::Email { address } => self.send_email(address, event).await,
+        }
+    }
+
+    fn email_enabled(&self) -> bool {
+        self.smtp.is_some()
+    }
+
+    async fn check_webhook_url(&self, url: &str) -> Result<(), NotificationSenderError> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| NotificationSenderError::DeliveryFailed(e.to_string()))?;
+        self.allowed_hosts
+            .check_url(&url)
+            .map_err(NotificationSenderError::DeliveryFailed)?;
+        if let Some(url::Host::Domain(host)) = url.host() {
+            WebhookResolver::resolve_host(&self.allowed_hosts, host)
+                .await
+                .map_err(NotificationSenderError::DeliveryFailed)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::notification::models::SubscriptionId;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// Accepts one SMTP session on `listener` and returns the message data.
+    async fn serve_one_mail(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        writer.write_all(b"220 localhost ready\r\n").await.unwrap();
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 queued\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let command = line.to_ascii_uppercase();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                b"250 localhost\r\n"
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                b"354 go ahead\r\n"
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"250 ok\r\n"
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_signs_the_body() {
+        let mut server = mockito::Server::new_async().await;
+        let event = NotificationEvent::test(&SubscriptionId::generate());
+        let mock = server
+            .mock("POST", "/hook")
+            .match_header("x-lilac-event", "test")
+            .match_header(
+                "x-lilac-signature",
+                mockito::Matcher::Regex("^sha256=[0-9a-f]{64}$".into()),
+            )
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "kind": "test" }),
+            ))
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let sender = ChannelNotificationSender::new(None, vec!["127.0.0.1".to_string()]).unwrap();
+        let channel = NotificationChannel::Webhook {
+            url: format!("{}/hook", server.url()),
+            secret: "secret".to_string(),
+        };
+        let result = sender.send(&channel, &event).await;
+
+        assert!(result.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_error_status() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/hook")
+            .with_status(500)
+            .create_async()
+            .await;
+
+        let sender = ChannelNotificationSender::new(None, vec!["127.0.0.1".to_string()]).unwrap();
+        let channel = NotificationChannel::Webhook {
+            url: format!("{}/hook", server.url()),
+            secret: "secret".to_string(),
+        };
+        let result = sender
+            .send(
+                &channel,
+                &NotificationEvent::test(&SubscriptionId::generate()),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(NotificationSenderError::DeliveryFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_refuses_private_addresses() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/hook")
+            .with_status(204)
+            .expect(0)
+            .create_async()
+            .await;
+        let sender = ChannelNotificationSender::new(None, Vec::new()).unwrap();
+        let event = NotificationEvent::test(&SubscriptionId::generate());
+
+        let port = server.socket_address().port();
+        for url in [
+            format!("{}/hook", server.url()),
+            format!("http://localhost:{port}/hook"),
+        ] {
+            let channel = NotificationChannel::Webhook {
+                url: url.clone(),
+                secret: "secret".to_string(),
+            };
+            let result = sender.send(&channel, &event).await;
+            assert!(
+                matches!(result, Err(NotificationSenderError::DeliveryFailed(_))),
+                "{url}: {result:?}"
+            );
+            assert!(sender.check_webhook_url(&url).await.is_err(), "{url}");
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_refuses_redirects_to_private_addresses() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/hook")
+            .with_status(307)
+            .with_header("location", "http://169.254.169.254/latest/meta-data")
+            .create_async()
+            .await;
+        let sender = ChannelNotificationSender::new(None, vec!["127.0.0.1".to_string()]).unwrap();
+        let channel = NotificationChannel::Webhook {
+            url: format!("{}/hook", server.url()),
+            secret: "secret".to_string(),
+        };
+
+        let result = sender
+            .send(
+                &channel,
+                &NotificationEvent::test(&SubscriptionId::generate()),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(NotificationSenderError::DeliveryFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_email_through_local_smtp_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve_one_mail(listener));
+
+        let config = SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: None,
+            password: None,
+            from: "Lilac <lilac@example.com>".to_string(),
+            security: SmtpSecurity::None,
+        };
+        let sender = ChannelNotificationSender::new(Some(&config), Vec::new()).unwrap();
+        let channel = NotificationChannel::Email {
+            address: "user@example.com".to_string(),
+        };
+        let result = sender
+            .send(
+                &channel,
+                &NotificationEvent::test(&SubscriptionId::generate()),
+            )
+            .await;
+
+        assert!(result.is_ok(), "{result:?}");
+        let data = server.await.unwrap();
+        assert!(data.contains("Subject: [Lilac] Test notification from Lilac"));
+        assert!(data.contains("To: user@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_send_email_without_smtp() {
+        let sender = ChannelNotificationSender::new(None, vec!["127.0.0.1".to_string()]).unwrap();
+        let channel = NotificationChannel::Email {
+            address: "user@example.com".to_string(),
+        };
+
+        let result = sender
+            .send(
+                &channel,
+                &NotificationEvent::test(&SubscriptionId::generate()),
+            )
+            .await;
+
+        assert!(!sender.email_enabled());
+        assert!(matches!(
+            result,
+            Err(NotificationSenderError::EmailNotConfigured)
+        ));
+    }
+}
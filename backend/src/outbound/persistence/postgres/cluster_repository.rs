@@ -7,7 +7,7 @@ use crate::{
             errors::ClusterApiKeyRepositoryError,
             models::{
                 Cluster, ClusterDetails, ClusterId, ClusterNode, ClusterSummary,
-                CreateClusterRequest, NodeId, UpdateNodeStatusRequest,
+                CreateClusterRequest, NodeCapacity, NodeId, UpdateNodeStatusRequest,
             },
             ports::{ClusterApiKeyRepository, ClusterRepository, ClusterRepositoryError},
         },
@@ -16,8 +16,8 @@ use crate::{
     },
     outbound::persistence::postgres::records::{
         ApiKeyRecord, ClusterDetailsRecord, ClusterNodeRecord, ClusterRecord, ClusterSummaryRecord,
-        CpuConfigurationRecord, GpuConfigurationRecord, NodeStatusRecord, TrainingJobRecord,
-        TrainingJobStatusRecord,
+        CpuConfigurationRecord, FailureReasonRecord, GpuConfigurationRecord, JobKindRecord,
+        NodeCapacityRecord, NodeStatusRecord, TrainingJobRecord, TrainingJobStatusRecord,
     },
 };
 
@@ -138,7 +138,7 @@ impl ClusterRepository for PostgresClusterRepository {
         let records = sqlx::query_as!(
             TrainingJobRecord,
             r#"
-            SELECT id, name, definition, status AS "status: TrainingJobStatusRecord", node_id, queue_id, resource_requirements, created_at, updated_at
+            SELECT id, name, definition, status AS "status: TrainingJobStatusRecord", node_id, queue_id, user_id, resource_requirements, started_at, assigned_at, pending_reason, labels, annotations, env, exposed_ports, kind AS "kind: JobKindRecord", session, last_activity_at, resubmitted_from, termination_grace_period_secs, restart_count, job_token, failure_reason AS "failure_reason: FailureReasonRecord", assigned_gpus, created_at, updated_at
             FROM training_jobs
             WHERE node_id = ANY(SELECT node_id FROM cluster_nodes WHERE cluster_id = $1)
             "#,
@@ -165,7 +165,7 @@ impl ClusterRepository for PostgresClusterRepository {
         let records = sqlx::query_as!(
             ClusterNodeRecord,
             r#"
-            SELECT node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id
+            SELECT node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id, cached_images, draining
             FROM cluster_nodes
             "#,
         )
@@ -184,7 +184,7 @@ impl ClusterRepository for PostgresClusterRepository {
         let records = sqlx::query_as!(
             ClusterNodeRecord,
             r#"
-            SELECT node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id
+            SELECT node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id, cached_images, draining
             FROM cluster_nodes
             WHERE cluster_id = $1
             "#,
@@ -204,7 +204,7 @@ impl ClusterRepository for PostgresClusterRepository {
         let record = sqlx::query_as!(
             ClusterNodeRecord,
             r#"
-            SELECT node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id
+            SELECT node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id, cached_images, draining
             FROM cluster_nodes
             WHERE node_id = $1
             "#,
@@ -212,7 +212,10 @@ impl ClusterRepository for PostgresClusterRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e: sqlx::Error| ClusterRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+        .map_err(|e: sqlx::Error| match e {
+            sqlx::Error::RowNotFound => ClusterRepositoryError::NotFound(id.to_string()),
+            _ => ClusterRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
         Ok(record.into())
     }
     async fn update_cluster_node_status(
@@ -222,14 +225,16 @@ impl ClusterRepository for PostgresClusterRepository {
         let record = sqlx::query_as!(
             ClusterNodeRecord,
             r#"
-            INSERT INTO cluster_nodes (node_id, cluster_id, node_status, heartbeat_timestamp, memory_mb, cpu, gpu, reported_job_id)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO cluster_nodes (node_id, cluster_id, node_status, heartbeat_timestamp, memory_mb, cpu, gpu, reported_job_id, cached_images, draining)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                 ON CONFLICT (node_id) DO UPDATE SET
                     node_status = EXCLUDED.node_status,
                     heartbeat_timestamp = EXCLUDED.heartbeat_timestamp,
                     reported_job_id = EXCLUDED.reported_job_id,
+                    cached_images = EXCLUDED.cached_images,
+                    draining = EXCLUDED.draining,
                     updated_at = NOW()
-                RETURNING node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id;
+                RETURNING node_id, cluster_id, node_status as "node_status: NodeStatusRecord", heartbeat_timestamp, memory_mb, cpu as "cpu: CpuConfigurationRecord", gpu as "gpu: GpuConfigurationRecord", created_at, updated_at, assigned_job_id, reported_job_id, cached_images, draining;
             "#,
             req.node_id.inner(),
             req.cluster_id.inner(),
@@ -247,12 +252,57 @@ impl ClusterRepository for PostgresClusterRepository {
                 .as_ref()
                 .map(|info| info.current_job_id)
                 .map(|id| id.into_inner()),
+            &req.cached_images,
+            req.draining,
         )
         .fetch_one(&self.pool)
         .await
         .map_err(|e: sqlx::Error| ClusterRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO cluster_node_capacities (node_id, cluster_id, cpu_millicores, memory_mb, gpu_count, last_seen_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (node_id) DO UPDATE SET
+                    cpu_millicores = EXCLUDED.cpu_millicores,
+                    memory_mb = EXCLUDED.memory_mb,
+                    gpu_count = EXCLUDED.gpu_count,
+                    last_seen_at = EXCLUDED.last_seen_at
+            "#,
+            req.node_id.inner(),
+            req.cluster_id.inner(),
+            req.cpu_info.millicores,
+            req.memory_info,
+            req.gpu_info.as_ref().map_or(0, |gpu| gpu.count),
+            req.heartbeat_timestamp,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| ClusterRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
         Ok(record.into())
     }
+
+    async fn list_node_capacities(
+        &self,
+        cluster_id: &ClusterId,
+    ) -> Result<Vec<NodeCapacity>, ClusterRepositoryError> {
+        let records = sqlx::query_as!(
+            NodeCapacityRecord,
+            r#"
+            SELECT node_id, cluster_id, cpu_millicores, memory_mb, gpu_count, last_seen_at
+            FROM cluster_node_capacities
+            WHERE cluster_id = $1
+            "#,
+            cluster_id.inner(),
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| ClusterRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
     async fn delete_cluster_node(&self, node_id: &NodeId) -> Result<(), ClusterRepositoryError> {
         sqlx::query!(
             "DELETE FROM cluster_nodes WHERE node_id = $1",
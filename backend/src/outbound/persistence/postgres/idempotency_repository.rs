@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::domain::idempotency::{
+    models::{IdempotencyRecord, StoredResponse},
+    ports::{IdempotencyRepository, IdempotencyRepositoryError},
+};
+
+use super::records::IdempotencyKeyRecord;
+
+pub struct PostgresIdempotencyRepository {
+    pool: PgPool,
+}
+
+impl PostgresIdempotencyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for PostgresIdempotencyRepository {
+    async fn insert_record(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<bool, IdempotencyRepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (scope, key, request_hash, created_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (scope, key) DO NOTHING
+            "#,
+            record.scope,
+            record.key,
+            record.request_hash,
+            record.created_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| IdempotencyRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn get_record(
+        &self,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, IdempotencyRepositoryError> {
+        let record = sqlx::query_as!(
+            IdempotencyKeyRecord,
+            r#"
+            SELECT scope, key, request_hash, response_status, response_content_type,
+                   response_body, created_at
+            FROM idempotency_keys
+            WHERE scope = $1 AND key = $2
+            "#,
+            scope,
+            key,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| IdempotencyRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(record.map(Into::into))
+    }
+
+    async fn set_response(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), IdempotencyRepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET response_status = $3, response_content_type = $4, response_body = $5
+            WHERE scope = $1 AND key = $2
+            "#,
+            scope,
+            key,
+            response.status as i32,
+            response.content_type,
+            response.body,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| IdempotencyRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_record(
+        &self,
+        scope: &str,
+        key: &str,
+    ) -> Result<(), IdempotencyRepositoryError> {
+        sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2",
+            scope,
+            key,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| IdempotencyRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_records_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, IdempotencyRepositoryError> {
+        let result = sqlx::query!("DELETE FROM idempotency_keys WHERE created_at < $1", before,)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| IdempotencyRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
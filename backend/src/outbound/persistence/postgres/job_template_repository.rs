@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::domain::{
+    job_template::{
+        models::{JobTemplate, JobTemplateId},
+        ports::{JobTemplateRepository, JobTemplateRepositoryError},
+    },
+    user::models::UserId,
+};
+
+use super::records::JobTemplateRecord;
+
+pub struct PostgresJobTemplateRepository {
+    pool: PgPool,
+}
+
+impl PostgresJobTemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JobTemplateRepository for PostgresJobTemplateRepository {
+    async fn create_template(
+        &self,
+        template: &JobTemplate,
+    ) -> Result<(), JobTemplateRepositoryError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO job_templates (
+                template_id, name, owner_id, shared, definition, queue_id,
+                resource_requirements, env, labels, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+            template.id.inner(),
+            template.name,
+            template.owner_id.inner(),
+            template.shared,
+            template.definition,
+            template.queue_id.map(|q| q.into_inner()),
+            &serde_json::to_value(&template.resource_requirements)
+                .map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&template.env).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&template.labels).map_err(|e| anyhow::anyhow!(e))?,
+            template.created_at,
+            template.updated_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                JobTemplateRepositoryError::Duplicate {
+                    field: "name".to_string(),
+                    value: template.name.clone(),
+                }
+            }
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                JobTemplateRepositoryError::QueueNotFound(
+                    template.queue_id.map(|q| q.to_string()).unwrap_or_default(),
+                )
+            }
+            _ => JobTemplateRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_template_by_id(
+        &self,
+        id: &JobTemplateId,
+    ) -> Result<JobTemplate, JobTemplateRepositoryError> {
+        let record = sqlx::query_as!(
+            JobTemplateRecord,
+            r#"
+            SELECT template_id, name, owner_id, shared, definition, queue_id,
+                   resource_requirements, env, labels, created_at, updated_at
+            FROM job_templates
+            WHERE template_id = $1
+            "#,
+            id.inner()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => JobTemplateRepositoryError::NotFound(id.to_string()),
+            _ => JobTemplateRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(record.try_into()?)
+    }
+
+    async fn list_templates_visible_to(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<JobTemplate>, JobTemplateRepositoryError> {
+        let rows = sqlx::query_as!(
+            JobTemplateRecord,
+            r#"
+            SELECT template_id, name, owner_id, shared, definition, queue_id,
+                   resource_requirements, env, labels, created_at, updated_at
+            FROM job_templates
+            WHERE owner_id = $1 OR shared
+            ORDER BY name ASC
+            "#,
+            user_id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| JobTemplateRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        let templates = rows
+            .into_iter()
+            .map(|row| row.try_into())
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(templates)
+    }
+
+    async fn delete_template(&self, id: &JobTemplateId) -> Result<(), JobTemplateRepositoryError> {
+        let result = sqlx::query!(
+            "DELETE FROM job_templates WHERE template_id = $1",
+            id.inner()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| JobTemplateRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(JobTemplateRepositoryError::NotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
 pub mod cluster_repository;
+pub mod idempotency_repository;
+pub mod job_template_repository;
+pub mod notification_repository;
 pub mod queue_repository;
+pub mod quota_repository;
 pub mod records;
 pub mod session_repository;
+pub mod sweep_repository;
 pub mod training_job_repository;
 pub mod user_repository;
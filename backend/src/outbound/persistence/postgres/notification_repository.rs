@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::domain::{
+    notification::{
+        models::{Delivery, EventKind, Subscription, SubscriptionId, SubscriptionScope},
+        ports::{NotificationRepository, NotificationRepositoryError},
+    },
+    user::models::UserId,
+};
+
+use super::records::{DeliveryRecord, SubscriptionRecord};
+
+pub struct PostgresNotificationRepository {
+    pool: PgPool,
+}
+
+impl PostgresNotificationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn into_subscriptions(
+    rows: Vec<SubscriptionRecord>,
+) -> Result<Vec<Subscription>, NotificationRepositoryError> {
+    Ok(rows
+        .into_iter()
+        .map(|row| row.try_into())
+        .collect::<Result<Vec<_>, anyhow::Error>>()?)
+}
+
+fn into_deliveries(
+    rows: Vec<DeliveryRecord>,
+) -> Result<Vec<Delivery>, NotificationRepositoryError> {
+    Ok(rows
+        .into_iter()
+        .map(|row| row.try_into())
+        .collect::<Result<Vec<_>, anyhow::Error>>()?)
+}
+
+#[async_trait]
+impl NotificationRepository for PostgresNotificationRepository {
+    async fn create_subscription(
+        &self,
+        subscription: &Subscription,
+    ) -> Result<(), NotificationRepositoryError> {
+        let (scope, queue_id, job_id) = match &subscription.scope {
+            SubscriptionScope::User => ("user", None, None),
+            SubscriptionScope::Queue { queue_id } => ("queue", Some(queue_id.into_inner()), None),
+            SubscriptionScope::Job { job_id } => ("job", None, Some(job_id.into_inner())),
+        };
+        let event_kinds: Vec<String> = subscription
+            .event_kinds
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO notification_subscriptions (
+                subscription_id, owner_id, scope, queue_id, job_id, event_kinds, channel,
+                backlog_threshold, backlog_active, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            subscription.id.inner(),
+            subscription.owner_id.inner(),
+            scope,
+            queue_id,
+            job_id,
+            &event_kinds,
+            &serde_json::to_value(&subscription.channel).map_err(|e| anyhow::anyhow!(e))?,
+            subscription.backlog_threshold,
+            subscription.backlog_active,
+            subscription.created_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                NotificationRepositoryError::ScopeNotFound(
+                    queue_id
+                        .or(job_id)
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                )
+            }
+            _ => NotificationRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_subscription(
+        &self,
+        id: &SubscriptionId,
+    ) -> Result<Subscription, NotificationRepositoryError> {
+        let record = sqlx::query_as!(
+            SubscriptionRecord,
+            r#"
+            SELECT subscription_id, owner_id, scope, queue_id, job_id, event_kinds, channel,
+                   backlog_threshold, backlog_active, created_at
+            FROM notification_subscriptions
+            WHERE subscription_id = $1
+            "#,
+            id.inner()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => NotificationRepositoryError::NotFound(id.to_string()),
+            _ => NotificationRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(record.try_into()?)
+    }
+
+    async fn list_subscriptions_for_owner(
+        &self,
+        owner_id: &UserId,
+    ) -> Result<Vec<Subscription>, NotificationRepositoryError> {
+        let rows = sqlx::query_as!(
+            SubscriptionRecord,
+            r#"
+            SELECT subscription_id, owner_id, scope, queue_id, job_id, event_kinds, channel,
+                   backlog_threshold, backlog_active, created_at
+            FROM notification_subscriptions
+            WHERE owner_id = $1
+            ORDER BY created_at ASC
+            "#,
+            owner_id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        into_subscriptions(rows)
+    }
+
+    async fn list_subscriptions_for_event(
+        &self,
+        kind: EventKind,
+    ) -> Result<Vec<Subscription>, NotificationRepositoryError> {
+        let rows = sqlx::query_as!(
+            SubscriptionRecord,
+            r#"
+            SELECT subscription_id, owner_id, scope, queue_id, job_id, event_kinds, channel,
+                   backlog_threshold, backlog_active, created_at
+            FROM notification_subscriptions
+            WHERE $1 = ANY(event_kinds)
+            "#,
+            kind.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        into_subscriptions(rows)
+    }
+
+    async fn delete_subscription(
+        &self,
+        id: &SubscriptionId,
+    ) -> Result<(), NotificationRepositoryError> {
+        let result = sqlx::query!(
+            "DELETE FROM notification_subscriptions WHERE subscription_id = $1",
+            id.inner()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(NotificationRepositoryError::NotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn set_backlog_active(
+        &self,
+        id: &SubscriptionId,
+        active: bool,
+    ) -> Result<(), NotificationRepositoryError> {
+        sqlx::query!(
+            "UPDATE notification_subscriptions SET backlog_active = $2 WHERE subscription_id = $1",
+            id.inner(),
+            active
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn create_delivery(
+        &self,
+        delivery: &Delivery,
+    ) -> Result<(), NotificationRepositoryError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO notification_deliveries (
+                delivery_id, subscription_id, event_kind, payload, status, attempts, last_error,
+                next_attempt_at, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            delivery.id.inner(),
+            delivery.subscription_id.inner(),
+            delivery.event.kind.to_string(),
+            &serde_json::to_value(&delivery.event).map_err(|e| anyhow::anyhow!(e))?,
+            delivery.status.to_string(),
+            delivery.attempts,
+            delivery.last_error,
+            delivery.next_attempt_at,
+            delivery.created_at,
+            delivery.updated_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn update_delivery(
+        &self,
+        delivery: &Delivery,
+    ) -> Result<(), NotificationRepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE notification_deliveries
+            SET status = $2, attempts = $3, last_error = $4, next_attempt_at = $5
+            WHERE delivery_id = $1
+            "#,
+            delivery.id.inner(),
+            delivery.status.to_string(),
+            delivery.attempts,
+            delivery.last_error,
+            delivery.next_attempt_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn list_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Delivery>, NotificationRepositoryError> {
+        let rows = sqlx::query_as!(
+            DeliveryRecord,
+            r#"
+            SELECT delivery_id, subscription_id, payload, status, attempts, last_error,
+                   next_attempt_at, created_at, updated_at
+            FROM notification_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= $1
+            ORDER BY next_attempt_at ASC
+            LIMIT $2
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        into_deliveries(rows)
+    }
+
+    async fn list_deliveries(
+        &self,
+        subscription_id: &SubscriptionId,
+        limit: i64,
+    ) -> Result<Vec<Delivery>, NotificationRepositoryError> {
+        let rows = sqlx::query_as!(
+            DeliveryRecord,
+            r#"
+            SELECT delivery_id, subscription_id, payload, status, attempts, last_error,
+                   next_attempt_at, created_at, updated_at
+            FROM notification_deliveries
+            WHERE subscription_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            subscription_id.inner(),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| NotificationRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        into_deliveries(rows)
+    }
+}
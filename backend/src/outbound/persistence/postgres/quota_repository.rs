@@ -0,0 +1,203 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::domain::quota::{
+    models::{QuotaSubject, ResourceQuota},
+    ports::{QuotaRepository, QuotaRepositoryError},
+};
+
+use super::records::ResourceQuotaRecord;
+
+pub struct PostgresQuotaRepository {
+    pool: PgPool,
+}
+
+impl PostgresQuotaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn map_write_error(subject: &QuotaSubject, e: sqlx::Error) -> QuotaRepositoryError {
+    match e {
+        sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+            QuotaRepositoryError::NotFound(subject.to_string())
+        }
+        _ => QuotaRepositoryError::Unknown(anyhow::anyhow!(e)),
+    }
+}
+
+#[async_trait]
+impl QuotaRepository for PostgresQuotaRepository {
+    async fn get_quota(
+        &self,
+        subject: &QuotaSubject,
+    ) -> Result<Option<ResourceQuota>, QuotaRepositoryError> {
+        let record = match subject {
+            QuotaSubject::User(user_id) => {
+                sqlx::query_as!(
+                    ResourceQuotaRecord,
+                    r#"
+                    SELECT user_id AS subject_id, max_gpus, max_cpu_millicores, max_memory_mb,
+                           max_running_jobs, max_sessions, monthly_gpu_hours
+                    FROM user_quotas
+                    WHERE user_id = $1
+                    "#,
+                    user_id.inner()
+                )
+                .fetch_optional(&self.pool)
+                .await
+            }
+            QuotaSubject::Queue(queue_id) => {
+                sqlx::query_as!(
+                    ResourceQuotaRecord,
+                    r#"
+                    SELECT queue_id AS subject_id, max_gpus, max_cpu_millicores, max_memory_mb,
+                           max_running_jobs, max_sessions, monthly_gpu_hours
+                    FROM queue_quotas
+                    WHERE queue_id = $1
+                    "#,
+                    queue_id.inner()
+                )
+                .fetch_optional(&self.pool)
+                .await
+            }
+        }
+        .map_err(|e: sqlx::Error| QuotaRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(record.map(Into::into))
+    }
+
+    async fn set_quota(
+        &self,
+        subject: &QuotaSubject,
+        quota: &ResourceQuota,
+    ) -> Result<(), QuotaRepositoryError> {
+        match subject {
+            QuotaSubject::User(user_id) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO user_quotas (
+                        user_id, max_gpus, max_cpu_millicores, max_memory_mb, max_running_jobs,
+                        max_sessions, monthly_gpu_hours
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    ON CONFLICT (user_id) DO UPDATE SET
+                        max_gpus = EXCLUDED.max_gpus,
+                        max_cpu_millicores = EXCLUDED.max_cpu_millicores,
+                        max_memory_mb = EXCLUDED.max_memory_mb,
+                        max_running_jobs = EXCLUDED.max_running_jobs,
+                        max_sessions = EXCLUDED.max_sessions,
+                        monthly_gpu_hours = EXCLUDED.monthly_gpu_hours
+                    "#,
+                    user_id.inner(),
+                    quota.max_gpus,
+                    quota.max_cpu_millicores,
+                    quota.max_memory_mb,
+                    quota.max_running_jobs,
+                    quota.max_sessions,
+                    quota.monthly_gpu_hours,
+                )
+                .execute(&self.pool)
+                .await
+            }
+            QuotaSubject::Queue(queue_id) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO queue_quotas (
+                        queue_id, max_gpus, max_cpu_millicores, max_memory_mb, max_running_jobs,
+                        max_sessions, monthly_gpu_hours
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    ON CONFLICT (queue_id) DO UPDATE SET
+                        max_gpus = EXCLUDED.max_gpus,
+                        max_cpu_millicores = EXCLUDED.max_cpu_millicores,
+                        max_memory_mb = EXCLUDED.max_memory_mb,
+                        max_running_jobs = EXCLUDED.max_running_jobs,
+                        max_sessions = EXCLUDED.max_sessions,
+                        monthly_gpu_hours = EXCLUDED.monthly_gpu_hours
+                    "#,
+                    queue_id.inner(),
+                    quota.max_gpus,
+                    quota.max_cpu_millicores,
+                    quota.max_memory_mb,
+                    quota.max_running_jobs,
+                    quota.max_sessions,
+                    quota.monthly_gpu_hours,
+                )
+                .execute(&self.pool)
+                .await
+            }
+        }
+        .map_err(|e| map_write_error(subject, e))?;
+
+        Ok(())
+    }
+
+    async fn delete_quota(&self, subject: &QuotaSubject) -> Result<(), QuotaRepositoryError> {
+        let result = match subject {
+            QuotaSubject::User(user_id) => {
+                sqlx::query!(
+                    "DELETE FROM user_quotas WHERE user_id = $1",
+                    user_id.inner()
+                )
+                .execute(&self.pool)
+                .await
+            }
+            QuotaSubject::Queue(queue_id) => {
+                sqlx::query!(
+                    "DELETE FROM queue_quotas WHERE queue_id = $1",
+                    queue_id.inner()
+                )
+                .execute(&self.pool)
+                .await
+            }
+        }
+        .map_err(|e: sqlx::Error| QuotaRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(QuotaRepositoryError::NotFound(format!(
+                "quota for {subject}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn list_quotas(
+        &self,
+    ) -> Result<Vec<(QuotaSubject, ResourceQuota)>, QuotaRepositoryError> {
+        let user_quotas = sqlx::query_as!(
+            ResourceQuotaRecord,
+            r#"
+            SELECT user_id AS subject_id, max_gpus, max_cpu_millicores, max_memory_mb,
+                   max_running_jobs, max_sessions, monthly_gpu_hours
+            FROM user_quotas
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| QuotaRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        let queue_quotas = sqlx::query_as!(
+            ResourceQuotaRecord,
+            r#"
+            SELECT queue_id AS subject_id, max_gpus, max_cpu_millicores, max_memory_mb,
+                   max_running_jobs, max_sessions, monthly_gpu_hours
+            FROM queue_quotas
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| QuotaRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        let users = user_quotas
+            .into_iter()
+            .map(|record| (QuotaSubject::User(record.subject_id.into()), record.into()));
+        let queues = queue_quotas
+            .into_iter()
+            .map(|record| (QuotaSubject::Queue(record.subject_id.into()), record.into()));
+
+        Ok(users.chain(queues).collect())
+    }
+}
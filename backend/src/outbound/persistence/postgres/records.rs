@@ -1,10 +1,19 @@
 use crate::domain::{
+    accounting::models::UsageRecord,
     cluster::models::{
         Architecture, Cluster, ClusterCpuStats, ClusterDetails, ClusterGpuStats, ClusterJobStats,
         ClusterMemoryStats, ClusterNode, ClusterSummary, Cpu, CpuManufacturer, Gpu,
-        GpuManufacturer, GpuModel, NodeStatus,
+        GpuManufacturer, GpuModel, NodeCapacity, NodeStatus,
+    },
+    idempotency::models::{IdempotencyRecord, StoredResponse},
+    job_template::models::JobTemplate,
+    notification::models::{Delivery, Subscription, SubscriptionScope},
+    quota::models::ResourceQuota,
+    sweep::models::{SearchStrategy, Sweep, SweepStatus, Trial, TrialStatus},
+    training_job::models::{
+        FailureReason, JobKind, JobMetric, ResourceUsage, ResourceUsagePeaks, ResourceUsageSample,
+        TrainingJob, TrainingJobStatus,
     },
-    training_job::models::{TrainingJob, TrainingJobStatus},
     user::models::ApiKey,
 };
 use chrono::{DateTime, Utc};
@@ -289,6 +298,8 @@ pub struct ClusterNodeRecord {
     pub updated_at: DateTime<Utc>,
     pub assigned_job_id: Option<uuid::Uuid>,
     pub reported_job_id: Option<uuid::Uuid>,
+    pub cached_images: Vec<String>,
+    pub draining: bool,
 }
 
 impl From<ClusterNodeRecord> for ClusterNode {
@@ -317,6 +328,8 @@ impl From<ClusterNodeRecord> for ClusterNode {
             updated_at: record.updated_at,
             assigned_job_id: record.assigned_job_id.map(Into::into),
             reported_job_id: record.reported_job_id.map(Into::into),
+            cached_images: record.cached_images,
+            draining: record.draining,
         }
     }
 }
@@ -434,6 +447,56 @@ impl From<TrainingJobStatusRecord> for TrainingJobStatus {
     }
 }
 
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "training_job_kind", rename_all = "lowercase")]
+pub enum JobKindRecord {
+    Batch,
+    Interactive,
+}
+
+impl From<JobKind> for JobKindRecord {
+    fn from(value: JobKind) -> Self {
+        match value {
+            JobKind::Batch => Self::Batch,
+            JobKind::Interactive => Self::Interactive,
+        }
+    }
+}
+
+impl From<JobKindRecord> for JobKind {
+    fn from(value: JobKindRecord) -> Self {
+        match value {
+            JobKindRecord::Batch => Self::Batch,
+            JobKindRecord::Interactive => Self::Interactive,
+        }
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "training_job_failure_reason", rename_all = "snake_case")]
+pub enum FailureReasonRecord {
+    Error,
+    OutOfMemory,
+}
+
+impl From<FailureReason> for FailureReasonRecord {
+    fn from(value: FailureReason) -> Self {
+        match value {
+            FailureReason::Error => Self::Error,
+            FailureReason::OutOfMemory => Self::OutOfMemory,
+        }
+    }
+}
+
+impl From<FailureReasonRecord> for FailureReason {
+    fn from(value: FailureReasonRecord) -> Self {
+        match value {
+            FailureReasonRecord::Error => Self::Error,
+            FailureReasonRecord::OutOfMemory => Self::OutOfMemory,
+        }
+    }
+}
+
 #[derive(sqlx::FromRow)]
 pub struct TrainingJobRecord {
     pub id: Uuid,
@@ -442,7 +505,24 @@ pub struct TrainingJobRecord {
     pub status: TrainingJobStatusRecord,
     pub node_id: Option<Uuid>,
     pub queue_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
     pub resource_requirements: serde_json::Value,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub assigned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub pending_reason: Option<String>,
+    pub labels: serde_json::Value,
+    pub annotations: serde_json::Value,
+    pub env: serde_json::Value,
+    pub exposed_ports: serde_json::Value,
+    pub kind: JobKindRecord,
+    pub session: Option<serde_json::Value>,
+    pub last_activity_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub resubmitted_from: Option<Uuid>,
+    pub termination_grace_period_secs: i32,
+    pub restart_count: i32,
+    pub job_token: Option<String>,
+    pub failure_reason: Option<FailureReasonRecord>,
+    pub assigned_gpus: serde_json::Value,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -459,7 +539,500 @@ impl TryFrom<TrainingJobRecord> for TrainingJob {
             status: value.status.into(),
             node_id: value.node_id.map(|v| v.into()),
             queue_id: value.queue_id.map(Into::into),
+            user_id: value.user_id.map(Into::into),
             resource_requirements,
+            started_at: value.started_at,
+            assigned_at: value.assigned_at,
+            pending_reason: value.pending_reason,
+            labels: serde_json::from_value(value.labels)?,
+            annotations: serde_json::from_value(value.annotations)?,
+            env: serde_json::from_value(value.env)?,
+            exposed_ports: serde_json::from_value(value.exposed_ports)?,
+            kind: value.kind.into(),
+            session: value.session.map(serde_json::from_value).transpose()?,
+            last_activity_at: value.last_activity_at,
+            resubmitted_from: value.resubmitted_from.map(Into::into),
+            termination_grace_period_secs: value.termination_grace_period_secs,
+            restart_count: value.restart_count,
+            job_token: value.job_token,
+            failure_reason: value.failure_reason.map(Into::into),
+            assigned_gpus: serde_json::from_value(value.assigned_gpus)?,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ResourceUsageSampleRecord {
+    pub job_id: Uuid,
+    pub node_id: Uuid,
+    pub sampled_at: DateTime<Utc>,
+    pub cpu_millicores: i32,
+    pub memory_rss_mb: i32,
+    pub memory_peak_mb: i32,
+    pub network_rx_bytes: i64,
+    pub network_tx_bytes: i64,
+    pub block_read_bytes: i64,
+    pub block_write_bytes: i64,
+    pub gpus: serde_json::Value,
+}
+
+impl TryFrom<ResourceUsageSampleRecord> for ResourceUsageSample {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ResourceUsageSampleRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            job_id: value.job_id.into(),
+            node_id: value.node_id.into(),
+            sampled_at: value.sampled_at,
+            usage: ResourceUsage {
+                cpu_millicores: value.cpu_millicores,
+                memory_rss_mb: value.memory_rss_mb,
+                memory_peak_mb: value.memory_peak_mb,
+                network_rx_bytes: value.network_rx_bytes,
+                network_tx_bytes: value.network_tx_bytes,
+                block_read_bytes: value.block_read_bytes,
+                block_write_bytes: value.block_write_bytes,
+                gpus: serde_json::from_value(value.gpus)?,
+            },
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct JobMetricRecord {
+    pub job_id: Uuid,
+    pub name: String,
+    pub step: i64,
+    pub value: f64,
+    pub reported_at: DateTime<Utc>,
+}
+
+impl From<JobMetricRecord> for JobMetric {
+    fn from(value: JobMetricRecord) -> Self {
+        Self {
+            job_id: value.job_id.into(),
+            name: value.name,
+            step: value.step,
+            value: value.value,
+            reported_at: value.reported_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ResourceUsagePeaksRecord {
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub gpu_memory_mb: i32,
+    pub gpu_utilization_percent: i32,
+    pub network_rx_bytes: i64,
+    pub network_tx_bytes: i64,
+    pub block_read_bytes: i64,
+    pub block_write_bytes: i64,
+    pub last_sampled_at: DateTime<Utc>,
+}
+
+impl From<ResourceUsagePeaksRecord> for ResourceUsagePeaks {
+    fn from(value: ResourceUsagePeaksRecord) -> Self {
+        Self {
+            cpu_millicores: value.cpu_millicores,
+            memory_mb: value.memory_mb,
+            gpu_memory_mb: value.gpu_memory_mb,
+            gpu_utilization_percent: value.gpu_utilization_percent,
+            network_rx_bytes: value.network_rx_bytes,
+            network_tx_bytes: value.network_tx_bytes,
+            block_read_bytes: value.block_read_bytes,
+            block_write_bytes: value.block_write_bytes,
+            last_sampled_at: value.last_sampled_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct UsageRecordRecord {
+    pub job_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub queue_id: Option<Uuid>,
+    pub cluster_id: Uuid,
+    pub node_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub gpu_count: i32,
+    pub gpu_model: Option<String>,
+}
+
+impl From<UsageRecordRecord> for UsageRecord {
+    fn from(value: UsageRecordRecord) -> Self {
+        Self {
+            job_id: value.job_id.into(),
+            user_id: value.user_id.map(Into::into),
+            queue_id: value.queue_id.map(Into::into),
+            cluster_id: value.cluster_id.into(),
+            node_id: value.node_id.into(),
+            started_at: value.started_at,
+            ended_at: value.ended_at,
+            cpu_millicores: value.cpu_millicores,
+            memory_mb: value.memory_mb,
+            gpu_count: value.gpu_count,
+            gpu_model: value.gpu_model,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ResourceQuotaRecord {
+    pub subject_id: Uuid,
+    pub max_gpus: Option<i32>,
+    pub max_cpu_millicores: Option<i32>,
+    pub max_memory_mb: Option<i32>,
+    pub max_running_jobs: Option<i32>,
+    pub max_sessions: Option<i32>,
+    pub monthly_gpu_hours: Option<f64>,
+}
+
+impl From<ResourceQuotaRecord> for ResourceQuota {
+    fn from(value: ResourceQuotaRecord) -> Self {
+        Self {
+            max_gpus: value.max_gpus,
+            max_cpu_millicores: value.max_cpu_millicores,
+            max_memory_mb: value.max_memory_mb,
+            max_running_jobs: value.max_running_jobs,
+            max_sessions: value.max_sessions,
+            monthly_gpu_hours: value.monthly_gpu_hours,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct NodeCapacityRecord {
+    pub node_id: Uuid,
+    pub cluster_id: Uuid,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub gpu_count: i32,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+impl From<NodeCapacityRecord> for NodeCapacity {
+    fn from(value: NodeCapacityRecord) -> Self {
+        Self {
+            node_id: value.node_id.into(),
+            cluster_id: value.cluster_id.into(),
+            cpu_millicores: value.cpu_millicores,
+            memory_mb: value.memory_mb,
+            gpu_count: value.gpu_count,
+            last_seen_at: value.last_seen_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct JobTemplateRecord {
+    pub template_id: Uuid,
+    pub name: String,
+    pub owner_id: Uuid,
+    pub shared: bool,
+    pub definition: String,
+    pub queue_id: Option<Uuid>,
+    pub resource_requirements: serde_json::Value,
+    pub env: serde_json::Value,
+    pub labels: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<JobTemplateRecord> for JobTemplate {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JobTemplateRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.template_id.into(),
+            name: value.name,
+            owner_id: value.owner_id.into(),
+            shared: value.shared,
+            definition: value.definition,
+            queue_id: value.queue_id.map(Into::into),
+            resource_requirements: serde_json::from_value(value.resource_requirements)?,
+            env: serde_json::from_value(value.env)?,
+            labels: serde_json::from_value(value.labels)?,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct IdempotencyKeyRecord {
+    pub scope: String,
+    pub key: String,
+    pub request_hash: String,
+    pub response_status: Option<i32>,
+    pub response_content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<IdempotencyKeyRecord> for IdempotencyRecord {
+    fn from(value: IdempotencyKeyRecord) -> Self {
+        let response = match (value.response_status, value.response_body) {
+            (Some(status), Some(body)) => Some(StoredResponse {
+                status: status as u16,
+                content_type: value.response_content_type,
+                body,
+            }),
+            _ => None,
+        };
+        Self {
+            scope: value.scope,
+            key: value.key,
+            request_hash: value.request_hash,
+            response,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct SubscriptionRecord {
+    pub subscription_id: Uuid,
+    pub owner_id: Uuid,
+    pub scope: String,
+    pub queue_id: Option<Uuid>,
+    pub job_id: Option<Uuid>,
+    pub event_kinds: Vec<String>,
+    pub channel: serde_json::Value,
+    pub backlog_threshold: Option<i32>,
+    pub backlog_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<SubscriptionRecord> for Subscription {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SubscriptionRecord) -> Result<Self, Self::Error> {
+        let scope = match (value.scope.as_str(), value.queue_id, value.job_id) {
+            ("user", _, _) => SubscriptionScope::User,
+            ("queue", Some(queue_id), _) => SubscriptionScope::Queue {
+                queue_id: queue_id.into(),
+            },
+            ("job", _, Some(job_id)) => SubscriptionScope::Job {
+                job_id: job_id.into(),
+            },
+            (scope, _, _) => anyhow::bail!("invalid subscription scope '{scope}'"),
+        };
+
+        Ok(Self {
+            id: value.subscription_id.into(),
+            owner_id: value.owner_id.into(),
+            scope,
+            event_kinds: value
+                .event_kinds
+                .iter()
+                .map(|kind| kind.parse())
+                .collect::<Result<_, _>>()?,
+            channel: serde_json::from_value(value.channel)?,
+            backlog_threshold: value.backlog_threshold,
+            backlog_active: value.backlog_active,
+            created_at: value.created_at,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct DeliveryRecord {
+    pub delivery_id: Uuid,
+    pub subscription_id: Uuid,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<DeliveryRecord> for Delivery {
+    type Error = anyhow::Error;
+
+    fn try_from(value: DeliveryRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.delivery_id.into(),
+            subscription_id: value.subscription_id.into(),
+            event: serde_json::from_value(value.payload)?,
+            status: value.status.parse()?,
+            attempts: value.attempts,
+            last_error: value.last_error,
+            next_attempt_at: value.next_attempt_at,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "sweep_strategy", rename_all = "lowercase")]
+pub enum SearchStrategyRecord {
+    Grid,
+    Random,
+    Tpe,
+}
+
+impl From<SearchStrategy> for SearchStrategyRecord {
+    fn from(value: SearchStrategy) -> Self {
+        match value {
+            SearchStrategy::Grid => Self::Grid,
+            SearchStrategy::Random => Self::Random,
+            SearchStrategy::Tpe => Self::Tpe,
+        }
+    }
+}
+
+impl From<SearchStrategyRecord> for SearchStrategy {
+    fn from(value: SearchStrategyRecord) -> Self {
+        match value {
+            SearchStrategyRecord::Grid => Self::Grid,
+            SearchStrategyRecord::Random => Self::Random,
+            SearchStrategyRecord::Tpe => Self::Tpe,
+        }
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "sweep_status", rename_all = "lowercase")]
+pub enum SweepStatusRecord {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+impl From<SweepStatus> for SweepStatusRecord {
+    fn from(value: SweepStatus) -> Self {
+        match value {
+            SweepStatus::Running => Self::Running,
+            SweepStatus::Completed => Self::Completed,
+            SweepStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+impl From<SweepStatusRecord> for SweepStatus {
+    fn from(value: SweepStatusRecord) -> Self {
+        match value {
+            SweepStatusRecord::Running => Self::Running,
+            SweepStatusRecord::Completed => Self::Completed,
+            SweepStatusRecord::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "sweep_trial_status", rename_all = "lowercase")]
+pub enum TrialStatusRecord {
+    Running,
+    Succeeded,
+    Failed,
+    Stopped,
+}
+
+impl From<TrialStatus> for TrialStatusRecord {
+    fn from(value: TrialStatus) -> Self {
+        match value {
+            TrialStatus::Running => Self::Running,
+            TrialStatus::Succeeded => Self::Succeeded,
+            TrialStatus::Failed => Self::Failed,
+            TrialStatus::Stopped => Self::Stopped,
+        }
+    }
+}
+
+impl From<TrialStatusRecord> for TrialStatus {
+    fn from(value: TrialStatusRecord) -> Self {
+        match value {
+            TrialStatusRecord::Running => Self::Running,
+            TrialStatusRecord::Succeeded => Self::Succeeded,
+            TrialStatusRecord::Failed => Self::Failed,
+            TrialStatusRecord::Stopped => Self::Stopped,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct SweepRecord {
+    pub sweep_id: Uuid,
+    pub name: String,
+    pub user_id: Uuid,
+    pub queue_id: Uuid,
+    pub definition: String,
+    pub resource_requirements: serde_json::Value,
+    pub env: serde_json::Value,
+    pub labels: serde_json::Value,
+    pub parameters: serde_json::Value,
+    pub objective: serde_json::Value,
+    pub strategy: SearchStrategyRecord,
+    pub early_stopping: Option<serde_json::Value>,
+    pub max_trials: i32,
+    pub max_parallel: i32,
+    pub status: SweepStatusRecord,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SweepRecord> for Sweep {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SweepRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.sweep_id.into(),
+            name: value.name,
+            user_id: value.user_id.into(),
+            queue_id: value.queue_id.into(),
+            definition: value.definition,
+            resource_requirements: serde_json::from_value(value.resource_requirements)?,
+            env: serde_json::from_value(value.env)?,
+            labels: serde_json::from_value(value.labels)?,
+            parameters: serde_json::from_value(value.parameters)?,
+            objective: serde_json::from_value(value.objective)?,
+            strategy: value.strategy.into(),
+            early_stopping: value
+                .early_stopping
+                .map(serde_json::from_value)
+                .transpose()?,
+            max_trials: value.max_trials,
+            max_parallel: value.max_parallel,
+            status: value.status.into(),
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct TrialRecord {
+    pub sweep_id: Uuid,
+    pub number: i32,
+    pub job_id: Uuid,
+    pub parameters: serde_json::Value,
+    pub status: TrialStatusRecord,
+    pub objective_value: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<TrialRecord> for Trial {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TrialRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sweep_id: value.sweep_id.into(),
+            number: value.number,
+            job_id: value.job_id.into(),
+            parameters: serde_json::from_value(value.parameters)?,
+            status: value.status.into(),
+            objective_value: value.objective_value,
             created_at: value.created_at,
             updated_at: value.updated_at,
         })
@@ -0,0 +1,232 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::domain::sweep::{
+    models::{Sweep, SweepId, SweepStatus, Trial},
+    ports::{SweepRepository, SweepRepositoryError},
+};
+
+use super::records::{
+    SearchStrategyRecord, SweepRecord, SweepStatusRecord, TrialRecord, TrialStatusRecord,
+};
+
+pub struct PostgresSweepRepository {
+    pool: PgPool,
+}
+
+impl PostgresSweepRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SweepRepository for PostgresSweepRepository {
+    async fn create_sweep(&self, sweep: &Sweep) -> Result<(), SweepRepositoryError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO sweeps (
+                sweep_id, name, user_id, queue_id, definition, resource_requirements, env,
+                labels, parameters, objective, strategy, early_stopping, max_trials,
+                max_parallel, status, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            "#,
+            sweep.id.inner(),
+            sweep.name,
+            sweep.user_id.inner(),
+            sweep.queue_id.inner(),
+            sweep.definition,
+            &serde_json::to_value(&sweep.resource_requirements).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&sweep.env).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&sweep.labels).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&sweep.parameters).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&sweep.objective).map_err(|e| anyhow::anyhow!(e))?,
+            SearchStrategyRecord::from(sweep.strategy) as _,
+            sweep
+                .early_stopping
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()
+                .map_err(|e| anyhow::anyhow!(e))?,
+            sweep.max_trials,
+            sweep.max_parallel,
+            SweepStatusRecord::from(sweep.status) as _,
+            sweep.created_at,
+            sweep.updated_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                SweepRepositoryError::QueueNotFound(sweep.queue_id.to_string())
+            }
+            _ => SweepRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_sweep_by_id(&self, id: &SweepId) -> Result<Sweep, SweepRepositoryError> {
+        let record = sqlx::query_as!(
+            SweepRecord,
+            r#"
+            SELECT sweep_id, name, user_id, queue_id, definition, resource_requirements, env,
+                   labels, parameters, objective, strategy AS "strategy: SearchStrategyRecord",
+                   early_stopping, max_trials, max_parallel, status AS "status: SweepStatusRecord",
+                   created_at, updated_at
+            FROM sweeps
+            WHERE sweep_id = $1
+            "#,
+            id.inner()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => SweepRepositoryError::NotFound(id.to_string()),
+            _ => SweepRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(record.try_into()?)
+    }
+
+    async fn list_sweeps(
+        &self,
+        status: Option<SweepStatus>,
+    ) -> Result<Vec<Sweep>, SweepRepositoryError> {
+        let rows = sqlx::query_as!(
+            SweepRecord,
+            r#"
+            SELECT sweep_id, name, user_id, queue_id, definition, resource_requirements, env,
+                   labels, parameters, objective, strategy AS "strategy: SearchStrategyRecord",
+                   early_stopping, max_trials, max_parallel, status AS "status: SweepStatusRecord",
+                   created_at, updated_at
+            FROM sweeps
+            WHERE $1::sweep_status IS NULL OR status = $1
+            ORDER BY created_at DESC
+            "#,
+            status.map(SweepStatusRecord::from) as _
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| SweepRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        let sweeps = rows
+            .into_iter()
+            .map(|row| row.try_into())
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(sweeps)
+    }
+
+    async fn update_sweep_status(
+        &self,
+        id: &SweepId,
+        status: SweepStatus,
+    ) -> Result<(), SweepRepositoryError> {
+        let result = sqlx::query!(
+            "UPDATE sweeps SET status = $2 WHERE sweep_id = $1",
+            id.inner(),
+            SweepStatusRecord::from(status) as _,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| SweepRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(SweepRepositoryError::NotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_sweep(&self, id: &SweepId) -> Result<(), SweepRepositoryError> {
+        let result = sqlx::query!("DELETE FROM sweeps WHERE sweep_id = $1", id.inner())
+            .execute(&self.pool)
+            .await
+            .map_err(|e: sqlx::Error| SweepRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(SweepRepositoryError::NotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn create_trial(&self, trial: &Trial) -> Result<(), SweepRepositoryError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO sweep_trials (
+                sweep_id, number, job_id, parameters, status, objective_value, created_at,
+                updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            trial.sweep_id.inner(),
+            trial.number,
+            trial.job_id.inner(),
+            &serde_json::to_value(&trial.parameters).map_err(|e| anyhow::anyhow!(e))?,
+            TrialStatusRecord::from(trial.status) as _,
+            trial.objective_value,
+            trial.created_at,
+            trial.updated_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                SweepRepositoryError::NotFound(trial.sweep_id.to_string())
+            }
+            _ => SweepRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(())
+    }
+
+    async fn list_trials(&self, sweep_id: &SweepId) -> Result<Vec<Trial>, SweepRepositoryError> {
+        let rows = sqlx::query_as!(
+            TrialRecord,
+            r#"
+            SELECT sweep_id, number, job_id, parameters,
+                   status AS "status: TrialStatusRecord", objective_value, created_at, updated_at
+            FROM sweep_trials
+            WHERE sweep_id = $1
+            ORDER BY number ASC
+            "#,
+            sweep_id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| SweepRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        let trials = rows
+            .into_iter()
+            .map(|row| row.try_into())
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(trials)
+    }
+
+    async fn update_trial(&self, trial: &Trial) -> Result<(), SweepRepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sweep_trials
+            SET status = $3, objective_value = $4
+            WHERE sweep_id = $1 AND number = $2
+            "#,
+            trial.sweep_id.inner(),
+            trial.number,
+            TrialStatusRecord::from(trial.status) as _,
+            trial.objective_value,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| SweepRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(SweepRepositoryError::NotFound(trial.sweep_id.to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -1,16 +1,26 @@
 use async_trait::async_trait;
 use sqlx::PgPool;
 
+use chrono::{DateTime, Utc};
+
 use crate::domain::{
-    cluster::models::NodeId,
+    accounting::models::UsageRecord,
+    cluster::models::{ClusterId, NodeId},
     queue::models::QueueId,
     training_job::{
-        models::{GetTrainingJobsFilters, JobId, TrainingJob, TrainingJobStatus},
+        models::{
+            AssignedGpu, FailureReason, GetTrainingJobsFilters, JobId, JobMetric, LabelRequirement,
+            ResourceUsagePeaks, ResourceUsageSample, TrainingJob, TrainingJobMetadataPatch,
+            TrainingJobStatus,
+        },
         ports::{TrainingJobRepository, TrainingJobRepositoryError},
     },
 };
 
-use super::records::{TrainingJobRecord, TrainingJobStatusRecord};
+use super::records::{
+    FailureReasonRecord, JobKindRecord, JobMetricRecord, ResourceUsagePeaksRecord,
+    ResourceUsageSampleRecord, TrainingJobRecord, TrainingJobStatusRecord, UsageRecordRecord,
+};
 
 pub struct PostgresTrainingJobRepository {
     pool: PgPool,
@@ -26,14 +36,23 @@ impl PostgresTrainingJobRepository {
 impl TrainingJobRepository for PostgresTrainingJobRepository {
     async fn create(&self, training_job: &TrainingJob) -> Result<(), TrainingJobRepositoryError> {
         sqlx::query!(
-            "INSERT INTO training_jobs (id, name, definition, status, queue_id, resource_requirements, created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            "INSERT INTO training_jobs (id, name, definition, status, queue_id, user_id, resource_requirements, labels, annotations, env, exposed_ports, kind, session, resubmitted_from, termination_grace_period_secs, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)",
             training_job.id.inner(),
             training_job.name,
             training_job.definition,
             TrainingJobStatusRecord::from(training_job.status.clone()) as _,
             training_job.queue_id.map(|q| q.into_inner()),
+            training_job.user_id.map(|u| u.into_inner()),
             &serde_json::to_value(&training_job.resource_requirements).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&training_job.labels).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&training_job.annotations).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&training_job.env).map_err(|e| anyhow::anyhow!(e))?,
+            &serde_json::to_value(&training_job.exposed_ports).map_err(|e| anyhow::anyhow!(e))?,
+            JobKindRecord::from(training_job.kind) as _,
+            training_job.session.as_ref().map(serde_json::to_value).transpose().map_err(|e| anyhow::anyhow!(e))?,
+            training_job.resubmitted_from.map(|j| j.into_inner()),
+            training_job.termination_grace_period_secs,
             training_job.created_at,
             training_job.updated_at,
         )
@@ -51,7 +70,7 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
         let mut query = sqlx::QueryBuilder::new(
             r#"
             SELECT id, name, definition, status,
-                node_id, queue_id, resource_requirements, created_at, updated_at
+                node_id, queue_id, user_id, resource_requirements, started_at, assigned_at, pending_reason, labels, annotations, env, exposed_ports, kind, session, last_activity_at, resubmitted_from, termination_grace_period_secs, restart_count, job_token, failure_reason, assigned_gpus, created_at, updated_at
                 FROM training_jobs WHERE 1 = 1"#,
         );
 
@@ -70,6 +89,49 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
             query.push_bind(TrainingJobStatusRecord::from(status));
         }
 
+        if let Some(queue_id) = filters.queue_id {
+            query.push(" AND queue_id = ");
+            query.push_bind(queue_id.into_inner());
+        }
+
+        if let Some(user_id) = filters.user_id {
+            query.push(" AND user_id = ");
+            query.push_bind(user_id.into_inner());
+        }
+
+        if let Some(created_after) = filters.created_after {
+            query.push(" AND created_at >= ");
+            query.push_bind(created_after);
+        }
+
+        if let Some(created_before) = filters.created_before {
+            query.push(" AND created_at < ");
+            query.push_bind(created_before);
+        }
+
+        if let Some(selector) = filters.selector {
+            for requirement in selector.requirements {
+                match requirement {
+                    LabelRequirement::Equals { key, value } => {
+                        query.push(" AND labels @> ");
+                        query.push_bind(serde_json::json!({ key: value }));
+                    }
+                    LabelRequirement::NotEquals { key, value } => {
+                        query.push(" AND NOT labels @> ");
+                        query.push_bind(serde_json::json!({ key: value }));
+                    }
+                    LabelRequirement::Exists(key) => {
+                        query.push(" AND labels ? ");
+                        query.push_bind(key);
+                    }
+                    LabelRequirement::NotExists(key) => {
+                        query.push(" AND NOT labels ? ");
+                        query.push_bind(key);
+                    }
+                }
+            }
+        }
+
         let rows: Vec<TrainingJobRecord> = query
             .build_query_as()
             .fetch_all(&self.pool)
@@ -90,7 +152,15 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
         status: TrainingJobStatus,
     ) -> Result<(), TrainingJobRepositoryError> {
         sqlx::query!(
-            "UPDATE training_jobs SET status = $1 WHERE id = $2",
+            r#"
+            UPDATE training_jobs
+            SET status = $1,
+                started_at = CASE
+                    WHEN $1::training_job_status = 'running' AND status <> 'running' THEN NOW()
+                    ELSE started_at
+                END
+            WHERE id = $2
+            "#,
             TrainingJobStatusRecord::from(status) as _,
             job_id.inner()
         )
@@ -105,10 +175,63 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
         &self,
         job_id: &JobId,
         node_id: &NodeId,
+        job_token: &str,
     ) -> Result<(), TrainingJobRepositoryError> {
         sqlx::query!(
-            "UPDATE training_jobs SET status = 'starting', node_id = $1 WHERE id = $2",
+            "UPDATE training_jobs SET status = 'starting', node_id = $1, assigned_at = NOW(), pending_reason = NULL, job_token = $3 WHERE id = $2",
             node_id.inner(),
+            job_id.inner(),
+            job_token
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn set_pending_reason(
+        &self,
+        job_id: &JobId,
+        reason: Option<String>,
+    ) -> Result<(), TrainingJobRepositoryError> {
+        sqlx::query!(
+            "UPDATE training_jobs SET pending_reason = $1 WHERE id = $2",
+            reason,
+            job_id.inner()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn set_failure_reason(
+        &self,
+        job_id: &JobId,
+        reason: FailureReason,
+    ) -> Result<(), TrainingJobRepositoryError> {
+        sqlx::query!(
+            "UPDATE training_jobs SET failure_reason = $1 WHERE id = $2",
+            FailureReasonRecord::from(reason) as _,
+            job_id.inner()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn set_assigned_gpus(
+        &self,
+        job_id: &JobId,
+        gpus: &[AssignedGpu],
+    ) -> Result<(), TrainingJobRepositoryError> {
+        sqlx::query!(
+            "UPDATE training_jobs SET assigned_gpus = $1 WHERE id = $2",
+            serde_json::to_value(gpus).map_err(|e| anyhow::anyhow!(e))?,
             job_id.inner()
         )
         .execute(&self.pool)
@@ -127,7 +250,7 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
             r#"
             SELECT
                 id, name, definition, status AS "status: TrainingJobStatusRecord", node_id, queue_id,
-                resource_requirements, created_at, updated_at
+                user_id, resource_requirements, started_at, assigned_at, pending_reason, labels, annotations, env, exposed_ports, kind AS "kind: JobKindRecord", session, last_activity_at, resubmitted_from, termination_grace_period_secs, restart_count, job_token, failure_reason AS "failure_reason: FailureReasonRecord", assigned_gpus, created_at, updated_at
             FROM training_jobs
             WHERE status = 'queued' AND queue_id = $1
             ORDER BY created_at ASC
@@ -163,7 +286,7 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
             TrainingJobRecord,
             r#"
             SELECT id, name, definition, status AS "status: TrainingJobStatusRecord", node_id, queue_id,
-                   resource_requirements, created_at, updated_at
+                   user_id, resource_requirements, started_at, assigned_at, pending_reason, labels, annotations, env, exposed_ports, kind AS "kind: JobKindRecord", session, last_activity_at, resubmitted_from, termination_grace_period_secs, restart_count, job_token, failure_reason AS "failure_reason: FailureReasonRecord", assigned_gpus, created_at, updated_at
             FROM training_jobs
             WHERE id = $1
             "#,
@@ -179,9 +302,68 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
         Ok(record.try_into()?)
     }
 
+    async fn update_metadata(
+        &self,
+        job_id: &JobId,
+        patch: &TrainingJobMetadataPatch,
+    ) -> Result<TrainingJob, TrainingJobRepositoryError> {
+        let (removed_labels, set_labels) = split_patch(&patch.labels);
+        let (removed_annotations, set_annotations) = split_patch(&patch.annotations);
+
+        let record = sqlx::query_as!(
+            TrainingJobRecord,
+            r#"
+            UPDATE training_jobs
+            SET labels = (labels - $2::text[]) || $3::jsonb,
+                annotations = (annotations - $4::text[]) || $5::jsonb,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, name, definition, status AS "status: TrainingJobStatusRecord", node_id, queue_id,
+                      user_id, resource_requirements, started_at, assigned_at, pending_reason, labels, annotations, env, exposed_ports, kind AS "kind: JobKindRecord", session, last_activity_at, resubmitted_from, termination_grace_period_secs, restart_count, job_token, failure_reason AS "failure_reason: FailureReasonRecord", assigned_gpus, created_at, updated_at
+            "#,
+            job_id.inner(),
+            &removed_labels,
+            set_labels,
+            &removed_annotations,
+            set_annotations,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => TrainingJobRepositoryError::NotFound(job_id.to_string()),
+            _ => TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)),
+        })?;
+
+        Ok(record.try_into()?)
+    }
+
+    async fn record_session_activity(
+        &self,
+        job_id: &JobId,
+        at: DateTime<Utc>,
+    ) -> Result<(), TrainingJobRepositoryError> {
+        sqlx::query!(
+            "UPDATE training_jobs SET last_activity_at = $2
+             WHERE id = $1 AND (last_activity_at IS NULL OR last_activity_at < $2::timestamptz - INTERVAL '1 minute')",
+            job_id.inner(),
+            at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
     async fn reset_job_status(&self, job_id: &JobId) -> Result<(), TrainingJobRepositoryError> {
         sqlx::query!(
-            "UPDATE training_jobs SET status = 'queued', node_id = NULL WHERE id = $1",
+            r#"
+            UPDATE training_jobs
+            SET status = 'queued', node_id = NULL, started_at = NULL, assigned_at = NULL, job_token = NULL,
+                failure_reason = NULL, assigned_gpus = '[]',
+                restart_count = restart_count + CASE WHEN started_at IS NULL THEN 0 ELSE 1 END
+            WHERE id = $1
+            "#,
             job_id.inner()
         )
         .execute(&self.pool)
@@ -191,6 +373,40 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
         Ok(())
     }
 
+    async fn update_queue(
+        &self,
+        job_id: &JobId,
+        queue_id: &QueueId,
+    ) -> Result<(), TrainingJobRepositoryError> {
+        let result = sqlx::query!(
+            "UPDATE training_jobs SET queue_id = $2 WHERE id = $1",
+            job_id.inner(),
+            queue_id.inner()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(TrainingJobRepositoryError::NotFound(job_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, job_id: &JobId) -> Result<(), TrainingJobRepositoryError> {
+        let result = sqlx::query!("DELETE FROM training_jobs WHERE id = $1", job_id.inner())
+            .execute(&self.pool)
+            .await
+            .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(TrainingJobRepositoryError::NotFound(job_id.to_string()));
+        }
+
+        Ok(())
+    }
+
     async fn get_jobs_by_status(
         &self,
         status: TrainingJobStatus,
@@ -200,7 +416,7 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
             r#"
             SELECT
                 id, name, definition, status AS "status: TrainingJobStatusRecord", node_id, queue_id,
-                resource_requirements, created_at, updated_at
+                user_id, resource_requirements, started_at, assigned_at, pending_reason, labels, annotations, env, exposed_ports, kind AS "kind: JobKindRecord", session, last_activity_at, resubmitted_from, termination_grace_period_secs, restart_count, job_token, failure_reason AS "failure_reason: FailureReasonRecord", assigned_gpus, created_at, updated_at
             FROM training_jobs
             WHERE status = $1
             "#,
@@ -217,4 +433,359 @@ impl TrainingJobRepository for PostgresTrainingJobRepository {
 
         Ok(jobs)
     }
+
+    async fn get_queued_definitions(&self) -> Result<Vec<String>, TrainingJobRepositoryError> {
+        let definitions = sqlx::query_scalar!(
+            "SELECT DISTINCT definition FROM training_jobs WHERE status = 'queued' ORDER BY definition"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(definitions)
+    }
+
+    async fn get_queue_head_definitions(
+        &self,
+        cluster_id: &ClusterId,
+        depth: i64,
+    ) -> Result<Vec<String>, TrainingJobRepositoryError> {
+        let definitions = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT definition AS "definition!"
+            FROM (
+                SELECT j.definition,
+                       ROW_NUMBER() OVER (PARTITION BY j.queue_id ORDER BY j.created_at ASC) AS position
+                FROM training_jobs j
+                JOIN queue_cluster_assignments a ON a.queue_id = j.queue_id
+                WHERE j.status = 'queued' AND a.cluster_id = $1
+            ) heads
+            WHERE position <= $2
+            ORDER BY definition
+            "#,
+            cluster_id.inner(),
+            depth,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(definitions)
+    }
+
+    async fn record_usage(
+        &self,
+        sample: &ResourceUsageSample,
+    ) -> Result<(), TrainingJobRepositoryError> {
+        let usage = &sample.usage;
+        let mut tx =
+            self.pool.begin().await.map_err(|e: sqlx::Error| {
+                TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e))
+            })?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO training_job_usage_samples (
+                job_id, node_id, sampled_at, cpu_millicores, memory_rss_mb, memory_peak_mb,
+                network_rx_bytes, network_tx_bytes, block_read_bytes, block_write_bytes, gpus
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+            sample.job_id.inner(),
+            sample.node_id.inner(),
+            sample.sampled_at,
+            usage.cpu_millicores,
+            usage.memory_rss_mb,
+            usage.memory_peak_mb,
+            usage.network_rx_bytes,
+            usage.network_tx_bytes,
+            usage.block_read_bytes,
+            usage.block_write_bytes,
+            &serde_json::to_value(&usage.gpus).map_err(|e| anyhow::anyhow!(e))?,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO training_job_usage_peaks (
+                job_id, cpu_millicores, memory_mb, gpu_memory_mb, gpu_utilization_percent,
+                network_rx_bytes, network_tx_bytes, block_read_bytes, block_write_bytes,
+                last_sampled_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (job_id) DO UPDATE SET
+                cpu_millicores = GREATEST(training_job_usage_peaks.cpu_millicores, EXCLUDED.cpu_millicores),
+                memory_mb = GREATEST(training_job_usage_peaks.memory_mb, EXCLUDED.memory_mb),
+                gpu_memory_mb = GREATEST(training_job_usage_peaks.gpu_memory_mb, EXCLUDED.gpu_memory_mb),
+                gpu_utilization_percent = GREATEST(training_job_usage_peaks.gpu_utilization_percent, EXCLUDED.gpu_utilization_percent),
+                network_rx_bytes = GREATEST(training_job_usage_peaks.network_rx_bytes, EXCLUDED.network_rx_bytes),
+                network_tx_bytes = GREATEST(training_job_usage_peaks.network_tx_bytes, EXCLUDED.network_tx_bytes),
+                block_read_bytes = GREATEST(training_job_usage_peaks.block_read_bytes, EXCLUDED.block_read_bytes),
+                block_write_bytes = GREATEST(training_job_usage_peaks.block_write_bytes, EXCLUDED.block_write_bytes),
+                last_sampled_at = GREATEST(training_job_usage_peaks.last_sampled_at, EXCLUDED.last_sampled_at)
+            "#,
+            sample.job_id.inner(),
+            usage.cpu_millicores,
+            usage.memory_rss_mb.max(usage.memory_peak_mb),
+            usage.total_gpu_memory_mb(),
+            usage.mean_gpu_utilization_percent(),
+            usage.network_rx_bytes,
+            usage.network_tx_bytes,
+            usage.block_read_bytes,
+            usage.block_write_bytes,
+            sample.sampled_at,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        tx.commit()
+            .await
+            .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_usage_peaks(
+        &self,
+        job_id: &JobId,
+    ) -> Result<Option<ResourceUsagePeaks>, TrainingJobRepositoryError> {
+        let record = sqlx::query_as!(
+            ResourceUsagePeaksRecord,
+            r#"
+            SELECT cpu_millicores, memory_mb, gpu_memory_mb, gpu_utilization_percent,
+                   network_rx_bytes, network_tx_bytes, block_read_bytes, block_write_bytes,
+                   last_sampled_at
+            FROM training_job_usage_peaks
+            WHERE job_id = $1
+            "#,
+            job_id.inner()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(record.map(Into::into))
+    }
+
+    async fn get_usage_samples(
+        &self,
+        job_id: &JobId,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<ResourceUsageSample>, TrainingJobRepositoryError> {
+        let rows = sqlx::query_as!(
+            ResourceUsageSampleRecord,
+            r#"
+            SELECT job_id AS "job_id!", node_id AS "node_id!", sampled_at AS "sampled_at!",
+                   cpu_millicores AS "cpu_millicores!", memory_rss_mb AS "memory_rss_mb!",
+                   memory_peak_mb AS "memory_peak_mb!", network_rx_bytes AS "network_rx_bytes!",
+                   network_tx_bytes AS "network_tx_bytes!", block_read_bytes AS "block_read_bytes!",
+                   block_write_bytes AS "block_write_bytes!", gpus AS "gpus!"
+            FROM (
+                SELECT job_id, node_id, sampled_at, cpu_millicores, memory_rss_mb, memory_peak_mb,
+                       network_rx_bytes, network_tx_bytes, block_read_bytes, block_write_bytes, gpus
+                FROM training_job_usage_samples
+                WHERE job_id = $1 AND ($2::timestamptz IS NULL OR sampled_at > $2)
+                ORDER BY sampled_at DESC
+                LIMIT $3
+            ) latest
+            ORDER BY sampled_at ASC
+            "#,
+            job_id.inner(),
+            since,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        let samples = rows
+            .into_iter()
+            .map(|row| row.try_into())
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(samples)
+    }
+
+    async fn delete_usage_samples_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, TrainingJobRepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM training_job_usage_samples
+            WHERE job_id IN (
+                SELECT s.job_id
+                FROM training_job_usage_samples s
+                JOIN training_jobs j ON j.id = s.job_id
+                WHERE j.status IN ('succeeded', 'failed', 'cancelled')
+                GROUP BY s.job_id
+                HAVING MAX(s.sampled_at) < $1
+            )
+            "#,
+            before
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn create_usage_record(
+        &self,
+        record: &UsageRecord,
+    ) -> Result<(), TrainingJobRepositoryError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO training_job_usage_records (
+                job_id, user_id, queue_id, cluster_id, node_id, started_at, ended_at,
+                cpu_millicores, memory_mb, gpu_count, gpu_model
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+            record.job_id.inner(),
+            record.user_id.map(|u| u.into_inner()),
+            record.queue_id.map(|q| q.into_inner()),
+            record.cluster_id.inner(),
+            record.node_id.inner(),
+            record.started_at,
+            record.ended_at,
+            record.cpu_millicores,
+            record.memory_mb,
+            record.gpu_count,
+            record.gpu_model,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn list_usage_records(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<UsageRecord>, TrainingJobRepositoryError> {
+        let rows = sqlx::query_as!(
+            UsageRecordRecord,
+            r#"
+            SELECT job_id, user_id, queue_id, cluster_id, node_id, started_at, ended_at,
+                   cpu_millicores, memory_mb, gpu_count, gpu_model
+            FROM training_job_usage_records
+            WHERE started_at < $2 AND ended_at > $1
+            ORDER BY started_at ASC
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn record_metrics(
+        &self,
+        metrics: &[JobMetric],
+    ) -> Result<(), TrainingJobRepositoryError> {
+        let mut tx =
+            self.pool.begin().await.map_err(|e: sqlx::Error| {
+                TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e))
+            })?;
+
+        for metric in metrics {
+            sqlx::query!(
+                r#"
+                INSERT INTO training_job_metrics (job_id, name, step, value, reported_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (job_id, name, step) DO UPDATE SET
+                    value = EXCLUDED.value,
+                    reported_at = EXCLUDED.reported_at
+                "#,
+                metric.job_id.inner(),
+                metric.name,
+                metric.step,
+                metric.value,
+                metric.reported_at,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_metrics(&self, id: &JobId) -> Result<Vec<JobMetric>, TrainingJobRepositoryError> {
+        let rows = sqlx::query_as!(
+            JobMetricRecord,
+            r#"
+            SELECT job_id, name, step, value, reported_at
+            FROM training_job_metrics
+            WHERE job_id = $1
+            ORDER BY name ASC, step ASC
+            "#,
+            id.inner()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_metric_for_jobs(
+        &self,
+        ids: &[JobId],
+        name: &str,
+    ) -> Result<Vec<JobMetric>, TrainingJobRepositoryError> {
+        let ids = ids.iter().map(|id| id.into_inner()).collect::<Vec<_>>();
+        let rows = sqlx::query_as!(
+            JobMetricRecord,
+            r#"
+            SELECT job_id, name, step, value, reported_at
+            FROM training_job_metrics
+            WHERE job_id = ANY($1) AND name = $2
+            ORDER BY step ASC
+            "#,
+            &ids,
+            name
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e: sqlx::Error| TrainingJobRepositoryError::Unknown(anyhow::anyhow!(e)))?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Splits a metadata patch into the keys to remove and a JSON object of the
+/// keys to set.
+fn split_patch(
+    patch: &std::collections::HashMap<String, Option<String>>,
+) -> (Vec<String>, serde_json::Value) {
+    let removed = patch
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(key, _)| key.clone())
+        .collect();
+    let set = patch
+        .iter()
+        .filter_map(|(key, value)| {
+            value
+                .as_ref()
+                .map(|value| (key.clone(), serde_json::Value::String(value.clone())))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    (removed, serde_json::Value::Object(set))
 }
@@ -36,32 +36,27 @@ impl AgentSchedulerAdapter {
         job_id: &JobId,
         cluster_id: &ClusterId,
         requirements: &ResourceRequirements,
+        image: &str,
     ) -> Result<Option<NodeId>, AgentSchedulerError> {
         let mut nodes = self.cluster_repo.list_cluster_nodes(cluster_id).await?;
 
         // Filter nodes that are available and meet the resource requirements.
+        // Draining nodes are shutting down and take no new jobs.
         nodes.retain(|node| {
-            let status_ok =
-                node.node_status == NodeStatus::Available && node.assigned_job_id.is_none();
+            let status_ok = node.node_status == NodeStatus::Available
+                && node.assigned_job_id.is_none()
+                && !node.draining;
             if !status_ok {
                 return false;
             }
 
-            let cpu_ok = node.cpu.millicores >= requirements.cpu_millicores;
-            let mem_ok = node.memory_mb >= requirements.memory_mb;
-            let gpu_ok = match &requirements.gpus {
-                None => true,
-                Some(req_gpu) => match &node.gpu {
-                    Some(node_gpu) => node_gpu.count >= req_gpu.count,
-                    None => false,
-                },
-            };
-
-            cpu_ok && mem_ok && gpu_ok
+            node.capacity().fits(requirements)
         });
 
         // Sort the suitable nodes by memory in ascending order (best fit).
-        nodes.sort_by_key(|node| node.memory_mb);
+        // Among equal nodes, those that have the job's image cached come
+        // first, since the job starts on them without pulling it.
+        nodes.sort_by_key(|node| (node.memory_mb, !node.has_image(image)));
 
         let suitable_node = nodes.into_iter().next();
 
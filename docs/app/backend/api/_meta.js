@@ -2,7 +2,12 @@ export default {
   "index": "Introduction",
   "auth": "Authentication",
   "clusters": "Clusters",
+  "job-templates": "Job Templates",
+  "notifications": "Notifications",
   "queues": "Queues",
+  "quotas": "Quotas",
+  "sweeps": "Sweeps",
   "training-jobs": "Training Jobs",
+  "usage": "Usage",
   "users": "Users"
 }
\ No newline at end of file
@@ -266,17 +266,21 @@ export function ClusterOverview(props: ClusterOverviewProps) {
               {
                 accessorKey: 'nodeStatus',
                 header: 'Node Status',
-                cell: ({ cell }) => {
+                cell: ({ cell, row }) => {
                   const getStatusType = (nodeStatus: string) => {
                     switch (nodeStatus) {
                       case 'available':
                         return 'success';
                       case 'busy':
                         return 'error';
+                      case 'draining':
+                        return 'warning';
                     }
                   };
 
-                  const nodeStatus = cell.getValue() as string;
+                  const nodeStatus = row.original.draining
+                    ? 'draining'
+                    : (cell.getValue() as string);
                   return (
                     <Status
                       status={getStatusType(nodeStatus)}
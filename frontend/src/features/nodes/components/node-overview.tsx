@@ -45,10 +45,12 @@ export function NodeOverview(props: NodeOverviewProps) {
                 key: 'Status',
                 value: (
                   <Status
-                    status={getStatusType(node.nodeStatus)}
+                    status={
+                      node.draining ? 'warning' : getStatusType(node.nodeStatus)
+                    }
                     className='capitalize'
                   >
-                    {node.nodeStatus}
+                    {node.draining ? 'draining' : node.nodeStatus}
                   </Status>
                 ),
               },
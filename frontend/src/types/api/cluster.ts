@@ -64,6 +64,8 @@ export interface ClusterNode {
   id: string;
   clusterId: string;
   nodeStatus: 'busy' | 'available';
+  /** Whether the node's agent is shutting down and takes no new jobs. */
+  draining: boolean;
   lastHeartbeat: string;
   memoryMb: number;
   cpu: {